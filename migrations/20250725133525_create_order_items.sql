@@ -0,0 +1,30 @@
+-- migrations/YYYYMMDDHHMMSS_create_order_items/down.sql
+
+DROP TABLE IF EXISTS order_items;
+
+-- migrations/YYYYMMDDHHMMSS_create_order_items/up.sql
+
+-- The individual lines of an order, captured at checkout time.
+-- Prices are copied from the offer so that later price changes never alter
+-- what the customer was charged, and tickets are issued from these rows
+-- once the payment succeeds.
+CREATE TABLE order_items (
+    id SERIAL PRIMARY KEY,
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    offer_id INT NOT NULL REFERENCES offers(id) ON DELETE RESTRICT,
+
+    -- Denormalized from the offer so settlement reports can group by event cheaply.
+    event_id INT NOT NULL REFERENCES events(id) ON DELETE RESTRICT,
+    ticket_tier_id INT NOT NULL REFERENCES ticket_tiers(id) ON DELETE RESTRICT,
+
+    -- NULL for General Admission lines.
+    seat_id INT REFERENCES seats(id) ON DELETE RESTRICT,
+
+    quantity INT NOT NULL CHECK (quantity > 0),
+    unit_price DECIMAL(10, 2) NOT NULL,       -- Face value charged per ticket.
+    unit_service_fee DECIMAL(10, 2) NOT NULL  -- Platform fee charged per ticket.
+);
+
+-- ### INDEXES ###
+CREATE INDEX idx_order_items_order_id ON order_items(order_id);
+CREATE INDEX idx_order_items_event_id ON order_items(event_id);
@@ -0,0 +1,55 @@
+-- migrations/YYYYMMDDHHMMSS_create_settlement_ledger/down.sql
+
+DROP TABLE IF EXISTS organizer_payouts;
+DROP TABLE IF EXISTS settlement_entries;
+DROP TYPE IF EXISTS settlement_entry_type;
+
+-- migrations/YYYYMMDDHHMMSS_create_settlement_ledger/up.sql
+
+-- The kinds of money movement that make up an organizer's settlement.
+CREATE TYPE settlement_entry_type AS ENUM (
+    'sale',          -- Gross amount charged to the customer for the event.
+    'platform_fee',  -- Our service fee, kept by the platform.
+    'stripe_fee',    -- Processing fee taken by Stripe, from the charge's balance transaction.
+    'refund'         -- Money returned to the customer.
+);
+
+-- An append-only ledger of everything that affects what an organizer is owed.
+-- Amounts are signed from the organizer's point of view: sales are positive,
+-- fees and refunds are negative, so SUM(amount) is the net due.
+CREATE TABLE settlement_entries (
+    id SERIAL PRIMARY KEY,
+    event_id INT NOT NULL REFERENCES events(id) ON DELETE RESTRICT,
+    order_id UUID REFERENCES orders(id) ON DELETE RESTRICT,
+    entry_type settlement_entry_type NOT NULL,
+    amount DECIMAL(10, 2) NOT NULL,
+    currency VARCHAR(3) NOT NULL DEFAULT 'usd',
+
+    -- Set for entries imported from Stripe. A single balance transaction can be
+    -- split across several events when an order spans more than one event.
+    stripe_balance_transaction_id VARCHAR(255),
+
+    occurred_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    -- Makes re-running the Stripe sync idempotent.
+    UNIQUE (stripe_balance_transaction_id, event_id)
+);
+
+-- Payouts made by Stripe from an organizer's connected account to their bank.
+CREATE TABLE organizer_payouts (
+    id SERIAL PRIMARY KEY,
+    organizer_id INT NOT NULL REFERENCES users(id) ON DELETE RESTRICT,
+    stripe_payout_id VARCHAR(255) UNIQUE NOT NULL,
+    amount DECIMAL(10, 2) NOT NULL,
+    currency VARCHAR(3) NOT NULL,
+    status VARCHAR(50) NOT NULL, -- Stripe's payout status, e.g. 'pending', 'paid', 'failed'.
+    arrival_date TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    last_synced_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- ### INDEXES ###
+CREATE INDEX idx_settlement_entries_event_id ON settlement_entries(event_id, occurred_at);
+CREATE INDEX idx_settlement_entries_order_id ON settlement_entries(order_id);
+CREATE INDEX idx_organizer_payouts_organizer_id ON organizer_payouts(organizer_id, arrival_date);
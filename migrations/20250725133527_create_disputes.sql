@@ -0,0 +1,59 @@
+-- migrations/YYYYMMDDHHMMSS_create_disputes/down.sql
+
+ALTER TABLE tickets DROP COLUMN IF EXISTS voided_by_dispute_id;
+DROP TABLE IF EXISTS disputes;
+DROP TYPE IF EXISTS dispute_status;
+
+-- migrations/YYYYMMDDHHMMSS_create_disputes/up.sql
+
+-- Mirrors Stripe's dispute lifecycle. The `warning_*` states are inquiries
+-- (retrievals) that have not become a chargeback yet.
+CREATE TYPE dispute_status AS ENUM (
+    'warning_needs_response',
+    'warning_under_review',
+    'warning_closed',
+    'needs_response',
+    'under_review',
+    'won',
+    'lost'
+);
+
+-- A chargeback or inquiry raised by a customer's bank against one of our payments.
+CREATE TABLE disputes (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    payment_id UUID NOT NULL REFERENCES payments(id) ON DELETE RESTRICT,
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE RESTRICT,
+
+    -- The dispute ID in Stripe (e.g. 'dp_...'). Webhooks are matched on this.
+    stripe_dispute_id VARCHAR(255) UNIQUE NOT NULL,
+
+    status dispute_status NOT NULL,
+    reason VARCHAR(100) NOT NULL, -- Stripe's reason code, e.g. 'fraudulent', 'product_not_received'.
+    amount DECIMAL(10, 2) NOT NULL,
+    currency VARCHAR(3) NOT NULL DEFAULT 'usd',
+
+    -- What the dispute finally cost us (disputed amount plus Stripe's dispute fee). Set when lost.
+    amount_lost DECIMAL(10, 2) NOT NULL DEFAULT 0.00,
+
+    -- The deadline for submitting evidence to Stripe.
+    evidence_due_by TIMESTAMPTZ,
+
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    closed_at TIMESTAMPTZ,
+    last_updated TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Remember which tickets a dispute voided, so they can be restored if the dispute is won.
+ALTER TABLE tickets ADD COLUMN voided_by_dispute_id UUID REFERENCES disputes(id) ON DELETE SET NULL;
+
+-- Disputes lost or reinstated show up in the organizer's settlement ledger.
+ALTER TYPE settlement_entry_type ADD VALUE IF NOT EXISTS 'dispute';
+
+-- ### INDEXES ###
+CREATE INDEX idx_disputes_status ON disputes(status, evidence_due_by);
+CREATE INDEX idx_disputes_order_id ON disputes(order_id);
+
+CREATE TRIGGER set_timestamp
+BEFORE UPDATE ON disputes
+FOR EACH ROW
+EXECUTE PROCEDURE update_last_updated_column();
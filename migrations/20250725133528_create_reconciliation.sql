@@ -0,0 +1,63 @@
+-- migrations/YYYYMMDDHHMMSS_create_reconciliation/down.sql
+
+DROP TABLE IF EXISTS reconciliation_discrepancies;
+DROP TABLE IF EXISTS reconciliation_runs;
+DROP TYPE IF EXISTS reconciliation_discrepancy_kind;
+DROP TYPE IF EXISTS reconciliation_run_status;
+
+-- migrations/YYYYMMDDHHMMSS_create_reconciliation/up.sql
+
+CREATE TYPE reconciliation_run_status AS ENUM ('running', 'completed', 'failed');
+
+CREATE TYPE reconciliation_discrepancy_kind AS ENUM (
+    'missed_success',         -- The PaymentIntent succeeded but our payment is still pending (webhook missed).
+    'amount_mismatch',        -- The amount charged differs from what we recorded.
+    'status_mismatch',        -- Any other disagreement between the provider's status and ours.
+    'unknown_payment_intent', -- The provider took money for a PaymentIntent we have no payment for.
+    'missing_payment_intent'  -- We have a pending payment the provider does not know about.
+);
+
+-- One execution of the payment reconciliation job.
+CREATE TABLE reconciliation_runs (
+    id SERIAL PRIMARY KEY,
+    status reconciliation_run_status NOT NULL DEFAULT 'running',
+
+    -- The run compares payments created in [window_start, started_at].
+    window_start TIMESTAMPTZ NOT NULL,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMPTZ,
+
+    intents_checked INT NOT NULL DEFAULT 0,
+    auto_resolved INT NOT NULL DEFAULT 0,
+    discrepancies_found INT NOT NULL DEFAULT 0,
+
+    -- Set when the run failed, e.g. the provider could not be reached.
+    error_message TEXT
+);
+
+-- A mismatch found by a reconciliation run. Those that could not be fixed safely
+-- (auto_resolved = false) need a human to look at them.
+CREATE TABLE reconciliation_discrepancies (
+    id SERIAL PRIMARY KEY,
+    run_id INT NOT NULL REFERENCES reconciliation_runs(id) ON DELETE CASCADE,
+    kind reconciliation_discrepancy_kind NOT NULL,
+
+    stripe_payment_intent_id VARCHAR(255) NOT NULL,
+    payment_id UUID REFERENCES payments(id) ON DELETE SET NULL,
+    order_id UUID REFERENCES orders(id) ON DELETE SET NULL,
+
+    -- Both sides of the comparison, as each system reports them.
+    our_status VARCHAR(50),
+    provider_status VARCHAR(50),
+    our_amount DECIMAL(10, 2),
+    provider_amount DECIMAL(10, 2),
+
+    auto_resolved BOOLEAN NOT NULL DEFAULT FALSE,
+    details TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- ### INDEXES ###
+CREATE INDEX idx_reconciliation_runs_started_at ON reconciliation_runs(started_at DESC);
+CREATE INDEX idx_reconciliation_discrepancies_run_id ON reconciliation_discrepancies(run_id);
+CREATE INDEX idx_payments_created_at ON payments(created_at);
@@ -0,0 +1,16 @@
+-- migrations/YYYYMMDDHHMMSS_link_event_seats_to_orders/down.sql
+
+DROP INDEX IF EXISTS idx_event_seats_order_id;
+ALTER TABLE event_seats DROP CONSTRAINT IF EXISTS event_seats_order_id_fkey;
+ALTER TABLE event_seats ALTER COLUMN order_id TYPE INT USING NULL;
+
+-- migrations/YYYYMMDDHHMMSS_link_event_seats_to_orders/up.sql
+
+-- `event_seats.order_id` was created as INT before the orders table existed, but orders
+-- are keyed by UUID. Nothing could be stored in it, so existing values are dropped.
+ALTER TABLE event_seats ALTER COLUMN order_id TYPE UUID USING NULL;
+ALTER TABLE event_seats
+    ADD CONSTRAINT event_seats_order_id_fkey FOREIGN KEY (order_id) REFERENCES orders(id) ON DELETE SET NULL;
+
+-- Finalizing or expiring an order looks up its seats.
+CREATE INDEX idx_event_seats_order_id ON event_seats(order_id);
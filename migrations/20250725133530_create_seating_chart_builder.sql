@@ -0,0 +1,30 @@
+-- migrations/YYYYMMDDHHMMSS_create_seating_chart_builder/down.sql
+
+DROP TABLE IF EXISTS venue_managers;
+ALTER TABLE seats DROP CONSTRAINT IF EXISTS seats_row_id_seat_number_key;
+ALTER TABLE rows DROP CONSTRAINT IF EXISTS rows_section_id_name_key;
+ALTER TABLE sections DROP CONSTRAINT IF EXISTS sections_seating_chart_id_name_key;
+ALTER TABLE seating_charts DROP CONSTRAINT IF EXISTS seating_charts_venue_id_name_key;
+ALTER TABLE seating_charts DROP CONSTRAINT IF EXISTS seating_charts_name_key;
+ALTER TABLE seating_charts ADD CONSTRAINT seating_charts_name_key UNIQUE (name);
+
+-- migrations/YYYYMMDDHHMMSS_create_seating_chart_builder/up.sql
+
+-- Users allowed to build and edit the seating charts of a venue, besides admins.
+CREATE TABLE venue_managers (
+    venue_id INT NOT NULL REFERENCES venues(id) ON DELETE CASCADE,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (venue_id, user_id)
+);
+
+CREATE INDEX idx_venue_managers_user_id ON venue_managers(user_id);
+
+-- Chart names only need to be unique within their venue (every venue has a "Concert Layout").
+ALTER TABLE seating_charts DROP CONSTRAINT IF EXISTS seating_charts_name_key;
+ALTER TABLE seating_charts ADD CONSTRAINT seating_charts_venue_id_name_key UNIQUE (venue_id, name);
+
+-- Layout names are how buyers find their seat, so they must be unambiguous.
+ALTER TABLE sections ADD CONSTRAINT sections_seating_chart_id_name_key UNIQUE (seating_chart_id, name);
+ALTER TABLE rows ADD CONSTRAINT rows_section_id_name_key UNIQUE (section_id, name);
+ALTER TABLE seats ADD CONSTRAINT seats_row_id_seat_number_key UNIQUE (row_id, seat_number);
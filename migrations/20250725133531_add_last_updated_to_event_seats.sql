@@ -0,0 +1,18 @@
+-- migrations/YYYYMMDDHHMMSS_add_last_updated_to_event_seats/down.sql
+
+DROP TRIGGER IF EXISTS set_timestamp ON event_seats;
+DROP INDEX IF EXISTS idx_event_seats_last_updated;
+ALTER TABLE event_seats DROP COLUMN IF EXISTS last_updated;
+
+-- migrations/YYYYMMDDHHMMSS_add_last_updated_to_event_seats/up.sql
+
+-- When an event's seat map last changed. Rendered seat maps are cached against it.
+ALTER TABLE event_seats ADD COLUMN last_updated TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+CREATE TRIGGER set_timestamp
+BEFORE UPDATE ON event_seats
+FOR EACH ROW
+EXECUTE PROCEDURE update_last_updated_column();
+
+-- Finding the latest change of an event's seats is a single index lookup.
+CREATE INDEX idx_event_seats_last_updated ON event_seats(event_id, last_updated);
@@ -0,0 +1,16 @@
+-- migrations/YYYYMMDDHHMMSS_add_best_available_seating/down.sql
+
+ALTER TABLE event_seats DROP COLUMN IF EXISTS locked_by_user_id;
+ALTER TABLE seating_charts DROP COLUMN IF EXISTS stage_y;
+ALTER TABLE seating_charts DROP COLUMN IF EXISTS stage_x;
+
+-- migrations/YYYYMMDDHHMMSS_add_best_available_seating/up.sql
+
+-- The point seats are judged from, usually the middle of the stage.
+-- The closer a seat is to it, the better. Charts without one use the top center of the layout.
+ALTER TABLE seating_charts ADD COLUMN stage_x DECIMAL(8, 2);
+ALTER TABLE seating_charts ADD COLUMN stage_y DECIMAL(8, 2);
+
+-- Who holds a seat locked outside of an order (e.g. a best-available pick),
+-- so only they can go on to buy it.
+ALTER TABLE event_seats ADD COLUMN locked_by_user_id INT REFERENCES users(id) ON DELETE SET NULL;
@@ -0,0 +1,19 @@
+-- migrations/YYYYMMDDHHMMSS_add_seat_ordinals/down.sql
+
+ALTER TABLE seats DROP COLUMN IF EXISTS ordinal;
+
+-- migrations/YYYYMMDDHHMMSS_add_seat_ordinals/up.sql
+
+-- A seat's position along its row, used to tell which seats are next to each other.
+-- Layouts store it from the order seats are listed in; existing rows are numbered left to right.
+ALTER TABLE seats ADD COLUMN ordinal INT NOT NULL DEFAULT 0;
+
+UPDATE seats s
+SET ordinal = numbered.ordinal
+FROM (
+    SELECT id, ROW_NUMBER() OVER (PARTITION BY row_id ORDER BY pos_x, pos_y, id) AS ordinal
+    FROM seats
+) numbered
+WHERE s.id = numbered.id;
+
+CREATE INDEX idx_seats_row_id_ordinal ON seats(row_id, ordinal);
@@ -0,0 +1,42 @@
+-- migrations/YYYYMMDDHHMMSS_create_seat_holds/down.sql
+
+-- Postgres can't drop an enum value, so 'held' stays in seat_status; its seats go back on sale.
+UPDATE event_seats SET status = 'available' WHERE status::text = 'held';
+ALTER TABLE tickets DROP COLUMN IF EXISTS seat_hold_id;
+ALTER TABLE event_seats DROP COLUMN IF EXISTS hold_id;
+DROP TABLE IF EXISTS seat_holds;
+
+-- migrations/YYYYMMDDHHMMSS_create_seat_holds/up.sql
+
+-- Seats an organizer keeps off general sale for a purpose: artist guests, press, sponsors.
+ALTER TYPE seat_status ADD VALUE IF NOT EXISTS 'held';
+
+CREATE TABLE seat_holds (
+    id SERIAL PRIMARY KEY,
+    event_id INT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    -- When the hold's remaining seats go back on sale by themselves.
+    -- NULL keeps them held until the organizer releases them.
+    release_at TIMESTAMPTZ,
+    -- Set once the whole hold has been released.
+    released_at TIMESTAMPTZ,
+    created_by INT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_updated TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- A name can be reused once the previous hold of that name is released.
+CREATE UNIQUE INDEX idx_seat_holds_event_id_name ON seat_holds(event_id, name) WHERE released_at IS NULL;
+CREATE INDEX idx_seat_holds_release_at ON seat_holds(release_at) WHERE released_at IS NULL;
+
+CREATE TRIGGER set_timestamp
+BEFORE UPDATE ON seat_holds
+FOR EACH ROW
+EXECUTE PROCEDURE update_last_updated_column();
+
+-- The hold a seat is in. Comped seats keep it for the record.
+ALTER TABLE event_seats ADD COLUMN hold_id INT REFERENCES seat_holds(id) ON DELETE SET NULL;
+CREATE INDEX idx_event_seats_hold_id ON event_seats(hold_id);
+
+-- The hold a complimentary ticket was issued from. NULL for paid tickets.
+ALTER TABLE tickets ADD COLUMN seat_hold_id INT REFERENCES seat_holds(id) ON DELETE SET NULL;
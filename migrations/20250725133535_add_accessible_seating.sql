@@ -0,0 +1,20 @@
+-- migrations/YYYYMMDDHHMMSS_add_accessible_seating/down.sql
+
+ALTER TABLE events DROP COLUMN IF EXISTS accessible_release_hours;
+ALTER TABLE offers DROP COLUMN IF EXISTS is_accessible;
+ALTER TABLE seats DROP COLUMN IF EXISTS attributes;
+DROP TYPE IF EXISTS seat_attribute;
+
+-- migrations/YYYYMMDDHHMMSS_add_accessible_seating/up.sql
+
+CREATE TYPE seat_attribute AS ENUM ('wheelchair', 'companion', 'restricted_view', 'aisle');
+
+ALTER TABLE seats ADD COLUMN attributes seat_attribute[] NOT NULL DEFAULT '{}';
+
+-- Offers for buyers who need a wheelchair space or sit with someone who does.
+-- Only they can buy wheelchair and companion seats until the event's release time.
+ALTER TABLE offers ADD COLUMN is_accessible BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- How many hours before the start wheelchair and companion seats go on general sale.
+ALTER TABLE events ADD COLUMN accessible_release_hours INT NOT NULL DEFAULT 24
+    CHECK (accessible_release_hours >= 0);
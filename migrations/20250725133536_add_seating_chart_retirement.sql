@@ -0,0 +1,13 @@
+-- migrations/YYYYMMDDHHMMSS_add_seating_chart_retirement/down.sql
+
+ALTER TABLE seats DROP COLUMN IF EXISTS retired_at;
+ALTER TABLE rows DROP COLUMN IF EXISTS retired_at;
+ALTER TABLE sections DROP COLUMN IF EXISTS retired_at;
+
+-- migrations/YYYYMMDDHHMMSS_add_seating_chart_retirement/up.sql
+
+-- Rebuilding a chart that events use keeps the parts left out of the new layout, as
+-- sold tickets still point at them, but retires them so they're no longer set up or shown.
+ALTER TABLE sections ADD COLUMN retired_at TIMESTAMPTZ;
+ALTER TABLE rows ADD COLUMN retired_at TIMESTAMPTZ;
+ALTER TABLE seats ADD COLUMN retired_at TIMESTAMPTZ;
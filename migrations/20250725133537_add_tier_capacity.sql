@@ -0,0 +1,14 @@
+-- migrations/YYYYMMDDHHMMSS_add_tier_capacity/down.sql
+
+ALTER TABLE ticket_tiers DROP COLUMN IF EXISTS quantity_sold;
+
+-- migrations/YYYYMMDDHHMMSS_add_tier_capacity/up.sql
+
+-- General admission tickets sold across all of a tier's offers. Presale and public offers
+-- may overlap, so checkout takes tickets from the tier as well as from the offer.
+ALTER TABLE ticket_tiers ADD COLUMN quantity_sold INT NOT NULL DEFAULT 0 CHECK (quantity_sold >= 0);
+
+UPDATE ticket_tiers tt
+SET quantity_sold = sold.quantity
+FROM (SELECT ticket_tier_id, SUM(quantity_sold)::INT AS quantity FROM offers GROUP BY ticket_tier_id) sold
+WHERE sold.ticket_tier_id = tt.id;
@@ -0,0 +1,39 @@
+-- migrations/YYYYMMDDHHMMSS_create_offer_access_codes/down.sql
+
+DROP TABLE IF EXISTS offer_unlocks;
+DROP TABLE IF EXISTS offer_access_codes;
+ALTER TABLE offers DROP COLUMN IF EXISTS is_presale;
+
+-- migrations/YYYYMMDDHHMMSS_create_offer_access_codes/up.sql
+
+-- Presale offers are hidden from the public and sold only to users who unlocked them,
+-- with the offer's shared `access_code` or one of its generated codes.
+ALTER TABLE offers ADD COLUMN is_presale BOOLEAN NOT NULL DEFAULT FALSE;
+UPDATE offers SET is_presale = TRUE WHERE access_code IS NOT NULL;
+
+-- Codes generated in bulk for an offer (e.g. for a fan club), each usable by a limited
+-- number of users.
+CREATE TABLE offer_access_codes (
+    id SERIAL PRIMARY KEY,
+    offer_id INT NOT NULL REFERENCES offers(id) ON DELETE CASCADE,
+    batch_name VARCHAR(100) NOT NULL,
+    code VARCHAR(32) NOT NULL UNIQUE,
+    max_uses INT NOT NULL DEFAULT 1 CHECK (max_uses >= 1),
+    use_count INT NOT NULL DEFAULT 0 CHECK (use_count >= 0 AND use_count <= max_uses),
+    created_by INT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_offer_access_codes_offer_id ON offer_access_codes(offer_id, batch_name);
+
+-- Which users have unlocked which presale offers, until when, and with which generated code.
+CREATE TABLE offer_unlocks (
+    offer_id INT NOT NULL REFERENCES offers(id) ON DELETE CASCADE,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    access_code_id INT REFERENCES offer_access_codes(id) ON DELETE SET NULL,
+    unlocked_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (offer_id, user_id)
+);
+
+CREATE INDEX idx_offer_unlocks_access_code_id ON offer_unlocks(access_code_id);
@@ -0,0 +1,41 @@
+-- migrations/YYYYMMDDHHMMSS_create_waiting_rooms/down.sql
+
+DROP TABLE IF EXISTS waiting_room_entries;
+DROP TABLE IF EXISTS waiting_rooms;
+
+-- migrations/YYYYMMDDHHMMSS_create_waiting_rooms/up.sql
+
+-- A virtual queue in front of a high-demand on-sale. Buyers join before it opens,
+-- get a random place in line when it does, and are let in a few at a time.
+CREATE TABLE waiting_rooms (
+    event_id INT PRIMARY KEY REFERENCES events(id) ON DELETE CASCADE,
+    opens_at TIMESTAMPTZ NOT NULL,
+    -- How many buyers are let in per minute.
+    admit_per_minute INT NOT NULL CHECK (admit_per_minute > 0),
+    -- How long an admitted buyer has to check out.
+    admission_minutes INT NOT NULL DEFAULT 15 CHECK (admission_minutes > 0),
+    -- Set once the places in line have been drawn.
+    opened_at TIMESTAMPTZ,
+    -- Everyone up to this place in line has been let in.
+    admitted_through INT NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_updated TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_waiting_rooms_opens_at ON waiting_rooms(opens_at) WHERE opened_at IS NULL;
+
+CREATE TRIGGER set_timestamp
+BEFORE UPDATE ON waiting_rooms
+FOR EACH ROW
+EXECUTE PROCEDURE update_last_updated_column();
+
+CREATE TABLE waiting_room_entries (
+    event_id INT NOT NULL REFERENCES waiting_rooms(event_id) ON DELETE CASCADE,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- NULL until the room opens; whoever joins later goes to the back of the line.
+    position INT,
+    joined_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    admitted_at TIMESTAMPTZ,
+    PRIMARY KEY (event_id, user_id),
+    UNIQUE (event_id, position)
+);
@@ -0,0 +1,47 @@
+-- migrations/YYYYMMDDHHMMSS_create_waitlists/down.sql
+
+ALTER TABLE event_seats DROP COLUMN IF EXISTS waitlist_entry_id;
+DROP TABLE IF EXISTS waitlist_entries;
+DROP TYPE IF EXISTS waitlist_status;
+
+-- migrations/YYYYMMDDHHMMSS_create_waitlists/up.sql
+
+CREATE TYPE waitlist_status AS ENUM ('waiting', 'offered', 'purchased', 'expired', 'left');
+
+-- Buyers waiting for tickets to a sold-out event, or one of its tiers, to come back.
+-- Returned inventory is reserved for them first come, first served.
+CREATE TABLE waitlist_entries (
+    id SERIAL PRIMARY KEY,
+    event_id INT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    -- NULL waits for any tier of the event.
+    ticket_tier_id INT REFERENCES ticket_tiers(id) ON DELETE CASCADE,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    quantity INT NOT NULL CHECK (quantity BETWEEN 1 AND 10),
+    status waitlist_status NOT NULL DEFAULT 'waiting',
+    -- While offered: the offer the reserved tickets sell through, and the secret of the purchase link.
+    offer_id INT REFERENCES offers(id) ON DELETE SET NULL,
+    claim_token VARCHAR(64) UNIQUE,
+    offered_at TIMESTAMPTZ,
+    offer_expires_at TIMESTAMPTZ,
+    order_id UUID REFERENCES orders(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_updated TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- One place in line per user and event.
+CREATE UNIQUE INDEX idx_waitlist_entries_event_id_user_id
+    ON waitlist_entries(event_id, user_id) WHERE status IN ('waiting', 'offered');
+CREATE INDEX idx_waitlist_entries_event_id_created_at
+    ON waitlist_entries(event_id, created_at) WHERE status = 'waiting';
+CREATE INDEX idx_waitlist_entries_offer_expires_at
+    ON waitlist_entries(offer_expires_at) WHERE status = 'offered';
+
+CREATE TRIGGER set_timestamp
+BEFORE UPDATE ON waitlist_entries
+FOR EACH ROW
+EXECUTE PROCEDURE update_last_updated_column();
+
+-- Seats locked for a waitlisted buyer. The expiry sweep leaves them alone;
+-- they go to the next in line when the purchase link expires.
+ALTER TABLE event_seats ADD COLUMN waitlist_entry_id INT REFERENCES waitlist_entries(id) ON DELETE SET NULL;
+CREATE INDEX idx_event_seats_waitlist_entry_id ON event_seats(waitlist_entry_id);
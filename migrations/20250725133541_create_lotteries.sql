@@ -0,0 +1,65 @@
+-- migrations/YYYYMMDDHHMMSS_create_lotteries/down.sql
+
+DROP TABLE IF EXISTS lottery_entries;
+DROP TABLE IF EXISTS lotteries;
+DROP TYPE IF EXISTS lottery_entry_status;
+
+-- migrations/YYYYMMDDHHMMSS_create_lotteries/up.sql
+
+-- Registration-based sales for very high-demand events: fans register for a quantity,
+-- and a seeded random draw decides who may buy from the lottery's dedicated offer.
+CREATE TABLE lotteries (
+    id SERIAL PRIMARY KEY,
+    event_id INT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    -- The offer winners buy through. It is hidden from the public sale.
+    offer_id INT NOT NULL UNIQUE REFERENCES offers(id) ON DELETE CASCADE,
+    registration_opens_at TIMESTAMPTZ NOT NULL,
+    registration_closes_at TIMESTAMPTZ NOT NULL,
+    max_per_user INT NOT NULL CHECK (max_per_user BETWEEN 1 AND 10),
+    purchase_window_hours INT NOT NULL DEFAULT 48 CHECK (purchase_window_hours > 0),
+    -- Drawn when the lottery is created; its SHA-256 is public from the start and the seed
+    -- itself once the draw has run, so anyone can check the draw wasn't rigged.
+    seed VARCHAR(64) NOT NULL,
+    seed_hash VARCHAR(64) NOT NULL,
+    -- SHA-256 of the registrations the draw ran on.
+    input_hash VARCHAR(64),
+    -- Tickets the draw had to hand out.
+    capacity INT,
+    drawn_at TIMESTAMPTZ,
+    created_by INT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_updated TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (registration_closes_at > registration_opens_at)
+);
+
+CREATE INDEX idx_lotteries_event_id ON lotteries(event_id);
+
+CREATE TRIGGER set_timestamp
+BEFORE UPDATE ON lotteries
+FOR EACH ROW
+EXECUTE PROCEDURE update_last_updated_column();
+
+CREATE TYPE lottery_entry_status AS ENUM ('registered', 'won', 'lost', 'purchased', 'expired');
+
+CREATE TABLE lottery_entries (
+    id SERIAL PRIMARY KEY,
+    lottery_id INT NOT NULL REFERENCES lotteries(id) ON DELETE CASCADE,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    quantity INT NOT NULL CHECK (quantity > 0),
+    status lottery_entry_status NOT NULL DEFAULT 'registered',
+    -- Set by the draw: the entry's place in the random order, and the tickets it won.
+    draw_rank INT,
+    allocated_quantity INT NOT NULL DEFAULT 0,
+    purchase_expires_at TIMESTAMPTZ,
+    order_id UUID REFERENCES orders(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_updated TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (lottery_id, user_id)
+);
+
+CREATE INDEX idx_lottery_entries_purchase_expires_at ON lottery_entries(purchase_expires_at) WHERE status = 'won';
+
+CREATE TRIGGER set_timestamp
+BEFORE UPDATE ON lottery_entries
+FOR EACH ROW
+EXECUTE PROCEDURE update_last_updated_column();
@@ -0,0 +1,80 @@
+-- migrations/YYYYMMDDHHMMSS_create_offer_price_rules/down.sql
+
+DROP FUNCTION IF EXISTS offer_current_price(INT);
+DROP TABLE IF EXISTS offer_price_rules;
+DROP TYPE IF EXISTS price_adjustment_type;
+DROP TYPE IF EXISTS price_rule_kind;
+ALTER TABLE offers DROP COLUMN IF EXISTS price_floor, DROP COLUMN IF EXISTS price_ceiling;
+
+-- migrations/YYYYMMDDHHMMSS_create_offer_price_rules/up.sql
+
+-- Bounds on what an offer's pricing rules may take its price to.
+ALTER TABLE offers
+    ADD COLUMN price_floor DECIMAL(10, 2) CHECK (price_floor >= 0),
+    ADD COLUMN price_ceiling DECIMAL(10, 2),
+    ADD CONSTRAINT offers_price_bounds_check CHECK (price_floor <= price_ceiling);
+
+-- 'sell_through': applies once `threshold` percent of the offer's tickets are sold.
+-- 'time_to_event': applies from `threshold` hours before the event starts.
+CREATE TYPE price_rule_kind AS ENUM ('sell_through', 'time_to_event');
+CREATE TYPE price_adjustment_type AS ENUM ('percent', 'amount');
+
+CREATE TABLE offer_price_rules (
+    id SERIAL PRIMARY KEY,
+    offer_id INT NOT NULL REFERENCES offers(id) ON DELETE CASCADE,
+    kind price_rule_kind NOT NULL,
+    threshold INT NOT NULL CHECK (threshold > 0),
+    adjustment_type price_adjustment_type NOT NULL,
+    -- Negative for a discount.
+    adjustment DECIMAL(10, 2) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (offer_id, kind, threshold),
+    CHECK (kind <> 'sell_through' OR threshold <= 100),
+    CHECK (adjustment_type <> 'percent' OR adjustment > -100)
+);
+
+-- The price an offer sells at right now. Of each kind of rule, only the step reached last
+-- applies: the highest sell-through threshold reached, and the nearest time-to-event one.
+-- The sell-through step applies first, then the time step; the result is kept within the
+-- offer's floor and ceiling and rounded to cents.
+CREATE FUNCTION offer_current_price(p_offer_id INT) RETURNS DECIMAL(10, 2)
+LANGUAGE plpgsql STABLE AS $$
+DECLARE
+    v_offer RECORD;
+    v_rule RECORD;
+    v_price NUMERIC;
+BEGIN
+    SELECT o.price, o.price_floor, o.price_ceiling, o.quantity_sold, o.quantity_for_sale, e.start_time
+    INTO v_offer
+    FROM offers o
+    JOIN ticket_tiers tt ON tt.id = o.ticket_tier_id
+    JOIN events e ON e.id = tt.event_id
+    WHERE o.id = p_offer_id;
+    IF NOT FOUND THEN
+        RETURN NULL;
+    END IF;
+
+    v_price := v_offer.price;
+    FOR v_rule IN
+        (SELECT adjustment_type, adjustment FROM offer_price_rules
+         WHERE offer_id = p_offer_id AND kind = 'sell_through' AND v_offer.quantity_for_sale > 0
+           AND v_offer.quantity_sold * 100 >= threshold * v_offer.quantity_for_sale
+         ORDER BY threshold DESC LIMIT 1)
+        UNION ALL
+        (SELECT adjustment_type, adjustment FROM offer_price_rules
+         WHERE offer_id = p_offer_id AND kind = 'time_to_event'
+           AND v_offer.start_time - make_interval(hours => threshold) <= NOW()
+         ORDER BY threshold ASC LIMIT 1)
+    LOOP
+        IF v_rule.adjustment_type = 'percent' THEN
+            v_price := v_price * (100 + v_rule.adjustment) / 100;
+        ELSE
+            v_price := v_price + v_rule.adjustment;
+        END IF;
+    END LOOP;
+
+    v_price := GREATEST(v_price, COALESCE(v_offer.price_floor, 0), 0);
+    v_price := LEAST(v_price, COALESCE(v_offer.price_ceiling, v_price));
+    RETURN ROUND(v_price, 2);
+END;
+$$;
@@ -0,0 +1,94 @@
+-- migrations/YYYYMMDDHHMMSS_create_bundles_and_addons/down.sql
+
+DROP TABLE IF EXISTS vouchers;
+DROP TYPE IF EXISTS voucher_status;
+DROP TABLE IF EXISTS order_addons;
+ALTER TABLE order_items DROP COLUMN IF EXISTS order_bundle_id;
+DROP TABLE IF EXISTS order_bundles;
+DROP TABLE IF EXISTS offer_bundle_components;
+ALTER TABLE offers DROP COLUMN IF EXISTS is_bundle;
+DROP TABLE IF EXISTS addon_items;
+
+-- migrations/YYYYMMDDHHMMSS_create_bundles_and_addons/up.sql
+
+-- Things an event sells besides tickets, e.g. parking or a merch voucher.
+CREATE TABLE addon_items (
+    id SERIAL PRIMARY KEY,
+    event_id INT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    description TEXT,
+    total_inventory INT NOT NULL CHECK (total_inventory >= 0),
+    quantity_sold INT NOT NULL DEFAULT 0 CHECK (quantity_sold >= 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_updated TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_addon_items_event_id ON addon_items(event_id);
+
+CREATE TRIGGER set_timestamp
+BEFORE UPDATE ON addon_items
+FOR EACH ROW
+EXECUTE PROCEDURE update_last_updated_column();
+
+-- A bundle offer sells packages; each package is made of the components below.
+-- Its `quantity_for_sale` and `quantity_sold` count packages, not tickets.
+ALTER TABLE offers ADD COLUMN is_bundle BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- One part of a bundle: tickets of a general admission tier, or an add-on.
+CREATE TABLE offer_bundle_components (
+    id SERIAL PRIMARY KEY,
+    offer_id INT NOT NULL REFERENCES offers(id) ON DELETE CASCADE,
+    ticket_tier_id INT REFERENCES ticket_tiers(id) ON DELETE RESTRICT,
+    addon_item_id INT REFERENCES addon_items(id) ON DELETE RESTRICT,
+    quantity INT NOT NULL CHECK (quantity BETWEEN 1 AND 20),
+    CHECK ((ticket_tier_id IS NULL) <> (addon_item_id IS NULL))
+);
+
+CREATE INDEX idx_offer_bundle_components_offer_id ON offer_bundle_components(offer_id);
+
+-- The bundles bought in an order, at the price charged per package. Their tickets are
+-- recorded as order items and their add-ons as order add-ons, both linked back here.
+CREATE TABLE order_bundles (
+    id SERIAL PRIMARY KEY,
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    offer_id INT NOT NULL REFERENCES offers(id) ON DELETE RESTRICT,
+    quantity INT NOT NULL CHECK (quantity > 0),
+    unit_price DECIMAL(10, 2) NOT NULL
+);
+
+CREATE INDEX idx_order_bundles_order_id ON order_bundles(order_id);
+
+ALTER TABLE order_items ADD COLUMN order_bundle_id INT REFERENCES order_bundles(id) ON DELETE CASCADE;
+
+-- The add-ons bought in an order.
+CREATE TABLE order_addons (
+    id SERIAL PRIMARY KEY,
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    addon_item_id INT NOT NULL REFERENCES addon_items(id) ON DELETE RESTRICT,
+    event_id INT NOT NULL REFERENCES events(id) ON DELETE RESTRICT,
+    quantity INT NOT NULL CHECK (quantity > 0),
+    -- Zero for add-ons that came in a bundle; the bundle's price is on its tickets.
+    unit_price DECIMAL(10, 2) NOT NULL,
+    order_bundle_id INT REFERENCES order_bundles(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_order_addons_order_id ON order_addons(order_id);
+
+CREATE TYPE voucher_status AS ENUM ('valid', 'redeemed', 'voided');
+
+-- One scannable voucher per add-on bought, issued with the order's tickets.
+CREATE TABLE vouchers (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE RESTRICT,
+    event_id INT NOT NULL REFERENCES events(id) ON DELETE RESTRICT,
+    addon_item_id INT NOT NULL REFERENCES addon_items(id) ON DELETE RESTRICT,
+    qr_code_data TEXT UNIQUE NOT NULL DEFAULT uuid_generate_v4(),
+    status voucher_status NOT NULL DEFAULT 'valid',
+    voided_by_dispute_id UUID REFERENCES disputes(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    redeemed_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_vouchers_user_id ON vouchers(user_id);
+CREATE INDEX idx_vouchers_order_id ON vouchers(order_id);
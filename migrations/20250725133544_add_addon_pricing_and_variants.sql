@@ -0,0 +1,41 @@
+-- migrations/YYYYMMDDHHMMSS_add_addon_pricing_and_variants/down.sql
+
+ALTER TABLE vouchers DROP COLUMN IF EXISTS addon_variant_id;
+ALTER TABLE order_addons DROP COLUMN IF EXISTS addon_variant_id;
+DROP TABLE IF EXISTS addon_variants;
+ALTER TABLE addon_items DROP CONSTRAINT IF EXISTS addon_items_donation_not_redeemable;
+ALTER TABLE addon_items
+    DROP COLUMN IF EXISTS is_redeemable,
+    DROP COLUMN IF EXISTS is_donation,
+    DROP COLUMN IF EXISTS price;
+UPDATE addon_items SET total_inventory = quantity_sold WHERE total_inventory IS NULL;
+ALTER TABLE addon_items ALTER COLUMN total_inventory SET NOT NULL;
+
+-- migrations/YYYYMMDDHHMMSS_add_addon_pricing_and_variants/up.sql
+
+-- Add-ons can be bought on their own. For donations, `price` is the least a buyer may give;
+-- the amount itself is theirs to choose. Redeemable add-ons are issued as vouchers.
+ALTER TABLE addon_items
+    ADD COLUMN price DECIMAL(10, 2) NOT NULL DEFAULT 0 CHECK (price >= 0),
+    ADD COLUMN is_donation BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN is_redeemable BOOLEAN NOT NULL DEFAULT TRUE,
+    ADD CONSTRAINT addon_items_donation_not_redeemable CHECK (NOT (is_donation AND is_redeemable));
+
+-- No inventory means the add-on can't sell out, e.g. a donation.
+ALTER TABLE addon_items ALTER COLUMN total_inventory DROP NOT NULL;
+
+-- Variants of an add-on, e.g. the sizes of a shirt, each with its own inventory.
+-- An add-on with variants is always bought as one of them.
+CREATE TABLE addon_variants (
+    id SERIAL PRIMARY KEY,
+    addon_item_id INT NOT NULL REFERENCES addon_items(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    total_inventory INT NOT NULL CHECK (total_inventory >= 0),
+    quantity_sold INT NOT NULL DEFAULT 0 CHECK (quantity_sold >= 0),
+    UNIQUE (addon_item_id, name)
+);
+
+CREATE INDEX idx_addon_variants_addon_item_id ON addon_variants(addon_item_id);
+
+ALTER TABLE order_addons ADD COLUMN addon_variant_id INT REFERENCES addon_variants(id) ON DELETE RESTRICT;
+ALTER TABLE vouchers ADD COLUMN addon_variant_id INT REFERENCES addon_variants(id) ON DELETE RESTRICT;
@@ -0,0 +1,46 @@
+-- migrations/YYYYMMDDHHMMSS_create_carts/down.sql
+
+ALTER TABLE event_seats DROP COLUMN IF EXISTS cart_id;
+DROP TABLE IF EXISTS cart_items;
+DROP TABLE IF EXISTS carts;
+
+-- migrations/YYYYMMDDHHMMSS_create_carts/up.sql
+
+-- A user's server-side cart. Everything in it is held until the cart's shared expiry.
+CREATE TABLE carts (
+    id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL UNIQUE REFERENCES users(id) ON DELETE CASCADE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_updated TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_carts_expires_at ON carts(expires_at);
+
+CREATE TRIGGER set_timestamp
+BEFORE UPDATE ON carts
+FOR EACH ROW
+EXECUTE PROCEDURE update_last_updated_column();
+
+-- The tickets in a cart, from any number of events. General admission tickets are taken
+-- from their offer and tier while in the cart; seats are locked for the cart.
+CREATE TABLE cart_items (
+    id SERIAL PRIMARY KEY,
+    cart_id INT NOT NULL REFERENCES carts(id) ON DELETE CASCADE,
+    offer_id INT NOT NULL REFERENCES offers(id) ON DELETE CASCADE,
+    event_id INT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    ticket_tier_id INT NOT NULL REFERENCES ticket_tiers(id) ON DELETE CASCADE,
+    seat_id INT REFERENCES seats(id) ON DELETE CASCADE,
+    quantity INT NOT NULL CHECK (quantity > 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (seat_id IS NULL OR quantity = 1)
+);
+
+CREATE INDEX idx_cart_items_cart_id ON cart_items(cart_id);
+-- General admission tickets of an offer are one line, whose quantity grows.
+CREATE UNIQUE INDEX idx_cart_items_general_admission ON cart_items(cart_id, offer_id) WHERE seat_id IS NULL;
+
+-- Seats locked for a cart. Like waitlist reservations, they are left alone by the sweep of
+-- stray seat locks and released with the cart.
+ALTER TABLE event_seats ADD COLUMN cart_id INT REFERENCES carts(id) ON DELETE SET NULL;
+CREATE INDEX idx_event_seats_cart_id ON event_seats(cart_id) WHERE cart_id IS NOT NULL;
@@ -0,0 +1,100 @@
+-- migrations/YYYYMMDDHHMMSS_create_gift_cards_and_store_credit/down.sql
+
+ALTER TABLE orders DROP COLUMN IF EXISTS credit_applied;
+DROP TABLE IF EXISTS store_credit_entries;
+DROP TABLE IF EXISTS store_credit_accounts;
+DROP TABLE IF EXISTS gift_card_entries;
+DROP TABLE IF EXISTS gift_cards;
+DROP TYPE IF EXISTS store_credit_entry_type;
+DROP TYPE IF EXISTS gift_card_entry_type;
+DROP TYPE IF EXISTS gift_card_status;
+
+-- migrations/YYYYMMDDHHMMSS_create_gift_cards_and_store_credit/up.sql
+
+CREATE TYPE gift_card_status AS ENUM (
+    'pending',   -- Bought, waiting for its order to be paid.
+    'active',    -- Paid for; its code can be redeemed.
+    'cancelled'  -- Its order expired unpaid.
+);
+
+-- Gift cards bought with an order. Once paid for, the code is redeemed onto an account
+-- as store credit.
+CREATE TABLE gift_cards (
+    id SERIAL PRIMARY KEY,
+    code VARCHAR(32) NOT NULL UNIQUE,
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE RESTRICT,
+    purchaser_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    recipient_email VARCHAR(255),
+    message TEXT,
+    initial_amount DECIMAL(10, 2) NOT NULL CHECK (initial_amount > 0),
+    balance DECIMAL(10, 2) NOT NULL CHECK (balance >= 0 AND balance <= initial_amount),
+    status gift_card_status NOT NULL DEFAULT 'pending',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_updated TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_gift_cards_order_id ON gift_cards(order_id);
+CREATE INDEX idx_gift_cards_purchaser_id ON gift_cards(purchaser_id);
+
+CREATE TRIGGER set_timestamp
+BEFORE UPDATE ON gift_cards
+FOR EACH ROW
+EXECUTE PROCEDURE update_last_updated_column();
+
+CREATE TYPE gift_card_entry_type AS ENUM (
+    'issue',      -- The card's value, once its order was paid.
+    'redemption'  -- Value moved onto an account as store credit.
+);
+
+-- An append-only ledger of a gift card's balance. SUM(amount) is the card's balance.
+CREATE TABLE gift_card_entries (
+    id SERIAL PRIMARY KEY,
+    gift_card_id INT NOT NULL REFERENCES gift_cards(id) ON DELETE CASCADE,
+    entry_type gift_card_entry_type NOT NULL,
+    amount DECIMAL(10, 2) NOT NULL CHECK (amount <> 0),
+    -- Who redeemed it, for redemptions.
+    user_id INT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_gift_card_entries_gift_card_id ON gift_card_entries(gift_card_id);
+
+CREATE TYPE store_credit_entry_type AS ENUM (
+    'gift_card',              -- A gift card redeemed onto the account.
+    'refund',                 -- An order refunded as credit.
+    'order_payment',          -- Credit spent on an order.
+    'order_payment_reversal'  -- Credit given back when the order it paid for went unpaid.
+);
+
+-- A user's store credit balance, kept next to its ledger so spending can lock the row
+-- and check the balance in one statement.
+CREATE TABLE store_credit_accounts (
+    user_id INT PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    balance DECIMAL(10, 2) NOT NULL DEFAULT 0 CHECK (balance >= 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_updated TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TRIGGER set_timestamp
+BEFORE UPDATE ON store_credit_accounts
+FOR EACH ROW
+EXECUTE PROCEDURE update_last_updated_column();
+
+-- An append-only ledger of store credit. Amounts are signed: credit given is positive and
+-- credit spent negative, so SUM(amount) per user is their balance.
+CREATE TABLE store_credit_entries (
+    id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    entry_type store_credit_entry_type NOT NULL,
+    amount DECIMAL(10, 2) NOT NULL CHECK (amount <> 0),
+    order_id UUID REFERENCES orders(id) ON DELETE RESTRICT,
+    gift_card_id INT REFERENCES gift_cards(id) ON DELETE RESTRICT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_store_credit_entries_user_id ON store_credit_entries(user_id, created_at);
+
+-- How much of an order was paid with store credit. Only the rest is charged to the card.
+ALTER TABLE orders
+    ADD COLUMN credit_applied DECIMAL(10, 2) NOT NULL DEFAULT 0
+    CHECK (credit_applied >= 0 AND credit_applied <= total_amount);
@@ -0,0 +1,75 @@
+-- migrations/YYYYMMDDHHMMSS_create_group_bookings/down.sql
+
+DROP TABLE IF EXISTS group_booking_shares;
+DROP TABLE IF EXISTS group_bookings;
+DROP TYPE IF EXISTS group_share_status;
+DROP TYPE IF EXISTS group_booking_status;
+DROP INDEX IF EXISTS idx_payments_order_id;
+
+-- migrations/YYYYMMDDHHMMSS_create_group_bookings/up.sql
+
+-- A group booking's order is paid by each member separately, so an order can have
+-- several payments.
+ALTER TABLE payments DROP CONSTRAINT IF EXISTS payments_order_id_key;
+CREATE INDEX idx_payments_order_id ON payments(order_id);
+
+CREATE TYPE group_booking_status AS ENUM (
+    'open',       -- Members are paying their shares.
+    'completed',  -- Every share was paid.
+    'closed',     -- The deadline passed; the unpaid shares were released.
+    'cancelled'   -- The deadline passed without a single share paid.
+);
+
+-- Tickets one member reserves for a group, held by a pending order until the payment
+-- deadline while the members pay their shares.
+CREATE TABLE group_bookings (
+    id SERIAL PRIMARY KEY,
+    order_id UUID NOT NULL UNIQUE REFERENCES orders(id) ON DELETE RESTRICT,
+    organizer_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    event_id INT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    -- Shared with the members so they can pay.
+    invite_token VARCHAR(64) NOT NULL UNIQUE,
+    payment_deadline TIMESTAMPTZ NOT NULL,
+    status group_booking_status NOT NULL DEFAULT 'open',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_updated TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_group_bookings_open ON group_bookings(payment_deadline) WHERE status = 'open';
+
+CREATE TRIGGER set_timestamp
+BEFORE UPDATE ON group_bookings
+FOR EACH ROW
+EXECUTE PROCEDURE update_last_updated_column();
+
+CREATE TYPE group_share_status AS ENUM (
+    'unpaid',    -- Free for any member to pay.
+    'pending',   -- Claimed by a member whose payment is in progress.
+    'paid',      -- Paid; the ticket was issued to the payer.
+    'released'   -- Unpaid at the deadline and put back on sale.
+);
+
+-- One ticket of a group booking, paid for by whichever member claims it.
+CREATE TABLE group_booking_shares (
+    id SERIAL PRIMARY KEY,
+    group_booking_id INT NOT NULL REFERENCES group_bookings(id) ON DELETE CASCADE,
+    -- The order line the ticket is on. Released shares' lines are trimmed from the order.
+    order_item_id INT REFERENCES order_items(id) ON DELETE SET NULL,
+    offer_id INT NOT NULL REFERENCES offers(id) ON DELETE CASCADE,
+    ticket_tier_id INT NOT NULL REFERENCES ticket_tiers(id) ON DELETE CASCADE,
+    seat_id INT REFERENCES seats(id) ON DELETE CASCADE,
+    -- The ticket's price plus its service fee.
+    amount DECIMAL(10, 2) NOT NULL CHECK (amount >= 0),
+    status group_share_status NOT NULL DEFAULT 'unpaid',
+    -- The member paying, or who paid, for it.
+    user_id INT REFERENCES users(id) ON DELETE SET NULL,
+    payment_id UUID REFERENCES payments(id) ON DELETE SET NULL,
+    -- A claim lapses at this time if its payment hasn't gone through, freeing the share.
+    claim_expires_at TIMESTAMPTZ,
+    ticket_id UUID REFERENCES tickets(id) ON DELETE SET NULL,
+    paid_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_group_booking_shares_booking ON group_booking_shares(group_booking_id, status);
+CREATE INDEX idx_group_booking_shares_payment_id ON group_booking_shares(payment_id);
+CREATE INDEX idx_group_booking_shares_lapsed ON group_booking_shares(claim_expires_at) WHERE status = 'pending';
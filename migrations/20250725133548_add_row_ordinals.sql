@@ -0,0 +1,18 @@
+-- migrations/YYYYMMDDHHMMSS_add_row_ordinals/down.sql
+
+ALTER TABLE rows DROP COLUMN IF EXISTS ordinal;
+
+-- migrations/YYYYMMDDHHMMSS_add_row_ordinals/up.sql
+
+-- A row's position in its section, front to back, used to select ranges of rows.
+-- Layouts store it from the order rows are listed in, as rebuilt rows keep their old IDs;
+-- existing rows are numbered in the order they were created.
+ALTER TABLE rows ADD COLUMN ordinal INT NOT NULL DEFAULT 0;
+
+UPDATE rows r
+SET ordinal = numbered.ordinal
+FROM (
+    SELECT id, ROW_NUMBER() OVER (PARTITION BY section_id ORDER BY id) AS ordinal
+    FROM rows
+) numbered
+WHERE r.id = numbered.id;
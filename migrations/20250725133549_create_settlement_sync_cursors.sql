@@ -0,0 +1,14 @@
+-- migrations/YYYYMMDDHHMMSS_create_settlement_sync_cursors/down.sql
+
+DROP TABLE IF EXISTS settlement_sync_cursors;
+
+-- migrations/YYYYMMDDHHMMSS_create_settlement_sync_cursors/up.sql
+
+-- How far each organizer's settlement sync has read Stripe's balance transactions, so the
+-- next sync only pages through the newer ones.
+CREATE TABLE settlement_sync_cursors (
+    organizer_id INT PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    -- The `created` time of the newest balance transaction read.
+    synced_until TIMESTAMPTZ NOT NULL,
+    last_synced_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
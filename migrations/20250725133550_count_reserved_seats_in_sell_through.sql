@@ -0,0 +1,103 @@
+-- migrations/YYYYMMDDHHMMSS_count_reserved_seats_in_sell_through/down.sql
+
+DROP INDEX IF EXISTS idx_order_items_offer_seats;
+
+CREATE OR REPLACE FUNCTION offer_current_price(p_offer_id INT) RETURNS DECIMAL(10, 2)
+LANGUAGE plpgsql STABLE AS $$
+DECLARE
+    v_offer RECORD;
+    v_rule RECORD;
+    v_price NUMERIC;
+BEGIN
+    SELECT o.price, o.price_floor, o.price_ceiling, o.quantity_sold, o.quantity_for_sale, e.start_time
+    INTO v_offer
+    FROM offers o
+    JOIN ticket_tiers tt ON tt.id = o.ticket_tier_id
+    JOIN events e ON e.id = tt.event_id
+    WHERE o.id = p_offer_id;
+    IF NOT FOUND THEN
+        RETURN NULL;
+    END IF;
+
+    v_price := v_offer.price;
+    FOR v_rule IN
+        (SELECT adjustment_type, adjustment FROM offer_price_rules
+         WHERE offer_id = p_offer_id AND kind = 'sell_through' AND v_offer.quantity_for_sale > 0
+           AND v_offer.quantity_sold * 100 >= threshold * v_offer.quantity_for_sale
+         ORDER BY threshold DESC LIMIT 1)
+        UNION ALL
+        (SELECT adjustment_type, adjustment FROM offer_price_rules
+         WHERE offer_id = p_offer_id AND kind = 'time_to_event'
+           AND v_offer.start_time - make_interval(hours => threshold) <= NOW()
+         ORDER BY threshold ASC LIMIT 1)
+    LOOP
+        IF v_rule.adjustment_type = 'percent' THEN
+            v_price := v_price * (100 + v_rule.adjustment) / 100;
+        ELSE
+            v_price := v_price + v_rule.adjustment;
+        END IF;
+    END LOOP;
+
+    v_price := GREATEST(v_price, COALESCE(v_offer.price_floor, 0), 0);
+    v_price := LEAST(v_price, COALESCE(v_offer.price_ceiling, v_price));
+    RETURN ROUND(v_price, 2);
+END;
+$$;
+
+-- migrations/YYYYMMDDHHMMSS_count_reserved_seats_in_sell_through/up.sql
+
+-- Sell-through price steps count the reserved seats held or sold through an offer, which
+-- checkout locks without touching the offer's `quantity_sold`.
+CREATE INDEX idx_order_items_offer_seats ON order_items(offer_id) WHERE seat_id IS NOT NULL;
+
+CREATE OR REPLACE FUNCTION offer_current_price(p_offer_id INT) RETURNS DECIMAL(10, 2)
+LANGUAGE plpgsql STABLE AS $$
+DECLARE
+    v_offer RECORD;
+    v_rule RECORD;
+    v_price NUMERIC;
+    v_sold INT;
+BEGIN
+    SELECT o.price, o.price_floor, o.price_ceiling, o.quantity_sold, o.quantity_for_sale, e.start_time
+    INTO v_offer
+    FROM offers o
+    JOIN ticket_tiers tt ON tt.id = o.ticket_tier_id
+    JOIN events e ON e.id = tt.event_id
+    WHERE o.id = p_offer_id;
+    IF NOT FOUND THEN
+        RETURN NULL;
+    END IF;
+
+    -- Reserved seats don't go through `quantity_sold`: count the seats held or sold through
+    -- the offer on its own (bundled seats are counted as packages by the bundle offer).
+    SELECT v_offer.quantity_sold + COALESCE(SUM(oi.quantity), 0)
+    INTO v_sold
+    FROM order_items oi
+    JOIN orders ord ON ord.id = oi.order_id
+    WHERE oi.offer_id = p_offer_id AND oi.seat_id IS NOT NULL AND oi.order_bundle_id IS NULL
+      AND ord.status IN ('pending', 'completed');
+
+    v_price := v_offer.price;
+    FOR v_rule IN
+        (SELECT adjustment_type, adjustment FROM offer_price_rules
+         WHERE offer_id = p_offer_id AND kind = 'sell_through' AND v_offer.quantity_for_sale > 0
+           AND v_sold * 100 >= threshold * v_offer.quantity_for_sale
+         ORDER BY threshold DESC LIMIT 1)
+        UNION ALL
+        (SELECT adjustment_type, adjustment FROM offer_price_rules
+         WHERE offer_id = p_offer_id AND kind = 'time_to_event'
+           AND v_offer.start_time - make_interval(hours => threshold) <= NOW()
+         ORDER BY threshold ASC LIMIT 1)
+    LOOP
+        IF v_rule.adjustment_type = 'percent' THEN
+            v_price := v_price * (100 + v_rule.adjustment) / 100;
+        ELSE
+            v_price := v_price + v_rule.adjustment;
+        END IF;
+    END LOOP;
+
+    v_price := GREATEST(v_price, COALESCE(v_offer.price_floor, 0), 0);
+    v_price := LEAST(v_price, COALESCE(v_offer.price_ceiling, v_price));
+    RETURN ROUND(v_price, 2);
+END;
+$$;
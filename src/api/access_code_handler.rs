@@ -0,0 +1,50 @@
+use crate::{
+    errors::AppError,
+    models::{AccessCodeBatch, AccessCodeListQuery, GenerateAccessCodesPayload, Offer, OfferAccessCode, UnlockOffersPayload},
+    service::access_code_service,
+    AppState,
+};
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+
+/// Handler for a user to unlock an event's presale offers with an access code.
+/// POST /api/events/:event_id/offers/unlock
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn unlock_offers(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    Extension(user_id): Extension<i32>,
+    Json(payload): Json<UnlockOffersPayload>,
+) -> Result<Json<Vec<Offer>>, AppError> {
+    let offers = access_code_service::unlock_offers(&app_state.db_pool, event_id, user_id, &payload).await?;
+    Ok(Json(offers))
+}
+
+/// Handler for an organizer to generate a batch of access codes for an offer.
+/// POST /api/offers/:offer_id/access-codes
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn generate_codes(
+    State(app_state): State<AppState>,
+    Path(offer_id): Path<i32>,
+    Extension(organizer_id): Extension<i32>,
+    Json(payload): Json<GenerateAccessCodesPayload>,
+) -> Result<(StatusCode, Json<AccessCodeBatch>), AppError> {
+    let batch = access_code_service::generate_codes(&app_state.db_pool, offer_id, organizer_id, &payload).await?;
+    Ok((StatusCode::CREATED, Json(batch)))
+}
+
+/// Handler for an organizer to list the access codes of an offer with their usage.
+/// GET /api/offers/:offer_id/access-codes?batch_name=Fan%20Club
+#[tracing::instrument(skip(app_state))]
+pub async fn list_codes(
+    State(app_state): State<AppState>,
+    Path(offer_id): Path<i32>,
+    Extension(organizer_id): Extension<i32>,
+    Query(query): Query<AccessCodeListQuery>,
+) -> Result<Json<Vec<OfferAccessCode>>, AppError> {
+    let codes = access_code_service::list_codes(&app_state.db_pool, offer_id, organizer_id, &query).await?;
+    Ok(Json(codes))
+}
@@ -0,0 +1,46 @@
+use crate::{
+    errors::AppError,
+    models::{AddonItemInfo, CreateAddonItemPayload, VoucherDetails},
+    service::addon_service,
+    AppState,
+};
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    Json,
+};
+
+/// Handler for an organizer to add an item to their event's add-on catalog.
+/// POST /api/events/:event_id/addons
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn create_addon_item(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    Extension(organizer_id): Extension<i32>,
+    Json(payload): Json<CreateAddonItemPayload>,
+) -> Result<(StatusCode, Json<AddonItemInfo>), AppError> {
+    let item = addon_service::create_addon_item(&app_state.db_pool, event_id, organizer_id, &payload).await?;
+    Ok((StatusCode::CREATED, Json(item)))
+}
+
+/// Handler to list an event's add-on catalog.
+/// GET /api/events/:event_id/addons
+#[tracing::instrument(skip(app_state))]
+pub async fn list_addon_items(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+) -> Result<Json<Vec<AddonItemInfo>>, AppError> {
+    let items = addon_service::list_addon_items(&app_state.db_pool, event_id).await?;
+    Ok(Json(items))
+}
+
+/// Handler for an authenticated user to list their add-on vouchers.
+/// GET /api/me/vouchers
+#[tracing::instrument(skip(app_state))]
+pub async fn list_my_vouchers(
+    State(app_state): State<AppState>,
+    Extension(user_id): Extension<i32>,
+) -> Result<Json<Vec<VoucherDetails>>, AppError> {
+    let vouchers = addon_service::list_my_vouchers(&app_state.db_pool, user_id).await?;
+    Ok(Json(vouchers))
+}
@@ -0,0 +1,74 @@
+use crate::{
+    api::order_handler::ADMISSION_TOKEN_HEADER,
+    errors::AppError,
+    models::{order::CreateOrderResponse, CartView, OrderItemPayload},
+    service::{cart_service, order_service},
+    AppState,
+};
+use axum::{
+    extract::{Extension, Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+
+/// Handler for a user to see their cart and how long it's held for.
+/// GET /api/cart
+#[tracing::instrument(skip(app_state))]
+pub async fn get_cart(
+    State(app_state): State<AppState>,
+    Extension(user_id): Extension<i32>,
+) -> Result<Json<CartView>, AppError> {
+    let cart = cart_service::get_cart(&app_state.db_pool, user_id).await?;
+    Ok(Json(cart))
+}
+
+/// Handler to add a seat or general admission tickets to the user's cart.
+/// Events behind a waiting room also need the admission token in `X-Admission-Token`.
+/// POST /api/cart/items
+#[tracing::instrument(skip(app_state, headers, payload))]
+pub async fn add_item(
+    State(app_state): State<AppState>,
+    Extension(user_id): Extension<i32>,
+    headers: HeaderMap,
+    Json(payload): Json<OrderItemPayload>,
+) -> Result<(StatusCode, Json<CartView>), AppError> {
+    let admission_token = headers.get(ADMISSION_TOKEN_HEADER).and_then(|value| value.to_str().ok());
+    let cart = cart_service::add_item(&app_state.db_pool, user_id, &payload, admission_token).await?;
+    Ok((StatusCode::CREATED, Json(cart)))
+}
+
+/// Handler to take an item out of the user's cart.
+/// DELETE /api/cart/items/:item_id
+#[tracing::instrument(skip(app_state))]
+pub async fn remove_item(
+    State(app_state): State<AppState>,
+    Path(item_id): Path<i32>,
+    Extension(user_id): Extension<i32>,
+) -> Result<StatusCode, AppError> {
+    cart_service::remove_item(&app_state.db_pool, user_id, item_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Handler for a user to empty their cart.
+/// DELETE /api/cart
+#[tracing::instrument(skip(app_state))]
+pub async fn clear_cart(
+    State(app_state): State<AppState>,
+    Extension(user_id): Extension<i32>,
+) -> Result<StatusCode, AppError> {
+    cart_service::clear_cart(&app_state.db_pool, user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Handler to check out the user's cart as one pending order.
+/// Returns the order and a Stripe client secret, like `POST /api/orders`.
+/// POST /api/cart/checkout
+#[tracing::instrument(skip(app_state))]
+pub async fn checkout(
+    State(app_state): State<AppState>,
+    Extension(user_id): Extension<i32>,
+) -> Result<(StatusCode, Json<CreateOrderResponse>), AppError> {
+    let (order, stripe_client_secret) =
+        order_service::create_cart_order(&app_state.db_pool, app_state.payment_provider.as_ref(), user_id).await?;
+    Ok((StatusCode::CREATED, Json(CreateOrderResponse { order, stripe_client_secret })))
+}
@@ -0,0 +1,21 @@
+use crate::{
+    errors::AppError,
+    models::{DisputeCase, DisputeQueueQuery},
+    service::dispute_service,
+    AppState,
+};
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+
+/// Handler for the admin dispute queue, with the evidence held for each dispute.
+/// GET /api/disputes?status=needs_response
+#[tracing::instrument(skip(app_state))]
+pub async fn list_disputes(
+    State(app_state): State<AppState>,
+    Query(query): Query<DisputeQueueQuery>,
+) -> Result<Json<Vec<DisputeCase>>, AppError> {
+    let cases = dispute_service::list_dispute_queue(&app_state.db_pool, query.status).await?;
+    Ok(Json(cases))
+}
@@ -0,0 +1,79 @@
+use crate::{
+    api::order_handler::ADMISSION_TOKEN_HEADER,
+    errors::AppError,
+    models::{CreateGroupBookingPayload, GroupBookingView, GroupPaymentResponse, PayGroupSharesPayload},
+    service::group_booking_service,
+    AppState,
+};
+use axum::{
+    extract::{Extension, Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+
+/// Handler for an organizer to reserve tickets for their group. Returns the booking with
+/// its invite token, to share with the members so they can pay.
+/// Events behind a waiting room also need the admission token in `X-Admission-Token`.
+/// POST /api/events/:event_id/group-bookings
+#[tracing::instrument(skip(app_state, headers, payload))]
+pub async fn create_group_booking(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    Extension(user_id): Extension<i32>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateGroupBookingPayload>,
+) -> Result<(StatusCode, Json<GroupBookingView>), AppError> {
+    let admission_token = headers.get(ADMISSION_TOKEN_HEADER).and_then(|value| value.to_str().ok());
+    let booking = group_booking_service::create_group_booking(
+        &app_state.db_pool,
+        event_id,
+        user_id,
+        payload,
+        admission_token,
+    )
+    .await?;
+    Ok((StatusCode::CREATED, Json(booking)))
+}
+
+/// Handler for the organizer to list their group bookings.
+/// GET /api/me/group-bookings
+#[tracing::instrument(skip(app_state))]
+pub async fn list_my_group_bookings(
+    State(app_state): State<AppState>,
+    Extension(user_id): Extension<i32>,
+) -> Result<Json<Vec<GroupBookingView>>, AppError> {
+    let bookings = group_booking_service::list_my_group_bookings(&app_state.db_pool, user_id).await?;
+    Ok(Json(bookings))
+}
+
+/// Handler for a member to see a group booking through its invite link.
+/// GET /api/group-bookings/:invite_token
+#[tracing::instrument(skip(app_state, invite_token))]
+pub async fn get_group_booking(
+    State(app_state): State<AppState>,
+    Path(invite_token): Path<String>,
+) -> Result<Json<GroupBookingView>, AppError> {
+    let booking = group_booking_service::get_group_booking(&app_state.db_pool, &invite_token).await?;
+    Ok(Json(booking))
+}
+
+/// Handler for a member to pay for some of a group's tickets. Returns the shares claimed and
+/// a Stripe client secret; the tickets are issued to the member once the payment succeeds.
+/// POST /api/group-bookings/:invite_token/payments
+#[tracing::instrument(skip(app_state, invite_token, payload))]
+pub async fn pay_shares(
+    State(app_state): State<AppState>,
+    Path(invite_token): Path<String>,
+    Extension(user_id): Extension<i32>,
+    Json(payload): Json<PayGroupSharesPayload>,
+) -> Result<(StatusCode, Json<GroupPaymentResponse>), AppError> {
+    let payment = group_booking_service::pay_shares(
+        &app_state.db_pool,
+        app_state.payment_provider.as_ref(),
+        &invite_token,
+        user_id,
+        &payload,
+    )
+    .await?;
+    Ok((StatusCode::CREATED, Json(payment)))
+}
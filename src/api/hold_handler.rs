@@ -0,0 +1,92 @@
+use crate::{
+    errors::AppError,
+    models::{
+        AddHoldSeatsPayload, CompIssueResult, CreateSeatHoldPayload, IssueCompsPayload, ReleaseSeatHoldPayload,
+        SeatHold, SeatHoldResult, SeatHoldSummary, UpdateSeatHoldPayload,
+    },
+    service::hold_service,
+    AppState,
+};
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    Json,
+};
+
+/// Handler for an organizer to put seats of their event on a named hold.
+/// POST /api/events/:event_id/holds
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn create_hold(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    Extension(organizer_id): Extension<i32>,
+    Json(payload): Json<CreateSeatHoldPayload>,
+) -> Result<(StatusCode, Json<SeatHoldResult>), AppError> {
+    let result = hold_service::create_hold(&app_state.db_pool, event_id, organizer_id, &payload).await?;
+    Ok((StatusCode::CREATED, Json(result)))
+}
+
+/// Handler to list the holds of an event.
+/// GET /api/events/:event_id/holds
+#[tracing::instrument(skip(app_state))]
+pub async fn list_holds(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    Extension(organizer_id): Extension<i32>,
+) -> Result<Json<Vec<SeatHoldSummary>>, AppError> {
+    let holds = hold_service::list_holds(&app_state.db_pool, event_id, organizer_id).await?;
+    Ok(Json(holds))
+}
+
+/// Handler to rename a hold or reschedule its release.
+/// PATCH /api/holds/:hold_id
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn update_hold(
+    State(app_state): State<AppState>,
+    Path(hold_id): Path<i32>,
+    Extension(organizer_id): Extension<i32>,
+    Json(payload): Json<UpdateSeatHoldPayload>,
+) -> Result<Json<SeatHold>, AppError> {
+    let hold = hold_service::update_hold(&app_state.db_pool, hold_id, organizer_id, &payload).await?;
+    Ok(Json(hold))
+}
+
+/// Handler to add seats to a hold.
+/// POST /api/holds/:hold_id/seats
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn add_seats(
+    State(app_state): State<AppState>,
+    Path(hold_id): Path<i32>,
+    Extension(organizer_id): Extension<i32>,
+    Json(payload): Json<AddHoldSeatsPayload>,
+) -> Result<Json<SeatHoldResult>, AppError> {
+    let result = hold_service::add_seats(&app_state.db_pool, hold_id, organizer_id, &payload).await?;
+    Ok(Json(result))
+}
+
+/// Handler to release some or all held seats back to general sale.
+/// POST /api/holds/:hold_id/release
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn release_hold(
+    State(app_state): State<AppState>,
+    Path(hold_id): Path<i32>,
+    Extension(organizer_id): Extension<i32>,
+    payload: Option<Json<ReleaseSeatHoldPayload>>,
+) -> Result<Json<SeatHold>, AppError> {
+    let payload = payload.map(|Json(p)| p).unwrap_or_default();
+    let hold = hold_service::release_hold(&app_state.db_pool, hold_id, organizer_id, &payload).await?;
+    Ok(Json(hold))
+}
+
+/// Handler to issue held seats to a user as complimentary tickets.
+/// POST /api/holds/:hold_id/comps
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn issue_comps(
+    State(app_state): State<AppState>,
+    Path(hold_id): Path<i32>,
+    Extension(organizer_id): Extension<i32>,
+    Json(payload): Json<IssueCompsPayload>,
+) -> Result<(StatusCode, Json<CompIssueResult>), AppError> {
+    let result = hold_service::issue_comps(&app_state.db_pool, hold_id, organizer_id, &payload).await?;
+    Ok((StatusCode::CREATED, Json(result)))
+}
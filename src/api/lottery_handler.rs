@@ -0,0 +1,127 @@
+use crate::{
+    errors::AppError,
+    models::{
+        order::CreateOrderResponse, CreateLotteryPayload, Lottery, LotteryEntry, LotteryInfo, LotteryResults,
+        RegisterForLotteryPayload,
+    },
+    service::{lottery_service, order_service},
+    AppState,
+};
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    Json,
+};
+
+/// Handler for an organizer to sell a general admission offer by lottery.
+/// POST /api/events/:event_id/lotteries
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn create_lottery(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    Extension(user_id): Extension<i32>,
+    Json(payload): Json<CreateLotteryPayload>,
+) -> Result<(StatusCode, Json<Lottery>), AppError> {
+    let lottery = lottery_service::create_lottery(&app_state.db_pool, event_id, user_id, &payload).await?;
+    Ok((StatusCode::CREATED, Json(lottery)))
+}
+
+/// Public handler to list the lotteries of an event.
+/// GET /api/events/:event_id/lotteries
+#[tracing::instrument(skip(app_state))]
+pub async fn list_lotteries(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+) -> Result<Json<Vec<LotteryInfo>>, AppError> {
+    let lotteries = lottery_service::list_lotteries(&app_state.db_pool, event_id).await?;
+    Ok(Json(lotteries))
+}
+
+/// Public handler to fetch a lottery.
+/// GET /api/lotteries/:lottery_id
+#[tracing::instrument(skip(app_state))]
+pub async fn get_lottery(
+    State(app_state): State<AppState>,
+    Path(lottery_id): Path<i32>,
+) -> Result<Json<LotteryInfo>, AppError> {
+    let lottery = lottery_service::get_lottery(&app_state.db_pool, lottery_id).await?;
+    Ok(Json(lottery))
+}
+
+/// Public handler to fetch the published outcome of a lottery's draw.
+/// GET /api/lotteries/:lottery_id/results
+#[tracing::instrument(skip(app_state))]
+pub async fn get_results(
+    State(app_state): State<AppState>,
+    Path(lottery_id): Path<i32>,
+) -> Result<Json<LotteryResults>, AppError> {
+    let results = lottery_service::get_results(&app_state.db_pool, lottery_id).await?;
+    Ok(Json(results))
+}
+
+/// Handler for a user to register for a lottery or change their quantity.
+/// PUT /api/lotteries/:lottery_id/registration
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn register(
+    State(app_state): State<AppState>,
+    Path(lottery_id): Path<i32>,
+    Extension(user_id): Extension<i32>,
+    Json(payload): Json<RegisterForLotteryPayload>,
+) -> Result<Json<LotteryEntry>, AppError> {
+    let entry = lottery_service::register(&app_state.db_pool, lottery_id, user_id, &payload).await?;
+    Ok(Json(entry))
+}
+
+/// Handler to fetch the user's entry in a lottery, with what they won.
+/// GET /api/lotteries/:lottery_id/registration
+#[tracing::instrument(skip(app_state))]
+pub async fn get_my_entry(
+    State(app_state): State<AppState>,
+    Path(lottery_id): Path<i32>,
+    Extension(user_id): Extension<i32>,
+) -> Result<Json<LotteryEntry>, AppError> {
+    let entry = lottery_service::get_my_entry(&app_state.db_pool, lottery_id, user_id).await?;
+    Ok(Json(entry))
+}
+
+/// Handler for a user to withdraw from a lottery.
+/// DELETE /api/lotteries/:lottery_id/registration
+#[tracing::instrument(skip(app_state))]
+pub async fn withdraw(
+    State(app_state): State<AppState>,
+    Path(lottery_id): Path<i32>,
+    Extension(user_id): Extension<i32>,
+) -> Result<StatusCode, AppError> {
+    lottery_service::withdraw(&app_state.db_pool, lottery_id, user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Handler for an organizer to run a lottery's draw.
+/// POST /api/lotteries/:lottery_id/draw
+#[tracing::instrument(skip(app_state))]
+pub async fn draw(
+    State(app_state): State<AppState>,
+    Path(lottery_id): Path<i32>,
+    Extension(user_id): Extension<i32>,
+) -> Result<Json<LotteryResults>, AppError> {
+    let results = lottery_service::draw(&app_state.db_pool, lottery_id, user_id).await?;
+    Ok(Json(results))
+}
+
+/// Handler for a winner to check out their lottery tickets.
+/// POST /api/lotteries/:lottery_id/checkout
+#[tracing::instrument(skip(app_state))]
+pub async fn checkout(
+    State(app_state): State<AppState>,
+    Path(lottery_id): Path<i32>,
+    Extension(user_id): Extension<i32>,
+) -> Result<(StatusCode, Json<CreateOrderResponse>), AppError> {
+    let (order, stripe_client_secret) = order_service::create_lottery_order(
+        &app_state.db_pool,
+        app_state.payment_provider.as_ref(),
+        user_id,
+        lottery_id,
+    )
+    .await?;
+    Ok((StatusCode::CREATED, Json(CreateOrderResponse { order, stripe_client_secret })))
+}
@@ -6,23 +6,36 @@ use crate::{
 };
 use axum::{
     middleware,
-    routing::{delete, get, patch, post},
+    routing::{delete, get, patch, post, put},
     Router,
 };
 
 // Declare all the handler modules we've created.
+pub mod access_code_handler;
+pub mod addon_handler;
 pub mod attraction_handler;
 pub mod auth_handler;
+pub mod cart_handler;
 pub mod category_handler;
 pub mod csrf_handler;
+pub mod dispute_handler;
 pub mod event_handler;
+pub mod group_booking_handler;
+pub mod hold_handler;
+pub mod lottery_handler;
 pub mod order_handler;
 pub mod payment_handler;
 pub mod pricing_handler;
+pub mod reconciliation_handler;
 pub mod seating_handler;
+pub mod settlement_handler;
+pub mod store_credit_handler;
 pub mod ticket_handler;
 pub mod user_handler;
 pub mod venue_handler;
+pub mod waiting_room_handler;
+pub mod waitlist_handler;
+pub mod websocket_handler;
 pub mod organizer_handler; // <-- ADD the new handler module
 
 /// Assembles the master router for all API endpoints.
@@ -34,9 +47,19 @@ pub fn api_router() -> Router<AppState> {
         .route("/events/:id", get(event_handler::get_event_by_id))
         .route("/events/:event_id/offers", get(pricing_handler::list_public_offers_for_event))
         .route("/events/:event_id/seat-map", get(seating_handler::get_seat_map_for_event))
+        .route("/events/:event_id/seat-map.svg", get(seating_handler::get_seat_map_svg))
+        .route("/events/:event_id/seat-map/ws", get(websocket_handler::seat_map_ws_handler))
+        .route("/events/:event_id/waiting-room", get(waiting_room_handler::get_room))
+        .route("/events/:event_id/lotteries", get(lottery_handler::list_lotteries))
+        .route("/lotteries/:lottery_id", get(lottery_handler::get_lottery))
+        .route("/lotteries/:lottery_id/results", get(lottery_handler::get_results))
+        .route("/events/:event_id/addons", get(addon_handler::list_addon_items))
+        .route("/offers/:offer_id/components", get(pricing_handler::list_bundle_components))
         // Venues
         .route("/venues", get(venue_handler::list_venues))
         .route("/venues/:id", get(venue_handler::get_venue_by_id))
+        .route("/venues/:id/seating-charts", get(seating_handler::list_charts_for_venue))
+        .route("/seating-charts/:chart_id", get(seating_handler::get_chart_layout))
         // Categories
         .route("/segments", get(category_handler::list_segments))
         .route("/segments/:id/genres", get(category_handler::list_genres_by_segment))
@@ -56,9 +79,30 @@ pub fn api_router() -> Router<AppState> {
         // User Profile & Tickets
         .route("/auth/me", get(auth_handler::get_me))
         .route("/me/tickets", get(ticket_handler::get_my_tickets))
+        .route("/me/vouchers", get(addon_handler::list_my_vouchers))
         
         // Orders (Customer action)
         .route("/orders", post(order_handler::create_order))
+        .route("/orders/:order_id", get(order_handler::get_order))
+
+        // Cart (holds seats and tickets across events until checkout)
+        .route("/cart", get(cart_handler::get_cart))
+        .route("/cart", delete(cart_handler::clear_cart))
+        .route("/cart/items", post(cart_handler::add_item))
+        .route("/cart/items/:item_id", delete(cart_handler::remove_item))
+        .route("/cart/checkout", post(cart_handler::checkout))
+
+        // Gift cards and store credit
+        .route("/gift-cards", post(store_credit_handler::purchase_gift_card))
+        .route("/gift-cards/redeem", post(store_credit_handler::redeem_gift_card))
+        .route("/me/gift-cards", get(store_credit_handler::list_my_gift_cards))
+        .route("/me/credit", get(store_credit_handler::get_my_credit))
+
+        // Group bookings (each member pays their own share)
+        .route("/events/:event_id/group-bookings", post(group_booking_handler::create_group_booking))
+        .route("/me/group-bookings", get(group_booking_handler::list_my_group_bookings))
+        .route("/group-bookings/:invite_token", get(group_booking_handler::get_group_booking))
+        .route("/group-bookings/:invite_token/payments", post(group_booking_handler::pay_shares))
         
         // Event Management (Organizer role)
         .route("/events", post(event_handler::create_event))
@@ -69,18 +113,95 @@ pub fn api_router() -> Router<AppState> {
         .route("/events/:event_id/attractions", post(attraction_handler::add_attraction_to_event))
         .route("/events/:event_id/attractions/:attraction_id", delete(attraction_handler::remove_attraction_from_event))
         .route("/events/:event_id/tiers", post(pricing_handler::create_ticket_tier))
+        .route("/events/:event_id/seating", post(seating_handler::initialize_event_seating))
+        .route("/events/:event_id/seats", patch(seating_handler::update_event_seats))
+        .route("/tiers/:tier_id", patch(pricing_handler::update_ticket_tier))
         .route("/tiers/:tier_id/offers", post(pricing_handler::create_offer))
+        .route("/offers/:offer_id", patch(pricing_handler::update_offer))
+        .route("/offers/:offer_id/pricing-rules", get(pricing_handler::get_offer_pricing))
+        .route("/offers/:offer_id/pricing-rules", put(pricing_handler::set_offer_pricing))
+
+        // Bundles and the add-ons they include
+        .route("/events/:event_id/addons", post(addon_handler::create_addon_item))
+        .route("/offers/:offer_id/components", put(pricing_handler::set_bundle_components))
+
+        // Presale access codes
+        .route("/events/:event_id/offers/unlock", post(access_code_handler::unlock_offers))
+        .route("/offers/:offer_id/access-codes", post(access_code_handler::generate_codes))
+        .route("/offers/:offer_id/access-codes", get(access_code_handler::list_codes))
+
+        // Waiting rooms for high-demand on-sales
+        .route("/events/:event_id/waiting-room", put(waiting_room_handler::configure_room))
+        .route("/events/:event_id/waiting-room", delete(waiting_room_handler::remove_room))
+        .route("/events/:event_id/queue", post(waiting_room_handler::join_queue))
+        .route("/events/:event_id/queue", get(waiting_room_handler::get_queue_status))
+        .route("/events/:event_id/queue/ws", get(websocket_handler::queue_ws_handler))
+
+        // Waitlists for sold-out events
+        .route("/events/:event_id/waitlist", post(waitlist_handler::join_waitlist))
+        .route("/me/waitlist", get(waitlist_handler::list_my_entries))
+        .route("/waitlist/:entry_id", delete(waitlist_handler::leave_waitlist))
+        .route("/waitlist/claims/:token", get(waitlist_handler::get_claim))
+        .route("/waitlist/claims/:token/checkout", post(waitlist_handler::checkout_claim))
+
+        // Lottery sales for very high-demand events
+        .route("/events/:event_id/lotteries", post(lottery_handler::create_lottery))
+        .route("/lotteries/:lottery_id/registration", put(lottery_handler::register))
+        .route("/lotteries/:lottery_id/registration", get(lottery_handler::get_my_entry))
+        .route("/lotteries/:lottery_id/registration", delete(lottery_handler::withdraw))
+        .route("/lotteries/:lottery_id/draw", post(lottery_handler::draw))
+        .route("/lotteries/:lottery_id/checkout", post(lottery_handler::checkout))
+
+        // Seat holds and comps (Organizer role)
+        .route("/events/:event_id/holds", post(hold_handler::create_hold))
+        .route("/events/:event_id/holds", get(hold_handler::list_holds))
+        .route("/holds/:hold_id", patch(hold_handler::update_hold))
+        .route("/holds/:hold_id/seats", post(hold_handler::add_seats))
+        .route("/holds/:hold_id/release", post(hold_handler::release_hold))
+        .route("/holds/:hold_id/comps", post(hold_handler::issue_comps))
 
         // --- ADDED: Organizer-specific routes ---
-        .route("/organizer/stripe/onboarding-link", post(organizer_handler::get_onboarding_link));
+        .route("/organizer/stripe/onboarding-link", post(organizer_handler::get_onboarding_link))
+
+        // Organizer settlement reporting
+        .route("/organizer/settlements/sync", post(settlement_handler::sync_settlements))
+        .route("/organizer/statements/events", get(settlement_handler::list_event_statements))
+        .route("/organizer/statements/events.csv", get(settlement_handler::export_event_statements_csv))
+        .route("/organizer/statements/daily", get(settlement_handler::list_daily_statements))
+        .route("/organizer/statements/daily.csv", get(settlement_handler::export_daily_statements_csv))
+        .route("/organizer/events/:event_id/statement", get(settlement_handler::get_event_statement))
+        .route("/organizer/payouts", get(settlement_handler::list_payouts))
+
+        // Best-available seat selection
+        .route("/events/:event_id/best-available", post(seating_handler::find_best_available))
+
+        // Seating chart builder (admins and venue managers)
+        .route("/venues/:id/seating-charts", post(seating_handler::create_chart))
+        .route("/venues/:id/seating-charts/import", post(seating_handler::import_chart))
+        .route("/venues/:id/seating-charts/generate", post(seating_handler::generate_chart))
+        .route("/seating-charts/preview", post(seating_handler::preview_generated_layout))
+        .route("/seating-charts/:chart_id", patch(seating_handler::update_chart))
+        .route("/seating-charts/:chart_id", delete(seating_handler::delete_chart))
+        .route("/seating-charts/:chart_id/layout", put(seating_handler::replace_layout));
 
 
     // --- Admin-Only Routes (Auth and Admin Role required) ---
     let admin_routes = Router::new()
         .route("/venues", post(venue_handler::create_venue))
+        .route("/venues/:id/managers", post(venue_handler::add_venue_manager))
+        .route("/venues/:id/managers", get(venue_handler::list_venue_managers))
+        .route("/venues/:id/managers/:user_id", delete(venue_handler::remove_venue_manager))
         .route("/segments", post(category_handler::create_segment))
         .route("/segments/:id/genres", post(category_handler::create_genre))
         .route("/genres/:id/sub-genres", post(category_handler::create_sub_genre))
+        // --- Disputes ---
+        .route("/disputes", get(dispute_handler::list_disputes))
+        // --- Payment Reconciliation ---
+        .route("/reconciliation/runs", post(reconciliation_handler::run_reconciliation))
+        .route("/reconciliation/runs", get(reconciliation_handler::list_runs))
+        .route("/reconciliation/runs/:run_id/discrepancies", get(reconciliation_handler::list_discrepancies))
+        // --- Refunds ---
+        .route("/orders/:order_id/refund-as-credit", post(store_credit_handler::refund_order_as_credit))
         // You would also need an admin login endpoint, e.g., /admin/login in auth_routes
         .layer(middleware::from_fn(admin_guard));
 
@@ -1,32 +1,40 @@
 use crate::{
     errors::AppError,
-    models::{CreateOrderPayload},
-    service::order_service::{self, StripeClient}, // Assuming StripeClient is in order_service
+    models::{CreateOrderPayload, OrderReceipt},
+    service::order_service,
     AppState,
 };
 use crate::models::order::CreateOrderResponse;
 use axum::{
-    extract::{Extension, State},
-    http::StatusCode,
+    extract::{Extension, Path, State},
+    http::{HeaderMap, StatusCode},
     Json,
 };
+use uuid::Uuid;
+
+/// The header carrying a waiting room admission token.
+pub const ADMISSION_TOKEN_HEADER: &str = "x-admission-token";
 
 /// Handler to initiate the checkout process.
 /// Creates a pending order, locks inventory, and returns a Stripe client secret.
+/// Events behind a waiting room also need the admission token in `X-Admission-Token`.
 /// POST /api/orders
-#[tracing::instrument(skip(app_state, payload))]
+#[tracing::instrument(skip(app_state, headers, payload))]
 pub async fn create_order(
     State(app_state): State<AppState>,
     Extension(user_id): Extension<i32>,
+    headers: HeaderMap,
     Json(payload): Json<CreateOrderPayload>,
 ) -> Result<(StatusCode, Json<CreateOrderResponse>), AppError> {
-    // In a real app, the Stripe client would be part of AppState.
-    // let stripe_client = &app_state.stripe_client;
-    let stripe_client = StripeClient; // Using the placeholder for now
-
-    let (order, stripe_client_secret) =
-        order_service::create_order(&app_state.db_pool, &stripe_client, user_id, &payload)
-            .await?;
+    let admission_token = headers.get(ADMISSION_TOKEN_HEADER).and_then(|value| value.to_str().ok());
+    let (order, stripe_client_secret) = order_service::create_order(
+        &app_state.db_pool,
+        app_state.payment_provider.as_ref(),
+        user_id,
+        &payload,
+        admission_token,
+    )
+    .await?;
 
     let response = CreateOrderResponse {
         order,
@@ -34,4 +42,15 @@ pub async fn create_order(
     };
 
     Ok((StatusCode::CREATED, Json(response)))
-}
\ No newline at end of file
+}
+/// Handler for a user to see one of their orders as a receipt.
+/// GET /api/orders/:order_id
+#[tracing::instrument(skip(app_state))]
+pub async fn get_order(
+    State(app_state): State<AppState>,
+    Path(order_id): Path<Uuid>,
+    Extension(user_id): Extension<i32>,
+) -> Result<Json<OrderReceipt>, AppError> {
+    let receipt = order_service::get_order_receipt(&app_state.db_pool, order_id, user_id).await?;
+    Ok(Json(receipt))
+}
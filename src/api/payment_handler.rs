@@ -2,7 +2,7 @@
 
 use crate::config::CONFIG;
 use crate::errors::AppError;
-use crate::service::payment_service;
+use crate::service::{dispute_service, payment_service};
 use crate::AppState;
 use axum::{
     extract::State,
@@ -50,7 +50,8 @@ pub async fn stripe_webhook_handler(
                 // The service call is still async.
                 payment_service::finalize_order_on_payment_success(
                     &app_state.db_pool,
-                    &payment_intent.id.to_string(),
+                    app_state.payment_provider.as_ref(),
+                    payment_intent.id.as_str(),
                 )
                 .await?;
             } else {
@@ -63,6 +64,25 @@ pub async fn stripe_webhook_handler(
                 ));
             }
         }
+        EventType::ChargeDisputeCreated
+        | EventType::ChargeDisputeUpdated
+        | EventType::ChargeDisputeClosed
+        | EventType::ChargeDisputeFundsWithdrawn
+        | EventType::ChargeDisputeFundsReinstated => {
+            if let EventObject::Dispute(dispute) = event.data.object {
+                tracing::info!("Received {:?} for dispute {}", event.type_, dispute.id);
+                dispute_service::handle_dispute_event(&app_state.db_pool, &dispute).await?;
+            } else {
+                tracing::warn!(
+                    "{:?} event received, but object was not a Dispute: {:?}",
+                    event.type_,
+                    event.data.object
+                );
+                return Err(AppError::BadRequest(
+                    "Webhook data object type mismatch.".to_string(),
+                ));
+            }
+        }
         other_event_type => {
             tracing::info!("Received unhandled Stripe event type: {:?}", other_event_type);
         }
@@ -1,6 +1,9 @@
 use crate::{
     errors::AppError,
-    models::{CreateOfferPayload, CreateTicketTierPayload, Offer, TicketTier},
+    models::{
+        BundleComponent, CreateOfferPayload, CreateTicketTierPayload, Offer, OfferPricing, SetBundleComponentsPayload,
+        SetOfferPricingPayload, TicketTier, UpdateOfferPayload, UpdateTicketTierPayload,
+    },
     service::pricing_service,
     AppState,
 };
@@ -36,6 +39,19 @@ pub async fn list_tiers_for_event(
     Ok(Json(tiers))
 }
 
+/// Handler for an organizer to update a ticket tier of their event.
+/// PATCH /api/tiers/:tier_id
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn update_ticket_tier(
+    State(app_state): State<AppState>,
+    Path(tier_id): Path<i32>,
+    Extension(organizer_id): Extension<i32>,
+    Json(payload): Json<UpdateTicketTierPayload>,
+) -> Result<Json<TicketTier>, AppError> {
+    let tier = pricing_service::update_ticket_tier(&app_state.db_pool, tier_id, organizer_id, &payload).await?;
+    Ok(Json(tier))
+}
+
 // --- Offer Handlers ---
 
 /// Handler for an organizer to create a sales offer for a ticket tier.
@@ -51,6 +67,69 @@ pub async fn create_offer(
     Ok((StatusCode::CREATED, Json(offer)))
 }
 
+/// Handler for an organizer to update a sales offer.
+/// PATCH /api/offers/:offer_id
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn update_offer(
+    State(app_state): State<AppState>,
+    Path(offer_id): Path<i32>,
+    Extension(organizer_id): Extension<i32>,
+    Json(payload): Json<UpdateOfferPayload>,
+) -> Result<Json<Offer>, AppError> {
+    let offer = pricing_service::update_offer(&app_state.db_pool, offer_id, organizer_id, &payload).await?;
+    Ok(Json(offer))
+}
+
+/// Handler for an organizer to see an offer's pricing rules and current price.
+/// GET /api/offers/:offer_id/pricing-rules
+#[tracing::instrument(skip(app_state))]
+pub async fn get_offer_pricing(
+    State(app_state): State<AppState>,
+    Path(offer_id): Path<i32>,
+    Extension(organizer_id): Extension<i32>,
+) -> Result<Json<OfferPricing>, AppError> {
+    let pricing = pricing_service::get_offer_pricing(&app_state.db_pool, offer_id, organizer_id).await?;
+    Ok(Json(pricing))
+}
+
+/// Handler for an organizer to replace an offer's pricing rules and bounds.
+/// PUT /api/offers/:offer_id/pricing-rules
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn set_offer_pricing(
+    State(app_state): State<AppState>,
+    Path(offer_id): Path<i32>,
+    Extension(organizer_id): Extension<i32>,
+    Json(payload): Json<SetOfferPricingPayload>,
+) -> Result<Json<OfferPricing>, AppError> {
+    let pricing = pricing_service::set_offer_pricing(&app_state.db_pool, offer_id, organizer_id, &payload).await?;
+    Ok(Json(pricing))
+}
+
+/// Handler to list what each package of a bundle offer contains.
+/// GET /api/offers/:offer_id/components
+#[tracing::instrument(skip(app_state))]
+pub async fn list_bundle_components(
+    State(app_state): State<AppState>,
+    Path(offer_id): Path<i32>,
+) -> Result<Json<Vec<BundleComponent>>, AppError> {
+    let components = pricing_service::list_bundle_components(&app_state.db_pool, offer_id).await?;
+    Ok(Json(components))
+}
+
+/// Handler for an organizer to turn an offer into a bundle, or change its components.
+/// PUT /api/offers/:offer_id/components
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn set_bundle_components(
+    State(app_state): State<AppState>,
+    Path(offer_id): Path<i32>,
+    Extension(organizer_id): Extension<i32>,
+    Json(payload): Json<SetBundleComponentsPayload>,
+) -> Result<Json<Vec<BundleComponent>>, AppError> {
+    let components =
+        pricing_service::set_bundle_components(&app_state.db_pool, offer_id, organizer_id, &payload).await?;
+    Ok(Json(components))
+}
+
 /// Handler to list all publicly available offers for an event.
 /// This is what customers will see when they view an event page.
 /// GET /api/events/:event_id/offers
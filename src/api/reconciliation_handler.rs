@@ -0,0 +1,49 @@
+use crate::{
+    errors::AppError,
+    models::{ReconciliationDiscrepancy, ReconciliationRun},
+    service::reconciliation_service,
+    AppState,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Duration;
+
+/// Handler for an admin to run payment reconciliation now instead of waiting for the nightly job.
+/// POST /api/reconciliation/runs
+#[tracing::instrument(skip(app_state))]
+pub async fn run_reconciliation(
+    State(app_state): State<AppState>,
+) -> Result<(StatusCode, Json<ReconciliationRun>), AppError> {
+    let window = Duration::hours(reconciliation_service::DEFAULT_WINDOW_HOURS);
+    let run = reconciliation_service::run_reconciliation(
+        &app_state.db_pool,
+        app_state.payment_provider.as_ref(),
+        window,
+    )
+    .await?;
+    Ok((StatusCode::CREATED, Json(run)))
+}
+
+/// Handler to list recent reconciliation runs.
+/// GET /api/reconciliation/runs
+#[tracing::instrument(skip(app_state))]
+pub async fn list_runs(
+    State(app_state): State<AppState>,
+) -> Result<Json<Vec<ReconciliationRun>>, AppError> {
+    let runs = reconciliation_service::list_runs(&app_state.db_pool).await?;
+    Ok(Json(runs))
+}
+
+/// Handler to get the discrepancy report of a reconciliation run.
+/// GET /api/reconciliation/runs/:run_id/discrepancies
+#[tracing::instrument(skip(app_state))]
+pub async fn list_discrepancies(
+    State(app_state): State<AppState>,
+    Path(run_id): Path<i32>,
+) -> Result<Json<Vec<ReconciliationDiscrepancy>>, AppError> {
+    let discrepancies = reconciliation_service::list_discrepancies(&app_state.db_pool, run_id).await?;
+    Ok(Json(discrepancies))
+}
@@ -1,6 +1,17 @@
-use crate::{errors::AppError, models::SeatMapInfo, service::seating_service, AppState};
+use crate::{
+    errors::AppError,
+    models::{
+        BestAvailablePayload, BestAvailableResult, CreateSeatingChartPayload, GenerateLayoutPayload, RenderedSeatMap, SeatMapSvgQuery, GenerateSeatingChartPayload, ImportSeatingChartPayload, SeatMapInfo, SeatingChart, SeatingChartLayout,
+        SeatingLayout, UpdateEventSeatsPayload, UpdateEventSeatsResult, UpdateSeatingChartPayload, InitializeEventSeatingPayload,
+        EventSeatingReport,
+    },
+    service::{seat_selection_service, seating_service},
+    AppState,
+};
 use axum::{
-    extract::{Path, State},
+    extract::{Extension, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 
@@ -13,4 +24,196 @@ pub async fn get_seat_map_for_event(
 ) -> Result<Json<Vec<SeatMapInfo>>, AppError> {
     let seat_map = seating_service::get_seat_map_for_event(&app_state.db_pool, event_id).await?;
     Ok(Json(seat_map))
-}
\ No newline at end of file
+}
+
+/// Handler for the public to fetch an event's seat map rendered as SVG.
+/// Supports `?highlight=12,13` to mark seats as selected, and ETag revalidation.
+/// GET /api/events/:event_id/seat-map.svg
+#[tracing::instrument(skip(app_state, headers))]
+pub async fn get_seat_map_svg(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    Query(query): Query<SeatMapSvgQuery>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let if_none_match = headers.get(header::IF_NONE_MATCH).and_then(|v| v.to_str().ok());
+    let rendered =
+        seating_service::get_seat_map_svg(&app_state.db_pool, event_id, query.highlight.as_deref(), if_none_match)
+            .await?;
+
+    // Clients may keep the map, but must revalidate it as seats change all the time.
+    let response = match rendered {
+        RenderedSeatMap::NotModified { etag } => (
+            StatusCode::NOT_MODIFIED,
+            [(header::ETAG, etag), (header::CACHE_CONTROL, "no-cache".to_string())],
+        )
+            .into_response(),
+        RenderedSeatMap::Svg { etag, body } => (
+            [
+                (header::CONTENT_TYPE, "image/svg+xml".to_string()),
+                (header::ETAG, etag),
+                (header::CACHE_CONTROL, "no-cache".to_string()),
+            ],
+            body,
+        )
+            .into_response(),
+    };
+    Ok(response)
+}
+
+/// Handler to find, and optionally lock, the best available seats of an event.
+/// POST /api/events/:event_id/best-available
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn find_best_available(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    Extension(user_id): Extension<i32>,
+    Json(payload): Json<BestAvailablePayload>,
+) -> Result<Json<BestAvailableResult>, AppError> {
+    let result =
+        seat_selection_service::find_best_available(&app_state.db_pool, event_id, user_id, &payload).await?;
+    Ok(Json(result))
+}
+
+/// Handler for an organizer to set up (or re-sync) the seats of their event from a seating chart.
+/// POST /api/events/:event_id/seating
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn initialize_event_seating(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    Extension(organizer_id): Extension<i32>,
+    Json(payload): Json<InitializeEventSeatingPayload>,
+) -> Result<Json<EventSeatingReport>, AppError> {
+    let report = seating_service::initialize_event_seating(&app_state.db_pool, event_id, organizer_id, &payload).await?;
+    Ok(Json(report))
+}
+
+/// Handler for an organizer to re-tier or kill seats of their event in bulk.
+/// PATCH /api/events/:event_id/seats
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn update_event_seats(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    Extension(organizer_id): Extension<i32>,
+    Json(payload): Json<UpdateEventSeatsPayload>,
+) -> Result<Json<UpdateEventSeatsResult>, AppError> {
+    let result = seating_service::update_event_seats(&app_state.db_pool, event_id, organizer_id, &payload).await?;
+    Ok(Json(result))
+}
+
+// --- Seating Chart Builder Handlers ---
+
+/// Handler to list the seating charts of a venue.
+/// GET /api/venues/:id/seating-charts
+#[tracing::instrument(skip(app_state))]
+pub async fn list_charts_for_venue(
+    State(app_state): State<AppState>,
+    Path(venue_id): Path<i32>,
+) -> Result<Json<Vec<SeatingChart>>, AppError> {
+    let charts = seating_service::list_charts_for_venue(&app_state.db_pool, venue_id).await?;
+    Ok(Json(charts))
+}
+
+/// Handler to fetch a seating chart with its sections, rows and seats.
+/// GET /api/seating-charts/:chart_id
+#[tracing::instrument(skip(app_state))]
+pub async fn get_chart_layout(
+    State(app_state): State<AppState>,
+    Path(chart_id): Path<i32>,
+) -> Result<Json<SeatingChartLayout>, AppError> {
+    let layout = seating_service::get_chart_layout(&app_state.db_pool, chart_id).await?;
+    Ok(Json(layout))
+}
+
+/// Handler for an admin or venue manager to create an empty seating chart.
+/// POST /api/venues/:id/seating-charts
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn create_chart(
+    State(app_state): State<AppState>,
+    Path(venue_id): Path<i32>,
+    Extension(user_id): Extension<i32>,
+    Extension(role): Extension<String>,
+    Json(payload): Json<CreateSeatingChartPayload>,
+) -> Result<(StatusCode, Json<SeatingChart>), AppError> {
+    let chart = seating_service::create_chart(&app_state.db_pool, venue_id, user_id, &role, &payload).await?;
+    Ok((StatusCode::CREATED, Json(chart)))
+}
+
+/// Handler for an admin or venue manager to create a seating chart from a whole layout document.
+/// POST /api/venues/:id/seating-charts/import
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn import_chart(
+    State(app_state): State<AppState>,
+    Path(venue_id): Path<i32>,
+    Extension(user_id): Extension<i32>,
+    Extension(role): Extension<String>,
+    Json(payload): Json<ImportSeatingChartPayload>,
+) -> Result<(StatusCode, Json<SeatingChartLayout>), AppError> {
+    let layout = seating_service::import_chart(&app_state.db_pool, venue_id, user_id, &role, &payload).await?;
+    Ok((StatusCode::CREATED, Json(layout)))
+}
+
+/// Handler to preview the layout generated from section specs. Nothing is saved.
+/// POST /api/seating-charts/preview
+#[tracing::instrument(skip(payload))]
+pub async fn preview_generated_layout(
+    Json(payload): Json<GenerateLayoutPayload>,
+) -> Result<Json<SeatingLayout>, AppError> {
+    let layout = seating_service::preview_generated_layout(&payload)?;
+    Ok(Json(layout))
+}
+
+/// Handler for an admin or venue manager to create a seating chart from section specs.
+/// POST /api/venues/:id/seating-charts/generate
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn generate_chart(
+    State(app_state): State<AppState>,
+    Path(venue_id): Path<i32>,
+    Extension(user_id): Extension<i32>,
+    Extension(role): Extension<String>,
+    Json(payload): Json<GenerateSeatingChartPayload>,
+) -> Result<(StatusCode, Json<SeatingChartLayout>), AppError> {
+    let layout = seating_service::generate_chart(&app_state.db_pool, venue_id, user_id, &role, &payload).await?;
+    Ok((StatusCode::CREATED, Json(layout)))
+}
+
+/// Handler to replace the whole layout of a seating chart.
+/// PUT /api/seating-charts/:chart_id/layout
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn replace_layout(
+    State(app_state): State<AppState>,
+    Path(chart_id): Path<i32>,
+    Extension(user_id): Extension<i32>,
+    Extension(role): Extension<String>,
+    Json(payload): Json<SeatingLayout>,
+) -> Result<Json<SeatingChartLayout>, AppError> {
+    let layout = seating_service::replace_layout(&app_state.db_pool, chart_id, user_id, &role, &payload).await?;
+    Ok(Json(layout))
+}
+
+/// Handler to rename a seating chart or change its background.
+/// PATCH /api/seating-charts/:chart_id
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn update_chart(
+    State(app_state): State<AppState>,
+    Path(chart_id): Path<i32>,
+    Extension(user_id): Extension<i32>,
+    Extension(role): Extension<String>,
+    Json(payload): Json<UpdateSeatingChartPayload>,
+) -> Result<Json<SeatingChart>, AppError> {
+    let chart = seating_service::update_chart(&app_state.db_pool, chart_id, user_id, &role, &payload).await?;
+    Ok(Json(chart))
+}
+
+/// Handler to delete a seating chart that no event uses.
+/// DELETE /api/seating-charts/:chart_id
+#[tracing::instrument(skip(app_state))]
+pub async fn delete_chart(
+    State(app_state): State<AppState>,
+    Path(chart_id): Path<i32>,
+    Extension(user_id): Extension<i32>,
+    Extension(role): Extension<String>,
+) -> Result<StatusCode, AppError> {
+    seating_service::delete_chart(&app_state.db_pool, chart_id, user_id, &role).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,142 @@
+use crate::{
+    errors::AppError,
+    models::{DailyStatement, EventStatement, OrganizerPayout, SettlementSyncReport, StatementRangeQuery},
+    service::settlement_service,
+    utils::csv,
+    AppState,
+};
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+/// Handler for an organizer to reconcile their ledger and payouts against Stripe.
+/// POST /api/organizer/settlements/sync
+#[tracing::instrument(skip(app_state))]
+pub async fn sync_settlements(
+    State(app_state): State<AppState>,
+    Extension(organizer_id): Extension<i32>,
+) -> Result<Json<SettlementSyncReport>, AppError> {
+    let report =
+        settlement_service::sync_with_stripe(&app_state.db_pool, &app_state.stripe_client, organizer_id)
+            .await?;
+    Ok(Json(report))
+}
+
+/// Handler to list the settlement statement of each of the organizer's events.
+/// GET /api/organizer/statements/events
+#[tracing::instrument(skip(app_state))]
+pub async fn list_event_statements(
+    State(app_state): State<AppState>,
+    Extension(organizer_id): Extension<i32>,
+) -> Result<Json<Vec<EventStatement>>, AppError> {
+    let statements = settlement_service::list_event_statements(&app_state.db_pool, organizer_id).await?;
+    Ok(Json(statements))
+}
+
+/// Handler to export the per-event statements as CSV.
+/// GET /api/organizer/statements/events.csv
+#[tracing::instrument(skip(app_state))]
+pub async fn export_event_statements_csv(
+    State(app_state): State<AppState>,
+    Extension(organizer_id): Extension<i32>,
+) -> Result<Response, AppError> {
+    let statements = settlement_service::list_event_statements(&app_state.db_pool, organizer_id).await?;
+    let body = csv::to_csv(
+        &["event_id", "event_title", "gross_sales", "refunds", "platform_fees", "stripe_fees", "disputes", "net_due"],
+        statements.iter().map(|s| {
+            vec![
+                s.event_id.to_string(),
+                s.event_title.clone(),
+                s.gross_sales.to_string(),
+                s.refunds.to_string(),
+                s.platform_fees.to_string(),
+                s.stripe_fees.to_string(),
+                s.disputes.to_string(),
+                s.net_due.to_string(),
+            ]
+        }),
+    );
+    Ok(csv_response("event-statements.csv", body))
+}
+
+/// Handler to get the settlement statement of a single event.
+/// GET /api/organizer/events/:event_id/statement
+#[tracing::instrument(skip(app_state))]
+pub async fn get_event_statement(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    Extension(organizer_id): Extension<i32>,
+) -> Result<Json<EventStatement>, AppError> {
+    let statement =
+        settlement_service::get_event_statement(&app_state.db_pool, event_id, organizer_id).await?;
+    Ok(Json(statement))
+}
+
+/// Handler to list the organizer's daily statements.
+/// GET /api/organizer/statements/daily?from=YYYY-MM-DD&to=YYYY-MM-DD
+#[tracing::instrument(skip(app_state))]
+pub async fn list_daily_statements(
+    State(app_state): State<AppState>,
+    Extension(organizer_id): Extension<i32>,
+    Query(range): Query<StatementRangeQuery>,
+) -> Result<Json<Vec<DailyStatement>>, AppError> {
+    let statements =
+        settlement_service::list_daily_statements(&app_state.db_pool, organizer_id, range.from, range.to)
+            .await?;
+    Ok(Json(statements))
+}
+
+/// Handler to export the daily statements as CSV.
+/// GET /api/organizer/statements/daily.csv?from=YYYY-MM-DD&to=YYYY-MM-DD
+#[tracing::instrument(skip(app_state))]
+pub async fn export_daily_statements_csv(
+    State(app_state): State<AppState>,
+    Extension(organizer_id): Extension<i32>,
+    Query(range): Query<StatementRangeQuery>,
+) -> Result<Response, AppError> {
+    let statements =
+        settlement_service::list_daily_statements(&app_state.db_pool, organizer_id, range.from, range.to)
+            .await?;
+    let body = csv::to_csv(
+        &["date", "gross_sales", "refunds", "platform_fees", "stripe_fees", "disputes", "net_due", "paid_out"],
+        statements.iter().map(|s| {
+            vec![
+                s.date.to_string(),
+                s.gross_sales.to_string(),
+                s.refunds.to_string(),
+                s.platform_fees.to_string(),
+                s.stripe_fees.to_string(),
+                s.disputes.to_string(),
+                s.net_due.to_string(),
+                s.paid_out.to_string(),
+            ]
+        }),
+    );
+    Ok(csv_response("daily-statements.csv", body))
+}
+
+/// Handler to list the payouts made to the organizer's bank account.
+/// GET /api/organizer/payouts
+#[tracing::instrument(skip(app_state))]
+pub async fn list_payouts(
+    State(app_state): State<AppState>,
+    Extension(organizer_id): Extension<i32>,
+) -> Result<Json<Vec<OrganizerPayout>>, AppError> {
+    let payouts = settlement_service::list_payouts(&app_state.db_pool, organizer_id).await?;
+    Ok(Json(payouts))
+}
+
+/// Wraps a CSV body in a downloadable response.
+fn csv_response(filename: &str, body: String) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        body,
+    )
+        .into_response()
+}
@@ -0,0 +1,77 @@
+use crate::{
+    errors::AppError,
+    models::{order::CreateOrderResponse, GiftCard, PurchaseGiftCardPayload, RedeemGiftCardPayload, StoreCredit},
+    service::{order_service, store_credit_service},
+    AppState,
+};
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    Json,
+};
+use uuid::Uuid;
+
+/// Handler to buy a gift card. Returns its pending order and a Stripe client secret;
+/// the card can be redeemed once the payment succeeds.
+/// POST /api/gift-cards
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn purchase_gift_card(
+    State(app_state): State<AppState>,
+    Extension(user_id): Extension<i32>,
+    Json(payload): Json<PurchaseGiftCardPayload>,
+) -> Result<(StatusCode, Json<CreateOrderResponse>), AppError> {
+    let (order, stripe_client_secret) = order_service::create_gift_card_order(
+        &app_state.db_pool,
+        app_state.payment_provider.as_ref(),
+        user_id,
+        &payload,
+    )
+    .await?;
+    Ok((StatusCode::CREATED, Json(CreateOrderResponse { order, stripe_client_secret })))
+}
+
+/// Handler to list the gift cards the user bought.
+/// GET /api/me/gift-cards
+#[tracing::instrument(skip(app_state))]
+pub async fn list_my_gift_cards(
+    State(app_state): State<AppState>,
+    Extension(user_id): Extension<i32>,
+) -> Result<Json<Vec<GiftCard>>, AppError> {
+    let cards = store_credit_service::list_my_gift_cards(&app_state.db_pool, user_id).await?;
+    Ok(Json(cards))
+}
+
+/// Handler to redeem a gift card onto the user's account as store credit.
+/// POST /api/gift-cards/redeem
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn redeem_gift_card(
+    State(app_state): State<AppState>,
+    Extension(user_id): Extension<i32>,
+    Json(payload): Json<RedeemGiftCardPayload>,
+) -> Result<Json<StoreCredit>, AppError> {
+    let credit = store_credit_service::redeem(&app_state.db_pool, user_id, &payload).await?;
+    Ok(Json(credit))
+}
+
+/// Handler for the user's store credit balance and ledger.
+/// GET /api/me/credit
+#[tracing::instrument(skip(app_state))]
+pub async fn get_my_credit(
+    State(app_state): State<AppState>,
+    Extension(user_id): Extension<i32>,
+) -> Result<Json<StoreCredit>, AppError> {
+    let credit = store_credit_service::get_store_credit(&app_state.db_pool, user_id).await?;
+    Ok(Json(credit))
+}
+
+/// Handler for an admin to refund a completed order as store credit.
+/// Returns the buyer's store credit after the refund.
+/// POST /api/orders/:order_id/refund-as-credit
+#[tracing::instrument(skip(app_state))]
+pub async fn refund_order_as_credit(
+    State(app_state): State<AppState>,
+    Path(order_id): Path<Uuid>,
+) -> Result<Json<StoreCredit>, AppError> {
+    let credit = store_credit_service::refund_order_as_credit(&app_state.db_pool, order_id).await?;
+    Ok(Json(credit))
+}
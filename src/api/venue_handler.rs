@@ -1,6 +1,6 @@
 use crate::{
     errors::AppError,
-    models::{CreateVenuePayload, Venue},
+    models::{AssignVenueManagerPayload, CreateVenuePayload, Venue, VenueManager},
     service::venue_service,
     AppState,
 };
@@ -36,4 +36,38 @@ pub async fn get_venue_by_id(
 pub async fn list_venues(State(app_state): State<AppState>) -> Result<Json<Vec<Venue>>, AppError> {
     let venues = venue_service::list_all(&app_state.db_pool).await?;
     Ok(Json(venues))
-}
\ No newline at end of file
+}
+
+/// Handler for an admin to let a user manage a venue's seating charts.
+/// POST /api/venues/:id/managers
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn add_venue_manager(
+    State(app_state): State<AppState>,
+    Path(venue_id): Path<i32>,
+    Json(payload): Json<AssignVenueManagerPayload>,
+) -> Result<(StatusCode, Json<VenueManager>), AppError> {
+    let manager = venue_service::add_manager(&app_state.db_pool, venue_id, payload.user_id).await?;
+    Ok((StatusCode::CREATED, Json(manager)))
+}
+
+/// Handler for an admin to list the managers of a venue.
+/// GET /api/venues/:id/managers
+#[tracing::instrument(skip(app_state))]
+pub async fn list_venue_managers(
+    State(app_state): State<AppState>,
+    Path(venue_id): Path<i32>,
+) -> Result<Json<Vec<VenueManager>>, AppError> {
+    let managers = venue_service::list_managers(&app_state.db_pool, venue_id).await?;
+    Ok(Json(managers))
+}
+
+/// Handler for an admin to revoke a user's management of a venue.
+/// DELETE /api/venues/:id/managers/:user_id
+#[tracing::instrument(skip(app_state))]
+pub async fn remove_venue_manager(
+    State(app_state): State<AppState>,
+    Path((venue_id, user_id)): Path<(i32, i32)>,
+) -> Result<StatusCode, AppError> {
+    venue_service::remove_manager(&app_state.db_pool, venue_id, user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
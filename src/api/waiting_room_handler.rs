@@ -0,0 +1,72 @@
+use crate::{
+    errors::AppError,
+    models::{ConfigureWaitingRoomPayload, QueueStatus, WaitingRoom, WaitingRoomInfo},
+    service::waiting_room_service,
+    AppState,
+};
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    Json,
+};
+
+/// Handler for an organizer to put a waiting room in front of their event, or change it.
+/// PUT /api/events/:event_id/waiting-room
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn configure_room(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    Extension(organizer_id): Extension<i32>,
+    Json(payload): Json<ConfigureWaitingRoomPayload>,
+) -> Result<Json<WaitingRoom>, AppError> {
+    let room = waiting_room_service::configure_room(&app_state.db_pool, event_id, organizer_id, &payload).await?;
+    Ok(Json(room))
+}
+
+/// Handler to remove an event's waiting room.
+/// DELETE /api/events/:event_id/waiting-room
+#[tracing::instrument(skip(app_state))]
+pub async fn remove_room(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    Extension(organizer_id): Extension<i32>,
+) -> Result<StatusCode, AppError> {
+    waiting_room_service::remove_room(&app_state.db_pool, event_id, organizer_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Public handler to see an event's waiting room: when it opens and how far it has got.
+/// GET /api/events/:event_id/waiting-room
+#[tracing::instrument(skip(app_state))]
+pub async fn get_room(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+) -> Result<Json<WaitingRoomInfo>, AppError> {
+    let room = waiting_room_service::get_room_info(&app_state.db_pool, event_id).await?;
+    Ok(Json(room))
+}
+
+/// Handler for a user to join an event's queue.
+/// POST /api/events/:event_id/queue
+#[tracing::instrument(skip(app_state))]
+pub async fn join_queue(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    Extension(user_id): Extension<i32>,
+) -> Result<Json<QueueStatus>, AppError> {
+    let status = waiting_room_service::join_queue(&app_state.db_pool, event_id, user_id).await?;
+    Ok(Json(status))
+}
+
+/// Handler for a user to check their place in an event's queue.
+/// Once they are let in, the response carries their admission token.
+/// GET /api/events/:event_id/queue
+#[tracing::instrument(skip(app_state))]
+pub async fn get_queue_status(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    Extension(user_id): Extension<i32>,
+) -> Result<Json<QueueStatus>, AppError> {
+    let status = waiting_room_service::get_queue_status(&app_state.db_pool, event_id, user_id).await?;
+    Ok(Json(status))
+}
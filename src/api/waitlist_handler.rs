@@ -0,0 +1,77 @@
+use crate::{
+    errors::AppError,
+    models::{order::CreateOrderResponse, JoinWaitlistPayload, WaitlistClaim, WaitlistEntryDetails},
+    service::{order_service, waitlist_service},
+    AppState,
+};
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    Json,
+};
+
+/// Handler for a user to join the waitlist of a sold-out event or tier.
+/// POST /api/events/:event_id/waitlist
+#[tracing::instrument(skip(app_state, payload))]
+pub async fn join_waitlist(
+    State(app_state): State<AppState>,
+    Path(event_id): Path<i32>,
+    Extension(user_id): Extension<i32>,
+    Json(payload): Json<JoinWaitlistPayload>,
+) -> Result<(StatusCode, Json<WaitlistEntryDetails>), AppError> {
+    let entry = waitlist_service::join_waitlist(&app_state.db_pool, event_id, user_id, &payload).await?;
+    Ok((StatusCode::CREATED, Json(entry)))
+}
+
+/// Handler to list the user's waitlist entries and any purchase links offered to them.
+/// GET /api/me/waitlist
+#[tracing::instrument(skip(app_state))]
+pub async fn list_my_entries(
+    State(app_state): State<AppState>,
+    Extension(user_id): Extension<i32>,
+) -> Result<Json<Vec<WaitlistEntryDetails>>, AppError> {
+    let entries = waitlist_service::list_my_entries(&app_state.db_pool, user_id).await?;
+    Ok(Json(entries))
+}
+
+/// Handler for a user to leave a waitlist.
+/// DELETE /api/waitlist/:entry_id
+#[tracing::instrument(skip(app_state))]
+pub async fn leave_waitlist(
+    State(app_state): State<AppState>,
+    Path(entry_id): Path<i32>,
+    Extension(user_id): Extension<i32>,
+) -> Result<StatusCode, AppError> {
+    waitlist_service::leave_waitlist(&app_state.db_pool, entry_id, user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Handler to show the tickets reserved behind a purchase link.
+/// GET /api/waitlist/claims/:token
+#[tracing::instrument(skip(app_state, token))]
+pub async fn get_claim(
+    State(app_state): State<AppState>,
+    Path(token): Path<String>,
+    Extension(user_id): Extension<i32>,
+) -> Result<Json<WaitlistClaim>, AppError> {
+    let claim = waitlist_service::get_claim(&app_state.db_pool, user_id, &token).await?;
+    Ok(Json(claim))
+}
+
+/// Handler to check out the tickets reserved behind a purchase link.
+/// POST /api/waitlist/claims/:token/checkout
+#[tracing::instrument(skip(app_state, token))]
+pub async fn checkout_claim(
+    State(app_state): State<AppState>,
+    Path(token): Path<String>,
+    Extension(user_id): Extension<i32>,
+) -> Result<(StatusCode, Json<CreateOrderResponse>), AppError> {
+    let (order, stripe_client_secret) = order_service::create_waitlist_order(
+        &app_state.db_pool,
+        app_state.payment_provider.as_ref(),
+        user_id,
+        &token,
+    )
+    .await?;
+    Ok((StatusCode::CREATED, Json(CreateOrderResponse { order, stripe_client_secret })))
+}
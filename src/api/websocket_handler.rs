@@ -1,86 +1,203 @@
-use crate::AppState; // Import AppState
+use crate::{
+    db::event_query,
+    errors::AppError,
+    models::{QueueState, QueueStatus, SeatMapMessage},
+    service::{
+        realtime_service::{self, EventChannels},
+        waiting_room_service,
+    },
+    AppState,
+};
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        Path, State,
+        Extension, Path, State,
     },
-    response::IntoResponse,
-    Extension,
+    response::Response,
 };
-use dashmap::DashMap;
 use futures::{sink::SinkExt, stream::StreamExt};
+use sqlx::PgPool;
 use std::sync::Arc;
-use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
 use tracing::info;
 
-// This type alias can now be removed as we'll pass the Arc<DashMap> directly.
-type ProjectWsSenders = Arc<DashMap<i32, broadcast::Sender<String>>>;
-
-/// WebSocket handler for project-specific updates.
-/// GET /api/projects/:project_id/ws
-pub async fn project_ws_handler(
+/// WebSocket handler for live seat map updates of an event.
+/// Pushes a `seat_status` message whenever seats are locked, released or sold.
+/// Clients should open the socket before fetching `GET /api/events/:event_id/seat-map`,
+/// so no change is missed between the two.
+/// GET /api/events/:event_id/seat-map/ws
+pub async fn seat_map_ws_handler(
     ws: WebSocketUpgrade,
-    Path(project_id): Path<i32>,
-    Extension(user_id): Extension<i32>,
-    State(app_state): State<AppState>, // FIX: Extract AppState
-) -> impl IntoResponse {
-    // 1. Authorize user access to the project
+    Path(event_id): Path<i32>,
+    State(app_state): State<AppState>,
+) -> Result<Response, AppError> {
+    // 1. Make sure the event exists before holding a connection open for it.
+    event_query::get_by_id(&app_state.db_pool, event_id).await?;
+
+    // 2. Upgrade the HTTP connection to a WebSocket
+    let channels = app_state.event_ws_senders.clone();
+    Ok(ws.on_upgrade(move |socket| handle_seat_map_socket(socket, event_id, channels)))
+}
+
+async fn handle_seat_map_socket(socket: WebSocket, event_id: i32, channels: Arc<EventChannels>) {
+    info!("WebSocket: Client connected to seat map of event {}", event_id);
+
+    let (mut sender_ws, mut receiver_ws) = socket.split();
+    let mut receiver_broadcast = realtime_service::subscribe(&channels, event_id);
+
+    // Forward seat map updates from the broadcast channel to the WebSocket client.
+    let mut send_task = tokio::spawn(async move {
+        loop {
+            let msg = match receiver_broadcast.recv().await {
+                Ok(msg) => msg,
+                // The client fell behind and missed updates; tell it to refetch the seat map.
+                Err(RecvError::Lagged(skipped)) => {
+                    info!("WebSocket: Client of event {} lagged by {} messages.", event_id, skipped);
+                    match serde_json::to_string(&SeatMapMessage::Resync { event_id }) {
+                        Ok(msg) => msg,
+                        Err(_) => break,
+                    }
+                }
+                Err(RecvError::Closed) => break,
+            };
+            if sender_ws.send(Message::Text(msg)).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    // The seat map is read-only; we only read from the client to notice when it leaves.
+    let mut recv_task = tokio::spawn(async move {
+        while let Some(Ok(msg)) = receiver_ws.next().await {
+            if let Message::Close(_) = msg {
+                break;
+            }
+        }
+    });
 
+    let send_finished = tokio::select! {
+        _ = &mut send_task => {
+            recv_task.abort();
+            true
+        }
+        _ = &mut recv_task => {
+            send_task.abort();
+            false
+        }
+    };
 
-    info!("WebSocket: User {} attempting to connect to project {}", user_id, project_id);
+    // Wait for the aborted sender to drop its receiver before checking for remaining clients.
+    if !send_finished {
+        let _ = send_task.await;
+    }
+    realtime_service::release(&channels, event_id);
+    info!("WebSocket: Client disconnected from seat map of event {}", event_id);
+}
+
+/// WebSocket handler for a user's place in an event's queue.
+/// Pushes the user's `QueueStatus` whenever the line moves, and once more with the
+/// admission token when they are let in.
+/// GET /api/events/:event_id/queue/ws
+pub async fn queue_ws_handler(
+    ws: WebSocketUpgrade,
+    Path(event_id): Path<i32>,
+    Extension(user_id): Extension<i32>,
+    State(app_state): State<AppState>,
+) -> Result<Response, AppError> {
+    // 1. Only users in the queue get a socket; this also gives us their starting status.
+    let status = waiting_room_service::get_queue_status(&app_state.db_pool, event_id, user_id).await?;
 
     // 2. Upgrade the HTTP connection to a WebSocket
-    ws.on_upgrade(move |socket| {
-        handle_project_socket(
-            socket,
-            project_id,
-            user_id,
-            app_state.project_ws_senders.clone(), // Clone the Arc and pass it
-        )
-    })
+    let channels = app_state.event_ws_senders.clone();
+    let pool = app_state.db_pool.clone();
+    Ok(ws.on_upgrade(move |socket| handle_queue_socket(socket, status, user_id, pool, channels)))
 }
 
-async fn handle_project_socket(
+async fn handle_queue_socket(
     socket: WebSocket,
-    project_id: i32,
+    mut status: QueueStatus,
     user_id: i32,
-    project_ws_senders: Arc<DashMap<i32, broadcast::Sender<String>>>, // Accept the cloned Arc
+    pool: Arc<PgPool>,
+    channels: Arc<EventChannels>,
 ) {
-    info!("WebSocket: User {} connected to project {}", user_id, project_id);
+    let event_id = status.event_id;
+    info!("WebSocket: User {} connected to the queue of event {}", user_id, event_id);
 
     let (mut sender_ws, mut receiver_ws) = socket.split();
+    let mut receiver_broadcast = realtime_service::subscribe(&channels, event_id);
 
-    let sender_broadcast = project_ws_senders
-        .entry(project_id)
-        .or_insert_with(|| {
-            info!("WebSocket: Created new broadcast channel for project {}", project_id);
-            broadcast::channel::<String>(16).0
-        })
-        .clone();
-
-    let mut receiver_broadcast = sender_broadcast.subscribe();
-
-    // Spawn a task to send messages from the broadcast channel to the WebSocket client
+    // Work out the user's place from the progress of the line; only go to the database
+    // when their place was just drawn, they were let in, or updates were missed.
     let mut send_task = tokio::spawn(async move {
-        while let Ok(msg) = receiver_broadcast.recv().await {
-            if sender_ws.send(Message::Text(msg)).await.is_err() {
-                info!("WebSocket: Failed to send message to user {} for project {}. Disconnecting.", user_id, project_id);
+        let mut refetch = false;
+        loop {
+            if send_status(&mut sender_ws, &status).await.is_err() {
                 break;
             }
+            loop {
+                let message = match receiver_broadcast.recv().await {
+                    Ok(msg) => serde_json::from_str::<SeatMapMessage>(&msg).ok(),
+                    Err(RecvError::Lagged(_)) => Some(SeatMapMessage::Resync { event_id }),
+                    Err(RecvError::Closed) => return,
+                };
+                match message {
+                    Some(SeatMapMessage::QueueProgress { admitted_through, .. }) if status.state == QueueState::Waiting => {
+                        match status.position {
+                            Some(position) if position > admitted_through => {
+                                status.people_ahead = Some(position - admitted_through - 1);
+                            }
+                            _ => refetch = true,
+                        }
+                        break;
+                    }
+                    Some(SeatMapMessage::Resync { .. }) => {
+                        refetch = true;
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+            if std::mem::take(&mut refetch) {
+                match waiting_room_service::get_queue_status(&pool, event_id, user_id).await {
+                    Ok(fresh) => status = fresh,
+                    // The room was removed; there is nothing left to wait for.
+                    Err(_) => break,
+                }
+            }
         }
     });
 
-    // Spawn a task to receive messages from the WebSocket client
+    // We only read from the client to notice when it leaves.
     let mut recv_task = tokio::spawn(async move {
-        while let Some(Ok(Message::Text(text))) = receiver_ws.next().await {
-            info!("WebSocket: Received message from user {} for project {}: {}", user_id, project_id, text);
+        while let Some(Ok(msg)) = receiver_ws.next().await {
+            if let Message::Close(_) = msg {
+                break;
+            }
         }
     });
 
-    tokio::select! {
-        _ = &mut send_task => recv_task.abort(),
-        _ = &mut recv_task => send_task.abort(),
+    let send_finished = tokio::select! {
+        _ = &mut send_task => {
+            recv_task.abort();
+            true
+        }
+        _ = &mut recv_task => {
+            send_task.abort();
+            false
+        }
+    };
+
+    if !send_finished {
+        let _ = send_task.await;
     }
+    realtime_service::release(&channels, event_id);
+    info!("WebSocket: User {} disconnected from the queue of event {}", user_id, event_id);
+}
 
-    info!("WebSocket: User {} disconnected from project {}", user_id, project_id);
-}
\ No newline at end of file
+async fn send_status<S>(sender_ws: &mut S, status: &QueueStatus) -> Result<(), ()>
+where
+    S: SinkExt<Message> + Unpin,
+{
+    let msg = serde_json::to_string(status).map_err(|_| ())?;
+    sender_ws.send(Message::Text(msg)).await.map_err(|_| ())
+}
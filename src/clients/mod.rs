@@ -1 +1,2 @@
-pub mod stripe_client;
\ No newline at end of file
+pub mod stripe_client;
+pub mod payment_provider;
@@ -0,0 +1,334 @@
+// File: src/clients/payment_provider.rs
+
+use std::future::Future;
+
+use chrono::{DateTime, Utc};
+use dashmap::{DashMap, DashSet};
+use stripe::{
+    CancelPaymentIntent, CreatePaymentIntent, CreatePaymentIntentAutomaticPaymentMethods, CreateRefund, Currency,
+    ErrorCode, ListPaymentIntents, PaymentIntent, PaymentIntentCancellationReason, PaymentIntentId,
+    PaymentIntentStatus, RangeBounds, RangeQuery, Refund, RequestError, RequestStrategy, StripeError,
+};
+
+use crate::config::CONFIG;
+use crate::errors::AppError;
+
+/// How many PaymentIntents to request per page from Stripe.
+const STRIPE_PAGE_SIZE: u64 = 100;
+
+/// The status of a PaymentIntent, as far as our bookkeeping is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderPaymentStatus {
+    /// Waiting on the customer (payment method, confirmation or 3DS action).
+    RequiresAction,
+    Processing,
+    Succeeded,
+    Canceled,
+}
+
+impl ProviderPaymentStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::RequiresAction => "requires_action",
+            Self::Processing => "processing",
+            Self::Succeeded => "succeeded",
+            Self::Canceled => "canceled",
+        }
+    }
+}
+
+/// A newly created PaymentIntent. The client secret is handed to the frontend.
+#[derive(Debug, Clone)]
+pub struct CreatedPaymentIntent {
+    pub id: String,
+    pub client_secret: String,
+}
+
+/// A PaymentIntent as reported by the provider.
+#[derive(Debug, Clone)]
+pub struct ProviderPaymentIntent {
+    pub id: String,
+    pub status: ProviderPaymentStatus,
+    /// In the smallest currency unit, e.g. cents.
+    pub amount: i64,
+    pub currency: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The operations checkout, reconciliation and group bookings need from a payment provider.
+/// Implemented by the real Stripe client and by an in-memory fake.
+pub trait PaymentProvider: Send + Sync {
+    /// Creates a PaymentIntent for `amount` (in cents).
+    fn create_payment_intent(
+        &self,
+        amount: i64,
+        currency: &str,
+    ) -> impl Future<Output = Result<CreatedPaymentIntent, AppError>> + Send;
+
+    /// Lists every PaymentIntent created at or after `since`.
+    fn list_payment_intents_since(
+        &self,
+        since: DateTime<Utc>,
+    ) -> impl Future<Output = Result<Vec<ProviderPaymentIntent>, AppError>> + Send;
+
+    /// Cancels a PaymentIntent the customer hasn't paid yet, so it can no longer be confirmed.
+    /// Fails if it already succeeded or is processing.
+    fn cancel_payment_intent(&self, payment_intent_id: &str) -> impl Future<Output = Result<(), AppError>> + Send;
+
+    /// Refunds a succeeded PaymentIntent in full. Refunding it again succeeds without refunding
+    /// twice, so a retried webhook can safely refund the same payment.
+    fn refund_payment_intent(&self, payment_intent_id: &str) -> impl Future<Output = Result<(), AppError>> + Send;
+}
+
+// --- Stripe ---
+
+impl PaymentProvider for stripe::Client {
+    async fn create_payment_intent(
+        &self,
+        amount: i64,
+        currency: &str,
+    ) -> Result<CreatedPaymentIntent, AppError> {
+        let currency = currency
+            .parse::<Currency>()
+            .map_err(|_| AppError::BadRequest(format!("Unsupported currency: {}", currency)))?;
+
+        let mut params = CreatePaymentIntent::new(amount, currency);
+        params.automatic_payment_methods =
+            Some(CreatePaymentIntentAutomaticPaymentMethods { allow_redirects: None, enabled: true });
+
+        let pi = PaymentIntent::create(self, params).await?;
+        Ok(CreatedPaymentIntent {
+            id: pi.id.to_string(),
+            client_secret: pi.client_secret.unwrap_or_default(),
+        })
+    }
+
+    async fn list_payment_intents_since(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<ProviderPaymentIntent>, AppError> {
+        let mut intents = Vec::new();
+        let mut starting_after = None;
+        loop {
+            let mut params = ListPaymentIntents::new();
+            params.created = Some(RangeQuery::Bounds(RangeBounds {
+                gte: Some(since.timestamp()),
+                ..Default::default()
+            }));
+            params.limit = Some(STRIPE_PAGE_SIZE);
+            params.starting_after = starting_after.take();
+
+            let page = PaymentIntent::list(self, &params).await?;
+            intents.extend(page.data.iter().map(|pi| ProviderPaymentIntent {
+                id: pi.id.to_string(),
+                status: match pi.status {
+                    PaymentIntentStatus::Succeeded => ProviderPaymentStatus::Succeeded,
+                    PaymentIntentStatus::Processing => ProviderPaymentStatus::Processing,
+                    PaymentIntentStatus::Canceled => ProviderPaymentStatus::Canceled,
+                    _ => ProviderPaymentStatus::RequiresAction,
+                },
+                amount: pi.amount,
+                currency: pi.currency.to_string(),
+                created_at: DateTime::from_timestamp(pi.created, 0).unwrap_or_else(Utc::now),
+            }));
+
+            match (page.has_more, page.data.last()) {
+                (true, Some(last)) => starting_after = Some(last.id.clone()),
+                _ => break,
+            }
+        }
+        Ok(intents)
+    }
+
+    async fn cancel_payment_intent(&self, payment_intent_id: &str) -> Result<(), AppError> {
+        let params = CancelPaymentIntent {
+            cancellation_reason: Some(PaymentIntentCancellationReason::Abandoned),
+        };
+        PaymentIntent::cancel(self, payment_intent_id, params).await?;
+        Ok(())
+    }
+
+    async fn refund_payment_intent(&self, payment_intent_id: &str) -> Result<(), AppError> {
+        let payment_intent_id = payment_intent_id
+            .parse::<PaymentIntentId>()
+            .map_err(|_| AppError::BadRequest(format!("Invalid PaymentIntent ID: {}", payment_intent_id)))?;
+        let mut params = CreateRefund::new();
+        params.payment_intent = Some(payment_intent_id.clone());
+
+        // The key makes a retried refund return the first one instead of refunding again;
+        // once Stripe forgets the key, the charge reports it was refunded already.
+        let client = self.clone().with_strategy(RequestStrategy::Idempotent(format!("refund-{}", payment_intent_id)));
+        match Refund::create(&client, params).await {
+            Ok(_) => Ok(()),
+            Err(StripeError::Stripe(RequestError { code: Some(ErrorCode::ChargeAlreadyRefunded), .. })) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+// --- Fake ---
+
+/// An in-memory payment provider for local development and tests.
+/// PaymentIntents never leave the process; their status is changed with `set_status`.
+#[derive(Debug, Default)]
+pub struct FakePaymentProvider {
+    intents: DashMap<String, ProviderPaymentIntent>,
+    refunded: DashSet<String>,
+}
+
+impl FakePaymentProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Simulates the customer (or Stripe) moving a PaymentIntent to a new status.
+    /// Returns `false` if no such PaymentIntent exists.
+    #[allow(dead_code)] // Used by the tests to script payment scenarios.
+    pub fn set_status(&self, payment_intent_id: &str, status: ProviderPaymentStatus) -> bool {
+        match self.intents.get_mut(payment_intent_id) {
+            Some(mut intent) => {
+                intent.status = status;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The current status of a PaymentIntent.
+    #[allow(dead_code)] // Used by the tests to check the outcome of payment scenarios.
+    pub fn status(&self, payment_intent_id: &str) -> Option<ProviderPaymentStatus> {
+        self.intents.get(payment_intent_id).map(|intent| intent.status)
+    }
+
+    /// Whether a PaymentIntent was refunded.
+    #[allow(dead_code)] // Used by the tests to check the outcome of payment scenarios.
+    pub fn is_refunded(&self, payment_intent_id: &str) -> bool {
+        self.refunded.contains(payment_intent_id)
+    }
+}
+
+impl PaymentProvider for FakePaymentProvider {
+    async fn create_payment_intent(
+        &self,
+        amount: i64,
+        currency: &str,
+    ) -> Result<CreatedPaymentIntent, AppError> {
+        let id = format!("pi_{}", uuid::Uuid::new_v4().simple());
+        let client_secret = format!("{}_secret_{}", id, uuid::Uuid::new_v4().simple());
+        self.intents.insert(
+            id.clone(),
+            ProviderPaymentIntent {
+                id: id.clone(),
+                status: ProviderPaymentStatus::RequiresAction,
+                amount,
+                currency: currency.to_string(),
+                created_at: Utc::now(),
+            },
+        );
+        Ok(CreatedPaymentIntent { id, client_secret })
+    }
+
+    async fn list_payment_intents_since(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<ProviderPaymentIntent>, AppError> {
+        Ok(self
+            .intents
+            .iter()
+            .filter(|intent| intent.created_at >= since)
+            .map(|intent| intent.value().clone())
+            .collect())
+    }
+
+    async fn cancel_payment_intent(&self, payment_intent_id: &str) -> Result<(), AppError> {
+        let mut intent = self
+            .intents
+            .get_mut(payment_intent_id)
+            .ok_or_else(|| AppError::BadRequest(format!("No such PaymentIntent: {}", payment_intent_id)))?;
+        match intent.status {
+            ProviderPaymentStatus::Succeeded | ProviderPaymentStatus::Processing => Err(AppError::BadRequest(format!(
+                "PaymentIntent {} is {} and can't be canceled.",
+                payment_intent_id,
+                intent.status.as_str()
+            ))),
+            _ => {
+                intent.status = ProviderPaymentStatus::Canceled;
+                Ok(())
+            }
+        }
+    }
+
+    async fn refund_payment_intent(&self, payment_intent_id: &str) -> Result<(), AppError> {
+        match self.intents.get(payment_intent_id) {
+            Some(intent) if intent.status == ProviderPaymentStatus::Succeeded => {
+                self.refunded.insert(payment_intent_id.to_string());
+                Ok(())
+            }
+            Some(intent) => Err(AppError::BadRequest(format!(
+                "PaymentIntent {} is {} and can't be refunded.",
+                payment_intent_id,
+                intent.status.as_str()
+            ))),
+            None => Err(AppError::BadRequest(format!("No such PaymentIntent: {}", payment_intent_id))),
+        }
+    }
+}
+
+// --- Configured provider ---
+
+/// The payment provider selected by the `PAYMENT_PROVIDER` setting.
+pub enum PaymentGateway {
+    Stripe(Box<stripe::Client>),
+    Fake(FakePaymentProvider),
+}
+
+impl PaymentProvider for PaymentGateway {
+    async fn create_payment_intent(
+        &self,
+        amount: i64,
+        currency: &str,
+    ) -> Result<CreatedPaymentIntent, AppError> {
+        match self {
+            Self::Stripe(client) => client.create_payment_intent(amount, currency).await,
+            Self::Fake(fake) => fake.create_payment_intent(amount, currency).await,
+        }
+    }
+
+    async fn list_payment_intents_since(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<ProviderPaymentIntent>, AppError> {
+        match self {
+            Self::Stripe(client) => client.list_payment_intents_since(since).await,
+            Self::Fake(fake) => fake.list_payment_intents_since(since).await,
+        }
+    }
+
+    async fn cancel_payment_intent(&self, payment_intent_id: &str) -> Result<(), AppError> {
+        match self {
+            Self::Stripe(client) => client.cancel_payment_intent(payment_intent_id).await,
+            Self::Fake(fake) => fake.cancel_payment_intent(payment_intent_id).await,
+        }
+    }
+
+    async fn refund_payment_intent(&self, payment_intent_id: &str) -> Result<(), AppError> {
+        match self {
+            Self::Stripe(client) => client.refund_payment_intent(payment_intent_id).await,
+            Self::Fake(fake) => fake.refund_payment_intent(payment_intent_id).await,
+        }
+    }
+}
+
+/// Creates the payment provider named in the config.
+/// Defaults to Stripe; the fake provider is only used when asked for by name, for local
+/// development without Stripe keys.
+pub fn create_payment_gateway(stripe_client: &stripe::Client) -> PaymentGateway {
+    match CONFIG.payment_provider.as_deref() {
+        Some("stripe") | None => PaymentGateway::Stripe(Box::new(stripe_client.clone())),
+        Some("fake") => {
+            tracing::warn!("PAYMENT_PROVIDER is \"fake\": checkouts can't be paid for real.");
+            PaymentGateway::Fake(FakePaymentProvider::new())
+        }
+        Some(other) => panic!("Unknown PAYMENT_PROVIDER: {} (expected \"stripe\" or \"fake\")", other),
+    }
+}
@@ -19,6 +19,8 @@ pub struct Config {
 
     pub stripe_secret_key: String,
     pub stripe_webhook_secret: String,
+    // "stripe" or "fake". Defaults to Stripe when unset.
+    pub payment_provider: Option<String>,
 }
 
 // 2. A function to load the configuration from the environment.
@@ -0,0 +1,177 @@
+use crate::{
+    errors::AppError,
+    models::{AccessCodeMatch, Offer, OfferAccessCode},
+};
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+
+/// Inserts generated codes for an offer. Codes that already exist are skipped, so the
+/// caller can generate replacements. Returns the codes inserted.
+pub async fn insert_codes(
+    tx: &mut Transaction<'_, Postgres>,
+    offer_id: i32,
+    batch_name: &str,
+    codes: &[String],
+    max_uses: i32,
+    created_by: i32,
+) -> Result<Vec<String>, AppError> {
+    let inserted = sqlx::query_scalar!(
+        r#"
+        INSERT INTO offer_access_codes (offer_id, batch_name, code, max_uses, created_by)
+        SELECT $1, $2, code, $4, $5 FROM UNNEST($3::text[]) AS code
+        ON CONFLICT (code) DO NOTHING
+        RETURNING code
+        "#,
+        offer_id,
+        batch_name,
+        codes,
+        max_uses,
+        created_by
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    Ok(inserted)
+}
+
+/// Makes an offer a presale, hidden from the public until unlocked.
+pub async fn mark_offer_presale(tx: &mut Transaction<'_, Postgres>, offer_id: i32) -> Result<(), AppError> {
+    sqlx::query!("UPDATE offers SET is_presale = TRUE WHERE id = $1", offer_id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// Lists the generated codes of an offer, optionally of one batch, with their usage.
+pub async fn list_codes(
+    pool: &PgPool,
+    offer_id: i32,
+    batch_name: Option<&str>,
+) -> Result<Vec<OfferAccessCode>, AppError> {
+    sqlx::query_as!(
+        OfferAccessCode,
+        r#"
+        SELECT
+            ac.id, ac.offer_id, ac.batch_name, ac.code, ac.max_uses, ac.use_count,
+            (SELECT MAX(u.unlocked_at) FROM offer_unlocks u WHERE u.access_code_id = ac.id) AS last_used_at,
+            ac.created_at
+        FROM offer_access_codes ac
+        WHERE ac.offer_id = $1 AND ($2::text IS NULL OR ac.batch_name = $2)
+        ORDER BY ac.batch_name, ac.id
+        "#,
+        offer_id,
+        batch_name
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Finds the offers of an event whose shared access code is `code`, ignoring case.
+pub async fn find_shared_code_offers(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+    code: &str,
+) -> Result<Vec<i32>, AppError> {
+    let offer_ids = sqlx::query_scalar!(
+        r#"
+        SELECT o.id
+        FROM offers o
+        JOIN ticket_tiers tt ON o.ticket_tier_id = tt.id
+        WHERE tt.event_id = $1 AND UPPER(o.access_code) = UPPER($2)
+        "#,
+        event_id,
+        code
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    Ok(offer_ids)
+}
+
+/// Finds the generated code of an event's offer matching `code`, ignoring case, and locks
+/// it until the end of the transaction so its uses are counted one at a time.
+pub async fn lock_generated_code(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+    code: &str,
+    user_id: i32,
+) -> Result<Option<AccessCodeMatch>, AppError> {
+    sqlx::query_as!(
+        AccessCodeMatch,
+        r#"
+        SELECT
+            ac.id,
+            ac.offer_id,
+            ac.use_count < ac.max_uses AS "has_uses_left!",
+            EXISTS (
+                SELECT 1 FROM offer_unlocks u WHERE u.access_code_id = ac.id AND u.user_id = $3
+            ) AS "redeemed_by_user!"
+        FROM offer_access_codes ac
+        JOIN offers o ON ac.offer_id = o.id
+        JOIN ticket_tiers tt ON o.ticket_tier_id = tt.id
+        WHERE tt.event_id = $1 AND ac.code = UPPER($2)
+        FOR UPDATE OF ac
+        "#,
+        event_id,
+        code,
+        user_id
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Counts one more use of a generated code.
+pub async fn count_code_use(tx: &mut Transaction<'_, Postgres>, code_id: i32) -> Result<(), AppError> {
+    sqlx::query!("UPDATE offer_access_codes SET use_count = use_count + 1 WHERE id = $1", code_id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// Unlocks offers for a user until `expires_at`. Unlocking again extends the unlock and
+/// keeps the generated code it was first redeemed with.
+pub async fn unlock_offers(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: i32,
+    offer_ids: &[i32],
+    access_code_id: Option<i32>,
+    expires_at: DateTime<Utc>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO offer_unlocks (offer_id, user_id, access_code_id, expires_at)
+        SELECT offer_id, $1, $3, $4 FROM UNNEST($2::int[]) AS offer_id
+        ON CONFLICT (offer_id, user_id) DO UPDATE SET
+            access_code_id = COALESCE(offer_unlocks.access_code_id, EXCLUDED.access_code_id),
+            unlocked_at = NOW(),
+            expires_at = EXCLUDED.expires_at
+        "#,
+        user_id,
+        offer_ids,
+        access_code_id,
+        expires_at
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Fetches the given offers, cheapest first.
+pub async fn list_offers(tx: &mut Transaction<'_, Postgres>, offer_ids: &[i32]) -> Result<Vec<Offer>, AppError> {
+    sqlx::query_as!(
+        Offer,
+        r#"
+        SELECT id, ticket_tier_id, name, status AS "status: _", price, quantity_for_sale, quantity_sold,
+               sale_start_time, sale_end_time, min_per_order, max_per_order, access_code, is_accessible,
+               is_presale, is_bundle, offer_current_price(id) AS "current_price!"
+        FROM offers WHERE id = ANY($1)
+        ORDER BY offer_current_price(id), id
+        "#,
+        offer_ids
+    )
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
@@ -0,0 +1,272 @@
+use crate::{
+    errors::AppError,
+    models::{AddonItem, AddonVariant, CreateAddonItemPayload, OrderAddon, VoucherDetails},
+};
+use rust_decimal::Decimal;
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// Adds an item to an event's add-on catalog, along with its variants.
+/// An item sold in variants holds as much inventory as its variants together.
+pub async fn create_addon_item(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+    payload: &CreateAddonItemPayload,
+    price: Decimal,
+    is_redeemable: bool,
+) -> Result<AddonItem, AppError> {
+    let total_inventory = if payload.variants.is_empty() {
+        payload.total_inventory
+    } else {
+        Some(payload.variants.iter().map(|v| v.total_inventory).sum())
+    };
+    let item = sqlx::query_as!(
+        AddonItem,
+        r#"
+        INSERT INTO addon_items (event_id, name, description, price, is_donation, is_redeemable, total_inventory)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, event_id, name, description, price, is_donation, is_redeemable, total_inventory,
+                  quantity_sold, created_at, last_updated
+        "#,
+        event_id,
+        payload.name,
+        payload.description,
+        price,
+        payload.is_donation,
+        is_redeemable,
+        total_inventory
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+
+    for variant in &payload.variants {
+        sqlx::query!(
+            "INSERT INTO addon_variants (addon_item_id, name, total_inventory) VALUES ($1, $2, $3)",
+            item.id,
+            variant.name,
+            variant.total_inventory
+        )
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_unique_violation() => {
+                AppError::BadRequest(format!("Variant '{}' is listed twice.", variant.name))
+            }
+            e => AppError::from(e),
+        })?;
+    }
+    Ok(item)
+}
+
+/// Fetches a single add-on item by its ID.
+pub async fn get_addon_item<'e, E>(executor: E, addon_item_id: i32) -> Result<AddonItem, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as!(
+        AddonItem,
+        r#"
+        SELECT id, event_id, name, description, price, is_donation, is_redeemable, total_inventory,
+               quantity_sold, created_at, last_updated
+        FROM addon_items WHERE id = $1
+        "#,
+        addon_item_id
+    )
+    .fetch_one(executor)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Lists an event's add-on catalog, by name.
+pub async fn list_addon_items_for_event(pool: &PgPool, event_id: i32) -> Result<Vec<AddonItem>, AppError> {
+    sqlx::query_as!(
+        AddonItem,
+        r#"
+        SELECT id, event_id, name, description, price, is_donation, is_redeemable, total_inventory,
+               quantity_sold, created_at, last_updated
+        FROM addon_items WHERE event_id = $1
+        ORDER BY name, id
+        "#,
+        event_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Lists the variants of the given add-on items, in the order they were added.
+pub async fn list_variants<'e, E>(executor: E, addon_item_ids: &[i32]) -> Result<Vec<AddonVariant>, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as!(
+        AddonVariant,
+        r#"
+        SELECT id, addon_item_id, name, total_inventory, quantity_sold
+        FROM addon_variants WHERE addon_item_id = ANY($1)
+        ORDER BY addon_item_id, id
+        "#,
+        addon_item_ids
+    )
+    .fetch_all(executor)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Takes `quantity` of an add-on, and of its variant if given, from their inventory.
+pub async fn take_inventory(
+    tx: &mut Transaction<'_, Postgres>,
+    addon_item_id: i32,
+    addon_variant_id: Option<i32>,
+    quantity: i32,
+) -> Result<(), AppError> {
+    let result = sqlx::query!(
+        "UPDATE addon_items SET quantity_sold = quantity_sold + $1
+         WHERE id = $2 AND (total_inventory IS NULL OR (total_inventory - quantity_sold) >= $1)",
+        quantity,
+        addon_item_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::BadRequest(format!("Not enough of add-on {} left.", addon_item_id)));
+    }
+
+    if let Some(addon_variant_id) = addon_variant_id {
+        let result = sqlx::query!(
+            "UPDATE addon_variants SET quantity_sold = quantity_sold + $1
+             WHERE id = $2 AND addon_item_id = $3 AND (total_inventory - quantity_sold) >= $1",
+            quantity,
+            addon_variant_id,
+            addon_item_id
+        )
+        .execute(&mut **tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::BadRequest(format!(
+                "Not enough of variant {} of add-on {} left.",
+                addon_variant_id, addon_item_id
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Gives the add-ons of the given orders back to the catalog.
+pub async fn release_addon_inventory(
+    tx: &mut Transaction<'_, Postgres>,
+    order_ids: &[Uuid],
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        UPDATE addon_items ai
+        SET quantity_sold = GREATEST(ai.quantity_sold - released.quantity, 0)
+        FROM (
+            SELECT addon_item_id, SUM(quantity)::INT AS quantity
+            FROM order_addons
+            WHERE order_id = ANY($1)
+            GROUP BY addon_item_id
+        ) released
+        WHERE ai.id = released.addon_item_id
+        "#,
+        order_ids
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE addon_variants av
+        SET quantity_sold = GREATEST(av.quantity_sold - released.quantity, 0)
+        FROM (
+            SELECT addon_variant_id, SUM(quantity)::INT AS quantity
+            FROM order_addons
+            WHERE order_id = ANY($1) AND addon_variant_id IS NOT NULL
+            GROUP BY addon_variant_id
+        ) released
+        WHERE av.id = released.addon_variant_id
+        "#,
+        order_ids
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Lists the add-ons bought in an order, with their names.
+pub async fn list_addons_for_order<'e, E>(executor: E, order_id: Uuid) -> Result<Vec<OrderAddon>, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as!(
+        OrderAddon,
+        r#"
+        SELECT oa.id, oa.addon_item_id, oa.addon_variant_id, ai.name, av.name AS "variant_name?",
+               oa.event_id, oa.quantity, oa.unit_price, oa.order_bundle_id
+        FROM order_addons oa
+        JOIN addon_items ai ON ai.id = oa.addon_item_id
+        LEFT JOIN addon_variants av ON av.id = oa.addon_variant_id
+        WHERE oa.order_id = $1
+        ORDER BY oa.id
+        "#,
+        order_id
+    )
+    .fetch_all(executor)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Issues one voucher for every redeemable add-on bought in an order.
+/// MUST be run in the same transaction that completes the order.
+pub async fn create_vouchers_for_order(
+    tx: &mut Transaction<'_, Postgres>,
+    order_id: Uuid,
+    user_id: i32,
+) -> Result<u64, AppError> {
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO vouchers (order_id, user_id, event_id, addon_item_id, addon_variant_id)
+        SELECT oa.order_id, $2, oa.event_id, oa.addon_item_id, oa.addon_variant_id
+        FROM order_addons oa
+        JOIN addon_items ai ON ai.id = oa.addon_item_id
+        CROSS JOIN LATERAL generate_series(1, oa.quantity)
+        WHERE oa.order_id = $1 AND ai.is_redeemable
+        ORDER BY oa.id
+        "#,
+        order_id,
+        user_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Lists a user's add-on vouchers, soonest event first.
+pub async fn list_vouchers_for_user(pool: &PgPool, user_id: i32) -> Result<Vec<VoucherDetails>, AppError> {
+    sqlx::query_as!(
+        VoucherDetails,
+        r#"
+        SELECT
+            v.id AS voucher_id,
+            v.order_id,
+            v.qr_code_data,
+            v.status AS "status: _",
+            ai.name AS addon_name,
+            av.name AS "variant_name?",
+            v.event_id,
+            e.title AS event_title,
+            e.start_time AS event_start_time,
+            v.redeemed_at
+        FROM vouchers v
+        JOIN addon_items ai ON ai.id = v.addon_item_id
+        LEFT JOIN addon_variants av ON av.id = v.addon_variant_id
+        JOIN events e ON e.id = v.event_id
+        WHERE v.user_id = $1
+        ORDER BY e.start_time, v.created_at, v.id
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
@@ -0,0 +1,250 @@
+use crate::{
+    db::realtime_query,
+    errors::AppError,
+    models::{Cart, CartItem, CartItemDetails, SeatStatus, SeatStatusChange},
+};
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+
+/// Fetches a user's cart and locks it until the end of the transaction.
+pub async fn lock_cart(tx: &mut Transaction<'_, Postgres>, user_id: i32) -> Result<Option<Cart>, AppError> {
+    sqlx::query_as!(
+        Cart,
+        "SELECT id, user_id, expires_at, created_at, last_updated FROM carts WHERE user_id = $1 FOR UPDATE",
+        user_id
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Fetches a user's cart, if it hasn't expired.
+pub async fn get_active_cart(pool: &PgPool, user_id: i32) -> Result<Cart, AppError> {
+    sqlx::query_as!(
+        Cart,
+        "SELECT id, user_id, expires_at, created_at, last_updated FROM carts
+         WHERE user_id = $1 AND expires_at > NOW()",
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Creates an empty cart for a user, holding its items until `expires_at`.
+pub async fn create_cart(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: i32,
+    expires_at: DateTime<Utc>,
+) -> Result<Cart, AppError> {
+    sqlx::query_as!(
+        Cart,
+        r#"
+        INSERT INTO carts (user_id, expires_at) VALUES ($1, $2)
+        RETURNING id, user_id, expires_at, created_at, last_updated
+        "#,
+        user_id,
+        expires_at
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Locks the carts whose holds have run out. Carts already locked, e.g. mid-checkout,
+/// are skipped and picked up by the next sweep.
+pub async fn lock_expired_carts(tx: &mut Transaction<'_, Postgres>) -> Result<Vec<i32>, AppError> {
+    let ids = sqlx::query_scalar!(
+        "SELECT id FROM carts WHERE expires_at < NOW() ORDER BY id FOR UPDATE SKIP LOCKED"
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+    Ok(ids)
+}
+
+/// Deletes the given carts along with their items.
+pub async fn delete_carts(tx: &mut Transaction<'_, Postgres>, cart_ids: &[i32]) -> Result<(), AppError> {
+    sqlx::query!("DELETE FROM carts WHERE id = ANY($1)", cart_ids)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+// --- Cart Items ---
+
+/// Lists the items of the given carts.
+pub async fn list_items(tx: &mut Transaction<'_, Postgres>, cart_ids: &[i32]) -> Result<Vec<CartItem>, AppError> {
+    sqlx::query_as!(
+        CartItem,
+        r#"
+        SELECT id, cart_id, offer_id, event_id, ticket_tier_id, seat_id, quantity
+        FROM cart_items WHERE cart_id = ANY($1)
+        ORDER BY id
+        "#,
+        cart_ids
+    )
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Lists a cart's items with their offer and event, at the offers' current prices.
+pub async fn list_item_details(pool: &PgPool, cart_id: i32) -> Result<Vec<CartItemDetails>, AppError> {
+    sqlx::query_as!(
+        CartItemDetails,
+        r#"
+        SELECT
+            ci.id, ci.offer_id, o.name AS offer_name, ci.event_id, e.title AS event_title,
+            ci.ticket_tier_id, ci.seat_id, ci.quantity,
+            offer_current_price(ci.offer_id) AS "unit_price!"
+        FROM cart_items ci
+        JOIN offers o ON o.id = ci.offer_id
+        JOIN events e ON e.id = ci.event_id
+        WHERE ci.cart_id = $1
+        ORDER BY ci.id
+        "#,
+        cart_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Counts the lines in a cart.
+pub async fn count_items(tx: &mut Transaction<'_, Postgres>, cart_id: i32) -> Result<i64, AppError> {
+    let count = sqlx::query_scalar!(r#"SELECT COUNT(*) AS "count!" FROM cart_items WHERE cart_id = $1"#, cart_id)
+        .fetch_one(&mut **tx)
+        .await?;
+    Ok(count)
+}
+
+/// Adds general admission tickets to a cart. More tickets of an offer already in the
+/// cart are added to its line.
+pub async fn add_general_admission(
+    tx: &mut Transaction<'_, Postgres>,
+    cart_id: i32,
+    offer_id: i32,
+    event_id: i32,
+    ticket_tier_id: i32,
+    quantity: i32,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO cart_items (cart_id, offer_id, event_id, ticket_tier_id, quantity)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (cart_id, offer_id) WHERE seat_id IS NULL
+        DO UPDATE SET quantity = cart_items.quantity + EXCLUDED.quantity
+        "#,
+        cart_id,
+        offer_id,
+        event_id,
+        ticket_tier_id,
+        quantity
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Locks a seat for a cart until the cart expires and adds it to the cart.
+/// A seat the user already holds outside of an order, e.g. from a best-available pick,
+/// is taken over by the cart. Returns `false` if the seat isn't available or is zoned into
+/// another ticket tier than the offer's.
+pub async fn add_seat(
+    tx: &mut Transaction<'_, Postgres>,
+    cart: &Cart,
+    offer_id: i32,
+    event_id: i32,
+    ticket_tier_id: i32,
+    seat_id: i32,
+) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        "UPDATE event_seats SET status = 'locked', lock_expires_at = $1, locked_by_user_id = $4, cart_id = $5
+         WHERE event_id = $2 AND seat_id = $3 AND ticket_tier_id = $6
+           AND (status = 'available'
+                OR (status = 'locked' AND order_id IS NULL AND locked_by_user_id = $4
+                    AND waitlist_entry_id IS NULL AND cart_id IS NULL))",
+        cart.expires_at,
+        event_id,
+        seat_id,
+        cart.user_id,
+        cart.id,
+        ticket_tier_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    sqlx::query!(
+        "INSERT INTO cart_items (cart_id, offer_id, event_id, ticket_tier_id, seat_id, quantity)
+         VALUES ($1, $2, $3, $4, $5, 1)",
+        cart.id,
+        offer_id,
+        event_id,
+        ticket_tier_id,
+        seat_id
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    let change = SeatStatusChange { event_id, seat_id, status: SeatStatus::Locked };
+    realtime_query::notify_seat_changes(&mut **tx, &[change]).await?;
+    Ok(true)
+}
+
+/// Removes an item from a cart. Its inventory is left to the caller to release.
+pub async fn delete_item(
+    tx: &mut Transaction<'_, Postgres>,
+    cart_id: i32,
+    item_id: i32,
+) -> Result<CartItem, AppError> {
+    sqlx::query_as!(
+        CartItem,
+        r#"
+        DELETE FROM cart_items WHERE id = $1 AND cart_id = $2
+        RETURNING id, cart_id, offer_id, event_id, ticket_tier_id, seat_id, quantity
+        "#,
+        item_id,
+        cart_id
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+// --- Cart Seats ---
+
+/// Puts the seats locked for the given carts back on sale, or only the given seat.
+/// Live seat maps are notified as part of the transaction.
+pub async fn release_seats(
+    tx: &mut Transaction<'_, Postgres>,
+    cart_ids: &[i32],
+    seat_id: Option<i32>,
+) -> Result<Vec<SeatStatusChange>, AppError> {
+    let changes = sqlx::query_as!(
+        SeatStatusChange,
+        r#"
+        UPDATE event_seats
+        SET status = 'available', lock_expires_at = NULL, locked_by_user_id = NULL, cart_id = NULL
+        WHERE cart_id = ANY($1) AND ($2::int IS NULL OR seat_id = $2) AND status = 'locked' AND order_id IS NULL
+        RETURNING event_id, seat_id, status AS "status: _"
+        "#,
+        cart_ids,
+        seat_id
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    realtime_query::notify_seat_changes(&mut **tx, &changes).await?;
+    Ok(changes)
+}
+
+/// Hands the seats locked for a cart over to its user, for checkout to take into an order.
+pub async fn take_seats(tx: &mut Transaction<'_, Postgres>, cart_id: i32) -> Result<(), AppError> {
+    sqlx::query!("UPDATE event_seats SET cart_id = NULL WHERE cart_id = $1", cart_id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,173 @@
+use crate::{
+    errors::AppError,
+    models::{Dispute, DisputeStatus, DisputeTicketEvidence},
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// Inserts a dispute, or refreshes its status, reason and deadline if it is already known.
+/// Stripe sends several webhooks per dispute, so this must be idempotent.
+#[allow(clippy::too_many_arguments)]
+pub async fn upsert_dispute(
+    tx: &mut Transaction<'_, Postgres>,
+    payment_id: Uuid,
+    order_id: Uuid,
+    stripe_dispute_id: &str,
+    status: DisputeStatus,
+    reason: &str,
+    amount: Decimal,
+    currency: &str,
+    evidence_due_by: Option<DateTime<Utc>>,
+) -> Result<Dispute, AppError> {
+    sqlx::query_as!(
+        Dispute,
+        r#"
+        INSERT INTO disputes
+            (payment_id, order_id, stripe_dispute_id, status, reason, amount, currency, evidence_due_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (stripe_dispute_id) DO UPDATE
+        SET status = EXCLUDED.status,
+            reason = EXCLUDED.reason,
+            evidence_due_by = EXCLUDED.evidence_due_by
+        RETURNING id, payment_id, order_id, stripe_dispute_id, status AS "status: _", reason, amount,
+                  currency, amount_lost, evidence_due_by, created_at, closed_at, last_updated
+        "#,
+        payment_id,
+        order_id,
+        stripe_dispute_id,
+        status as _,
+        reason,
+        amount,
+        currency,
+        evidence_due_by
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Marks a dispute as closed and records what it cost us.
+/// `closed_at` keeps the time of the first close if Stripe repeats the webhook.
+pub async fn close_dispute(
+    tx: &mut Transaction<'_, Postgres>,
+    dispute_id: Uuid,
+    amount_lost: Decimal,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE disputes SET amount_lost = $2, closed_at = COALESCE(closed_at, NOW()) WHERE id = $1",
+        dispute_id,
+        amount_lost
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Voids every still-valid ticket and add-on voucher of a disputed order, remembering the
+/// dispute that voided it. Checked-in tickets and redeemed vouchers are left alone; they are
+/// evidence that the service was delivered.
+/// In a group booking, only the tickets bought with the disputed payment are voided.
+/// Returns the number of tickets voided.
+pub async fn void_tickets_for_dispute(
+    tx: &mut Transaction<'_, Postgres>,
+    dispute_id: Uuid,
+    order_id: Uuid,
+    payment_id: Uuid,
+) -> Result<u64, AppError> {
+    let result = sqlx::query!(
+        "UPDATE tickets SET status = 'voided', voided_by_dispute_id = $1
+         WHERE order_id = $2 AND status = 'valid'
+           AND NOT EXISTS (
+               SELECT 1 FROM group_booking_shares s WHERE s.ticket_id = tickets.id AND s.payment_id <> $3
+           )",
+        dispute_id,
+        order_id,
+        payment_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    sqlx::query!(
+        "UPDATE vouchers SET status = 'voided', voided_by_dispute_id = $1
+         WHERE order_id = $2 AND status = 'valid'",
+        dispute_id,
+        order_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Makes the tickets and vouchers voided by a dispute valid again, e.g. after the dispute was won.
+/// Returns the number of tickets restored.
+pub async fn restore_tickets_for_dispute(
+    tx: &mut Transaction<'_, Postgres>,
+    dispute_id: Uuid,
+) -> Result<u64, AppError> {
+    let result = sqlx::query!(
+        "UPDATE tickets SET status = 'valid', voided_by_dispute_id = NULL
+         WHERE voided_by_dispute_id = $1 AND status = 'voided'",
+        dispute_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    sqlx::query!(
+        "UPDATE vouchers SET status = 'valid', voided_by_dispute_id = NULL
+         WHERE voided_by_dispute_id = $1 AND status = 'voided'",
+        dispute_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Lists disputes in the given statuses, those with the nearest evidence deadline first.
+pub async fn list_disputes_by_status(
+    pool: &PgPool,
+    statuses: &[DisputeStatus],
+) -> Result<Vec<Dispute>, AppError> {
+    sqlx::query_as!(
+        Dispute,
+        r#"
+        SELECT id, payment_id, order_id, stripe_dispute_id, status AS "status: _", reason, amount,
+               currency, amount_lost, evidence_due_by, created_at, closed_at, last_updated
+        FROM disputes
+        WHERE status = ANY($1)
+        ORDER BY evidence_due_by ASC NULLS LAST, created_at ASC
+        "#,
+        statuses as _
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Fetches the tickets of an order along with their event and check-in details.
+pub async fn list_ticket_evidence_for_order(
+    pool: &PgPool,
+    order_id: Uuid,
+) -> Result<Vec<DisputeTicketEvidence>, AppError> {
+    sqlx::query_as!(
+        DisputeTicketEvidence,
+        r#"
+        SELECT
+            t.id AS ticket_id,
+            t.event_id,
+            e.title AS event_title,
+            e.start_time AS event_start_time,
+            t.seat_id,
+            t.price_paid,
+            t.status AS "status: _",
+            t.checked_in_at
+        FROM tickets t
+        JOIN events e ON e.id = t.event_id
+        WHERE t.order_id = $1
+        ORDER BY t.created_at
+        "#,
+        order_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
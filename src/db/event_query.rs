@@ -18,13 +18,14 @@ where
         Event,
         r#"
         INSERT INTO events 
-            (title, description, start_time, end_time, venue_id, segment_id, genre_id, sub_genre_id, organizer_id)
+            (title, description, start_time, end_time, venue_id, segment_id, genre_id, sub_genre_id, organizer_id,
+             accessible_release_hours)
         VALUES 
-            ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ($1, $2, $3, $4, $5, $6, $7, $8, $9, COALESCE($10, 24))
         RETURNING 
             id, organizer_id, venue_id, segment_id, genre_id, sub_genre_id, title, 
             description, status AS "status: _", start_time, end_time, price_min, price_max, 
-            created_at, last_updated
+            accessible_release_hours, created_at, last_updated
         "#,
         payload.title,
         payload.description,
@@ -34,7 +35,8 @@ where
         payload.segment_id,
         payload.genre_id,
         payload.sub_genre_id,
-        organizer_id
+        organizer_id,
+        payload.accessible_release_hours
     )
     .fetch_one(executor)
     .await
@@ -49,7 +51,7 @@ pub async fn get_by_id(pool: &PgPool, id: i32) -> Result<Event, AppError> {
         SELECT 
             id, organizer_id, venue_id, segment_id, genre_id, sub_genre_id, title, 
             description, status AS "status: _", start_time, end_time, price_min, price_max, 
-            created_at, last_updated
+            accessible_release_hours, created_at, last_updated
         FROM events WHERE id = $1
         "#,
         id
@@ -67,7 +69,7 @@ pub async fn list_published(pool: &PgPool) -> Result<Vec<Event>, AppError> {
         SELECT 
             id, organizer_id, venue_id, segment_id, genre_id, sub_genre_id, title, 
             description, status AS "status: _", start_time, end_time, price_min, price_max, 
-            created_at, last_updated
+            accessible_release_hours, created_at, last_updated
         FROM events 
         WHERE status = 'published' AND start_time > NOW()
         ORDER BY start_time ASC
@@ -90,19 +92,21 @@ pub async fn update(pool: &PgPool, id: i32, payload: &UpdateEventPayload) -> Res
             status = COALESCE($3, status),
             start_time = COALESCE($4, start_time),
             end_time = COALESCE($5, end_time),
+            accessible_release_hours = COALESCE($7, accessible_release_hours),
             last_updated = NOW()
         WHERE id = $6
         RETURNING 
             id, organizer_id, venue_id, segment_id, genre_id, sub_genre_id, title, 
             description, status AS "status: _", start_time, end_time, price_min, price_max, 
-            created_at, last_updated
+            accessible_release_hours, created_at, last_updated
         "#,
         payload.title,
         payload.description,
         payload.status as _,
         payload.start_time,
         payload.end_time,
-        id
+        id,
+        payload.accessible_release_hours
     )
     .fetch_one(pool)
     .await
@@ -0,0 +1,139 @@
+use crate::{
+    errors::AppError,
+    models::{GiftCard, PurchaseGiftCardPayload},
+};
+use rust_decimal::Decimal;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// Creates a gift card bought with a pending order. It stays 'pending', and can't be
+/// redeemed, until the order is paid.
+pub async fn create_gift_card(
+    tx: &mut Transaction<'_, Postgres>,
+    code: &str,
+    order_id: Uuid,
+    purchaser_id: i32,
+    payload: &PurchaseGiftCardPayload,
+) -> Result<GiftCard, AppError> {
+    sqlx::query_as!(
+        GiftCard,
+        r#"
+        INSERT INTO gift_cards (code, order_id, purchaser_id, recipient_email, message, initial_amount, balance)
+        VALUES ($1, $2, $3, $4, $5, $6, $6)
+        RETURNING id, code, order_id, purchaser_id, recipient_email, message, initial_amount, balance,
+                  status AS "status: _", created_at
+        "#,
+        code,
+        order_id,
+        purchaser_id,
+        payload.recipient_email,
+        payload.message,
+        payload.amount
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Activates the gift cards bought with an order once it's paid, recording each card's value
+/// in its ledger. Returns the number of cards activated.
+pub async fn activate_for_order(tx: &mut Transaction<'_, Postgres>, order_id: Uuid) -> Result<u64, AppError> {
+    let result = sqlx::query!(
+        r#"
+        WITH activated AS (
+            UPDATE gift_cards SET status = 'active'
+            WHERE order_id = $1 AND status = 'pending'
+            RETURNING id, initial_amount
+        )
+        INSERT INTO gift_card_entries (gift_card_id, entry_type, amount)
+        SELECT id, 'issue', initial_amount FROM activated
+        "#,
+        order_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Returns whether an order bought any gift cards.
+pub async fn exists_for_order(tx: &mut Transaction<'_, Postgres>, order_id: Uuid) -> Result<bool, AppError> {
+    let exists = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM gift_cards WHERE order_id = $1) AS "exists!""#,
+        order_id
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(exists)
+}
+
+/// Cancels the gift cards of orders that expired unpaid.
+pub async fn cancel_for_orders(tx: &mut Transaction<'_, Postgres>, order_ids: &[Uuid]) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE gift_cards SET status = 'cancelled' WHERE order_id = ANY($1) AND status = 'pending'",
+        order_ids
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Fetches a gift card by its code and locks it until the end of the transaction.
+pub async fn lock_by_code(tx: &mut Transaction<'_, Postgres>, code: &str) -> Result<GiftCard, AppError> {
+    sqlx::query_as!(
+        GiftCard,
+        r#"
+        SELECT id, code, order_id, purchaser_id, recipient_email, message, initial_amount, balance,
+               status AS "status: _", created_at
+        FROM gift_cards WHERE code = $1
+        FOR UPDATE
+        "#,
+        code
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Takes `amount` off a gift card's balance for a user redeeming it, recording it in the
+/// card's ledger.
+pub async fn redeem(
+    tx: &mut Transaction<'_, Postgres>,
+    gift_card_id: i32,
+    user_id: i32,
+    amount: Decimal,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE gift_cards SET balance = balance - $2 WHERE id = $1",
+        gift_card_id,
+        amount
+    )
+    .execute(&mut **tx)
+    .await?;
+    sqlx::query!(
+        "INSERT INTO gift_card_entries (gift_card_id, entry_type, amount, user_id)
+         VALUES ($1, 'redemption', $2, $3)",
+        gift_card_id,
+        -amount,
+        user_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Lists the gift cards a user bought, newest first.
+pub async fn list_for_purchaser(pool: &PgPool, purchaser_id: i32) -> Result<Vec<GiftCard>, AppError> {
+    sqlx::query_as!(
+        GiftCard,
+        r#"
+        SELECT id, code, order_id, purchaser_id, recipient_email, message, initial_amount, balance,
+               status AS "status: _", created_at
+        FROM gift_cards WHERE purchaser_id = $1
+        ORDER BY created_at DESC
+        "#,
+        purchaser_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
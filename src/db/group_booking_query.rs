@@ -0,0 +1,457 @@
+use crate::{
+    db::realtime_query,
+    errors::AppError,
+    models::{GroupBooking, GroupBookingStatus, GroupShare, SeatStatusChange, Ticket},
+};
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// Creates a group booking for the pending order holding its tickets, with a share for
+/// every ticket of the order.
+pub async fn create_booking(
+    tx: &mut Transaction<'_, Postgres>,
+    order_id: Uuid,
+    organizer_id: i32,
+    event_id: i32,
+    invite_token: &str,
+    payment_deadline: DateTime<Utc>,
+) -> Result<GroupBooking, AppError> {
+    let booking = sqlx::query_as!(
+        GroupBooking,
+        r#"
+        INSERT INTO group_bookings (order_id, organizer_id, event_id, invite_token, payment_deadline)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, order_id, organizer_id, event_id, invite_token, payment_deadline,
+                  status AS "status: _", created_at
+        "#,
+        order_id,
+        organizer_id,
+        event_id,
+        invite_token,
+        payment_deadline
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+
+    sqlx::query!(
+        "INSERT INTO group_booking_shares (group_booking_id, order_item_id, offer_id, ticket_tier_id, seat_id, amount)
+         SELECT $1, oi.id, oi.offer_id, oi.ticket_tier_id, oi.seat_id, oi.unit_price + oi.unit_service_fee
+         FROM order_items oi CROSS JOIN generate_series(1, oi.quantity)
+         WHERE oi.order_id = $2
+         ORDER BY oi.id",
+        booking.id,
+        order_id
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(booking)
+}
+
+/// Fetches a group booking by its invite token.
+pub async fn get_by_token(pool: &PgPool, invite_token: &str) -> Result<GroupBooking, AppError> {
+    sqlx::query_as!(
+        GroupBooking,
+        r#"
+        SELECT id, order_id, organizer_id, event_id, invite_token, payment_deadline,
+               status AS "status: _", created_at
+        FROM group_bookings WHERE invite_token = $1
+        "#,
+        invite_token
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Fetches a group booking by its invite token and locks it until the end of the transaction.
+pub async fn lock_by_token(tx: &mut Transaction<'_, Postgres>, invite_token: &str) -> Result<GroupBooking, AppError> {
+    sqlx::query_as!(
+        GroupBooking,
+        r#"
+        SELECT id, order_id, organizer_id, event_id, invite_token, payment_deadline,
+               status AS "status: _", created_at
+        FROM group_bookings WHERE invite_token = $1
+        FOR UPDATE
+        "#,
+        invite_token
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Fetches the group booking an order holds the tickets of, if any, and locks it.
+pub async fn lock_by_order(tx: &mut Transaction<'_, Postgres>, order_id: Uuid) -> Result<Option<GroupBooking>, AppError> {
+    sqlx::query_as!(
+        GroupBooking,
+        r#"
+        SELECT id, order_id, organizer_id, event_id, invite_token, payment_deadline,
+               status AS "status: _", created_at
+        FROM group_bookings WHERE order_id = $1
+        FOR UPDATE
+        "#,
+        order_id
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Lists the group bookings a user organized, newest first.
+pub async fn list_for_organizer(pool: &PgPool, organizer_id: i32) -> Result<Vec<GroupBooking>, AppError> {
+    sqlx::query_as!(
+        GroupBooking,
+        r#"
+        SELECT id, order_id, organizer_id, event_id, invite_token, payment_deadline,
+               status AS "status: _", created_at
+        FROM group_bookings WHERE organizer_id = $1
+        ORDER BY created_at DESC
+        "#,
+        organizer_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Locks the open group bookings whose payment deadline has passed.
+pub async fn lock_due_bookings(tx: &mut Transaction<'_, Postgres>) -> Result<Vec<GroupBooking>, AppError> {
+    sqlx::query_as!(
+        GroupBooking,
+        r#"
+        SELECT id, order_id, organizer_id, event_id, invite_token, payment_deadline,
+               status AS "status: _", created_at
+        FROM group_bookings WHERE status = 'open' AND payment_deadline < NOW()
+        ORDER BY payment_deadline
+        FOR UPDATE SKIP LOCKED
+        "#
+    )
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Moves a group booking to a new status.
+pub async fn set_status(
+    tx: &mut Transaction<'_, Postgres>,
+    booking_id: i32,
+    status: GroupBookingStatus,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE group_bookings SET status = $2 WHERE id = $1",
+        booking_id,
+        status as GroupBookingStatus
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+// --- Shares ---
+
+/// Lists the shares of a group booking.
+pub async fn list_shares<'e, E>(executor: E, booking_id: i32) -> Result<Vec<GroupShare>, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as!(
+        GroupShare,
+        r#"
+        SELECT id, offer_id, ticket_tier_id, seat_id, amount, status AS "status: _", user_id,
+               claim_expires_at, paid_at
+        FROM group_booking_shares WHERE group_booking_id = $1
+        ORDER BY id
+        "#,
+        booking_id
+    )
+    .fetch_all(executor)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Claims unpaid shares of a group booking for a member about to pay for them, until
+/// `claim_expires_at`. Shares already claimed or paid are left alone, so fewer shares than
+/// asked for may be returned.
+pub async fn claim_shares(
+    tx: &mut Transaction<'_, Postgres>,
+    booking_id: i32,
+    share_ids: &[i32],
+    user_id: i32,
+    claim_expires_at: DateTime<Utc>,
+) -> Result<Vec<GroupShare>, AppError> {
+    sqlx::query_as!(
+        GroupShare,
+        r#"
+        UPDATE group_booking_shares
+        SET status = 'pending', user_id = $3, claim_expires_at = $4
+        WHERE group_booking_id = $1 AND id = ANY($2) AND status = 'unpaid'
+        RETURNING id, offer_id, ticket_tier_id, seat_id, amount, status AS "status: _", user_id,
+                  claim_expires_at, paid_at
+        "#,
+        booking_id,
+        share_ids,
+        user_id,
+        claim_expires_at
+    )
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Links claimed shares to the payment paying for them.
+pub async fn set_payment(tx: &mut Transaction<'_, Postgres>, share_ids: &[i32], payment_id: Uuid) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE group_booking_shares SET payment_id = $2 WHERE id = ANY($1)",
+        share_ids,
+        payment_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Locks the shares a payment was made for that aren't paid or released yet: still claimed
+/// for it, or freed when the claim lapsed but not claimed by anyone else since.
+pub async fn lock_shares_for_payment(
+    tx: &mut Transaction<'_, Postgres>,
+    payment_id: Uuid,
+) -> Result<Vec<GroupShare>, AppError> {
+    sqlx::query_as!(
+        GroupShare,
+        r#"
+        SELECT id, offer_id, ticket_tier_id, seat_id, amount, status AS "status: _", user_id,
+               claim_expires_at, paid_at
+        FROM group_booking_shares
+        WHERE payment_id = $1 AND status IN ('pending', 'unpaid')
+        ORDER BY id
+        FOR UPDATE
+        "#,
+        payment_id
+    )
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Returns whether a payment already paid for its shares, e.g. when Stripe retries the webhook.
+pub async fn has_paid_shares(tx: &mut Transaction<'_, Postgres>, payment_id: Uuid) -> Result<bool, AppError> {
+    let exists = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM group_booking_shares WHERE payment_id = $1 AND status = 'paid') AS "exists!""#,
+        payment_id
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(exists)
+}
+
+/// Marks shares as paid by the member who claimed them. Returns them.
+pub async fn mark_paid(tx: &mut Transaction<'_, Postgres>, share_ids: &[i32]) -> Result<Vec<GroupShare>, AppError> {
+    sqlx::query_as!(
+        GroupShare,
+        r#"
+        UPDATE group_booking_shares
+        SET status = 'paid', paid_at = NOW(), claim_expires_at = NULL
+        WHERE id = ANY($1)
+        RETURNING id, offer_id, ticket_tier_id, seat_id, amount, status AS "status: _", user_id,
+                  claim_expires_at, paid_at
+        "#,
+        share_ids
+    )
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Issues the ticket of each paid share to the member who paid for it.
+pub async fn issue_tickets(
+    tx: &mut Transaction<'_, Postgres>,
+    order_id: Uuid,
+    event_id: i32,
+    shares: &[GroupShare],
+) -> Result<Vec<Ticket>, AppError> {
+    let mut tickets = Vec::with_capacity(shares.len());
+    for share in shares {
+        let ticket = sqlx::query_as!(
+            Ticket,
+            r#"
+            INSERT INTO tickets (order_id, user_id, event_id, ticket_tier_id, seat_id, price_paid)
+            SELECT $1, s.user_id, $2, s.ticket_tier_id, s.seat_id, oi.unit_price
+            FROM group_booking_shares s JOIN order_items oi ON oi.id = s.order_item_id
+            WHERE s.id = $3
+            RETURNING id, order_id, user_id, event_id, ticket_tier_id, seat_id, price_paid,
+                      qr_code_data, status AS "status: _", created_at, checked_in_at
+            "#,
+            order_id,
+            event_id,
+            share.id
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+
+        sqlx::query!("UPDATE group_booking_shares SET ticket_id = $2 WHERE id = $1", share.id, ticket.id)
+            .execute(&mut **tx)
+            .await?;
+        tickets.push(ticket);
+    }
+    Ok(tickets)
+}
+
+/// Marks the seats of paid shares as sold.
+pub async fn mark_seats_sold(
+    tx: &mut Transaction<'_, Postgres>,
+    order_id: Uuid,
+    seat_ids: &[i32],
+) -> Result<Vec<SeatStatusChange>, AppError> {
+    let changes = sqlx::query_as!(
+        SeatStatusChange,
+        r#"
+        UPDATE event_seats
+        SET status = 'sold', lock_expires_at = NULL, locked_by_user_id = NULL
+        WHERE order_id = $1 AND seat_id = ANY($2) AND status = 'locked'
+        RETURNING event_id, seat_id, status AS "status: _"
+        "#,
+        order_id,
+        seat_ids
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    realtime_query::notify_seat_changes(&mut **tx, &changes).await?;
+    Ok(changes)
+}
+
+/// Counts the shares of a group booking that aren't paid yet.
+pub async fn count_outstanding(tx: &mut Transaction<'_, Postgres>, booking_id: i32) -> Result<i64, AppError> {
+    let count = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM group_booking_shares WHERE group_booking_id = $1 AND status <> 'paid'"#,
+        booking_id
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(count)
+}
+
+/// Frees the shares whose claim lapsed before their payment went through, so other members
+/// can pay for them, and fails those payments. The shares keep their payment and member until
+/// someone else claims them, so a payment that still goes through can pay for them.
+/// Returns the PaymentIntent IDs of the failed payments, to cancel with the provider.
+pub async fn release_lapsed_claims(tx: &mut Transaction<'_, Postgres>) -> Result<Vec<String>, AppError> {
+    let payment_ids = sqlx::query_scalar!(
+        r#"
+        WITH lapsed AS (
+            SELECT id, payment_id FROM group_booking_shares
+            WHERE status = 'pending' AND claim_expires_at < NOW()
+            FOR UPDATE SKIP LOCKED
+        ), freed AS (
+            UPDATE group_booking_shares s
+            SET status = 'unpaid', claim_expires_at = NULL
+            FROM lapsed WHERE s.id = lapsed.id
+        )
+        SELECT DISTINCT payment_id AS "payment_id!" FROM lapsed WHERE payment_id IS NOT NULL
+        "#
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    fail_payments(tx, &payment_ids).await
+}
+
+/// Fails the payments in progress for the shares of a group booking, e.g. at its deadline.
+/// Returns their PaymentIntent IDs, to cancel with the provider.
+pub async fn fail_claimed_payments(tx: &mut Transaction<'_, Postgres>, booking_id: i32) -> Result<Vec<String>, AppError> {
+    let payment_ids = sqlx::query_scalar!(
+        r#"
+        SELECT DISTINCT payment_id AS "payment_id!" FROM group_booking_shares
+        WHERE group_booking_id = $1 AND status = 'pending' AND payment_id IS NOT NULL
+        "#,
+        booking_id
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    fail_payments(tx, &payment_ids).await
+}
+
+/// Releases the shares of a group booking still unpaid at its deadline. They keep their
+/// payment, so one that still goes through is refunded. Returns the released shares.
+pub async fn release_unpaid_shares(tx: &mut Transaction<'_, Postgres>, booking_id: i32) -> Result<Vec<GroupShare>, AppError> {
+    sqlx::query_as!(
+        GroupShare,
+        r#"
+        UPDATE group_booking_shares
+        SET status = 'released', claim_expires_at = NULL
+        WHERE group_booking_id = $1 AND status IN ('unpaid', 'pending')
+        RETURNING id, offer_id, ticket_tier_id, seat_id, amount, status AS "status: _", user_id,
+                  claim_expires_at, paid_at
+        "#,
+        booking_id
+    )
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+async fn fail_payments(tx: &mut Transaction<'_, Postgres>, payment_ids: &[Uuid]) -> Result<Vec<String>, AppError> {
+    let payment_intent_ids = sqlx::query_scalar!(
+        "UPDATE payments SET status = 'failed' WHERE id = ANY($1) AND status = 'pending'
+         RETURNING stripe_payment_intent_id",
+        payment_ids
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+    Ok(payment_intent_ids)
+}
+
+/// Takes the released shares of a group booking off its order, so the order only holds
+/// what was paid for, and recomputes the order's totals.
+pub async fn trim_order(tx: &mut Transaction<'_, Postgres>, booking_id: i32, order_id: Uuid) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        WITH released AS (
+            SELECT order_item_id, COUNT(*)::int AS count FROM group_booking_shares
+            WHERE group_booking_id = $1 AND status = 'released' AND order_item_id IS NOT NULL
+            GROUP BY order_item_id
+        ), trimmed AS (
+            UPDATE order_items oi SET quantity = oi.quantity - r.count
+            FROM released r WHERE oi.id = r.order_item_id AND oi.quantity > r.count
+        )
+        DELETE FROM order_items oi
+        USING released r WHERE oi.id = r.order_item_id AND oi.quantity <= r.count
+        "#,
+        booking_id
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE orders o SET
+            subtotal = t.subtotal,
+            service_fee = t.service_fee,
+            total_amount = t.subtotal + t.service_fee
+        FROM (
+            SELECT COALESCE(SUM(quantity * unit_price), 0) AS subtotal,
+                   COALESCE(SUM(quantity * unit_service_fee), 0) AS service_fee
+            FROM order_items WHERE order_id = $1
+        ) t
+        WHERE o.id = $1
+        "#,
+        order_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Returns whether an order holds the tickets of a group booking.
+pub async fn is_group_order(tx: &mut Transaction<'_, Postgres>, order_id: Uuid) -> Result<bool, AppError> {
+    let exists = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM group_bookings WHERE order_id = $1) AS "exists!""#,
+        order_id
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(exists)
+}
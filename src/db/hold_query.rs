@@ -0,0 +1,212 @@
+use crate::{
+    db::realtime_query,
+    errors::AppError,
+    models::{SeatHold, SeatHoldSummary, SeatStatus, SeatStatusChange, UpdateSeatHoldPayload},
+};
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// Creates a new hold for an event.
+pub async fn create_hold(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+    name: &str,
+    release_at: Option<DateTime<Utc>>,
+    created_by: i32,
+) -> Result<SeatHold, AppError> {
+    sqlx::query_as!(
+        SeatHold,
+        r#"
+        INSERT INTO seat_holds (event_id, name, release_at, created_by)
+        VALUES ($1, $2, $3, $4)
+        RETURNING *
+        "#,
+        event_id,
+        name,
+        release_at,
+        created_by
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| hold_name_conflict(e, name))
+}
+
+/// Fetches a hold by its ID.
+pub async fn get_hold<'e, E>(executor: E, hold_id: i32) -> Result<SeatHold, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as!(SeatHold, "SELECT * FROM seat_holds WHERE id = $1", hold_id)
+        .fetch_one(executor)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Fetches a hold and locks it until the end of the transaction, so it can't be
+/// released while its seats are being changed.
+pub async fn lock_hold(tx: &mut Transaction<'_, Postgres>, hold_id: i32) -> Result<SeatHold, AppError> {
+    sqlx::query_as!(SeatHold, "SELECT * FROM seat_holds WHERE id = $1 FOR UPDATE", hold_id)
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Lists the holds of an event, open ones first, with their seat counts.
+pub async fn list_holds_for_event(pool: &PgPool, event_id: i32) -> Result<Vec<SeatHoldSummary>, AppError> {
+    sqlx::query_as!(
+        SeatHoldSummary,
+        r#"
+        SELECT
+            h.id,
+            h.event_id,
+            h.name,
+            h.release_at,
+            h.released_at,
+            (SELECT COUNT(*) FROM event_seats es WHERE es.hold_id = h.id AND es.status = 'held') AS "held_seats!",
+            (SELECT COUNT(*) FROM tickets t WHERE t.seat_hold_id = h.id) AS "comped_seats!"
+        FROM seat_holds h
+        WHERE h.event_id = $1
+        ORDER BY h.released_at IS NOT NULL, h.created_at
+        "#,
+        event_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Updates the name and/or release time of a hold.
+pub async fn update_hold(
+    pool: &PgPool,
+    hold_id: i32,
+    payload: &UpdateSeatHoldPayload,
+) -> Result<SeatHold, AppError> {
+    sqlx::query_as!(
+        SeatHold,
+        r#"
+        UPDATE seat_holds
+        SET name = COALESCE($2, name),
+            release_at = COALESCE($3, release_at)
+        WHERE id = $1
+        RETURNING *
+        "#,
+        hold_id,
+        payload.name,
+        payload.release_at
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| hold_name_conflict(e, payload.name.as_deref().unwrap_or_default()))
+}
+
+/// Closes a hold. Its seats must already have been released.
+pub async fn mark_hold_released(tx: &mut Transaction<'_, Postgres>, hold_id: i32) -> Result<(), AppError> {
+    sqlx::query!("UPDATE seat_holds SET released_at = NOW() WHERE id = $1", hold_id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// Lists the open holds whose scheduled release time has passed.
+pub async fn list_due_holds(pool: &PgPool) -> Result<Vec<i32>, AppError> {
+    let ids = sqlx::query_scalar!(
+        "SELECT id FROM seat_holds WHERE released_at IS NULL AND release_at <= NOW() ORDER BY release_at"
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(ids)
+}
+
+/// Puts the given seats of an event on a hold. Only available seats are taken.
+/// Live seat maps are notified as part of the transaction.
+pub async fn hold_seats(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+    hold_id: i32,
+    seat_ids: &[i32],
+) -> Result<Vec<SeatStatusChange>, AppError> {
+    let changes = sqlx::query_as!(
+        SeatStatusChange,
+        r#"
+        UPDATE event_seats
+        SET status = 'held', hold_id = $2
+        WHERE event_id = $1 AND seat_id = ANY($3) AND status = 'available'
+        RETURNING event_id, seat_id, status AS "status: _"
+        "#,
+        event_id,
+        hold_id,
+        seat_ids
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    realtime_query::notify_seat_changes(&mut **tx, &changes).await?;
+    Ok(changes)
+}
+
+/// Puts seats still on a hold back on general sale: the given ones, or all of them.
+/// Live seat maps are notified as part of the transaction.
+pub async fn release_hold_seats(
+    tx: &mut Transaction<'_, Postgres>,
+    hold_id: i32,
+    seat_ids: Option<&[i32]>,
+) -> Result<Vec<SeatStatusChange>, AppError> {
+    let changes = sqlx::query_as!(
+        SeatStatusChange,
+        r#"
+        UPDATE event_seats
+        SET status = 'available', hold_id = NULL
+        WHERE hold_id = $1 AND status = 'held' AND ($2::int[] IS NULL OR seat_id = ANY($2))
+        RETURNING event_id, seat_id, status AS "status: _"
+        "#,
+        hold_id,
+        seat_ids as Option<&[i32]>
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    realtime_query::notify_seat_changes(&mut **tx, &changes).await?;
+    Ok(changes)
+}
+
+/// Marks held seats as sold to a complimentary order and returns each seat's tier.
+/// Only seats still on the hold are taken; the caller compares the result with what it asked for.
+/// Live seat maps are notified as part of the transaction.
+pub async fn sell_held_seats(
+    tx: &mut Transaction<'_, Postgres>,
+    hold_id: i32,
+    seat_ids: &[i32],
+    order_id: Uuid,
+) -> Result<Vec<(i32, i32)>, AppError> {
+    let rows = sqlx::query!(
+        r#"
+        UPDATE event_seats
+        SET status = 'sold', order_id = $3
+        WHERE hold_id = $1 AND status = 'held' AND seat_id = ANY($2)
+        RETURNING event_id, seat_id, ticket_tier_id
+        "#,
+        hold_id,
+        seat_ids,
+        order_id
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    let changes: Vec<SeatStatusChange> = rows
+        .iter()
+        .map(|r| SeatStatusChange { event_id: r.event_id, seat_id: r.seat_id, status: SeatStatus::Sold })
+        .collect();
+    realtime_query::notify_seat_changes(&mut **tx, &changes).await?;
+    Ok(rows.into_iter().map(|r| (r.seat_id, r.ticket_tier_id)).collect())
+}
+
+/// Turns the unique violation on an open hold's name into a readable error.
+fn hold_name_conflict(e: sqlx::Error, name: &str) -> AppError {
+    match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            AppError::BadRequest(format!("This event already has an open hold named '{}'.", name))
+        }
+        e => AppError::from(e),
+    }
+}
@@ -0,0 +1,356 @@
+use crate::{
+    errors::AppError,
+    models::{CreateLotteryPayload, Lottery, LotteryDrawResult, LotteryEntry, LotteryEntryStatus},
+};
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// Creates a lottery for an offer and hides the offer from the public sale, so it is
+/// only sold to the lottery's winners.
+pub async fn create_lottery(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+    created_by: i32,
+    payload: &CreateLotteryPayload,
+    seed: &str,
+    seed_hash: &str,
+) -> Result<Lottery, AppError> {
+    let lottery = sqlx::query_as!(
+        Lottery,
+        r#"
+        INSERT INTO lotteries (event_id, offer_id, registration_opens_at, registration_closes_at, max_per_user,
+                               purchase_window_hours, seed, seed_hash, created_by)
+        VALUES ($1, $2, $3, $4, $5, COALESCE($6, 48), $7, $8, $9)
+        RETURNING id, event_id, offer_id, registration_opens_at, registration_closes_at, max_per_user,
+                  purchase_window_hours, seed, seed_hash, input_hash, capacity, drawn_at, created_by,
+                  created_at, last_updated
+        "#,
+        event_id,
+        payload.offer_id,
+        payload.registration_opens_at,
+        payload.registration_closes_at,
+        payload.max_per_user,
+        payload.purchase_window_hours,
+        seed,
+        seed_hash,
+        created_by
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            AppError::BadRequest("This offer is already sold by lottery.".to_string())
+        }
+        e => AppError::from(e),
+    })?;
+
+    sqlx::query!("UPDATE offers SET is_presale = TRUE WHERE id = $1", payload.offer_id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(lottery)
+}
+
+/// Fetches a single lottery by its ID.
+pub async fn get_lottery<'e, E>(executor: E, lottery_id: i32) -> Result<Lottery, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as!(
+        Lottery,
+        r#"
+        SELECT id, event_id, offer_id, registration_opens_at, registration_closes_at, max_per_user,
+               purchase_window_hours, seed, seed_hash, input_hash, capacity, drawn_at, created_by,
+               created_at, last_updated
+        FROM lotteries WHERE id = $1
+        "#,
+        lottery_id
+    )
+    .fetch_one(executor)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Fetches a lottery and locks it until the end of the transaction.
+pub async fn lock_lottery(tx: &mut Transaction<'_, Postgres>, lottery_id: i32) -> Result<Lottery, AppError> {
+    sqlx::query_as!(
+        Lottery,
+        r#"
+        SELECT id, event_id, offer_id, registration_opens_at, registration_closes_at, max_per_user,
+               purchase_window_hours, seed, seed_hash, input_hash, capacity, drawn_at, created_by,
+               created_at, last_updated
+        FROM lotteries WHERE id = $1
+        FOR UPDATE
+        "#,
+        lottery_id
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Lists the lotteries of an event, by when their registration opens.
+pub async fn list_lotteries_for_event(pool: &PgPool, event_id: i32) -> Result<Vec<Lottery>, AppError> {
+    sqlx::query_as!(
+        Lottery,
+        r#"
+        SELECT id, event_id, offer_id, registration_opens_at, registration_closes_at, max_per_user,
+               purchase_window_hours, seed, seed_hash, input_hash, capacity, drawn_at, created_by,
+               created_at, last_updated
+        FROM lotteries WHERE event_id = $1
+        ORDER BY registration_opens_at, id
+        "#,
+        event_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Returns how many users registered for a lottery and how many tickets they asked for.
+pub async fn count_registrations(pool: &PgPool, lottery_id: i32) -> Result<(i64, i64), AppError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT COUNT(*) AS "registrations!", COALESCE(SUM(quantity), 0)::bigint AS "requested_tickets!"
+        FROM lottery_entries WHERE lottery_id = $1
+        "#,
+        lottery_id
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok((row.registrations, row.requested_tickets))
+}
+
+/// Lists the IDs of the given offers that are sold by lottery.
+pub async fn list_lottery_offers(pool: &PgPool, offer_ids: &[i32]) -> Result<Vec<i32>, AppError> {
+    let ids = sqlx::query_scalar!("SELECT offer_id FROM lotteries WHERE offer_id = ANY($1)", offer_ids)
+        .fetch_all(pool)
+        .await?;
+    Ok(ids)
+}
+
+/// Registers a user for a lottery, or changes the quantity of their registration.
+pub async fn upsert_entry(
+    pool: &PgPool,
+    lottery_id: i32,
+    user_id: i32,
+    quantity: i32,
+) -> Result<LotteryEntry, AppError> {
+    sqlx::query_as!(
+        LotteryEntry,
+        r#"
+        INSERT INTO lottery_entries (lottery_id, user_id, quantity)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (lottery_id, user_id) DO UPDATE SET quantity = EXCLUDED.quantity
+        RETURNING id, lottery_id, user_id, quantity, status AS "status: _", draw_rank, allocated_quantity,
+                  purchase_expires_at, order_id, created_at, last_updated
+        "#,
+        lottery_id,
+        user_id,
+        quantity
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Fetches a user's entry in a lottery.
+pub async fn get_entry(pool: &PgPool, lottery_id: i32, user_id: i32) -> Result<LotteryEntry, AppError> {
+    sqlx::query_as!(
+        LotteryEntry,
+        r#"
+        SELECT id, lottery_id, user_id, quantity, status AS "status: _", draw_rank, allocated_quantity,
+               purchase_expires_at, order_id, created_at, last_updated
+        FROM lottery_entries WHERE lottery_id = $1 AND user_id = $2
+        "#,
+        lottery_id,
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Fetches a user's entry in a lottery and locks it until the end of the transaction.
+pub async fn lock_entry(
+    tx: &mut Transaction<'_, Postgres>,
+    lottery_id: i32,
+    user_id: i32,
+) -> Result<LotteryEntry, AppError> {
+    sqlx::query_as!(
+        LotteryEntry,
+        r#"
+        SELECT id, lottery_id, user_id, quantity, status AS "status: _", draw_rank, allocated_quantity,
+               purchase_expires_at, order_id, created_at, last_updated
+        FROM lottery_entries WHERE lottery_id = $1 AND user_id = $2
+        FOR UPDATE
+        "#,
+        lottery_id,
+        user_id
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Withdraws a user's registration from a lottery. Returns the number of rows deleted.
+pub async fn delete_entry(pool: &PgPool, lottery_id: i32, user_id: i32) -> Result<u64, AppError> {
+    let result = sqlx::query!(
+        "DELETE FROM lottery_entries WHERE lottery_id = $1 AND user_id = $2 AND status = 'registered'",
+        lottery_id,
+        user_id
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Locks the registrations of a lottery, in the order they were made.
+pub async fn lock_registered_entries(
+    tx: &mut Transaction<'_, Postgres>,
+    lottery_id: i32,
+) -> Result<Vec<LotteryEntry>, AppError> {
+    sqlx::query_as!(
+        LotteryEntry,
+        r#"
+        SELECT id, lottery_id, user_id, quantity, status AS "status: _", draw_rank, allocated_quantity,
+               purchase_expires_at, order_id, created_at, last_updated
+        FROM lottery_entries
+        WHERE lottery_id = $1 AND status = 'registered'
+        ORDER BY id
+        FOR UPDATE
+        "#,
+        lottery_id
+    )
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Returns how many general admission tickets an offer can still sell, bounded by its tier,
+/// and locks the offer until the end of the transaction.
+pub async fn lock_offer_capacity(tx: &mut Transaction<'_, Postgres>, offer_id: i32) -> Result<i32, AppError> {
+    let capacity = sqlx::query_scalar!(
+        r#"
+        SELECT GREATEST(LEAST(o.quantity_for_sale - o.quantity_sold, tt.total_inventory - tt.quantity_sold), 0)
+            AS "capacity!"
+        FROM offers o
+        JOIN ticket_tiers tt ON tt.id = o.ticket_tier_id
+        WHERE o.id = $1
+        FOR UPDATE OF o
+        "#,
+        offer_id
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(capacity)
+}
+
+/// Records the outcome of a draw: each entry's rank, and the tickets it won. Winners get
+/// a purchase window of `window_hours`; the rest lose.
+pub async fn record_draw(
+    tx: &mut Transaction<'_, Postgres>,
+    entry_ids: &[i32],
+    ranks: &[i32],
+    allocations: &[i32],
+    window_hours: i32,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        UPDATE lottery_entries le
+        SET draw_rank = d.draw_rank,
+            allocated_quantity = d.allocated,
+            status = CASE WHEN d.allocated > 0 THEN 'won' ELSE 'lost' END::lottery_entry_status,
+            purchase_expires_at = CASE WHEN d.allocated > 0 THEN NOW() + make_interval(hours => $4) END
+        FROM UNNEST($1::int[], $2::int[], $3::int[]) AS d(entry_id, draw_rank, allocated)
+        WHERE le.id = d.entry_id
+        "#,
+        entry_ids,
+        ranks,
+        allocations,
+        window_hours
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Marks a lottery as drawn, publishing the hash of the registrations it ran on.
+pub async fn finish_draw(
+    tx: &mut Transaction<'_, Postgres>,
+    lottery_id: i32,
+    input_hash: &str,
+    capacity: i32,
+) -> Result<Lottery, AppError> {
+    sqlx::query_as!(
+        Lottery,
+        r#"
+        UPDATE lotteries SET input_hash = $2, capacity = $3, drawn_at = NOW()
+        WHERE id = $1
+        RETURNING id, event_id, offer_id, registration_opens_at, registration_closes_at, max_per_user,
+                  purchase_window_hours, seed, seed_hash, input_hash, capacity, drawn_at, created_by,
+                  created_at, last_updated
+        "#,
+        lottery_id,
+        input_hash,
+        capacity
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Lists the drawn registrations of a lottery, in draw order.
+pub async fn list_draw_results<'e, E>(executor: E, lottery_id: i32) -> Result<Vec<LotteryDrawResult>, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as!(
+        LotteryDrawResult,
+        r#"
+        SELECT id AS entry_id, quantity, draw_rank, allocated_quantity
+        FROM lottery_entries
+        WHERE lottery_id = $1 AND draw_rank IS NOT NULL
+        ORDER BY draw_rank
+        "#,
+        lottery_id
+    )
+    .fetch_all(executor)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Locks the winning entries whose purchase window has passed.
+pub async fn lock_lapsed_entries(tx: &mut Transaction<'_, Postgres>) -> Result<Vec<LotteryEntry>, AppError> {
+    sqlx::query_as!(
+        LotteryEntry,
+        r#"
+        SELECT id, lottery_id, user_id, quantity, status AS "status: _", draw_rank, allocated_quantity,
+               purchase_expires_at, order_id, created_at, last_updated
+        FROM lottery_entries
+        WHERE status = 'won' AND purchase_expires_at <= NOW()
+        ORDER BY purchase_expires_at
+        FOR UPDATE SKIP LOCKED
+        "#
+    )
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Closes a winning entry: purchased with an order, or expired.
+pub async fn close_entry(
+    tx: &mut Transaction<'_, Postgres>,
+    entry_id: i32,
+    status: LotteryEntryStatus,
+    order_id: Option<Uuid>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE lottery_entries SET status = $2, order_id = $3 WHERE id = $1",
+        entry_id,
+        status as LotteryEntryStatus,
+        order_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
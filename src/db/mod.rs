@@ -20,10 +20,50 @@ pub mod pricing_query;
 // Query module for the complex seating chart system.
 pub mod seating_query;
 
+// Query module for organizer seat holds.
+pub mod hold_query;
+
+// Query module for presale access codes and the offers users unlocked with them.
+pub mod access_code_query;
+
+// Query module for the virtual waiting rooms in front of high-demand on-sales.
+pub mod waiting_room_query;
+
+// Query module for the waitlists of sold-out events and the inventory reserved for them.
+pub mod waitlist_query;
+
+// Query module for lottery sales: registrations, the seeded draw and winners' purchase windows.
+pub mod lottery_query;
+
+// Query module for the add-on catalogs of events and the vouchers issued for add-ons.
+pub mod addon_query;
+
+// Query module for users' carts and the inventory held in them.
+pub mod cart_query;
+
+// Query modules for gift cards and the store credit they're redeemed into.
+pub mod gift_card_query;
+pub mod store_credit_query;
+
+// Query module for group bookings, whose members each pay their own share.
+pub mod group_booking_query;
+
+// Cross-instance notifications of seat and inventory changes (Postgres LISTEN/NOTIFY).
+pub mod realtime_query;
+
 // Query modules for the core e-commerce transactional loop.
 pub mod order_query;
 pub mod ticket_query;
 pub mod payment_query;
 
+// Query module for organizer settlement reporting.
+pub mod settlement_query;
+
+// Query module for payment disputes and chargebacks.
+pub mod dispute_query;
+
+// Query module for the payment reconciliation job.
+pub mod reconciliation_query;
+
 // Query modules for Stripes Multivendor organizers
 pub mod organizer_query; 
\ No newline at end of file
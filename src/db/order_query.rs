@@ -1,11 +1,19 @@
 use crate::{
+    db::{addon_query, pricing_query, realtime_query},
     errors::AppError,
-    models::{CreateOrderPayload, Order},
+    models::{BundleComponent, CreateOrderPayload, Order, OrderBundle, OrderItem, SeatStatus, SeatStatusChange},
 };
-use rust_decimal::Decimal;
-use sqlx::{Postgres, Transaction};
+use rust_decimal::{prelude::ToPrimitive, Decimal, RoundingStrategy};
+use sqlx::{Executor, PgPool, Postgres, Transaction};
 use uuid::Uuid;
 
+/// The most a buyer can donate through a single add-on.
+const MAX_DONATION: i64 = 100_000;
+
+/// An order line before it is recorded:
+/// `(offer_id, event_id, ticket_tier_id, seat_id, quantity, unit_price, order_bundle_id)`.
+type OrderLine = (i32, i32, i32, Option<i32>, i32, Decimal, Option<i32>);
+
 /// Creates a new order in a 'pending' state and locks the associated seats/inventory.
 /// This is the first step in the checkout process and MUST be executed within a transaction.
 /// It calculates the total price based on the items provided.
@@ -18,57 +26,156 @@ pub async fn create_pending_order(
     // 1. Calculate totals and gather item details from the database
     let mut subtotal = Decimal::ZERO;
     let service_fee_per_ticket = Decimal::new(250, 2); // Example: $2.50 fee
+    // Recorded once the order exists.
+    let mut lines: Vec<OrderLine> = Vec::with_capacity(payload.items.len());
+    // Bundles bought: (offer_id, event_id, packages, package_price, components).
+    let mut bundles: Vec<(i32, i32, i32, Decimal, Vec<BundleComponent>)> = Vec::new();
+    // The service fee is charged per ticket, including the tickets in bundles.
+    let mut ticket_count: i64 = 0;
+    // Seat locks expire together with the order, so the expiry sweep releases both at once.
+    let expires_at = chrono::Utc::now() + chrono::Duration::minutes(order_expiry_minutes);
+    // What changed, announced to live seat maps once everything is locked.
+    let mut locked_seats: Vec<SeatStatusChange> = Vec::new();
+    let mut touched_offers: Vec<i32> = Vec::new();
 
     for item in &payload.items {
-        let offer_price: (Decimal,) = sqlx::query_as("SELECT price FROM offers WHERE id = $1")
-            .bind(item.offer_id)
-            .fetch_one(&mut **tx)
-            .await?;
-        
-        subtotal += offer_price.0 * Decimal::from(item.quantity);
+        if item.seat_id.is_some() && item.quantity != 1 {
+            return Err(AppError::BadRequest("Reserved seat items must have a quantity of 1.".to_string()));
+        }
+
+        let offer = get_checkout_offer(tx, item.offer_id, user_id).await?;
+        let (offer_price, event_id, ticket_tier_id) = (offer.price, offer.event_id, offer.ticket_tier_id);
+
+        subtotal += offer_price * Decimal::from(item.quantity);
+
+        // A bundle takes its packages from the offer and every component's share from its tier
+        // or add-on. Any shortfall fails the whole checkout.
+        if offer.is_bundle {
+            if item.seat_id.is_some() {
+                return Err(AppError::BadRequest(format!(
+                    "Offer {} sells packages and can't be booked for a seat.",
+                    item.offer_id
+                )));
+            }
+            take_offer_inventory(tx, item.offer_id, item.quantity).await?;
+            let components = pricing_query::list_bundle_components(&mut **tx, item.offer_id).await?;
+            for component in &components {
+                let quantity = component.quantity * item.quantity;
+                if let Some(tier_id) = component.ticket_tier_id {
+                    take_tier_inventory(tx, tier_id, quantity).await?;
+                    ticket_count += i64::from(quantity);
+                } else if let Some(addon_item_id) = component.addon_item_id {
+                    addon_query::take_inventory(tx, addon_item_id, None, quantity).await?;
+                }
+            }
+            if !components.iter().any(|c| c.ticket_tier_id.is_some()) {
+                return Err(AppError::BadRequest(format!("Offer {} has no tickets in its packages.", item.offer_id)));
+            }
+            bundles.push((item.offer_id, event_id, item.quantity, offer_price, components));
+            touched_offers.push(item.offer_id);
+            continue;
+        }
+
+        ticket_count += i64::from(item.quantity);
+        lines.push((item.offer_id, event_id, ticket_tier_id, item.seat_id, item.quantity, offer_price, None));
 
         // 2. Lock the inventory
         if let Some(seat_id) = item.seat_id {
-            // Lock a specific seat for reserved seating
+            // Lock a specific seat for reserved seating. A seat the buyer already holds
+            // (e.g. from a best-available pick) is taken over by the order.
+            // The seat must be zoned into the offer's tier, so it's sold at that tier's price.
             let result = sqlx::query!(
-                "UPDATE event_seats SET status = 'locked'
-                 WHERE event_id = (SELECT tt.event_id FROM ticket_tiers tt JOIN offers o ON tt.id = o.ticket_tier_id WHERE o.id = $1)
-                 AND seat_id = $2 AND status = 'available'",
-                item.offer_id,
-                seat_id
+                "UPDATE event_seats SET status = 'locked', lock_expires_at = $1, locked_by_user_id = $4
+                 WHERE event_id = $2 AND seat_id = $3 AND ticket_tier_id = $5
+                   AND (status = 'available'
+                        OR (status = 'locked' AND order_id IS NULL AND locked_by_user_id = $4
+                            AND waitlist_entry_id IS NULL AND cart_id IS NULL))",
+                expires_at,
+                event_id,
+                seat_id,
+                user_id,
+                ticket_tier_id
             )
             .execute(&mut **tx)
             .await?;
             if result.rows_affected() == 0 {
-                return Err(AppError::BadRequest(format!("Seat {} is no longer available.", seat_id)));
+                return Err(seat_lock_error(tx, event_id, seat_id, item.offer_id, ticket_tier_id).await);
             }
+
+            check_seat_access(tx, event_id, seat_id, item.offer_id, offer.is_accessible).await?;
+            locked_seats.push(SeatStatusChange { event_id, seat_id, status: SeatStatus::Locked });
         } else {
             // Decrement inventory for General Admission
-            let result = sqlx::query!(
-                "UPDATE offers SET quantity_sold = quantity_sold + $1
-                 WHERE id = $2 AND (quantity_for_sale - quantity_sold) >= $1",
-                item.quantity,
-                item.offer_id
-            )
-            .execute(&mut **tx)
-            .await?;
-            if result.rows_affected() == 0 {
-                return Err(AppError::BadRequest("Not enough tickets available for this offer.".to_string()));
-            }
+            take_offer_inventory(tx, item.offer_id, item.quantity).await?;
+            // Offers of a tier can overlap, so the tier's own inventory must cover them too.
+            take_tier_inventory(tx, ticket_tier_id, item.quantity).await?;
+            touched_offers.push(item.offer_id);
         }
     }
     
-    let total_service_fee = service_fee_per_ticket * Decimal::from(payload.items.iter().map(|i| i.quantity as u64).sum::<u64>());
+    // 3. Price the add-ons and take them from the catalog. The service fee is for tickets only.
+    // (addon_item_id, addon_variant_id, event_id, quantity, unit_price), recorded once the order exists.
+    let mut addon_lines: Vec<(i32, Option<i32>, i32, i32, Decimal)> = Vec::with_capacity(payload.addons.len());
+    for addon in &payload.addons {
+        let item = addon_query::get_addon_item(&mut **tx, addon.addon_item_id)
+            .await
+            .map_err(|_| AppError::BadRequest(format!("Add-on with ID {} not found.", addon.addon_item_id)))?;
+
+        // Donations are given at the buyer's amount, at least the add-on's price.
+        let unit_price = match (item.is_donation, addon.amount) {
+            (false, None) => item.price,
+            (false, Some(_)) => {
+                return Err(AppError::BadRequest(format!("Add-on {} has a fixed price.", item.id)));
+            }
+            (true, Some(amount))
+                if amount > Decimal::ZERO
+                    && amount >= item.price
+                    && amount <= Decimal::from(MAX_DONATION)
+                    && amount.normalize().scale() <= 2 =>
+            {
+                amount
+            }
+            (true, _) => {
+                return Err(AppError::BadRequest(format!(
+                    "Donation {} needs an amount in cents between {} and {}.",
+                    item.id,
+                    item.price.max(Decimal::new(1, 2)),
+                    MAX_DONATION
+                )));
+            }
+        };
+
+        // Add-ons sold in variants are bought as one of them.
+        let variants = addon_query::list_variants(&mut **tx, &[item.id]).await?;
+        match addon.variant_id {
+            Some(variant_id) if !variants.iter().any(|v| v.id == variant_id) => {
+                return Err(AppError::BadRequest(format!(
+                    "Add-on {} has no variant {}.",
+                    item.id, variant_id
+                )));
+            }
+            None if !variants.is_empty() => {
+                return Err(AppError::BadRequest(format!("Pick a variant of add-on {}.", item.id)));
+            }
+            _ => {}
+        }
+        addon_query::take_inventory(tx, item.id, addon.variant_id, addon.quantity).await?;
+
+        subtotal += unit_price * Decimal::from(addon.quantity);
+        addon_lines.push((item.id, addon.variant_id, item.event_id, addon.quantity, unit_price));
+    }
+
+    let total_service_fee = service_fee_per_ticket * Decimal::from(ticket_count);
     let total_amount = subtotal + total_service_fee;
-    let expires_at = chrono::Utc::now() + chrono::Duration::minutes(order_expiry_minutes);
 
-    // 3. Create the 'pending' order record
+    // 4. Create the 'pending' order record
     let order = sqlx::query_as!(
         Order,
         r#"
         INSERT INTO orders (user_id, subtotal, service_fee, total_amount, expires_at, status)
         VALUES ($1, $2, $3, $4, $5, 'pending')
-        RETURNING id, user_id, status AS "status: _", subtotal, service_fee, total_amount, created_at, last_updated, expires_at
+        RETURNING id, user_id, status AS "status: _", subtotal, service_fee, total_amount, credit_applied,
+                  created_at, last_updated, expires_at
         "#,
         user_id,
         subtotal,
@@ -79,19 +186,495 @@ pub async fn create_pending_order(
     .fetch_one(&mut **tx)
     .await?;
 
+    // 5. Record the bundles. Each package's price is spread over its tickets to the cent, so
+    // the tickets' prices add up to what was charged; its add-ons come with it at no price.
+    for (offer_id, event_id, packages, package_price, components) in bundles {
+        let order_bundle_id = sqlx::query_scalar!(
+            "INSERT INTO order_bundles (order_id, offer_id, quantity, unit_price)
+             VALUES ($1, $2, $3, $4) RETURNING id",
+            order.id,
+            offer_id,
+            packages,
+            package_price
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+
+        let bundle_total = package_price * Decimal::from(packages);
+        let bundle_tickets: i32 = components.iter().filter(|c| c.ticket_tier_id.is_some()).map(|c| c.quantity * packages).sum();
+        let share = (bundle_total / Decimal::from(bundle_tickets)).round_dp_with_strategy(2, RoundingStrategy::ToZero);
+        // The cents left over go one each to the first tickets.
+        let mut extra_cents = ((bundle_total - share * Decimal::from(bundle_tickets)) * Decimal::ONE_HUNDRED)
+            .to_i32()
+            .unwrap_or(0);
+
+        for component in components {
+            let quantity = component.quantity * packages;
+            if let Some(tier_id) = component.ticket_tier_id {
+                let with_extra = extra_cents.min(quantity);
+                extra_cents -= with_extra;
+                if with_extra > 0 {
+                    lines.push((offer_id, event_id, tier_id, None, with_extra, share + Decimal::new(1, 2), Some(order_bundle_id)));
+                }
+                if quantity > with_extra {
+                    lines.push((offer_id, event_id, tier_id, None, quantity - with_extra, share, Some(order_bundle_id)));
+                }
+            } else if let Some(addon_item_id) = component.addon_item_id {
+                sqlx::query!(
+                    "INSERT INTO order_addons (order_id, addon_item_id, event_id, quantity, unit_price, order_bundle_id)
+                     VALUES ($1, $2, $3, $4, 0, $5)",
+                    order.id,
+                    addon_item_id,
+                    event_id,
+                    quantity,
+                    order_bundle_id
+                )
+                .execute(&mut **tx)
+                .await?;
+            }
+        }
+    }
+
+    // 6. Record what was bought, at the price charged, so tickets can be issued from it after payment.
+    let mut event_ids: Vec<i32> = Vec::new();
+    for (offer_id, event_id, ticket_tier_id, seat_id, quantity, unit_price, order_bundle_id) in lines {
+        sqlx::query!(
+            "INSERT INTO order_items
+                (order_id, offer_id, event_id, ticket_tier_id, seat_id, quantity, unit_price, unit_service_fee,
+                 order_bundle_id)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+            order.id,
+            offer_id,
+            event_id,
+            ticket_tier_id,
+            seat_id,
+            quantity,
+            unit_price,
+            service_fee_per_ticket,
+            order_bundle_id
+        )
+        .execute(&mut **tx)
+        .await?;
+        if !event_ids.contains(&event_id) {
+            event_ids.push(event_id);
+        }
+
+        // Link the locked seat to the order, so it can be sold or released with it.
+        if let Some(seat_id) = seat_id {
+            sqlx::query!(
+                "UPDATE event_seats SET order_id = $1 WHERE event_id = $2 AND seat_id = $3",
+                order.id,
+                event_id,
+                seat_id
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+    }
+
+    // 7. Record the add-ons bought on their own.
+    for (addon_item_id, addon_variant_id, event_id, quantity, unit_price) in addon_lines {
+        sqlx::query!(
+            "INSERT INTO order_addons (order_id, addon_item_id, addon_variant_id, event_id, quantity, unit_price)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+            order.id,
+            addon_item_id,
+            addon_variant_id,
+            event_id,
+            quantity,
+            unit_price
+        )
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    // 8. Announce the taken inventory. Delivered only if the checkout commits.
+    realtime_query::notify_seat_changes(&mut **tx, &locked_seats).await?;
+    realtime_query::notify_offer_inventory(&mut **tx, &touched_offers).await?;
+
+    // 9. Sales can move prices up a step, so the events' price ranges follow.
+    pricing_query::refresh_price_ranges(&mut **tx, &event_ids).await?;
+
     Ok(order)
 }
 
+/// An offer as checkout sees it: what it sells at right now and where.
+#[derive(Debug)]
+pub struct CheckoutOffer {
+    pub price: Decimal,
+    pub event_id: i32,
+    pub ticket_tier_id: i32,
+    pub is_accessible: bool,
+    pub is_bundle: bool,
+}
+
+/// Fetches an offer for a user to buy from, at its current price.
+/// Presale offers are only sold to users who unlocked them with an access code, and
+/// lottery offers to winners and to the waitlisted users their unsold tickets went to.
+pub async fn get_checkout_offer(
+    tx: &mut Transaction<'_, Postgres>,
+    offer_id: i32,
+    user_id: i32,
+) -> Result<CheckoutOffer, AppError> {
+    let offer_info: (Decimal, i32, i32, bool, bool, bool) = sqlx::query_as(
+        "SELECT offer_current_price(o.id), tt.event_id, o.ticket_tier_id, o.is_accessible, o.is_bundle,
+                NOT o.is_presale OR EXISTS (
+                    SELECT 1 FROM offer_unlocks u
+                    WHERE u.offer_id = o.id AND u.user_id = $2 AND u.expires_at > NOW()
+                ) OR EXISTS (
+                    SELECT 1 FROM lottery_entries le JOIN lotteries l ON l.id = le.lottery_id
+                    WHERE l.offer_id = o.id AND le.user_id = $2 AND le.status = 'won'
+                      AND le.purchase_expires_at > NOW()
+                ) OR EXISTS (
+                    SELECT 1 FROM waitlist_entries w
+                    WHERE w.offer_id = o.id AND w.user_id = $2 AND w.status = 'offered'
+                      AND w.offer_expires_at > NOW()
+                )
+         FROM offers o JOIN ticket_tiers tt ON o.ticket_tier_id = tt.id
+         WHERE o.id = $1"
+    )
+    .bind(offer_id)
+    .bind(user_id)
+    .fetch_one(&mut **tx)
+    .await?;
+    let (price, event_id, ticket_tier_id, is_accessible, is_bundle, unlocked) = offer_info;
+    if !unlocked {
+        return Err(AppError::Forbidden(format!(
+            "Offer {} is a presale. Unlock it with an access code first.",
+            offer_id
+        )));
+    }
+    Ok(CheckoutOffer { price, event_id, ticket_tier_id, is_accessible, is_bundle })
+}
+
+/// Explains why a seat couldn't be locked for an offer: it is zoned into another ticket tier,
+/// or it isn't available any more.
+pub async fn seat_lock_error(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+    seat_id: i32,
+    offer_id: i32,
+    ticket_tier_id: i32,
+) -> AppError {
+    let seat_tier_id = sqlx::query_scalar!(
+        "SELECT ticket_tier_id FROM event_seats WHERE event_id = $1 AND seat_id = $2",
+        event_id,
+        seat_id
+    )
+    .fetch_optional(&mut **tx)
+    .await;
+    match seat_tier_id {
+        Ok(Some(seat_tier_id)) if seat_tier_id != ticket_tier_id => AppError::BadRequest(format!(
+            "Seat {} is in another ticket tier and can't be bought through offer {}.",
+            seat_id, offer_id
+        )),
+        Err(e) => e.into(),
+        _ => AppError::BadRequest(format!("Seat {} is no longer available.", seat_id)),
+    }
+}
+
+/// Checks a seat can be bought through an offer. Wheelchair and companion seats go only
+/// through accessibility offers until the event releases them, and accessibility offers
+/// only sell those seats.
+pub async fn check_seat_access(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+    seat_id: i32,
+    offer_id: i32,
+    offer_is_accessible: bool,
+) -> Result<(), AppError> {
+    let access = sqlx::query!(
+        r#"
+        SELECT
+            s.attributes && ARRAY['wheelchair', 'companion']::seat_attribute[] AS "is_accessible!",
+            e.start_time - make_interval(hours => e.accessible_release_hours) AS "release_at!"
+        FROM seats s, events e
+        WHERE s.id = $1 AND e.id = $2
+        "#,
+        seat_id,
+        event_id
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    if access.is_accessible && !offer_is_accessible && chrono::Utc::now() < access.release_at {
+        return Err(AppError::BadRequest(format!(
+            "Seat {} is reserved for accessibility bookings until {}.",
+            seat_id,
+            access.release_at.to_rfc3339()
+        )));
+    }
+    if offer_is_accessible && !access.is_accessible {
+        return Err(AppError::BadRequest(format!(
+            "Offer {} is only for wheelchair and companion seats.",
+            offer_id
+        )));
+    }
+    Ok(())
+}
+
+/// Takes `quantity` from what an offer has left for sale.
+async fn take_offer_inventory(tx: &mut Transaction<'_, Postgres>, offer_id: i32, quantity: i32) -> Result<(), AppError> {
+    let result = sqlx::query!(
+        "UPDATE offers SET quantity_sold = quantity_sold + $1
+         WHERE id = $2 AND (quantity_for_sale - quantity_sold) >= $1",
+        quantity,
+        offer_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::BadRequest("Not enough tickets available for this offer.".to_string()));
+    }
+    Ok(())
+}
+
+/// Takes `quantity` tickets from a tier's inventory.
+async fn take_tier_inventory(tx: &mut Transaction<'_, Postgres>, tier_id: i32, quantity: i32) -> Result<(), AppError> {
+    let result = sqlx::query!(
+        "UPDATE ticket_tiers SET quantity_sold = quantity_sold + $1
+         WHERE id = $2 AND (total_inventory - quantity_sold) >= $1",
+        quantity,
+        tier_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::BadRequest("Not enough tickets left in this ticket tier.".to_string()));
+    }
+    Ok(())
+}
+
+/// Fetches all the line items of an order.
+pub async fn get_items_for_order<'e, E>(executor: E, order_id: Uuid) -> Result<Vec<OrderItem>, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as!(
+        OrderItem,
+        "SELECT id, order_id, offer_id, event_id, ticket_tier_id, seat_id, quantity, unit_price, unit_service_fee,
+                order_bundle_id
+         FROM order_items WHERE order_id = $1 ORDER BY id",
+        order_id
+    )
+    .fetch_all(executor)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Fetches the bundles bought in an order.
+pub async fn get_bundles_for_order<'e, E>(executor: E, order_id: Uuid) -> Result<Vec<OrderBundle>, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as!(
+        OrderBundle,
+        "SELECT id, offer_id, quantity, unit_price FROM order_bundles WHERE order_id = $1 ORDER BY id",
+        order_id
+    )
+    .fetch_all(executor)
+    .await
+    .map_err(AppError::from)
+}
+
 /// Updates an order's status to 'completed'.
+/// Returns `false` if the order was not pending (e.g. a duplicate webhook), so callers
+/// can avoid issuing tickets twice.
 pub async fn mark_order_completed(
     tx: &mut Transaction<'_, Postgres>,
     order_id: Uuid,
-) -> Result<(), AppError> {
-    sqlx::query!(
+) -> Result<bool, AppError> {
+    let result = sqlx::query!(
         "UPDATE orders SET status = 'completed' WHERE id = $1 AND status = 'pending'",
         order_id
     )
     .execute(&mut **tx)
     .await?;
-    Ok(())
-}
\ No newline at end of file
+    Ok(result.rows_affected() > 0)
+}
+/// Fetches a single order by its ID.
+pub async fn get_by_id(pool: &PgPool, order_id: Uuid) -> Result<Order, AppError> {
+    sqlx::query_as!(
+        Order,
+        r#"
+        SELECT id, user_id, status AS "status: _", subtotal, service_fee, total_amount, credit_applied,
+               created_at, last_updated, expires_at
+        FROM orders WHERE id = $1
+        "#,
+        order_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Records how much of a pending order is paid with store credit.
+pub async fn set_credit_applied(
+    tx: &mut Transaction<'_, Postgres>,
+    order_id: Uuid,
+    amount: Decimal,
+) -> Result<Order, AppError> {
+    sqlx::query_as!(
+        Order,
+        r#"
+        UPDATE orders SET credit_applied = $2 WHERE id = $1 AND status = 'pending'
+        RETURNING id, user_id, status AS "status: _", subtotal, service_fee, total_amount, credit_applied,
+                  created_at, last_updated, expires_at
+        "#,
+        order_id,
+        amount
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Moves a completed order to 'refunded', e.g. after its payment was charged back.
+/// Returns `false` if the order was not completed, e.g. it was refunded already.
+pub async fn mark_order_refunded(
+    tx: &mut Transaction<'_, Postgres>,
+    order_id: Uuid,
+) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        "UPDATE orders SET status = 'refunded' WHERE id = $1 AND status = 'completed'",
+        order_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Cancels a pending order, e.g. a group booking's when no share was paid by its deadline.
+pub async fn mark_order_cancelled(
+    tx: &mut Transaction<'_, Postgres>,
+    order_id: Uuid,
+) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        "UPDATE orders SET status = 'cancelled' WHERE id = $1 AND status = 'pending'",
+        order_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Cancels every pending order whose checkout window has passed.
+/// Group booking orders are left to the group booking job, which keeps the shares paid by
+/// their deadline.
+/// Returns the IDs of the cancelled orders so their inventory can be released.
+pub async fn cancel_expired_orders(tx: &mut Transaction<'_, Postgres>) -> Result<Vec<Uuid>, AppError> {
+    let ids = sqlx::query_scalar!(
+        "UPDATE orders SET status = 'cancelled'
+         WHERE status = 'pending' AND expires_at < NOW()
+           AND NOT EXISTS (SELECT 1 FROM group_bookings g WHERE g.order_id = orders.id)
+         RETURNING id"
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+    Ok(ids)
+}
+
+/// Lists the events the given orders bought tickets for.
+pub async fn list_event_ids_for_orders(
+    tx: &mut Transaction<'_, Postgres>,
+    order_ids: &[Uuid],
+) -> Result<Vec<i32>, AppError> {
+    let ids = sqlx::query_scalar!(
+        "SELECT DISTINCT event_id FROM order_items WHERE order_id = ANY($1) ORDER BY event_id",
+        order_ids
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+    Ok(ids)
+}
+
+/// Gives the general admission tickets of the given orders back to their offers and tiers,
+/// and their add-ons back to the catalog.
+pub async fn release_general_admission_inventory(
+    tx: &mut Transaction<'_, Postgres>,
+    order_ids: &[Uuid],
+) -> Result<(), AppError> {
+    let offer_ids = sqlx::query_scalar!(
+        r#"
+        UPDATE offers o
+        SET quantity_sold = GREATEST(o.quantity_sold - released.quantity, 0)
+        FROM (
+            -- Bundle offers count packages, not the tickets in them.
+            SELECT offer_id, SUM(quantity)::INT AS quantity
+            FROM (
+                SELECT offer_id, quantity FROM order_items
+                WHERE order_id = ANY($1) AND seat_id IS NULL AND order_bundle_id IS NULL
+                UNION ALL
+                SELECT offer_id, quantity FROM order_bundles WHERE order_id = ANY($1)
+            ) sold
+            GROUP BY offer_id
+        ) released
+        WHERE o.id = released.offer_id
+        RETURNING o.id
+        "#,
+        order_ids
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE ticket_tiers tt
+        SET quantity_sold = GREATEST(tt.quantity_sold - released.quantity, 0)
+        FROM (
+            SELECT ticket_tier_id, SUM(quantity)::INT AS quantity
+            FROM order_items
+            WHERE order_id = ANY($1) AND seat_id IS NULL
+            GROUP BY ticket_tier_id
+        ) released
+        WHERE tt.id = released.ticket_tier_id
+        "#,
+        order_ids
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    addon_query::release_addon_inventory(tx, order_ids).await?;
+
+    realtime_query::notify_offer_inventory(&mut **tx, &offer_ids).await
+}
+
+/// Creates a pending order for gift cards worth `amount`. Gift cards carry no service fee.
+pub async fn create_gift_card_order(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: i32,
+    amount: Decimal,
+    order_expiry_minutes: i64,
+) -> Result<Order, AppError> {
+    let expires_at = chrono::Utc::now() + chrono::Duration::minutes(order_expiry_minutes);
+    sqlx::query_as!(
+        Order,
+        r#"
+        INSERT INTO orders (user_id, subtotal, service_fee, total_amount, expires_at, status)
+        VALUES ($1, $2, 0, $2, $3, 'pending')
+        RETURNING id, user_id, status AS "status: _", subtotal, service_fee, total_amount, credit_applied,
+                  created_at, last_updated, expires_at
+        "#,
+        user_id,
+        amount,
+        expires_at
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Creates a completed, zero-value order for complimentary tickets.
+/// Nothing is charged, so there is no payment and no order lines.
+pub async fn create_comp_order(tx: &mut Transaction<'_, Postgres>, user_id: i32) -> Result<Uuid, AppError> {
+    let order_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO orders (user_id, subtotal, service_fee, total_amount, expires_at, status)
+        VALUES ($1, 0, 0, 0, NOW(), 'completed')
+        RETURNING id
+        "#,
+        user_id
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(order_id)
+}
@@ -1,6 +1,7 @@
 use crate::{errors::AppError, models::Payment};
 use rust_decimal::Decimal;
-use sqlx::{Postgres, Transaction};
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
 use uuid::Uuid;
 
 /// Creates a new payment record linked to an order, starting in a 'pending' state.
@@ -47,4 +48,89 @@ pub async fn mark_payment_succeeded(
         tracing::warn!("Attempted to mark a non-pending or non-existent payment as succeeded: {}", stripe_payment_intent_id);
     }
     Ok(())
-}
\ No newline at end of file
+}
+/// Marks a payment we had given up on as 'succeeded', e.g. a group booking share payment that
+/// went through after its claim lapsed.
+pub async fn mark_failed_payment_succeeded(
+    tx: &mut Transaction<'_, Postgres>,
+    payment_id: Uuid,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE payments SET status = 'succeeded' WHERE id = $1 AND status = 'failed'",
+        payment_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Fetches a payment by its Stripe PaymentIntent ID.
+pub async fn get_by_payment_intent_id(
+    tx: &mut Transaction<'_, Postgres>,
+    stripe_payment_intent_id: &str,
+) -> Result<Payment, AppError> {
+    sqlx::query_as!(
+        Payment,
+        r#"
+        SELECT id, order_id, status AS "status: _", amount_charged, currency, amount_refunded,
+               stripe_payment_intent_id, stripe_customer_id, payment_method_type,
+               created_at, last_updated
+        FROM payments WHERE stripe_payment_intent_id = $1
+        "#,
+        stripe_payment_intent_id
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Marks a payment as 'refunded', adding `amount` to what has been returned to the customer.
+pub async fn mark_payment_refunded(
+    tx: &mut Transaction<'_, Postgres>,
+    payment_id: Uuid,
+    amount: Decimal,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE payments SET status = 'refunded', amount_refunded = LEAST(amount_refunded + $2, amount_charged)
+         WHERE id = $1 AND status <> 'refunded'",
+        payment_id,
+        amount
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Lists every payment created at or after `since`. Used by reconciliation.
+pub async fn list_created_since(pool: &PgPool, since: DateTime<Utc>) -> Result<Vec<Payment>, AppError> {
+    sqlx::query_as!(
+        Payment,
+        r#"
+        SELECT id, order_id, status AS "status: _", amount_charged, currency, amount_refunded,
+               stripe_payment_intent_id, stripe_customer_id, payment_method_type,
+               created_at, last_updated
+        FROM payments WHERE created_at >= $1
+        ORDER BY created_at
+        "#,
+        since
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Marks the still-pending payments of the given orders as 'failed', e.g. after the orders expired.
+/// Returns their PaymentIntent IDs, so the PaymentIntents can be cancelled.
+pub async fn mark_pending_payments_failed(
+    tx: &mut Transaction<'_, Postgres>,
+    order_ids: &[Uuid],
+) -> Result<Vec<String>, AppError> {
+    sqlx::query_scalar!(
+        "UPDATE payments SET status = 'failed' WHERE order_id = ANY($1) AND status = 'pending'
+         RETURNING stripe_payment_intent_id",
+        order_ids
+    )
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
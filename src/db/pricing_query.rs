@@ -1,6 +1,10 @@
 use crate::{
     errors::AppError,
-    models::{CreateOfferPayload, CreateTicketTierPayload, Offer, TicketTier},
+    models::{
+        BundleComponent, CreateOfferPayload, CreateTicketTierPayload, Offer, OfferPriceRule, PriceAdjustmentType,
+        PriceRuleKind, SetBundleComponentsPayload, SetOfferPricingPayload, TicketTier, UpdateOfferPayload,
+        UpdateTicketTierPayload,
+    },
 };
 use rust_decimal::Decimal;
 use sqlx::{Executor, PgPool, Postgres, Transaction};
@@ -30,6 +34,103 @@ where
     .map_err(AppError::from)
 }
 
+/// Fetches a single ticket tier by its ID.
+pub async fn get_ticket_tier<'e, E>(executor: E, tier_id: i32) -> Result<TicketTier, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as!(TicketTier, "SELECT * FROM ticket_tiers WHERE id = $1", tier_id)
+        .fetch_one(executor)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Fetches a ticket tier and locks it until the end of the transaction, so its offers
+/// can't be changed by anyone else meanwhile.
+pub async fn lock_ticket_tier(tx: &mut Transaction<'_, Postgres>, tier_id: i32) -> Result<TicketTier, AppError> {
+    sqlx::query_as!(TicketTier, "SELECT * FROM ticket_tiers WHERE id = $1 FOR UPDATE", tier_id)
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Updates a ticket tier. Uses COALESCE to only update non-None fields.
+pub async fn update_ticket_tier(
+    tx: &mut Transaction<'_, Postgres>,
+    tier_id: i32,
+    payload: &UpdateTicketTierPayload,
+) -> Result<TicketTier, AppError> {
+    sqlx::query_as!(
+        TicketTier,
+        r#"
+        UPDATE ticket_tiers
+        SET name = COALESCE($2, name),
+            description = COALESCE($3, description),
+            total_inventory = COALESCE($4, total_inventory)
+        WHERE id = $1
+        RETURNING *
+        "#,
+        tier_id,
+        payload.name,
+        payload.description,
+        payload.total_inventory
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Locks an event and returns its venue's capacity, if known, together with the inventory
+/// of its tiers other than `exclude_tier_id`.
+pub async fn get_event_allocation(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+    exclude_tier_id: Option<i32>,
+) -> Result<(Option<i32>, i64), AppError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT
+            v.capacity,
+            (SELECT COALESCE(SUM(tt.total_inventory), 0) FROM ticket_tiers tt
+             WHERE tt.event_id = e.id AND tt.id IS DISTINCT FROM $2) AS "allocated!"
+        FROM events e
+        LEFT JOIN venues v ON e.venue_id = v.id
+        WHERE e.id = $1
+        FOR UPDATE OF e
+        "#,
+        event_id,
+        exclude_tier_id
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+
+    Ok((row.capacity, row.allocated))
+}
+
+/// Returns how many tickets the public offers of a tier (those that aren't presales)
+/// have for sale together, leaving out `exclude_offer_id`, and the most any one offer has.
+pub async fn get_tier_allocation(
+    tx: &mut Transaction<'_, Postgres>,
+    tier_id: i32,
+    exclude_offer_id: Option<i32>,
+) -> Result<(i64, i32), AppError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT
+            COALESCE(SUM(quantity_for_sale) FILTER (WHERE NOT is_presale), 0) AS "public_allocated!",
+            COALESCE(MAX(quantity_for_sale), 0) AS "largest_offer!"
+        FROM offers
+        WHERE ticket_tier_id = $1 AND id IS DISTINCT FROM $2
+        "#,
+        tier_id,
+        exclude_offer_id
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+
+    Ok((row.public_allocated, row.largest_offer))
+}
+
 /// Lists all ticket tiers for a given event.
 pub async fn list_tiers_for_event(
     pool: &PgPool,
@@ -58,10 +159,12 @@ pub async fn create_offer(
     let offer = sqlx::query_as!(
         Offer,
         r#"
-        INSERT INTO offers (ticket_tier_id, name, price, quantity_for_sale, sale_start_time, sale_end_time, access_code)
-        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        INSERT INTO offers (ticket_tier_id, name, price, quantity_for_sale, sale_start_time, sale_end_time, access_code,
+                            is_accessible, is_presale)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
         RETURNING id, ticket_tier_id, name, status AS "status: _", price, quantity_for_sale, quantity_sold,
-                  sale_start_time, sale_end_time, min_per_order, max_per_order, access_code
+                  sale_start_time, sale_end_time, min_per_order, max_per_order, access_code, is_accessible,
+                  is_presale, is_bundle, price AS current_price
         "#,
         ticket_tier_id,
         payload.name,
@@ -69,23 +172,98 @@ pub async fn create_offer(
         payload.quantity_for_sale,
         payload.sale_start_time,
         payload.sale_end_time,
-        payload.access_code
+        payload.access_code,
+        payload.is_accessible,
+        payload.access_code.is_some()
     )
     .fetch_one(&mut **tx)
     .await?;
 
     // Step 2: Update the denormalized price range on the parent event
-    // This is a classic use case for a transaction to ensure data consistency.
-    sqlx::query!(
+    refresh_event_price_range(tx, ticket_tier_id).await?;
+
+    Ok(offer)
+}
+
+/// Fetches a single offer by its ID.
+pub async fn get_offer<'e, E>(executor: E, offer_id: i32) -> Result<Offer, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as!(
+        Offer,
+        r#"
+        SELECT id, ticket_tier_id, name, status AS "status: _", price, quantity_for_sale, quantity_sold,
+               sale_start_time, sale_end_time, min_per_order, max_per_order, access_code, is_accessible,
+               is_presale, is_bundle, offer_current_price(id) AS "current_price!"
+        FROM offers WHERE id = $1
+        "#,
+        offer_id
+    )
+    .fetch_one(executor)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Updates an offer. Uses COALESCE to only update non-None fields.
+/// This function MUST be called within a transaction because it also updates the event's price range.
+pub async fn update_offer(
+    tx: &mut Transaction<'_, Postgres>,
+    offer_id: i32,
+    payload: &UpdateOfferPayload,
+) -> Result<Offer, AppError> {
+    let ticket_tier_id = sqlx::query_scalar!(
+        r#"
+        UPDATE offers
+        SET name = COALESCE($2, name),
+            status = COALESCE($3, status),
+            price = COALESCE($4, price),
+            quantity_for_sale = COALESCE($5, quantity_for_sale),
+            sale_start_time = COALESCE($6, sale_start_time),
+            sale_end_time = COALESCE($7, sale_end_time)
+        WHERE id = $1
+        RETURNING ticket_tier_id
+        "#,
+        offer_id,
+        payload.name,
+        payload.status as _,
+        payload.price,
+        payload.quantity_for_sale,
+        payload.sale_start_time,
+        payload.sale_end_time
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+
+    refresh_event_price_range(tx, ticket_tier_id).await?;
+    get_offer(&mut **tx, offer_id).await
+}
+
+/// Recomputes the denormalized price range of the event a tier belongs to from its offers.
+async fn refresh_event_price_range(tx: &mut Transaction<'_, Postgres>, ticket_tier_id: i32) -> Result<(), AppError> {
+    let event_id = sqlx::query_scalar!("SELECT event_id FROM ticket_tiers WHERE id = $1", ticket_tier_id)
+        .fetch_one(&mut **tx)
+        .await?;
+    refresh_price_ranges(&mut **tx, &[event_id]).await?;
+    Ok(())
+}
+
+/// Recomputes the denormalized price ranges of the given events from what their offers sell at
+/// right now, pricing rules included. Returns how many events' ranges changed.
+pub async fn refresh_price_ranges<'e, E>(executor: E, event_ids: &[i32]) -> Result<u64, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query!(
         r#"
         WITH event_price_range AS (
             SELECT
                 tt.event_id,
-                MIN(o.price) as min_price,
-                MAX(o.price) as max_price
+                MIN(offer_current_price(o.id)) as min_price,
+                MAX(offer_current_price(o.id)) as max_price
             FROM offers o
             JOIN ticket_tiers tt ON o.ticket_tier_id = tt.id
-            WHERE tt.event_id = (SELECT event_id FROM ticket_tiers WHERE id = $1)
+            WHERE tt.event_id = ANY($1)
             GROUP BY tt.event_id
         )
         UPDATE events e
@@ -93,14 +271,188 @@ pub async fn create_offer(
             price_min = epr.min_price,
             price_max = epr.max_price
         FROM event_price_range epr
-        WHERE e.id = epr.event_id;
+        WHERE e.id = epr.event_id
+          AND (e.price_min, e.price_max) IS DISTINCT FROM (epr.min_price, epr.max_price);
         "#,
-        ticket_tier_id,
+        event_ids,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Lists the upcoming events with offers priced by rules, whose price ranges move with
+/// sales and time.
+pub async fn list_dynamically_priced_events(pool: &PgPool) -> Result<Vec<i32>, AppError> {
+    let ids = sqlx::query_scalar!(
+        r#"
+        SELECT DISTINCT tt.event_id AS "event_id!"
+        FROM offer_price_rules r
+        JOIN offers o ON o.id = r.offer_id
+        JOIN ticket_tiers tt ON tt.id = o.ticket_tier_id
+        JOIN events e ON e.id = tt.event_id
+        WHERE e.start_time > NOW()
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(ids)
+}
+
+// --- Pricing Rule Queries ---
+
+/// Fetches an offer's base and current price and its bounds, as
+/// `(base_price, current_price, price_floor, price_ceiling)`.
+pub async fn get_offer_price_bounds<'e, E>(
+    executor: E,
+    offer_id: i32,
+) -> Result<(Decimal, Decimal, Option<Decimal>, Option<Decimal>), AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let row = sqlx::query!(
+        r#"
+        SELECT price, offer_current_price(id) AS "current_price!", price_floor, price_ceiling
+        FROM offers WHERE id = $1
+        "#,
+        offer_id
+    )
+    .fetch_one(executor)
+    .await?;
+    Ok((row.price, row.current_price, row.price_floor, row.price_ceiling))
+}
+
+/// Lists an offer's pricing rules, by kind and threshold.
+pub async fn list_price_rules<'e, E>(executor: E, offer_id: i32) -> Result<Vec<OfferPriceRule>, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as!(
+        OfferPriceRule,
+        r#"
+        SELECT id, offer_id, kind AS "kind: _", threshold, adjustment_type AS "adjustment_type: _", adjustment
+        FROM offer_price_rules WHERE offer_id = $1
+        ORDER BY kind, threshold
+        "#,
+        offer_id
+    )
+    .fetch_all(executor)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Replaces an offer's pricing rules and bounds, then refreshes its event's price range.
+pub async fn replace_price_rules(
+    tx: &mut Transaction<'_, Postgres>,
+    offer_id: i32,
+    payload: &SetOfferPricingPayload,
+) -> Result<(), AppError> {
+    let ticket_tier_id = sqlx::query_scalar!(
+        "UPDATE offers SET price_floor = $2, price_ceiling = $3 WHERE id = $1 RETURNING ticket_tier_id",
+        offer_id,
+        payload.price_floor,
+        payload.price_ceiling
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+
+    sqlx::query!("DELETE FROM offer_price_rules WHERE offer_id = $1", offer_id)
+        .execute(&mut **tx)
+        .await?;
+    for rule in &payload.rules {
+        sqlx::query!(
+            "INSERT INTO offer_price_rules (offer_id, kind, threshold, adjustment_type, adjustment)
+             VALUES ($1, $2, $3, $4, $5)",
+            offer_id,
+            rule.kind as PriceRuleKind,
+            rule.threshold,
+            rule.adjustment_type as PriceAdjustmentType,
+            rule.adjustment
+        )
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_unique_violation() => {
+                AppError::BadRequest("Two pricing rules of the same kind can't share a threshold.".to_string())
+            }
+            e => AppError::from(e),
+        })?;
+    }
+
+    refresh_event_price_range(tx, ticket_tier_id).await
+}
+
+// --- Bundle Queries ---
+
+/// Checks whether a tier is sold by seat, i.e. has seats on the event's seat map.
+pub async fn is_reserved_tier<'e, E>(executor: E, tier_id: i32) -> Result<bool, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let is_reserved = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM event_seats WHERE ticket_tier_id = $1) AS "is_reserved!""#,
+        tier_id
+    )
+    .fetch_one(executor)
+    .await?;
+    Ok(is_reserved)
+}
+
+/// Lists what each package of a bundle offer contains, tickets first.
+pub async fn list_bundle_components<'e, E>(executor: E, offer_id: i32) -> Result<Vec<BundleComponent>, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as!(
+        BundleComponent,
+        r#"
+        SELECT id, offer_id, ticket_tier_id, addon_item_id, quantity
+        FROM offer_bundle_components WHERE offer_id = $1
+        ORDER BY ticket_tier_id IS NULL, id
+        "#,
+        offer_id
+    )
+    .fetch_all(executor)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Replaces the components of an offer's packages and marks it as a bundle.
+/// Only offers that haven't sold anything yet can change what they sell.
+pub async fn replace_bundle_components(
+    tx: &mut Transaction<'_, Postgres>,
+    offer_id: i32,
+    payload: &SetBundleComponentsPayload,
+) -> Result<(), AppError> {
+    let result = sqlx::query!(
+        "UPDATE offers SET is_bundle = TRUE WHERE id = $1 AND quantity_sold = 0",
+        offer_id
     )
     .execute(&mut **tx)
     .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::BadRequest(
+            "This offer has already sold, so what it sells can't change.".to_string(),
+        ));
+    }
 
-    Ok(offer)
+    sqlx::query!("DELETE FROM offer_bundle_components WHERE offer_id = $1", offer_id)
+        .execute(&mut **tx)
+        .await?;
+    for component in &payload.components {
+        sqlx::query!(
+            "INSERT INTO offer_bundle_components (offer_id, ticket_tier_id, addon_item_id, quantity)
+             VALUES ($1, $2, $3, $4)",
+            offer_id,
+            component.ticket_tier_id,
+            component.addon_item_id,
+            component.quantity
+        )
+        .execute(&mut **tx)
+        .await?;
+    }
+    Ok(())
 }
 
 /// Lists all publicly visible and currently on-sale offers for a given event.
@@ -115,16 +467,17 @@ pub async fn list_public_offers_for_event(
         SELECT
             o.id, o.ticket_tier_id, o.name, o.status AS "status: _", o.price, o.quantity_for_sale, 
             o.quantity_sold, o.sale_start_time, o.sale_end_time, o.min_per_order, 
-            o.max_per_order, o.access_code
+            o.max_per_order, o.access_code, o.is_accessible, o.is_presale, o.is_bundle,
+            offer_current_price(o.id) AS "current_price!"
         FROM offers o
         JOIN ticket_tiers tt ON o.ticket_tier_id = tt.id
         WHERE
             tt.event_id = $1
             AND o.status = 'on_sale'
-            AND o.access_code IS NULL -- Exclude presale offers
+            AND NOT o.is_presale -- Exclude presale offers
             AND (o.sale_start_time IS NULL OR o.sale_start_time <= NOW())
             AND (o.sale_end_time IS NULL OR o.sale_end_time > NOW())
-        ORDER BY o.price ASC
+        ORDER BY offer_current_price(o.id) ASC
         "#,
         event_id
     )
@@ -0,0 +1,126 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    errors::AppError,
+    models::{EventUpdateNotification, QueueProgress, SeatStatusChange},
+};
+use sqlx::{Executor, Postgres};
+
+/// The Postgres channel that carries seat and inventory changes between instances.
+pub const EVENT_UPDATES_CHANNEL: &str = "event_updates";
+
+/// Postgres caps a notification payload at 8000 bytes, so large changes are split.
+const SEATS_PER_NOTIFICATION: usize = 100;
+
+/// Announces seat status changes to every instance listening on `event_updates`.
+/// Inside a transaction, the notification is only delivered if the transaction commits.
+pub async fn notify_seat_changes<'e, E>(executor: E, changes: &[SeatStatusChange]) -> Result<(), AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    let mut by_event: BTreeMap<i32, Vec<SeatStatusChange>> = BTreeMap::new();
+    for change in changes {
+        by_event.entry(change.event_id).or_default().push(change.clone());
+    }
+
+    let mut payloads = Vec::new();
+    for (event_id, seats) in by_event {
+        for chunk in seats.chunks(SEATS_PER_NOTIFICATION) {
+            let notification = EventUpdateNotification {
+                event_id,
+                seats: chunk.to_vec(),
+                offers: Vec::new(),
+                admitted_through: None,
+            };
+            payloads.push(serde_json::to_string(&notification).map_err(|e| {
+                AppError::InternalServerError(format!("Failed to encode seat notification: {}", e))
+            })?);
+        }
+    }
+
+    // `pg_notify` returns void, which the query macros can't describe.
+    sqlx::query("SELECT pg_notify($1, payload) FROM UNNEST($2::text[]) AS payload")
+        .bind(EVENT_UPDATES_CHANNEL)
+        .bind(payloads)
+        .execute(executor)
+        .await?;
+
+    Ok(())
+}
+
+/// Announces the current availability of the given general admission offers,
+/// one notification per event.
+/// Inside a transaction, the notification is only delivered if the transaction commits.
+pub async fn notify_offer_inventory<'e, E>(executor: E, offer_ids: &[i32]) -> Result<(), AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    if offer_ids.is_empty() {
+        return Ok(());
+    }
+
+    sqlx::query(
+        r#"
+        SELECT pg_notify(
+            $1,
+            json_build_object(
+                'event_id', tt.event_id,
+                'offers', json_agg(json_build_object(
+                    'offer_id', o.id,
+                    'quantity_available', GREATEST(
+                        LEAST(o.quantity_for_sale - o.quantity_sold, tt.total_inventory - tt.quantity_sold), 0
+                    )
+                ))
+            )::text
+        )
+        FROM offers o
+        JOIN ticket_tiers tt ON tt.id = o.ticket_tier_id
+        WHERE o.id = ANY($2)
+        GROUP BY tt.event_id
+        "#,
+    )
+    .bind(EVENT_UPDATES_CHANNEL)
+    .bind(offer_ids)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Announces how far the given waiting rooms have let buyers in.
+/// Inside a transaction, the notification is only delivered if the transaction commits.
+pub async fn notify_queue_progress<'e, E>(executor: E, progress: &[QueueProgress]) -> Result<(), AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    if progress.is_empty() {
+        return Ok(());
+    }
+
+    let payloads = progress
+        .iter()
+        .map(|p| {
+            let notification = EventUpdateNotification {
+                event_id: p.event_id,
+                seats: Vec::new(),
+                offers: Vec::new(),
+                admitted_through: Some(p.admitted_through),
+            };
+            serde_json::to_string(&notification).map_err(|e| {
+                AppError::InternalServerError(format!("Failed to encode queue notification: {}", e))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    sqlx::query("SELECT pg_notify($1, payload) FROM UNNEST($2::text[]) AS payload")
+        .bind(EVENT_UPDATES_CHANNEL)
+        .bind(payloads)
+        .execute(executor)
+        .await?;
+
+    Ok(())
+}
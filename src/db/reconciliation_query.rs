@@ -0,0 +1,132 @@
+use crate::{
+    errors::AppError,
+    models::{NewDiscrepancy, ReconciliationDiscrepancy, ReconciliationRun},
+};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+/// Starts a new reconciliation run in the 'running' state.
+pub async fn create_run(pool: &PgPool, window_start: DateTime<Utc>) -> Result<ReconciliationRun, AppError> {
+    sqlx::query_as!(
+        ReconciliationRun,
+        r#"
+        INSERT INTO reconciliation_runs (window_start)
+        VALUES ($1)
+        RETURNING id, status AS "status: _", window_start, started_at, finished_at,
+                  intents_checked, auto_resolved, discrepancies_found, error_message
+        "#,
+        window_start
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Marks a run as completed with its final counts.
+pub async fn complete_run(
+    pool: &PgPool,
+    run_id: i32,
+    intents_checked: i32,
+    auto_resolved: i32,
+    discrepancies_found: i32,
+) -> Result<ReconciliationRun, AppError> {
+    sqlx::query_as!(
+        ReconciliationRun,
+        r#"
+        UPDATE reconciliation_runs
+        SET status = 'completed', finished_at = NOW(),
+            intents_checked = $2, auto_resolved = $3, discrepancies_found = $4
+        WHERE id = $1
+        RETURNING id, status AS "status: _", window_start, started_at, finished_at,
+                  intents_checked, auto_resolved, discrepancies_found, error_message
+        "#,
+        run_id,
+        intents_checked,
+        auto_resolved,
+        discrepancies_found
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Marks a run as failed, keeping the error for the report.
+pub async fn fail_run(pool: &PgPool, run_id: i32, error_message: &str) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE reconciliation_runs SET status = 'failed', finished_at = NOW(), error_message = $2 WHERE id = $1",
+        run_id,
+        error_message
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Adds a discrepancy to a run's report.
+pub async fn insert_discrepancy(
+    pool: &PgPool,
+    run_id: i32,
+    discrepancy: &NewDiscrepancy,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO reconciliation_discrepancies
+            (run_id, kind, stripe_payment_intent_id, payment_id, order_id, our_status, provider_status,
+             our_amount, provider_amount, auto_resolved, details)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        "#,
+        run_id,
+        discrepancy.kind as _,
+        discrepancy.stripe_payment_intent_id,
+        discrepancy.payment_id,
+        discrepancy.order_id,
+        discrepancy.our_status,
+        discrepancy.provider_status,
+        discrepancy.our_amount,
+        discrepancy.provider_amount,
+        discrepancy.auto_resolved,
+        discrepancy.details
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Lists the most recent reconciliation runs, newest first.
+pub async fn list_runs(pool: &PgPool, limit: i64) -> Result<Vec<ReconciliationRun>, AppError> {
+    sqlx::query_as!(
+        ReconciliationRun,
+        r#"
+        SELECT id, status AS "status: _", window_start, started_at, finished_at,
+               intents_checked, auto_resolved, discrepancies_found, error_message
+        FROM reconciliation_runs
+        ORDER BY started_at DESC
+        LIMIT $1
+        "#,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Fetches the discrepancy report of a run. Unresolved discrepancies come first.
+pub async fn list_discrepancies_for_run(
+    pool: &PgPool,
+    run_id: i32,
+) -> Result<Vec<ReconciliationDiscrepancy>, AppError> {
+    sqlx::query_as!(
+        ReconciliationDiscrepancy,
+        r#"
+        SELECT id, run_id, kind AS "kind: _", stripe_payment_intent_id, payment_id, order_id,
+               our_status, provider_status, our_amount, provider_amount, auto_resolved, details, created_at
+        FROM reconciliation_discrepancies
+        WHERE run_id = $1
+        ORDER BY auto_resolved, id
+        "#,
+        run_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
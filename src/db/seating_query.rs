@@ -1,76 +1,405 @@
 use crate::{
+    db::realtime_query,
     errors::AppError,
-    models::{EventSeat, Seat, SeatMapInfo, SeatStatus, Section, Row},
+    models::{
+        CreateSeatingChartPayload, SeatCandidate, SeatSelector, Row, RowSeat, Seat, SeatAttribute, SeatMapInfo, SeatMapSeat, SeatMapVersion, SeatStatus,
+        SeatStatusChange,
+        SeatingChart, SeatingLayout, Section, UpdateSeatingChartPayload,
+    },
 };
 use rust_decimal::Decimal;
-use sqlx::{PgPool, Postgres, Transaction};
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use uuid::Uuid;
 
 // --- Layout Management Queries (The Template) ---
 
-/// Creates a new section within a seating chart.
-pub async fn create_section(
+/// Creates a new, empty seating chart for a venue.
+pub async fn create_chart<'e, E>(
+    executor: E,
+    venue_id: i32,
+    payload: &CreateSeatingChartPayload,
+) -> Result<SeatingChart, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as!(
+        SeatingChart,
+        r#"
+        INSERT INTO seating_charts (venue_id, name, background_image_url, stage_x, stage_y)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING *
+        "#,
+        venue_id,
+        payload.name,
+        payload.background_image_url,
+        payload.stage_x,
+        payload.stage_y
+    )
+    .fetch_one(executor)
+    .await
+    .map_err(chart_name_conflict)
+}
+
+/// Fetches a seating chart by its ID.
+pub async fn get_chart<'e, E>(executor: E, chart_id: i32) -> Result<SeatingChart, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as!(SeatingChart, "SELECT * FROM seating_charts WHERE id = $1", chart_id)
+        .fetch_one(executor)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Lists the seating charts of a venue.
+pub async fn list_charts_for_venue(pool: &PgPool, venue_id: i32) -> Result<Vec<SeatingChart>, AppError> {
+    sqlx::query_as!(
+        SeatingChart,
+        "SELECT * FROM seating_charts WHERE venue_id = $1 ORDER BY name",
+        venue_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Renames a seating chart or changes its background image.
+pub async fn update_chart(
     pool: &PgPool,
-    seating_chart_id: i32,
-    name: &str,
-) -> Result<Section, AppError> {
+    chart_id: i32,
+    payload: &UpdateSeatingChartPayload,
+) -> Result<SeatingChart, AppError> {
     sqlx::query_as!(
-        Section,
-        "INSERT INTO sections (seating_chart_id, name) VALUES ($1, $2) RETURNING *",
-        seating_chart_id,
-        name
+        SeatingChart,
+        r#"
+        UPDATE seating_charts
+        SET
+            name = COALESCE($1, name),
+            background_image_url = COALESCE($2, background_image_url),
+            stage_x = COALESCE($3, stage_x),
+            stage_y = COALESCE($4, stage_y)
+        WHERE id = $5
+        RETURNING *
+        "#,
+        payload.name,
+        payload.background_image_url,
+        payload.stage_x,
+        payload.stage_y,
+        chart_id
     )
     .fetch_one(pool)
     .await
+    .map_err(chart_name_conflict)
+}
+
+/// Turns a clash with another chart's name at the same venue into a client error.
+fn chart_name_conflict(e: sqlx::Error) -> AppError {
+    match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            AppError::BadRequest("This venue already has a seating chart with that name.".to_string())
+        }
+        e => AppError::from(e),
+    }
+}
+
+/// Deletes a seating chart and its whole layout.
+pub async fn delete_chart(tx: &mut Transaction<'_, Postgres>, chart_id: i32) -> Result<(), AppError> {
+    let result = sqlx::query!("DELETE FROM seating_charts WHERE id = $1", chart_id)
+        .execute(&mut **tx)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::Sqlx(sqlx::Error::RowNotFound));
+    }
+    Ok(())
+}
+
+/// Locks a seating chart for the rest of the transaction, so no event can start
+/// using its seats while the layout is being replaced or deleted.
+pub async fn lock_chart(tx: &mut Transaction<'_, Postgres>, chart_id: i32) -> Result<SeatingChart, AppError> {
+    sqlx::query_as!(
+        SeatingChart,
+        "SELECT * FROM seating_charts WHERE id = $1 FOR UPDATE",
+        chart_id
+    )
+    .fetch_one(&mut **tx)
+    .await
     .map_err(AppError::from)
 }
 
-/// Creates a new row within a section.
-pub async fn create_row(pool: &PgPool, section_id: i32, name: &str) -> Result<Row, AppError> {
+/// Checks whether any event has been set up with the seats of a chart.
+/// Such charts can't be deleted, and rebuilding them retires seats instead of deleting them.
+pub async fn is_chart_in_use(tx: &mut Transaction<'_, Postgres>, chart_id: i32) -> Result<bool, AppError> {
+    let in_use = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1
+            FROM event_seats es
+            JOIN seats s ON es.seat_id = s.id
+            JOIN rows r ON s.row_id = r.id
+            JOIN sections sec ON r.section_id = sec.id
+            WHERE sec.seating_chart_id = $1
+        ) AS "in_use!"
+        "#,
+        chart_id
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+
+    Ok(in_use)
+}
+
+/// Removes every section (and with them every row and seat) of a chart.
+pub async fn clear_layout(tx: &mut Transaction<'_, Postgres>, chart_id: i32) -> Result<(), AppError> {
+    sqlx::query!("DELETE FROM sections WHERE seating_chart_id = $1", chart_id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// Writes a whole layout into a chart with one statement per level.
+/// The layout must already be validated: names must be unique at each level.
+/// Sections, rows and seats the chart already has (by name) are updated in place and
+/// keep their IDs; those missing from the layout are retired.
+/// Returns the number of seats written.
+pub async fn write_layout(
+    tx: &mut Transaction<'_, Postgres>,
+    chart_id: i32,
+    layout: &SeatingLayout,
+) -> Result<u64, AppError> {
+    // 1. Sections, keyed by name to find their IDs.
+    let section_names: Vec<String> = layout.sections.iter().map(|s| s.name.clone()).collect();
+    let section_ids: HashMap<String, i32> = sqlx::query!(
+        r#"
+        INSERT INTO sections (seating_chart_id, name)
+        SELECT $1, name FROM UNNEST($2::text[]) AS name
+        ON CONFLICT (seating_chart_id, name) DO UPDATE SET retired_at = NULL
+        RETURNING id, name
+        "#,
+        chart_id,
+        &section_names
+    )
+    .fetch_all(&mut **tx)
+    .await?
+    .into_iter()
+    .map(|r| (r.name, r.id))
+    .collect();
+
+    // 2. Rows, keyed by their section and name. Rows are listed front to back,
+    //    which gives their ordinals.
+    let mut row_section_ids = Vec::new();
+    let mut row_names = Vec::new();
+    let mut row_ordinals = Vec::new();
+    for section in &layout.sections {
+        for (ordinal, row) in (1..).zip(&section.rows) {
+            row_section_ids.push(section_ids[&section.name]);
+            row_names.push(row.name.clone());
+            row_ordinals.push(ordinal);
+        }
+    }
+    let row_ids: HashMap<(i32, String), i32> = sqlx::query!(
+        r#"
+        INSERT INTO rows (section_id, name, ordinal)
+        SELECT * FROM UNNEST($1::int[], $2::text[], $3::int[])
+        ON CONFLICT (section_id, name) DO UPDATE SET ordinal = EXCLUDED.ordinal, retired_at = NULL
+        RETURNING id, section_id, name
+        "#,
+        &row_section_ids,
+        &row_names,
+        &row_ordinals
+    )
+    .fetch_all(&mut **tx)
+    .await?
+    .into_iter()
+    .map(|r| ((r.section_id, r.name), r.id))
+    .collect();
+
+    // 3. Seats, with the column defaults filled in for missing sizes.
+    //    Seats are listed in row order, which gives their ordinals. Arrays can't be
+    //    unnested per seat, so each seat's attributes travel as one comma-separated string.
+    let default_size = Decimal::from(20);
+    let mut seat_row_ids = Vec::new();
+    let mut seat_numbers = Vec::new();
+    let mut ordinals = Vec::new();
+    let mut xs = Vec::new();
+    let mut ys = Vec::new();
+    let mut widths = Vec::new();
+    let mut heights = Vec::new();
+    let mut attributes = Vec::new();
+    for section in &layout.sections {
+        let section_id = section_ids[&section.name];
+        for row in &section.rows {
+            let row_id = row_ids[&(section_id, row.name.clone())];
+            for (ordinal, seat) in (1..).zip(&row.seats) {
+                seat_row_ids.push(row_id);
+                seat_numbers.push(seat.seat_number.clone());
+                ordinals.push(ordinal);
+                xs.push(seat.pos_x);
+                ys.push(seat.pos_y);
+                widths.push(seat.width.unwrap_or(default_size));
+                heights.push(seat.height.unwrap_or(default_size));
+                attributes.push(seat.attributes.iter().map(|a| a.as_str()).collect::<Vec<_>>().join(","));
+            }
+        }
+    }
+    let seat_ids = sqlx::query_scalar!(
+        r#"
+        INSERT INTO seats (row_id, seat_number, pos_x, pos_y, width, height, ordinal, attributes)
+        SELECT row_id, seat_number, pos_x, pos_y, width, height, ordinal,
+               string_to_array(attributes, ',')::seat_attribute[]
+        FROM UNNEST(
+            $1::int[], $2::text[], $3::numeric[], $4::numeric[], $5::numeric[], $6::numeric[], $7::int[], $8::text[]
+        ) AS t(row_id, seat_number, pos_x, pos_y, width, height, ordinal, attributes)
+        ON CONFLICT (row_id, seat_number) DO UPDATE SET
+            pos_x = EXCLUDED.pos_x,
+            pos_y = EXCLUDED.pos_y,
+            width = EXCLUDED.width,
+            height = EXCLUDED.height,
+            ordinal = EXCLUDED.ordinal,
+            attributes = EXCLUDED.attributes,
+            retired_at = NULL
+        RETURNING id
+        "#,
+        &seat_row_ids,
+        &seat_numbers,
+        &xs,
+        &ys,
+        &widths,
+        &heights,
+        &ordinals,
+        &attributes
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    // 4. Retire whatever of the chart the layout left out.
+    let section_ids: Vec<i32> = section_ids.into_values().collect();
+    let row_ids: Vec<i32> = row_ids.into_values().collect();
+    sqlx::query!(
+        r#"
+        UPDATE sections SET retired_at = NOW()
+        WHERE seating_chart_id = $1 AND retired_at IS NULL AND NOT (id = ANY($2))
+        "#,
+        chart_id,
+        &section_ids
+    )
+    .execute(&mut **tx)
+    .await?;
+    sqlx::query!(
+        r#"
+        UPDATE rows r SET retired_at = NOW()
+        FROM sections sec
+        WHERE r.section_id = sec.id AND sec.seating_chart_id = $1
+          AND r.retired_at IS NULL AND NOT (r.id = ANY($2))
+        "#,
+        chart_id,
+        &row_ids
+    )
+    .execute(&mut **tx)
+    .await?;
+    sqlx::query!(
+        r#"
+        UPDATE seats s SET retired_at = NOW()
+        FROM rows r
+        JOIN sections sec ON r.section_id = sec.id
+        WHERE s.row_id = r.id AND sec.seating_chart_id = $1
+          AND s.retired_at IS NULL AND NOT (s.id = ANY($2))
+        "#,
+        chart_id,
+        &seat_ids
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(seat_ids.len() as u64)
+}
+
+/// Fetches the sections of a chart.
+pub async fn list_sections_for_chart(pool: &PgPool, chart_id: i32) -> Result<Vec<Section>, AppError> {
+    sqlx::query_as!(
+        Section,
+        "SELECT id, seating_chart_id, name FROM sections WHERE seating_chart_id = $1 AND retired_at IS NULL ORDER BY id",
+        chart_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Fetches the rows of every section of a chart.
+pub async fn list_rows_for_chart(pool: &PgPool, chart_id: i32) -> Result<Vec<Row>, AppError> {
     sqlx::query_as!(
         Row,
-        "INSERT INTO rows (section_id, name) VALUES ($1, $2) RETURNING *",
-        section_id,
-        name
+        r#"
+        SELECT r.id, r.section_id, r.name
+        FROM rows r
+        JOIN sections sec ON r.section_id = sec.id
+        WHERE sec.seating_chart_id = $1 AND r.retired_at IS NULL
+        ORDER BY r.section_id, r.ordinal
+        "#,
+        chart_id
     )
-    .fetch_one(pool)
+    .fetch_all(pool)
     .await
     .map_err(AppError::from)
 }
 
-/// Creates a new physical seat within a row, defining its position.
-pub async fn create_seat(
-    pool: &PgPool,
-    row_id: i32,
-    seat_number: &str,
-    pos_x: Decimal,
-    pos_y: Decimal,
-) -> Result<Seat, AppError> {
+/// Fetches every seat of a chart.
+pub async fn list_seats_for_chart(pool: &PgPool, chart_id: i32) -> Result<Vec<Seat>, AppError> {
     sqlx::query_as!(
         Seat,
-        "INSERT INTO seats (row_id, seat_number, pos_x, pos_y) VALUES ($1, $2, $3, $4) RETURNING *",
-        row_id,
-        seat_number,
-        pos_x,
-        pos_y
+        r#"
+        SELECT
+            s.id, s.row_id, s.seat_number, s.pos_x, s.pos_y, s.width, s.height, s.ordinal,
+            s.attributes AS "attributes: Vec<SeatAttribute>"
+        FROM seats s
+        JOIN rows r ON s.row_id = r.id
+        JOIN sections sec ON r.section_id = sec.id
+        WHERE sec.seating_chart_id = $1 AND s.retired_at IS NULL
+        ORDER BY s.row_id, s.ordinal, s.id
+        "#,
+        chart_id
     )
-    .fetch_one(pool)
+    .fetch_all(pool)
     .await
     .map_err(AppError::from)
 }
 
 // --- Event Seat Management Queries (The Instance) ---
 
-/// Populates the `event_seats` table for a new event.
-/// This crucial function creates a dynamic record for every physical seat in a chart,
-/// assigning them to a default ticket tier.
-/// MUST be run in a transaction.
-pub async fn create_event_seats_for_chart(
+/// Finds the seating chart an event's seats were set up from, if any.
+pub async fn get_event_chart_id(tx: &mut Transaction<'_, Postgres>, event_id: i32) -> Result<Option<i32>, AppError> {
+    let chart_id = sqlx::query_scalar!(
+        r#"
+        SELECT sec.seating_chart_id
+        FROM event_seats es
+        JOIN seats s ON es.seat_id = s.id
+        JOIN rows r ON s.row_id = r.id
+        JOIN sections sec ON r.section_id = sec.id
+        WHERE es.event_id = $1
+        LIMIT 1
+        "#,
+        event_id
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    Ok(chart_id)
+}
+
+/// Populates the `event_seats` table for an event.
+/// This crucial function creates a dynamic record for every live seat in a chart the
+/// event doesn't have yet, assigning them to a default ticket tier.
+/// Returns the IDs of the seats added. MUST be run in a transaction.
+pub async fn add_event_seats_for_chart(
     tx: &mut Transaction<'_, Postgres>,
     event_id: i32,
     seating_chart_id: i32,
     default_ticket_tier_id: i32,
-) -> Result<u64, AppError> {
-    let result = sqlx::query!(
+) -> Result<Vec<i32>, AppError> {
+    let seat_ids = sqlx::query_scalar!(
         r#"
         INSERT INTO event_seats (event_id, seat_id, ticket_tier_id, status)
         SELECT
@@ -81,16 +410,169 @@ pub async fn create_event_seats_for_chart(
         FROM seats s
         JOIN rows r ON s.row_id = r.id
         JOIN sections sec ON r.section_id = sec.id
-        WHERE sec.seating_chart_id = $2
+        WHERE sec.seating_chart_id = $2 AND s.retired_at IS NULL
+        ORDER BY s.row_id, s.ordinal, s.id
+        ON CONFLICT (event_id, seat_id) DO NOTHING
+        RETURNING seat_id
         "#,
         event_id,
         seating_chart_id,
         default_ticket_tier_id
     )
-    .execute(&mut **tx)
+    .fetch_all(&mut **tx)
+    .await?;
+
+    Ok(seat_ids)
+}
+
+/// Removes an event's seats that were retired from its chart, unless they are sold or
+/// in a checkout. Returns the IDs of the seats removed.
+pub async fn retire_event_seats(tx: &mut Transaction<'_, Postgres>, event_id: i32) -> Result<Vec<i32>, AppError> {
+    let seat_ids = sqlx::query_scalar!(
+        r#"
+        DELETE FROM event_seats es
+        USING seats s
+        WHERE es.seat_id = s.id AND es.event_id = $1 AND s.retired_at IS NOT NULL
+          AND es.status IN ('available', 'unavailable', 'held')
+        RETURNING es.seat_id
+        "#,
+        event_id
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    Ok(seat_ids)
+}
+
+/// Lists an event's seats that were retired from its chart but are kept, because they
+/// are sold or in a checkout.
+pub async fn list_kept_retired_event_seats(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+) -> Result<Vec<i32>, AppError> {
+    let seat_ids = sqlx::query_scalar!(
+        r#"
+        SELECT es.seat_id
+        FROM event_seats es
+        JOIN seats s ON es.seat_id = s.id
+        WHERE es.event_id = $1 AND s.retired_at IS NOT NULL
+        ORDER BY es.seat_id
+        "#,
+        event_id
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    Ok(seat_ids)
+}
+
+/// Finds the seats of an event that a selector matches, with their current status.
+pub async fn list_selected_event_seats(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+    selector: &SeatSelector,
+) -> Result<Vec<(i32, SeatStatus)>, AppError> {
+    let seats = match selector {
+        SeatSelector::Section { section } => sqlx::query!(
+            r#"
+            SELECT es.seat_id, es.status AS "status: SeatStatus"
+            FROM event_seats es
+            JOIN seats s ON es.seat_id = s.id
+            JOIN rows r ON s.row_id = r.id
+            JOIN sections sec ON r.section_id = sec.id
+            WHERE es.event_id = $1 AND sec.name = $2
+            ORDER BY es.seat_id
+            FOR UPDATE OF es
+            "#,
+            event_id,
+            section
+        )
+        .fetch_all(&mut **tx)
+        .await?
+        .into_iter()
+        .map(|r| (r.seat_id, r.status))
+        .collect(),
+        // Rows are ranged by their ordinals, the order the layout listed them, front to back.
+        SeatSelector::Rows { section, from_row, to_row } => sqlx::query!(
+            r#"
+            WITH section_seats AS (
+                SELECT es.seat_id, es.status, r.ordinal AS row_ordinal, r.name AS row_name
+                FROM event_seats es
+                JOIN seats s ON es.seat_id = s.id
+                JOIN rows r ON s.row_id = r.id
+                JOIN sections sec ON r.section_id = sec.id
+                WHERE es.event_id = $1 AND sec.name = $2
+                FOR UPDATE OF es
+            ),
+            bounds AS (
+                SELECT
+                    (SELECT MIN(row_ordinal) FROM section_seats WHERE row_name = $3) AS from_ordinal,
+                    (SELECT MIN(row_ordinal) FROM section_seats WHERE row_name = $4) AS to_ordinal
+            )
+            SELECT ss.seat_id AS "seat_id!", ss.status AS "status!: SeatStatus"
+            FROM section_seats ss, bounds b
+            WHERE ss.row_ordinal BETWEEN LEAST(b.from_ordinal, b.to_ordinal) AND GREATEST(b.from_ordinal, b.to_ordinal)
+            ORDER BY ss.seat_id
+            "#,
+            event_id,
+            section,
+            from_row,
+            to_row
+        )
+        .fetch_all(&mut **tx)
+        .await?
+        .into_iter()
+        .map(|r| (r.seat_id, r.status))
+        .collect(),
+        SeatSelector::Seats { seat_ids } => sqlx::query!(
+            r#"
+            SELECT seat_id, status AS "status: SeatStatus"
+            FROM event_seats
+            WHERE event_id = $1 AND seat_id = ANY($2)
+            ORDER BY seat_id
+            FOR UPDATE
+            "#,
+            event_id,
+            seat_ids
+        )
+        .fetch_all(&mut **tx)
+        .await?
+        .into_iter()
+        .map(|r| (r.seat_id, r.status))
+        .collect(),
+    };
+    Ok(seats)
+}
+
+/// Moves event seats to another tier and/or sets their status. Only seats that are
+/// available or unavailable are changed; locked and sold seats are left alone.
+/// Live seat maps are notified as part of the transaction.
+pub async fn update_event_seats(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+    seat_ids: &[i32],
+    ticket_tier_id: Option<i32>,
+    status: Option<SeatStatus>,
+) -> Result<Vec<SeatStatusChange>, AppError> {
+    let changes = sqlx::query_as!(
+        SeatStatusChange,
+        r#"
+        UPDATE event_seats
+        SET ticket_tier_id = COALESCE($3, ticket_tier_id),
+            status = COALESCE($4, status)
+        WHERE event_id = $1 AND seat_id = ANY($2) AND status IN ('available', 'unavailable')
+        RETURNING event_id, seat_id, status AS "status: _"
+        "#,
+        event_id,
+        seat_ids,
+        ticket_tier_id,
+        status as Option<SeatStatus>
+    )
+    .fetch_all(&mut **tx)
     .await?;
 
-    Ok(result.rows_affected())
+    realtime_query::notify_seat_changes(&mut **tx, &changes).await?;
+    Ok(changes)
 }
 
 /// Fetches all the data needed to render a complete, interactive seat map for an event.
@@ -107,17 +589,51 @@ pub async fn get_seat_map_for_event(
             s.seat_number,
             s.pos_x,
             s.pos_y,
+            s.attributes AS "attributes: Vec<SeatAttribute>",
             es.status as "status: _",
             es.ticket_tier_id,
             tt.name as ticket_tier_name,
-            o.price
+            offer_current_price(o.id) AS "price!"
         FROM event_seats es
         JOIN seats s ON es.seat_id = s.id
         JOIN ticket_tiers tt ON es.ticket_tier_id = tt.id
         -- We need a representative offer to get the price.
         -- This assumes one primary 'on_sale' offer per tier for display.
-        LEFT JOIN offers o ON tt.id = o.ticket_tier_id AND o.status = 'on_sale' AND o.access_code IS NULL
+        LEFT JOIN offers o ON tt.id = o.ticket_tier_id AND o.status = 'on_sale' AND NOT o.is_presale
+        WHERE es.event_id = $1
+        "#,
+        event_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Fetches every seat of an event with what's needed to draw it: its rectangle,
+/// its section and row, its status and its tier.
+pub async fn get_seat_map_seats(pool: &PgPool, event_id: i32) -> Result<Vec<SeatMapSeat>, AppError> {
+    sqlx::query_as!(
+        SeatMapSeat,
+        r#"
+        SELECT
+            s.id AS seat_id,
+            sec.name AS section_name,
+            r.name AS row_name,
+            s.seat_number,
+            s.pos_x,
+            s.pos_y,
+            s.width,
+            s.height,
+            es.status AS "status: _",
+            es.ticket_tier_id,
+            tt.name AS ticket_tier_name
+        FROM event_seats es
+        JOIN seats s ON es.seat_id = s.id
+        JOIN rows r ON s.row_id = r.id
+        JOIN sections sec ON r.section_id = sec.id
+        JOIN ticket_tiers tt ON es.ticket_tier_id = tt.id
         WHERE es.event_id = $1
+        ORDER BY sec.id, r.ordinal, s.id
         "#,
         event_id
     )
@@ -126,64 +642,267 @@ pub async fn get_seat_map_for_event(
     .map_err(AppError::from)
 }
 
+/// Returns how many seats an event has and when any of them last changed.
+pub async fn get_seat_map_version(pool: &PgPool, event_id: i32) -> Result<SeatMapVersion, AppError> {
+    sqlx::query_as!(
+        SeatMapVersion,
+        r#"
+        SELECT COUNT(*) AS "seat_count!", MAX(last_updated) AS last_changed
+        FROM event_seats
+        WHERE event_id = $1
+        "#,
+        event_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+
+/// Finds and releases all expired seat locks across the system.
+/// Seats locked by an order are left to the order expiry, which releases them together
+/// with the order, so a late payment can never be matched to a seat that was resold.
+/// Seats reserved for a waitlisted user are left to the waitlist, which passes them on, and
+/// seats in a cart to the cart expiry.
+/// This should be run periodically by a background worker/job.
+pub async fn release_expired_locks(pool: &PgPool) -> Result<Vec<SeatStatusChange>, AppError> {
+    let released = sqlx::query_as!(
+        SeatStatusChange,
+        r#"
+        UPDATE event_seats
+        SET status = 'available', lock_expires_at = NULL, locked_by_user_id = NULL
+        WHERE status = 'locked' AND lock_expires_at < NOW() AND order_id IS NULL AND waitlist_entry_id IS NULL
+          AND cart_id IS NULL
+        RETURNING event_id, seat_id, status AS "status: _"
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    realtime_query::notify_seat_changes(pool, &released).await?;
+    Ok(released)
+}
+
+/// Marks the seats locked by an order as sold. Run when the order is paid.
+pub async fn mark_order_seats_sold(
+    tx: &mut Transaction<'_, Postgres>,
+    order_id: Uuid,
+) -> Result<Vec<SeatStatusChange>, AppError> {
+    let changes = sqlx::query_as!(
+        SeatStatusChange,
+        r#"
+        UPDATE event_seats
+        SET status = 'sold', lock_expires_at = NULL, locked_by_user_id = NULL
+        WHERE order_id = $1 AND status = 'locked'
+        RETURNING event_id, seat_id, status AS "status: _"
+        "#,
+        order_id
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    realtime_query::notify_seat_changes(&mut **tx, &changes).await?;
+    Ok(changes)
+}
 
-/// Attempts to lock a specific seat for an event.
-/// This is a critical atomic operation for the checkout process.
-/// It will fail if the seat is not 'available'.
-pub async fn lock_seat(
+/// Releases the seats locked by the given orders, e.g. after they expired unpaid.
+pub async fn release_order_seats(
+    tx: &mut Transaction<'_, Postgres>,
+    order_ids: &[Uuid],
+) -> Result<Vec<SeatStatusChange>, AppError> {
+    let changes = sqlx::query_as!(
+        SeatStatusChange,
+        r#"
+        UPDATE event_seats
+        SET status = 'available', lock_expires_at = NULL, order_id = NULL, locked_by_user_id = NULL
+        WHERE order_id = ANY($1) AND status = 'locked'
+        RETURNING event_id, seat_id, status AS "status: _"
+        "#,
+        order_ids
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    realtime_query::notify_seat_changes(&mut **tx, &changes).await?;
+    Ok(changes)
+}
+
+/// Puts the seats sold to a refunded order back on sale.
+pub async fn release_refunded_order_seats(
+    tx: &mut Transaction<'_, Postgres>,
+    order_id: Uuid,
+) -> Result<Vec<SeatStatusChange>, AppError> {
+    let changes = sqlx::query_as!(
+        SeatStatusChange,
+        r#"
+        UPDATE event_seats
+        SET status = 'available', order_id = NULL
+        WHERE order_id = $1 AND status = 'sold'
+        RETURNING event_id, seat_id, status AS "status: _"
+        "#,
+        order_id
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    realtime_query::notify_seat_changes(&mut **tx, &changes).await?;
+    Ok(changes)
+}
+
+// --- Best Available Queries ---
+
+/// Fetches every seat of an event for the best-available search, in row order.
+/// Each seat's price is that of the cheapest public offer on sale for its tier.
+pub async fn list_seat_candidates(
     pool: &PgPool,
     event_id: i32,
-    seat_id: i32,
-    lock_duration_minutes: i64,
-) -> Result<(), AppError> {
-    let lock_expires_at = chrono::Utc::now()
-        + chrono::Duration::minutes(lock_duration_minutes);
-
-    let result = sqlx::query!(
-        "UPDATE event_seats
-         SET status = 'locked', lock_expires_at = $1
-         WHERE event_id = $2 AND seat_id = $3 AND status = 'available'",
-        lock_expires_at,
+    user_id: i32,
+) -> Result<Vec<SeatCandidate>, AppError> {
+    sqlx::query_as!(
+        SeatCandidate,
+        r#"
+        SELECT
+            s.id AS seat_id,
+            s.row_id,
+            sec.name AS section_name,
+            r.name AS row_name,
+            s.seat_number,
+            s.pos_x,
+            s.pos_y,
+            s.width,
+            s.height,
+            -- Seats the user already holds outside of an order count as available to them.
+            CASE
+                WHEN es.status = 'locked' AND es.order_id IS NULL AND es.locked_by_user_id = $2
+                THEN 'available'::seat_status
+                ELSE es.status
+            END AS "status!: _",
+            es.ticket_tier_id,
+            best_offer.id AS "offer_id?",
+            best_offer.price AS "price?",
+            -- Wheelchair and companion seats before their release are left to accessibility offers.
+            (s.attributes && ARRAY['wheelchair', 'companion']::seat_attribute[]
+                AND NOW() < e.start_time - make_interval(hours => e.accessible_release_hours)) AS "is_protected!",
+            sc.stage_x,
+            sc.stage_y
+        FROM event_seats es
+        JOIN events e ON es.event_id = e.id
+        JOIN seats s ON es.seat_id = s.id
+        JOIN rows r ON s.row_id = r.id
+        JOIN sections sec ON r.section_id = sec.id
+        JOIN seating_charts sc ON sec.seating_chart_id = sc.id
+        LEFT JOIN LATERAL (
+            SELECT o.id, offer_current_price(o.id) AS price
+            FROM offers o
+            WHERE o.ticket_tier_id = es.ticket_tier_id AND o.status = 'on_sale' AND NOT o.is_presale
+              AND NOT o.is_accessible
+            ORDER BY offer_current_price(o.id), o.id
+            LIMIT 1
+        ) best_offer ON TRUE
+        WHERE es.event_id = $1
+        ORDER BY s.row_id, s.ordinal, s.id
+        "#,
         event_id,
-        seat_id
+        user_id
     )
-    .execute(pool)
-    .await?;
-
-    if result.rows_affected() == 0 {
-        // This is not a RowNotFound error, it's a conflict. The seat was likely taken.
-        // We can create a specific error for this in `AppError` later if needed.
-        Err(AppError::BadRequest("Seat is not available for locking.".to_string()))
-    } else {
-        Ok(())
-    }
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
 }
 
-/// Unlocks a specific seat (e.g., user removes it from their cart).
-pub async fn unlock_seat(pool: &PgPool, event_id: i32, seat_id: i32) -> Result<(), AppError> {
-    sqlx::query!(
-        "UPDATE event_seats
-         SET status = 'available', lock_expires_at = NULL
-         WHERE event_id = $1 AND seat_id = $2 AND status = 'locked'",
+/// Releases the seats a user holds for an event outside of an order.
+/// Seats reserved for them from a waitlist, or in their cart, stay theirs.
+pub async fn release_user_seat_locks(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+    user_id: i32,
+) -> Result<Vec<SeatStatusChange>, AppError> {
+    let changes = sqlx::query_as!(
+        SeatStatusChange,
+        r#"
+        UPDATE event_seats
+        SET status = 'available', lock_expires_at = NULL, locked_by_user_id = NULL
+        WHERE event_id = $1 AND locked_by_user_id = $2 AND status = 'locked' AND order_id IS NULL
+          AND waitlist_entry_id IS NULL AND cart_id IS NULL
+        RETURNING event_id, seat_id, status AS "status: _"
+        "#,
         event_id,
-        seat_id
+        user_id
     )
-    .execute(pool)
+    .fetch_all(&mut **tx)
     .await?;
 
-    Ok(())
+    realtime_query::notify_seat_changes(&mut **tx, &changes).await?;
+    Ok(changes)
 }
 
-/// Finds and releases all expired seat locks across the system.
-/// This should be run periodically by a background worker/job.
-pub async fn release_expired_locks(pool: &PgPool) -> Result<u64, AppError> {
-    let result = sqlx::query!(
-        "UPDATE event_seats
-         SET status = 'available', lock_expires_at = NULL
-         WHERE status = 'locked' AND lock_expires_at < NOW()"
+/// Locks the given seats for a user until `expires_at`. Seats that are no longer
+/// available are skipped; the caller compares the result with what it asked for.
+pub async fn lock_seats_for_user(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+    user_id: i32,
+    seat_ids: &[i32],
+    expires_at: DateTime<Utc>,
+) -> Result<Vec<SeatStatusChange>, AppError> {
+    let changes = sqlx::query_as!(
+        SeatStatusChange,
+        r#"
+        UPDATE event_seats
+        SET status = 'locked', lock_expires_at = $4, locked_by_user_id = $2
+        WHERE event_id = $1 AND seat_id = ANY($3) AND status = 'available'
+        RETURNING event_id, seat_id, status AS "status: _"
+        "#,
+        event_id,
+        user_id,
+        seat_ids,
+        expires_at
     )
-    .execute(pool)
+    .fetch_all(&mut **tx)
     .await?;
 
-    Ok(result.rows_affected())
-}
\ No newline at end of file
+    realtime_query::notify_seat_changes(&mut **tx, &changes).await?;
+    Ok(changes)
+}
+
+// --- Seat Gap Queries ---
+
+/// Fetches every seat in the rows of an order's seats, in row order, for each event
+/// of the order. The order's seats are marked as selected.
+pub async fn list_row_seats_for_order<'e, E>(executor: E, order_id: Uuid) -> Result<Vec<RowSeat>, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as!(
+        RowSeat,
+        r#"
+        SELECT
+            es.event_id,
+            es.seat_id,
+            s.row_id,
+            sec.name AS section_name,
+            r.name AS row_name,
+            s.pos_x,
+            s.pos_y,
+            s.width,
+            s.height,
+            es.status AS "status: _",
+            COALESCE(es.order_id = $1, FALSE) AS "selected!"
+        FROM event_seats es
+        JOIN seats s ON es.seat_id = s.id
+        JOIN rows r ON s.row_id = r.id
+        JOIN sections sec ON r.section_id = sec.id
+        WHERE (es.event_id, s.row_id) IN (
+            SELECT oes.event_id, os.row_id
+            FROM event_seats oes
+            JOIN seats os ON oes.seat_id = os.id
+            WHERE oes.order_id = $1
+        )
+        ORDER BY es.event_id, s.row_id, s.ordinal, s.id
+        "#,
+        order_id
+    )
+    .fetch_all(executor)
+    .await
+    .map_err(AppError::from)
+}
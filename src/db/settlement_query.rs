@@ -0,0 +1,385 @@
+use crate::{
+    errors::AppError,
+    models::{DailyStatement, EventStatement, OrganizerPayout, SettlementEntryType},
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// One event's share of a payment, used to split Stripe fees and refunds
+/// across the events of an order.
+#[derive(Debug, sqlx::FromRow)]
+pub struct PaymentEventShare {
+    pub stripe_payment_intent_id: String,
+    pub order_id: Uuid,
+    pub event_id: i32,
+    pub gross_amount: Decimal,
+    pub payment_created_at: DateTime<Utc>,
+}
+
+// --- Ledger Writes ---
+
+/// Records the gross sale, tickets and add-ons, and the platform fee for every event in a
+/// completed order.
+/// MUST be run in the same transaction that completes the order.
+pub async fn record_order_sale(
+    tx: &mut Transaction<'_, Postgres>,
+    order_id: Uuid,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO settlement_entries (event_id, order_id, entry_type, amount, occurred_at)
+        SELECT oi.event_id, oi.order_id, kind.entry_type, kind.amount, NOW()
+        FROM (
+            SELECT
+                event_id,
+                order_id,
+                SUM(quantity * (unit_price + unit_service_fee)) AS gross,
+                SUM(quantity * unit_service_fee) AS fees
+            FROM (
+                SELECT event_id, order_id, quantity, unit_price, unit_service_fee
+                FROM order_items WHERE order_id = $1
+                UNION ALL
+                -- Add-ons carry no service fee.
+                SELECT event_id, order_id, quantity, unit_price, 0
+                FROM order_addons WHERE order_id = $1
+            ) lines
+            GROUP BY event_id, order_id
+        ) oi
+        CROSS JOIN LATERAL (
+            VALUES
+                ('sale'::settlement_entry_type, oi.gross),
+                ('platform_fee'::settlement_entry_type, -oi.fees)
+        ) AS kind(entry_type, amount)
+        "#,
+        order_id
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Reverses an order's sale in the organizers' ledger after it was refunded outside of Stripe,
+/// e.g. as store credit: the gross is refunded and our service fee given back.
+pub async fn record_order_refund(
+    tx: &mut Transaction<'_, Postgres>,
+    order_id: Uuid,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO settlement_entries (event_id, order_id, entry_type, amount, occurred_at)
+        SELECT
+            s.event_id,
+            s.order_id,
+            CASE s.entry_type WHEN 'sale' THEN 'refund'::settlement_entry_type ELSE s.entry_type END,
+            -s.amount,
+            NOW()
+        FROM settlement_entries s
+        WHERE s.order_id = $1 AND s.entry_type IN ('sale', 'platform_fee')
+          AND s.stripe_balance_transaction_id IS NULL
+        "#,
+        order_id
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Records a ledger entry imported from a Stripe balance transaction.
+/// Does nothing if the balance transaction was already recorded for this event.
+/// Returns the number of rows inserted (0 or 1).
+#[allow(clippy::too_many_arguments)]
+pub async fn insert_stripe_entry(
+    pool: &PgPool,
+    event_id: i32,
+    order_id: Uuid,
+    entry_type: SettlementEntryType,
+    amount: Decimal,
+    currency: &str,
+    stripe_balance_transaction_id: &str,
+    occurred_at: DateTime<Utc>,
+) -> Result<u64, AppError> {
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO settlement_entries
+            (event_id, order_id, entry_type, amount, currency, stripe_balance_transaction_id, occurred_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (stripe_balance_transaction_id, event_id) DO NOTHING
+        "#,
+        event_id,
+        order_id,
+        entry_type as _,
+        amount,
+        currency,
+        stripe_balance_transaction_id,
+        occurred_at
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Lists, per payment and event, the gross amount charged for an organizer's events.
+/// Only payments that actually moved money are included.
+pub async fn list_payment_shares_for_organizer(
+    pool: &PgPool,
+    organizer_id: i32,
+) -> Result<Vec<PaymentEventShare>, AppError> {
+    sqlx::query_as!(
+        PaymentEventShare,
+        r#"
+        SELECT
+            p.stripe_payment_intent_id,
+            p.order_id,
+            oi.event_id AS "event_id!",
+            SUM(oi.quantity * (oi.unit_price + oi.unit_service_fee)) AS "gross_amount!",
+            p.created_at AS payment_created_at
+        FROM payments p
+        JOIN (
+            SELECT order_id, event_id, quantity, unit_price, unit_service_fee FROM order_items
+            UNION ALL
+            SELECT order_id, event_id, quantity, unit_price, 0 FROM order_addons
+        ) oi ON oi.order_id = p.order_id
+        JOIN events e ON e.id = oi.event_id
+        WHERE e.organizer_id = $1 AND p.status IN ('succeeded', 'refunded')
+        GROUP BY p.stripe_payment_intent_id, p.order_id, oi.event_id, p.created_at
+        "#,
+        organizer_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Lists every event share of a single payment, across all organizers.
+/// Used when a fee or refund must be split proportionally over an order's events.
+pub async fn list_shares_for_payment_intent(
+    pool: &PgPool,
+    stripe_payment_intent_id: &str,
+) -> Result<Vec<PaymentEventShare>, AppError> {
+    sqlx::query_as!(
+        PaymentEventShare,
+        r#"
+        SELECT
+            p.stripe_payment_intent_id,
+            p.order_id,
+            oi.event_id AS "event_id!",
+            SUM(oi.quantity * (oi.unit_price + oi.unit_service_fee)) AS "gross_amount!",
+            p.created_at AS payment_created_at
+        FROM payments p
+        JOIN (
+            SELECT order_id, event_id, quantity, unit_price, unit_service_fee FROM order_items
+            UNION ALL
+            SELECT order_id, event_id, quantity, unit_price, 0 FROM order_addons
+        ) oi ON oi.order_id = p.order_id
+        WHERE p.stripe_payment_intent_id = $1
+        GROUP BY p.stripe_payment_intent_id, p.order_id, oi.event_id, p.created_at
+        ORDER BY oi.event_id
+        "#,
+        stripe_payment_intent_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Inserts or refreshes a payout fetched from an organizer's connected account.
+#[allow(clippy::too_many_arguments)]
+pub async fn upsert_payout(
+    pool: &PgPool,
+    organizer_id: i32,
+    stripe_payout_id: &str,
+    amount: Decimal,
+    currency: &str,
+    status: &str,
+    arrival_date: DateTime<Utc>,
+    created_at: DateTime<Utc>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO organizer_payouts
+            (organizer_id, stripe_payout_id, amount, currency, status, arrival_date, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (stripe_payout_id) DO UPDATE
+        SET status = EXCLUDED.status,
+            arrival_date = EXCLUDED.arrival_date,
+            last_synced_at = NOW()
+        "#,
+        organizer_id,
+        stripe_payout_id,
+        amount,
+        currency,
+        status,
+        arrival_date,
+        created_at
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Fetches the `created` time of the newest balance transaction an organizer's sync has read.
+pub async fn get_sync_cursor(pool: &PgPool, organizer_id: i32) -> Result<Option<DateTime<Utc>>, AppError> {
+    sqlx::query_scalar!(
+        "SELECT synced_until FROM settlement_sync_cursors WHERE organizer_id = $1",
+        organizer_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Moves an organizer's sync cursor forward to `synced_until`. It never moves back.
+pub async fn advance_sync_cursor(
+    pool: &PgPool,
+    organizer_id: i32,
+    synced_until: DateTime<Utc>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO settlement_sync_cursors (organizer_id, synced_until)
+        VALUES ($1, $2)
+        ON CONFLICT (organizer_id) DO UPDATE
+        SET synced_until = GREATEST(settlement_sync_cursors.synced_until, EXCLUDED.synced_until),
+            last_synced_at = NOW()
+        "#,
+        organizer_id,
+        synced_until
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// --- Statements ---
+
+/// Builds the settlement statement for every event owned by an organizer.
+pub async fn list_event_statements(
+    pool: &PgPool,
+    organizer_id: i32,
+) -> Result<Vec<EventStatement>, AppError> {
+    sqlx::query_as!(
+        EventStatement,
+        r#"
+        SELECT
+            e.id AS event_id,
+            e.title AS event_title,
+            COALESCE(SUM(se.amount) FILTER (WHERE se.entry_type = 'sale'), 0) AS "gross_sales!",
+            COALESCE(-SUM(se.amount) FILTER (WHERE se.entry_type = 'refund'), 0) AS "refunds!",
+            COALESCE(-SUM(se.amount) FILTER (WHERE se.entry_type = 'platform_fee'), 0) AS "platform_fees!",
+            COALESCE(-SUM(se.amount) FILTER (WHERE se.entry_type = 'stripe_fee'), 0) AS "stripe_fees!",
+            COALESCE(-SUM(se.amount) FILTER (WHERE se.entry_type = 'dispute'), 0) AS "disputes!",
+            COALESCE(SUM(se.amount), 0) AS "net_due!"
+        FROM events e
+        LEFT JOIN settlement_entries se ON se.event_id = e.id
+        WHERE e.organizer_id = $1
+        GROUP BY e.id, e.title
+        ORDER BY e.start_time DESC
+        "#,
+        organizer_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Builds the settlement statement for a single event.
+pub async fn get_event_statement(pool: &PgPool, event_id: i32) -> Result<EventStatement, AppError> {
+    sqlx::query_as!(
+        EventStatement,
+        r#"
+        SELECT
+            e.id AS event_id,
+            e.title AS event_title,
+            COALESCE(SUM(se.amount) FILTER (WHERE se.entry_type = 'sale'), 0) AS "gross_sales!",
+            COALESCE(-SUM(se.amount) FILTER (WHERE se.entry_type = 'refund'), 0) AS "refunds!",
+            COALESCE(-SUM(se.amount) FILTER (WHERE se.entry_type = 'platform_fee'), 0) AS "platform_fees!",
+            COALESCE(-SUM(se.amount) FILTER (WHERE se.entry_type = 'stripe_fee'), 0) AS "stripe_fees!",
+            COALESCE(-SUM(se.amount) FILTER (WHERE se.entry_type = 'dispute'), 0) AS "disputes!",
+            COALESCE(SUM(se.amount), 0) AS "net_due!"
+        FROM events e
+        LEFT JOIN settlement_entries se ON se.event_id = e.id
+        WHERE e.id = $1
+        GROUP BY e.id, e.title
+        "#,
+        event_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Builds one statement row per UTC day in `[from, to]` on which money moved,
+/// combining the ledger with the payouts made to the organizer's bank account.
+pub async fn list_daily_statements(
+    pool: &PgPool,
+    organizer_id: i32,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<DailyStatement>, AppError> {
+    sqlx::query_as!(
+        DailyStatement,
+        r#"
+        WITH ledger AS (
+            SELECT
+                (se.occurred_at AT TIME ZONE 'UTC')::date AS day,
+                COALESCE(SUM(se.amount) FILTER (WHERE se.entry_type = 'sale'), 0) AS gross_sales,
+                COALESCE(-SUM(se.amount) FILTER (WHERE se.entry_type = 'refund'), 0) AS refunds,
+                COALESCE(-SUM(se.amount) FILTER (WHERE se.entry_type = 'platform_fee'), 0) AS platform_fees,
+                COALESCE(-SUM(se.amount) FILTER (WHERE se.entry_type = 'stripe_fee'), 0) AS stripe_fees,
+                COALESCE(-SUM(se.amount) FILTER (WHERE se.entry_type = 'dispute'), 0) AS disputes,
+                SUM(se.amount) AS net_due
+            FROM settlement_entries se
+            JOIN events e ON e.id = se.event_id
+            WHERE e.organizer_id = $1
+              AND (se.occurred_at AT TIME ZONE 'UTC')::date BETWEEN $2 AND $3
+            GROUP BY 1
+        ),
+        paid AS (
+            SELECT (arrival_date AT TIME ZONE 'UTC')::date AS day, SUM(amount) AS paid_out
+            FROM organizer_payouts
+            WHERE organizer_id = $1
+              AND status = 'paid'
+              AND (arrival_date AT TIME ZONE 'UTC')::date BETWEEN $2 AND $3
+            GROUP BY 1
+        )
+        SELECT
+            COALESCE(l.day, p.day) AS "date!",
+            COALESCE(l.gross_sales, 0) AS "gross_sales!",
+            COALESCE(l.refunds, 0) AS "refunds!",
+            COALESCE(l.platform_fees, 0) AS "platform_fees!",
+            COALESCE(l.stripe_fees, 0) AS "stripe_fees!",
+            COALESCE(l.disputes, 0) AS "disputes!",
+            COALESCE(l.net_due, 0) AS "net_due!",
+            COALESCE(p.paid_out, 0) AS "paid_out!"
+        FROM ledger l
+        FULL OUTER JOIN paid p ON p.day = l.day
+        ORDER BY 1
+        "#,
+        organizer_id,
+        from,
+        to
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Lists the payouts synced for an organizer, newest first.
+pub async fn list_payouts(pool: &PgPool, organizer_id: i32) -> Result<Vec<OrganizerPayout>, AppError> {
+    sqlx::query_as!(
+        OrganizerPayout,
+        "SELECT id, organizer_id, stripe_payout_id, amount, currency, status, arrival_date, created_at, last_synced_at
+         FROM organizer_payouts WHERE organizer_id = $1 ORDER BY arrival_date DESC",
+        organizer_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
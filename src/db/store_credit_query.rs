@@ -0,0 +1,116 @@
+use crate::{
+    errors::AppError,
+    models::{StoreCreditEntry, StoreCreditEntryType},
+};
+use rust_decimal::Decimal;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// Gives a user store credit, recording where it came from in their ledger.
+pub async fn add_credit(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: i32,
+    entry_type: StoreCreditEntryType,
+    amount: Decimal,
+    order_id: Option<Uuid>,
+    gift_card_id: Option<i32>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        "INSERT INTO store_credit_accounts (user_id, balance) VALUES ($1, $2)
+         ON CONFLICT (user_id) DO UPDATE SET balance = store_credit_accounts.balance + EXCLUDED.balance",
+        user_id,
+        amount
+    )
+    .execute(&mut **tx)
+    .await?;
+    sqlx::query!(
+        "INSERT INTO store_credit_entries (user_id, entry_type, amount, order_id, gift_card_id)
+         VALUES ($1, $2, $3, $4, $5)",
+        user_id,
+        entry_type as StoreCreditEntryType,
+        amount,
+        order_id,
+        gift_card_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Spends a user's store credit on an order. Returns `false`, changing nothing, if their
+/// balance doesn't cover `amount`.
+pub async fn spend_credit(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: i32,
+    amount: Decimal,
+    order_id: Uuid,
+) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        "UPDATE store_credit_accounts SET balance = balance - $2 WHERE user_id = $1 AND balance >= $2",
+        user_id,
+        amount
+    )
+    .execute(&mut **tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+    sqlx::query!(
+        "INSERT INTO store_credit_entries (user_id, entry_type, amount, order_id)
+         VALUES ($1, 'order_payment', $2, $3)",
+        user_id,
+        -amount,
+        order_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(true)
+}
+
+/// Gives back the store credit spent on orders that went unpaid.
+pub async fn restore_for_orders(tx: &mut Transaction<'_, Postgres>, order_ids: &[Uuid]) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        WITH restored AS (
+            INSERT INTO store_credit_entries (user_id, entry_type, amount, order_id)
+            SELECT user_id, 'order_payment_reversal', credit_applied, id
+            FROM orders WHERE id = ANY($1) AND credit_applied > 0
+            RETURNING user_id, amount
+        )
+        UPDATE store_credit_accounts a SET balance = a.balance + r.total
+        FROM (SELECT user_id, SUM(amount) AS total FROM restored GROUP BY user_id) r
+        WHERE a.user_id = r.user_id
+        "#,
+        order_ids
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Fetches a user's store credit balance. Users who never had credit have none.
+pub async fn get_balance(pool: &PgPool, user_id: i32) -> Result<Decimal, AppError> {
+    let balance = sqlx::query_scalar!(
+        r#"SELECT COALESCE((SELECT balance FROM store_credit_accounts WHERE user_id = $1), 0) AS "balance!""#,
+        user_id
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(balance)
+}
+
+/// Lists a user's store credit ledger, newest first.
+pub async fn list_entries(pool: &PgPool, user_id: i32) -> Result<Vec<StoreCreditEntry>, AppError> {
+    sqlx::query_as!(
+        StoreCreditEntry,
+        r#"
+        SELECT id, entry_type AS "entry_type: _", amount, order_id, gift_card_id, created_at
+        FROM store_credit_entries WHERE user_id = $1
+        ORDER BY created_at DESC, id DESC
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
@@ -1,5 +1,4 @@
-use crate::{errors::AppError, models::{order::OrderItemPayload, Ticket}};
-use rust_decimal::Decimal;
+use crate::{errors::AppError, models::{OrderItem, Ticket}};
 use sqlx::{Postgres, Transaction};
 use uuid::Uuid;
 
@@ -10,25 +9,13 @@ pub async fn create_tickets_for_order(
     tx: &mut Transaction<'_, Postgres>,
     order_id: Uuid,
     user_id: i32,
-    // We would pass the original `CreateOrderPayload` items here
-    items: &Vec<OrderItemPayload>,
+    // The line items recorded when the order was placed.
+    items: &[OrderItem],
 ) -> Result<Vec<Ticket>, AppError> {
     let mut created_tickets = Vec::new();
 
     for item in items {
-        // Fetch necessary IDs for ticket creation
-        let ids: (i32, i32, Decimal) = sqlx::query_as(
-            "SELECT tt.event_id, o.ticket_tier_id, o.price
-             FROM offers o JOIN ticket_tiers tt ON o.ticket_tier_id = tt.id
-             WHERE o.id = $1"
-        )
-        .bind(item.offer_id)
-        .fetch_one(&mut **tx)
-        .await?;
-
-        let (event_id, ticket_tier_id, price_paid) = ids;
-
-        // Create a ticket for each quantity
+        // Create a ticket for each quantity, at the price captured on the order line.
         for _ in 0..item.quantity {
              let ticket = sqlx::query_as!(
                 Ticket,
@@ -40,10 +27,10 @@ pub async fn create_tickets_for_order(
                 "#,
                 order_id,
                 user_id,
-                event_id,
-                ticket_tier_id,
+                item.event_id,
+                item.ticket_tier_id,
                 item.seat_id, // This will be the same for quantity=1, null for GA
-                price_paid
+                item.unit_price
             )
             .fetch_one(&mut **tx)
             .await?;
@@ -53,4 +40,69 @@ pub async fn create_tickets_for_order(
     }
 
     Ok(created_tickets)
-}
\ No newline at end of file
+}
+
+/// Creates free tickets for seats comped from a hold, one per `(seat_id, ticket_tier_id)`.
+/// MUST be run in the same transaction that marks the seats as sold.
+pub async fn create_comp_tickets(
+    tx: &mut Transaction<'_, Postgres>,
+    order_id: Uuid,
+    user_id: i32,
+    event_id: i32,
+    hold_id: i32,
+    seats: &[(i32, i32)],
+) -> Result<Vec<Ticket>, AppError> {
+    let seat_ids: Vec<i32> = seats.iter().map(|&(seat_id, _)| seat_id).collect();
+    let tier_ids: Vec<i32> = seats.iter().map(|&(_, tier_id)| tier_id).collect();
+    sqlx::query_as!(
+        Ticket,
+        r#"
+        INSERT INTO tickets (order_id, user_id, event_id, ticket_tier_id, seat_id, price_paid, seat_hold_id)
+        SELECT $1, $2, $3, tier_id, seat_id, 0, $4
+        FROM UNNEST($5::int[], $6::int[]) AS s(seat_id, tier_id)
+        RETURNING id, order_id, user_id, event_id, ticket_tier_id, seat_id, price_paid,
+                  qr_code_data, status AS "status: _", created_at, checked_in_at
+        "#,
+        order_id,
+        user_id,
+        event_id,
+        hold_id,
+        &seat_ids,
+        &tier_ids
+    )
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Returns whether every ticket and add-on voucher of an order is still valid, i.e. none has
+/// been used, resold or voided by a dispute.
+pub async fn all_valid_for_order(tx: &mut Transaction<'_, Postgres>, order_id: Uuid) -> Result<bool, AppError> {
+    let all_valid = sqlx::query_scalar!(
+        r#"
+        SELECT NOT EXISTS (SELECT 1 FROM tickets WHERE order_id = $1 AND status <> 'valid')
+           AND NOT EXISTS (SELECT 1 FROM vouchers WHERE order_id = $1 AND status <> 'valid') AS "all_valid!"
+        "#,
+        order_id
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(all_valid)
+}
+
+/// Voids the tickets and add-on vouchers of a refunded order.
+pub async fn void_tickets_for_order(tx: &mut Transaction<'_, Postgres>, order_id: Uuid) -> Result<u64, AppError> {
+    let result = sqlx::query!(
+        "UPDATE tickets SET status = 'voided' WHERE order_id = $1 AND status = 'valid'",
+        order_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    sqlx::query!(
+        "UPDATE vouchers SET status = 'voided' WHERE order_id = $1 AND status = 'valid'",
+        order_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(result.rows_affected())
+}
@@ -1,6 +1,6 @@
 use crate::{
     errors::AppError,
-    models::{CreateVenuePayload, Venue},
+    models::{CreateVenuePayload, Venue, VenueManager},
 };
 use sqlx::{PgPool, Executor, Postgres};
 
@@ -39,4 +39,61 @@ pub async fn list_all(pool: &PgPool) -> Result<Vec<Venue>, AppError> {
         .fetch_all(pool)
         .await
         .map_err(AppError::from)
-}
\ No newline at end of file
+}
+
+/// Checks whether a user may manage the seating charts of a venue.
+pub async fn is_venue_manager(pool: &PgPool, venue_id: i32, user_id: i32) -> Result<bool, AppError> {
+    let is_manager = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM venue_managers WHERE venue_id = $1 AND user_id = $2) AS "is_manager!""#,
+        venue_id,
+        user_id
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(is_manager)
+}
+
+/// Makes a user a manager of a venue. Assigning an existing manager again is a no-op.
+pub async fn add_manager(pool: &PgPool, venue_id: i32, user_id: i32) -> Result<VenueManager, AppError> {
+    sqlx::query_as!(
+        VenueManager,
+        r#"
+        INSERT INTO venue_managers (venue_id, user_id) VALUES ($1, $2)
+        ON CONFLICT (venue_id, user_id) DO UPDATE SET venue_id = EXCLUDED.venue_id
+        RETURNING *
+        "#,
+        venue_id,
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Removes a user from the managers of a venue.
+pub async fn remove_manager(pool: &PgPool, venue_id: i32, user_id: i32) -> Result<(), AppError> {
+    let result = sqlx::query!(
+        "DELETE FROM venue_managers WHERE venue_id = $1 AND user_id = $2",
+        venue_id,
+        user_id
+    )
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::Sqlx(sqlx::Error::RowNotFound));
+    }
+    Ok(())
+}
+
+/// Lists the managers of a venue.
+pub async fn list_managers(pool: &PgPool, venue_id: i32) -> Result<Vec<VenueManager>, AppError> {
+    sqlx::query_as!(
+        VenueManager,
+        "SELECT * FROM venue_managers WHERE venue_id = $1 ORDER BY created_at",
+        venue_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::from)
+}
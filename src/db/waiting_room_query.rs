@@ -0,0 +1,211 @@
+use crate::{
+    errors::AppError,
+    models::{ConfigureWaitingRoomPayload, QueueProgress, WaitingRoom, WaitingRoomEntry, WaitingRoomInfo},
+};
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+
+/// Puts a waiting room in front of an event, or changes the one it has.
+pub async fn upsert_room(
+    pool: &PgPool,
+    event_id: i32,
+    payload: &ConfigureWaitingRoomPayload,
+) -> Result<WaitingRoom, AppError> {
+    sqlx::query_as!(
+        WaitingRoom,
+        r#"
+        INSERT INTO waiting_rooms (event_id, opens_at, admit_per_minute, admission_minutes)
+        VALUES ($1, $2, $3, COALESCE($4, 15))
+        ON CONFLICT (event_id) DO UPDATE
+        SET opens_at = EXCLUDED.opens_at,
+            admit_per_minute = EXCLUDED.admit_per_minute,
+            admission_minutes = COALESCE($4, waiting_rooms.admission_minutes)
+        RETURNING *
+        "#,
+        event_id,
+        payload.opens_at,
+        payload.admit_per_minute,
+        payload.admission_minutes
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Fetches the waiting room of an event, if it has one.
+pub async fn find_room<'e, E>(executor: E, event_id: i32) -> Result<Option<WaitingRoom>, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as!(WaitingRoom, "SELECT * FROM waiting_rooms WHERE event_id = $1", event_id)
+        .fetch_optional(executor)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Fetches the waiting room of an event and locks it until the end of the transaction,
+/// so places in line are handed out one at a time.
+pub async fn lock_room(tx: &mut Transaction<'_, Postgres>, event_id: i32) -> Result<WaitingRoom, AppError> {
+    sqlx::query_as!(WaitingRoom, "SELECT * FROM waiting_rooms WHERE event_id = $1 FOR UPDATE", event_id)
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Fetches the public view of an event's waiting room.
+pub async fn get_room_info(pool: &PgPool, event_id: i32) -> Result<WaitingRoomInfo, AppError> {
+    sqlx::query_as!(
+        WaitingRoomInfo,
+        r#"
+        SELECT
+            r.event_id, r.opens_at, r.opened_at, r.admit_per_minute, r.admitted_through,
+            (SELECT COUNT(*) FROM waiting_room_entries e WHERE e.event_id = r.event_id) AS "queue_length!"
+        FROM waiting_rooms r
+        WHERE r.event_id = $1
+        "#,
+        event_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Removes an event's waiting room and its queue, so checkout no longer needs an admission token.
+pub async fn delete_room(pool: &PgPool, event_id: i32) -> Result<u64, AppError> {
+    let result = sqlx::query!("DELETE FROM waiting_rooms WHERE event_id = $1", event_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+/// Fetches a user's entry in an event's queue, if they joined it.
+pub async fn find_entry<'e, E>(executor: E, event_id: i32, user_id: i32) -> Result<Option<WaitingRoomEntry>, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query_as!(
+        WaitingRoomEntry,
+        "SELECT user_id, position, admitted_at FROM waiting_room_entries WHERE event_id = $1 AND user_id = $2",
+        event_id,
+        user_id
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Puts a user in an event's queue, or back in it after their admission expired.
+/// Once the room is open, they go to the back of the line; before, their place is drawn at opening.
+/// The room must be locked by the caller.
+pub async fn join_queue(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+    user_id: i32,
+    is_open: bool,
+) -> Result<WaitingRoomEntry, AppError> {
+    sqlx::query_as!(
+        WaitingRoomEntry,
+        r#"
+        INSERT INTO waiting_room_entries (event_id, user_id, position)
+        SELECT $1, $2, CASE WHEN $3 THEN COALESCE(MAX(position), 0) + 1 END
+        FROM waiting_room_entries
+        WHERE event_id = $1
+        ON CONFLICT (event_id, user_id) DO UPDATE
+        SET position = EXCLUDED.position, joined_at = NOW(), admitted_at = NULL
+        RETURNING user_id, position, admitted_at
+        "#,
+        event_id,
+        user_id,
+        is_open
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Lists the waiting rooms whose opening time has come but whose line hasn't been drawn yet.
+pub async fn list_due_rooms(pool: &PgPool) -> Result<Vec<i32>, AppError> {
+    let ids = sqlx::query_scalar!(
+        "SELECT event_id FROM waiting_rooms WHERE opened_at IS NULL AND opens_at <= NOW() ORDER BY opens_at"
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(ids)
+}
+
+/// Opens a waiting room: everyone who joined before gets a place in line in random order,
+/// so arriving early doesn't pay off. Returns how many are in line.
+pub async fn open_room(tx: &mut Transaction<'_, Postgres>, event_id: i32) -> Result<u64, AppError> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE waiting_room_entries e
+        SET position = drawn.position
+        FROM (
+            SELECT user_id, ROW_NUMBER() OVER (ORDER BY random())::int AS position
+            FROM waiting_room_entries
+            WHERE event_id = $1
+        ) drawn
+        WHERE e.event_id = $1 AND e.user_id = drawn.user_id
+        "#,
+        event_id
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query!("UPDATE waiting_rooms SET opened_at = NOW() WHERE event_id = $1", event_id)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Lets the next buyers into every open waiting room that still has people in line:
+/// as many as each room admits in `interval_secs`. Returns the rooms that moved.
+pub async fn admit_next(tx: &mut Transaction<'_, Postgres>, interval_secs: f64) -> Result<Vec<QueueProgress>, AppError> {
+    sqlx::query_as!(
+        QueueProgress,
+        r#"
+        WITH advanced AS (
+            UPDATE waiting_rooms r
+            SET admitted_through = LEAST(
+                r.admitted_through + CEIL(r.admit_per_minute * $1::float8 / 60)::int,
+                line.last_position
+            )
+            FROM (
+                SELECT event_id, MAX(position) AS last_position
+                FROM waiting_room_entries
+                WHERE position IS NOT NULL
+                GROUP BY event_id
+            ) line
+            WHERE line.event_id = r.event_id AND r.opened_at IS NOT NULL AND r.admitted_through < line.last_position
+            RETURNING r.event_id, r.admitted_through
+        ), admitted AS (
+            UPDATE waiting_room_entries e
+            SET admitted_at = NOW()
+            FROM advanced a
+            WHERE e.event_id = a.event_id AND e.position <= a.admitted_through AND e.admitted_at IS NULL
+        )
+        SELECT event_id AS "event_id!", admitted_through AS "admitted_through!" FROM advanced
+        "#,
+        interval_secs
+    )
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Lists which of the events behind the given offers have a waiting room.
+pub async fn list_queued_events_for_offers(pool: &PgPool, offer_ids: &[i32]) -> Result<Vec<i32>, AppError> {
+    let ids = sqlx::query_scalar!(
+        r#"
+        SELECT DISTINCT r.event_id
+        FROM offers o
+        JOIN ticket_tiers tt ON tt.id = o.ticket_tier_id
+        JOIN waiting_rooms r ON r.event_id = tt.event_id
+        WHERE o.id = ANY($1)
+        "#,
+        offer_ids
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(ids)
+}
@@ -0,0 +1,520 @@
+use crate::{
+    db::realtime_query,
+    errors::AppError,
+    models::{ReservationOffer, SeatStatusChange, WaitlistEntry, WaitlistStatus},
+};
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// Puts a user on the waitlist of an event, or of one of its tiers.
+pub async fn create_entry(
+    pool: &PgPool,
+    event_id: i32,
+    ticket_tier_id: Option<i32>,
+    user_id: i32,
+    quantity: i32,
+) -> Result<WaitlistEntry, AppError> {
+    sqlx::query_as!(
+        WaitlistEntry,
+        r#"
+        INSERT INTO waitlist_entries (event_id, ticket_tier_id, user_id, quantity)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, event_id, ticket_tier_id, user_id, quantity, status AS "status: _", offer_id,
+                  claim_token, offered_at, offer_expires_at, order_id, created_at, last_updated
+        "#,
+        event_id,
+        ticket_tier_id,
+        user_id,
+        quantity
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            AppError::BadRequest("You are already on the waitlist for this event.".to_string())
+        }
+        e => AppError::from(e),
+    })
+}
+
+/// Puts users on the waitlist of an event in the given order, e.g. the losers of a lottery.
+/// Users already on it keep their place. Returns how many were added.
+pub async fn add_entries(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+    ticket_tier_id: Option<i32>,
+    user_ids: &[i32],
+    quantities: &[i32],
+) -> Result<u64, AppError> {
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO waitlist_entries (event_id, ticket_tier_id, user_id, quantity)
+        SELECT $1, $2, u.user_id, u.quantity
+        FROM UNNEST($3::int[], $4::int[]) WITH ORDINALITY AS u(user_id, quantity, ord)
+        ORDER BY u.ord
+        ON CONFLICT (event_id, user_id) WHERE status IN ('waiting', 'offered') DO NOTHING
+        "#,
+        event_id,
+        ticket_tier_id,
+        user_ids,
+        quantities
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Fetches a waitlist entry by its ID and locks it until the end of the transaction.
+pub async fn lock_entry(tx: &mut Transaction<'_, Postgres>, entry_id: i32) -> Result<WaitlistEntry, AppError> {
+    sqlx::query_as!(
+        WaitlistEntry,
+        r#"
+        SELECT id, event_id, ticket_tier_id, user_id, quantity, status AS "status: _", offer_id,
+               claim_token, offered_at, offer_expires_at, order_id, created_at, last_updated
+        FROM waitlist_entries WHERE id = $1
+        FOR UPDATE
+        "#,
+        entry_id
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Fetches the waitlist entry behind a purchase link.
+pub async fn get_entry_by_token(pool: &PgPool, claim_token: &str) -> Result<WaitlistEntry, AppError> {
+    sqlx::query_as!(
+        WaitlistEntry,
+        r#"
+        SELECT id, event_id, ticket_tier_id, user_id, quantity, status AS "status: _", offer_id,
+               claim_token, offered_at, offer_expires_at, order_id, created_at, last_updated
+        FROM waitlist_entries WHERE claim_token = $1
+        "#,
+        claim_token
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Fetches the waitlist entry behind a purchase link and locks it until the end of the transaction.
+pub async fn lock_entry_by_token(
+    tx: &mut Transaction<'_, Postgres>,
+    claim_token: &str,
+) -> Result<WaitlistEntry, AppError> {
+    sqlx::query_as!(
+        WaitlistEntry,
+        r#"
+        SELECT id, event_id, ticket_tier_id, user_id, quantity, status AS "status: _", offer_id,
+               claim_token, offered_at, offer_expires_at, order_id, created_at, last_updated
+        FROM waitlist_entries WHERE claim_token = $1
+        FOR UPDATE
+        "#,
+        claim_token
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Lists a user's waitlist entries, latest first, with how many wait ahead of each open one.
+pub async fn list_entries_for_user(
+    pool: &PgPool,
+    user_id: i32,
+) -> Result<Vec<(WaitlistEntry, Option<i64>)>, AppError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT w.id, w.event_id, w.ticket_tier_id, w.user_id, w.quantity, w.status AS "status: WaitlistStatus",
+               w.offer_id, w.claim_token, w.offered_at, w.offer_expires_at, w.order_id, w.created_at, w.last_updated,
+               CASE WHEN w.status = 'waiting' THEN (
+                   SELECT COUNT(*) FROM waitlist_entries a
+                   WHERE a.event_id = w.event_id AND a.status = 'waiting' AND (a.created_at, a.id) < (w.created_at, w.id)
+               ) END AS people_ahead
+        FROM waitlist_entries w
+        WHERE w.user_id = $1
+        ORDER BY w.created_at DESC
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| {
+            let entry = WaitlistEntry {
+                id: r.id,
+                event_id: r.event_id,
+                ticket_tier_id: r.ticket_tier_id,
+                user_id: r.user_id,
+                quantity: r.quantity,
+                status: r.status,
+                offer_id: r.offer_id,
+                claim_token: r.claim_token,
+                offered_at: r.offered_at,
+                offer_expires_at: r.offer_expires_at,
+                order_id: r.order_id,
+                created_at: r.created_at,
+                last_updated: r.last_updated,
+            };
+            (entry, r.people_ahead)
+        })
+        .collect())
+}
+
+/// Counts the tickets of an event, or of one of its tiers, that anyone can still buy:
+/// available seats, and what is left of the public general admission offers and of the
+/// offers of drawn lotteries.
+pub async fn count_available<'e, E>(executor: E, event_id: i32, ticket_tier_id: Option<i32>) -> Result<i64, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let count = sqlx::query_scalar!(
+        r#"
+        SELECT
+            (SELECT COUNT(*)
+             FROM event_seats es
+             JOIN seats s ON s.id = es.seat_id
+             WHERE es.event_id = $1 AND ($2::int IS NULL OR es.ticket_tier_id = $2) AND es.status = 'available'
+               AND NOT s.attributes && ARRAY['wheelchair', 'companion']::seat_attribute[])
+            +
+            (SELECT COALESCE(SUM(tier_left), 0)::bigint
+             FROM (
+                 SELECT LEAST(tt.total_inventory - tt.quantity_sold,
+                              SUM(GREATEST(o.quantity_for_sale - o.quantity_sold, 0))) AS tier_left
+                 FROM ticket_tiers tt
+                 JOIN offers o ON o.ticket_tier_id = tt.id
+                 WHERE tt.event_id = $1 AND ($2::int IS NULL OR tt.id = $2)
+                   AND o.status IN ('on_sale', 'sold_out') AND NOT o.is_bundle
+                   AND (NOT o.is_presale
+                        OR EXISTS (SELECT 1 FROM lotteries l WHERE l.offer_id = o.id AND l.drawn_at IS NOT NULL))
+                   AND NOT EXISTS (SELECT 1 FROM event_seats es WHERE es.ticket_tier_id = tt.id)
+                 GROUP BY tt.id
+             ) tiers) AS "count!"
+        "#,
+        event_id,
+        ticket_tier_id
+    )
+    .fetch_one(executor)
+    .await?;
+    Ok(count)
+}
+
+/// Lists the events that have users waiting for tickets.
+pub async fn list_waitlisted_events(tx: &mut Transaction<'_, Postgres>) -> Result<Vec<i32>, AppError> {
+    let ids = sqlx::query_scalar!("SELECT DISTINCT event_id FROM waitlist_entries WHERE status = 'waiting'")
+        .fetch_all(&mut **tx)
+        .await?;
+    Ok(ids)
+}
+
+/// Locks the waiting entries of an event, first come first.
+/// Entries locked by another transaction are skipped rather than waited for.
+pub async fn lock_waiting_entries(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+) -> Result<Vec<WaitlistEntry>, AppError> {
+    sqlx::query_as!(
+        WaitlistEntry,
+        r#"
+        SELECT id, event_id, ticket_tier_id, user_id, quantity, status AS "status: _", offer_id,
+               claim_token, offered_at, offer_expires_at, order_id, created_at, last_updated
+        FROM waitlist_entries
+        WHERE event_id = $1 AND status = 'waiting'
+        ORDER BY created_at, id
+        FOR UPDATE SKIP LOCKED
+        "#,
+        event_id
+    )
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Locks the offered entries whose purchase link has expired.
+pub async fn lock_lapsed_entries(tx: &mut Transaction<'_, Postgres>) -> Result<Vec<WaitlistEntry>, AppError> {
+    sqlx::query_as!(
+        WaitlistEntry,
+        r#"
+        SELECT id, event_id, ticket_tier_id, user_id, quantity, status AS "status: _", offer_id,
+               claim_token, offered_at, offer_expires_at, order_id, created_at, last_updated
+        FROM waitlist_entries
+        WHERE status = 'offered' AND offer_expires_at <= NOW()
+        ORDER BY offer_expires_at
+        FOR UPDATE SKIP LOCKED
+        "#
+    )
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Lists the public offers returned tickets of an event, or of one of its tiers, can be
+/// reserved through, cheapest first. Tickets a drawn lottery didn't sell go to the waitlist too.
+pub async fn list_reservation_offers(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+    ticket_tier_id: Option<i32>,
+) -> Result<Vec<ReservationOffer>, AppError> {
+    sqlx::query_as!(
+        ReservationOffer,
+        r#"
+        SELECT
+            o.id AS offer_id,
+            o.ticket_tier_id,
+            EXISTS (SELECT 1 FROM event_seats es WHERE es.ticket_tier_id = o.ticket_tier_id) AS "is_reserved!"
+        FROM offers o
+        JOIN ticket_tiers tt ON tt.id = o.ticket_tier_id
+        WHERE tt.event_id = $1 AND ($2::int IS NULL OR tt.id = $2)
+          AND o.status IN ('on_sale', 'sold_out') AND NOT o.is_bundle
+          AND (NOT o.is_presale
+               OR EXISTS (SELECT 1 FROM lotteries l WHERE l.offer_id = o.id AND l.drawn_at IS NOT NULL))
+        ORDER BY offer_current_price(o.id), o.id
+        "#,
+        event_id,
+        ticket_tier_id
+    )
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Fetches the offer a reservation was made through, and whether its tier is sold by seat.
+pub async fn get_reservation_offer(
+    tx: &mut Transaction<'_, Postgres>,
+    offer_id: i32,
+) -> Result<ReservationOffer, AppError> {
+    sqlx::query_as!(
+        ReservationOffer,
+        r#"
+        SELECT
+            o.id AS offer_id,
+            o.ticket_tier_id,
+            EXISTS (SELECT 1 FROM event_seats es WHERE es.ticket_tier_id = o.ticket_tier_id) AS "is_reserved!"
+        FROM offers o
+        WHERE o.id = $1
+        "#,
+        offer_id
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(AppError::from)
+}
+
+/// Finds up to `quantity` available seats of a tier, in seating order, and locks their rows.
+/// Wheelchair and companion seats are left to accessibility bookings.
+pub async fn find_available_seats(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+    ticket_tier_id: i32,
+    quantity: i32,
+) -> Result<Vec<i32>, AppError> {
+    let ids = sqlx::query_scalar!(
+        r#"
+        SELECT es.seat_id
+        FROM event_seats es
+        JOIN seats s ON s.id = es.seat_id
+        JOIN rows r ON r.id = s.row_id
+        WHERE es.event_id = $1 AND es.ticket_tier_id = $2 AND es.status = 'available'
+          AND NOT s.attributes && ARRAY['wheelchair', 'companion']::seat_attribute[]
+        ORDER BY r.section_id, r.ordinal, s.ordinal
+        LIMIT $3
+        FOR UPDATE OF es SKIP LOCKED
+        "#,
+        event_id,
+        ticket_tier_id,
+        quantity as i64
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+    Ok(ids)
+}
+
+/// Locks seats for a waitlisted user until their purchase link expires.
+/// Live seat maps are notified as part of the transaction.
+pub async fn reserve_seats(
+    tx: &mut Transaction<'_, Postgres>,
+    entry: &WaitlistEntry,
+    seat_ids: &[i32],
+    expires_at: DateTime<Utc>,
+) -> Result<Vec<SeatStatusChange>, AppError> {
+    let changes = sqlx::query_as!(
+        SeatStatusChange,
+        r#"
+        UPDATE event_seats
+        SET status = 'locked', lock_expires_at = $4, locked_by_user_id = $3, waitlist_entry_id = $5
+        WHERE event_id = $1 AND seat_id = ANY($2) AND status = 'available'
+        RETURNING event_id, seat_id, status AS "status: _"
+        "#,
+        entry.event_id,
+        seat_ids,
+        entry.user_id,
+        expires_at,
+        entry.id
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    realtime_query::notify_seat_changes(&mut **tx, &changes).await?;
+    Ok(changes)
+}
+
+/// Takes general admission tickets off an offer and its tier for a waitlisted user.
+/// Returns `false`, changing nothing, if either hasn't enough left.
+pub async fn reserve_general_admission(
+    tx: &mut Transaction<'_, Postgres>,
+    offer_id: i32,
+    ticket_tier_id: i32,
+    quantity: i32,
+) -> Result<bool, AppError> {
+    let offer = sqlx::query!(
+        "UPDATE offers SET quantity_sold = quantity_sold + $1
+         WHERE id = $2 AND (quantity_for_sale - quantity_sold) >= $1",
+        quantity,
+        offer_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    if offer.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    let tier = sqlx::query!(
+        "UPDATE ticket_tiers SET quantity_sold = quantity_sold + $1
+         WHERE id = $2 AND (total_inventory - quantity_sold) >= $1",
+        quantity,
+        ticket_tier_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    if tier.rows_affected() == 0 {
+        sqlx::query!("UPDATE offers SET quantity_sold = quantity_sold - $1 WHERE id = $2", quantity, offer_id)
+            .execute(&mut **tx)
+            .await?;
+        return Ok(false);
+    }
+
+    realtime_query::notify_offer_inventory(&mut **tx, &[offer_id]).await?;
+    Ok(true)
+}
+
+/// Gives general admission tickets reserved for a waitlisted user back to their offer and tier.
+pub async fn release_general_admission(
+    tx: &mut Transaction<'_, Postgres>,
+    offer_id: i32,
+    ticket_tier_id: i32,
+    quantity: i32,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE offers SET quantity_sold = GREATEST(quantity_sold - $1, 0) WHERE id = $2",
+        quantity,
+        offer_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    sqlx::query!(
+        "UPDATE ticket_tiers SET quantity_sold = GREATEST(quantity_sold - $1, 0) WHERE id = $2",
+        quantity,
+        ticket_tier_id
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    realtime_query::notify_offer_inventory(&mut **tx, &[offer_id]).await
+}
+
+/// Puts the seats still reserved for a waitlist entry back on sale.
+/// Live seat maps are notified as part of the transaction.
+pub async fn release_entry_seats(
+    tx: &mut Transaction<'_, Postgres>,
+    entry_id: i32,
+) -> Result<Vec<SeatStatusChange>, AppError> {
+    let changes = sqlx::query_as!(
+        SeatStatusChange,
+        r#"
+        UPDATE event_seats
+        SET status = 'available', lock_expires_at = NULL, locked_by_user_id = NULL, waitlist_entry_id = NULL
+        WHERE waitlist_entry_id = $1 AND status = 'locked' AND order_id IS NULL
+        RETURNING event_id, seat_id, status AS "status: _"
+        "#,
+        entry_id
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    realtime_query::notify_seat_changes(&mut **tx, &changes).await?;
+    Ok(changes)
+}
+
+/// Lists the seats reserved for a waitlist entry.
+pub async fn list_entry_seats<'e, E>(executor: E, entry_id: i32) -> Result<Vec<i32>, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let ids = sqlx::query_scalar!(
+        "SELECT seat_id FROM event_seats WHERE waitlist_entry_id = $1 AND status = 'locked' ORDER BY seat_id",
+        entry_id
+    )
+    .fetch_all(executor)
+    .await?;
+    Ok(ids)
+}
+
+/// Hands the seats reserved for a waitlist entry over to checkout, which locks them for
+/// the order. Returns the seats.
+pub async fn take_entry_seats(tx: &mut Transaction<'_, Postgres>, entry_id: i32) -> Result<Vec<i32>, AppError> {
+    let ids = sqlx::query_scalar!(
+        r#"
+        UPDATE event_seats SET waitlist_entry_id = NULL
+        WHERE waitlist_entry_id = $1 AND status = 'locked' AND order_id IS NULL
+        RETURNING seat_id
+        "#,
+        entry_id
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+    Ok(ids)
+}
+
+/// Marks a waitlist entry as offered, with the secret of its purchase link.
+pub async fn mark_offered(
+    tx: &mut Transaction<'_, Postgres>,
+    entry_id: i32,
+    offer_id: i32,
+    claim_token: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        UPDATE waitlist_entries
+        SET status = 'offered', offer_id = $2, claim_token = $3, offered_at = NOW(), offer_expires_at = $4
+        WHERE id = $1
+        "#,
+        entry_id,
+        offer_id,
+        claim_token,
+        expires_at
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Closes a waitlist entry: purchased with an order, expired or left.
+pub async fn close_entry(
+    tx: &mut Transaction<'_, Postgres>,
+    entry_id: i32,
+    status: WaitlistStatus,
+    order_id: Option<Uuid>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE waitlist_entries SET status = $2, order_id = $3 WHERE id = $1",
+        entry_id,
+        status as WaitlistStatus,
+        order_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
@@ -55,6 +55,10 @@ pub enum AppError {
 
     #[error("Stripe error")]
     Stripe(#[from] StripeError),
+
+    // A seat selection that would leave single empty seats behind, with a better one to try.
+    #[error("Seat gap: {message}")]
+    SeatGap { message: String, suggested_seat_ids: Vec<i32> },
 }
 
 // This is the magic: we implement `IntoResponse` for our `AppError`.
@@ -87,6 +91,12 @@ impl IntoResponse for AppError {
             AppError::InvalidCsrfToken => (StatusCode::FORBIDDEN, self.to_string()),
             AppError::Forbidden(message) => (StatusCode::FORBIDDEN, message),
 
+            // 409 - Conflict, with the seats to suggest instead.
+            AppError::SeatGap { message, suggested_seat_ids } => {
+                let body = Json(json!({ "error": message, "suggested_seat_ids": suggested_seat_ids }));
+                return (StatusCode::CONFLICT, body).into_response();
+            }
+
             // 404 - Not Found (from a specific database error)
             AppError::Sqlx(sqlx::Error::RowNotFound) => {
                 (StatusCode::NOT_FOUND, "Resource not found".to_string())
@@ -0,0 +1,46 @@
+// File: src/jobs/event_relay_job.rs
+
+use std::sync::Arc;
+
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use tokio::task::JoinHandle;
+use tokio::time::{self, Duration};
+
+use crate::db::realtime_query::EVENT_UPDATES_CHANNEL;
+use crate::service::realtime_service::{self, EventChannels};
+
+/// How long to wait before reconnecting after the listener fails.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Spawns the job that relays seat and inventory changes made by any instance
+/// to the WebSocket clients connected to this one.
+pub fn spawn(pool: Arc<PgPool>, channels: Arc<EventChannels>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = listen(&pool, &channels).await {
+                tracing::error!("Event update listener failed: {:?}", e);
+            }
+            // Whatever was sent while we were away is lost; clients must refetch.
+            realtime_service::resync_all(&channels);
+            time::sleep(RECONNECT_DELAY).await;
+        }
+    })
+}
+
+async fn listen(pool: &PgPool, channels: &EventChannels) -> Result<(), sqlx::Error> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(EVENT_UPDATES_CHANNEL).await?;
+    tracing::info!("Listening for event updates on '{}'", EVENT_UPDATES_CHANNEL);
+
+    loop {
+        match listener.try_recv().await? {
+            Some(notification) => realtime_service::relay_notification(channels, notification.payload()),
+            // The connection dropped and will be re-established on the next call.
+            None => {
+                tracing::warn!("Event update listener reconnected; asking clients to resync.");
+                realtime_service::resync_all(channels);
+            }
+        }
+    }
+}
@@ -0,0 +1,36 @@
+// File: src/jobs/expiry_sweep_job.rs
+
+use std::sync::Arc;
+
+use sqlx::PgPool;
+use tokio::task::JoinHandle;
+use tokio::time::{self, Duration, MissedTickBehavior};
+
+use crate::clients::payment_provider::PaymentProvider;
+use crate::service::{cart_service, order_service, seating_service};
+
+/// How often expired orders and seat locks are released.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Spawns the job that returns the inventory of abandoned checkouts to sale.
+/// Every sweep cancels expired pending orders and discards expired carts, then releases
+/// stray expired seat locks.
+pub fn spawn<P: PaymentProvider + 'static>(pool: Arc<PgPool>, provider: Arc<P>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = time::interval(SWEEP_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = order_service::expire_stale_orders(&pool, provider.as_ref()).await {
+                tracing::error!("Failed to expire stale orders: {:?}", e);
+            }
+            if let Err(e) = cart_service::expire_carts(&pool).await {
+                tracing::error!("Failed to expire carts: {:?}", e);
+            }
+            if let Err(e) = seating_service::release_expired_locks(&pool).await {
+                tracing::error!("Failed to release expired seat locks: {:?}", e);
+            }
+        }
+    })
+}
@@ -0,0 +1,28 @@
+// File: src/jobs/group_booking_job.rs
+
+use std::sync::Arc;
+
+use sqlx::PgPool;
+use tokio::task::JoinHandle;
+use tokio::time::{self, Duration, MissedTickBehavior};
+
+use crate::clients::payment_provider::PaymentProvider;
+use crate::service::group_booking_service;
+
+/// How often lapsed share claims are freed and group bookings past their deadline closed.
+const GROUP_BOOKING_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Spawns the job that closes group bookings at their payment deadline.
+pub fn spawn<P: PaymentProvider + 'static>(pool: Arc<PgPool>, provider: Arc<P>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = time::interval(GROUP_BOOKING_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = group_booking_service::close_due_bookings(&pool, provider.as_ref()).await {
+                tracing::error!("Failed to close group bookings: {:?}", e);
+            }
+        }
+    })
+}
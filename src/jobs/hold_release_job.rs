@@ -0,0 +1,27 @@
+// File: src/jobs/hold_release_job.rs
+
+use std::sync::Arc;
+
+use sqlx::PgPool;
+use tokio::task::JoinHandle;
+use tokio::time::{self, Duration, MissedTickBehavior};
+
+use crate::service::hold_service;
+
+/// How often scheduled hold releases are checked for.
+const RELEASE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Spawns the job that puts the seats of holds back on sale at their scheduled release time.
+pub fn spawn(pool: Arc<PgPool>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = time::interval(RELEASE_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = hold_service::release_due_holds(&pool).await {
+                tracing::error!("Failed to release scheduled seat holds: {:?}", e);
+            }
+        }
+    })
+}
@@ -0,0 +1,27 @@
+// File: src/jobs/lottery_job.rs
+
+use std::sync::Arc;
+
+use sqlx::PgPool;
+use tokio::task::JoinHandle;
+use tokio::time::{self, Duration, MissedTickBehavior};
+
+use crate::service::lottery_service;
+
+/// How often lapsed lottery purchase windows are closed.
+const LOTTERY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Spawns the job that hands the tickets of lapsed lottery purchase windows to the waitlist.
+pub fn spawn(pool: Arc<PgPool>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = time::interval(LOTTERY_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = lottery_service::expire_purchase_windows(&pool).await {
+                tracing::error!("Failed to expire lottery purchase windows: {:?}", e);
+            }
+        }
+    })
+}
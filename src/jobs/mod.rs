@@ -0,0 +1,12 @@
+// Background tasks that run alongside the web server.
+// Each job is spawned once from `main` and runs until the process exits.
+
+pub mod reconciliation_job;
+pub mod expiry_sweep_job;
+pub mod event_relay_job;
+pub mod hold_release_job;
+pub mod waiting_room_job;
+pub mod waitlist_job;
+pub mod lottery_job;
+pub mod price_range_job;
+pub mod group_booking_job;
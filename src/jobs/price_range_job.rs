@@ -0,0 +1,27 @@
+// File: src/jobs/price_range_job.rs
+
+use std::sync::Arc;
+
+use sqlx::PgPool;
+use tokio::task::JoinHandle;
+use tokio::time::{self, Duration, MissedTickBehavior};
+
+use crate::service::pricing_service;
+
+/// How often the price ranges of dynamically priced events are recomputed.
+const PRICE_RANGE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Spawns the job that keeps event price ranges in step with time-based pricing rules.
+pub fn spawn(pool: Arc<PgPool>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = time::interval(PRICE_RANGE_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = pricing_service::refresh_dynamic_price_ranges(&pool).await {
+                tracing::error!("Failed to refresh event price ranges: {:?}", e);
+            }
+        }
+    })
+}
@@ -0,0 +1,41 @@
+// File: src/jobs/reconciliation_job.rs
+
+use std::sync::Arc;
+
+use chrono::{Duration, NaiveTime, Utc};
+use sqlx::PgPool;
+use tokio::task::JoinHandle;
+
+use crate::clients::payment_provider::PaymentProvider;
+use crate::service::reconciliation_service;
+
+/// The time of day (UTC) at which the nightly run starts.
+const RUN_AT: NaiveTime = match NaiveTime::from_hms_opt(3, 0, 0) {
+    Some(time) => time,
+    None => panic!("invalid reconciliation time"),
+};
+
+/// Spawns the nightly payment reconciliation job.
+/// A failed run is logged and recorded; the job keeps going and tries again the next night.
+pub fn spawn<P: PaymentProvider + 'static>(pool: Arc<PgPool>, provider: Arc<P>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(until_next_run()).await;
+
+            let window = Duration::hours(reconciliation_service::DEFAULT_WINDOW_HOURS);
+            if let Err(e) = reconciliation_service::run_reconciliation(&pool, provider.as_ref(), window).await {
+                tracing::error!("Nightly payment reconciliation failed: {:?}", e);
+            }
+        }
+    })
+}
+
+/// How long to sleep until the next `RUN_AT`.
+fn until_next_run() -> std::time::Duration {
+    let now = Utc::now();
+    let mut next = now.date_naive().and_time(RUN_AT).and_utc();
+    if next <= now {
+        next += Duration::days(1);
+    }
+    (next - now).to_std().unwrap_or_default()
+}
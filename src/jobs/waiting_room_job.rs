@@ -0,0 +1,27 @@
+// File: src/jobs/waiting_room_job.rs
+
+use std::sync::Arc;
+
+use sqlx::PgPool;
+use tokio::task::JoinHandle;
+use tokio::time::{self, Duration, MissedTickBehavior};
+
+use crate::service::waiting_room_service;
+
+/// How often waiting rooms are opened and let the next buyers in.
+const ADMISSION_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Spawns the job that moves the waiting rooms of high-demand on-sales along.
+pub fn spawn(pool: Arc<PgPool>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = time::interval(ADMISSION_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = waiting_room_service::advance_waiting_rooms(&pool, ADMISSION_INTERVAL).await {
+                tracing::error!("Failed to advance waiting rooms: {:?}", e);
+            }
+        }
+    })
+}
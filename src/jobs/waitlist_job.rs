@@ -0,0 +1,27 @@
+// File: src/jobs/waitlist_job.rs
+
+use std::sync::Arc;
+
+use sqlx::PgPool;
+use tokio::task::JoinHandle;
+use tokio::time::{self, Duration, MissedTickBehavior};
+
+use crate::service::waitlist_service;
+
+/// How often expired purchase links are passed on and returned tickets are offered.
+const WAITLIST_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Spawns the job that moves the waitlists of sold-out events along.
+pub fn spawn(pool: Arc<PgPool>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = time::interval(WAITLIST_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = waitlist_service::process_waitlists(&pool).await {
+                tracing::error!("Failed to process waitlists: {:?}", e);
+            }
+        }
+    })
+}
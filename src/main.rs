@@ -23,6 +23,7 @@ use dashmap::DashMap;
 use tokio::sync::broadcast;
 use crate::{config::CONFIG};
 use stripe::Client as StripeClient; 
+use crate::clients::payment_provider::PaymentGateway;
 
 // Declare all your modules
 mod api;
@@ -34,16 +35,19 @@ mod utils;
 mod middleware;
 mod service;
 mod clients;
+mod jobs;
 
 // The central state for our application
 #[derive(Clone)]
 pub struct AppState {
     pub db_pool: Arc<PgPool>, // Already Arc
     pub csrf_config: CsrfConfig,
-    // NEW: DashMap to store broadcast senders per project.
-    // project_id -> broadcast::Sender<String> (we'll send JSON strings)
-    pub project_ws_senders: Arc<DashMap<i32, broadcast::Sender<String>>>,
+    // Broadcast senders for live seat map updates, per event.
+    // event_id -> broadcast::Sender<String> (we send JSON strings)
+    pub event_ws_senders: Arc<DashMap<i32, broadcast::Sender<String>>>,
         pub stripe_client: Arc<StripeClient>,
+    // The provider used to create and look up PaymentIntents (Stripe or the in-memory fake).
+    pub payment_provider: Arc<PaymentGateway>,
 }
 
 // Implement `FromRef` for the new Stripe client
@@ -66,10 +70,10 @@ impl FromRef<AppState> for CsrfConfig {
     }
 }
 
-// NEW: Implement FromRef for the new project_ws_senders
+// Implement FromRef for the per-event WebSocket senders
 impl FromRef<AppState> for Arc<DashMap<i32, broadcast::Sender<String>>> {
     fn from_ref(state: &AppState) -> Self {
-        state.project_ws_senders.clone()
+        state.event_ws_senders.clone()
     }
 }
 
@@ -100,18 +104,31 @@ async fn main() {
     let shared_db_pool = Arc::new(pool);
 
     // Initialize the DashMap for WebSocket senders
-    let project_ws_senders = Arc::new(DashMap::new());
+    let event_ws_senders = Arc::new(DashMap::new());
 
     // --- Stripe Client Initialization ---
     let stripe_client = clients::stripe_client::create_stripe_client();
     let shared_stripe_client = Arc::new(stripe_client); // Correctly wrapped in Arc
+    let payment_provider = Arc::new(clients::payment_provider::create_payment_gateway(&shared_stripe_client));
+
+    // --- Background Jobs ---
+    jobs::reconciliation_job::spawn(shared_db_pool.clone(), payment_provider.clone());
+    jobs::expiry_sweep_job::spawn(shared_db_pool.clone(), payment_provider.clone());
+    jobs::hold_release_job::spawn(shared_db_pool.clone());
+    jobs::waiting_room_job::spawn(shared_db_pool.clone());
+    jobs::waitlist_job::spawn(shared_db_pool.clone());
+    jobs::lottery_job::spawn(shared_db_pool.clone());
+    jobs::price_range_job::spawn(shared_db_pool.clone());
+    jobs::group_booking_job::spawn(shared_db_pool.clone(), payment_provider.clone());
+    jobs::event_relay_job::spawn(shared_db_pool.clone(), event_ws_senders.clone());
 
     // --- Create the single AppState ---
     let app_state = AppState {
         db_pool: shared_db_pool,
         csrf_config,
-        project_ws_senders: project_ws_senders,
+        event_ws_senders,
         stripe_client: shared_stripe_client, 
+        payment_provider,
     };
 
     // --- CORS Layer ---
@@ -122,12 +139,13 @@ async fn main() {
                 .parse::<HeaderValue>()
                 .expect("Invalid frontend origin"),
         )
-        .allow_methods([Method::GET, Method::POST, Method::PATCH, Method::DELETE])
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE])
         // IMPORTANT: You MUST allow the `x-csrf-token` header.
         .allow_headers([
             header::AUTHORIZATION,
             header::CONTENT_TYPE,
             header::HeaderName::from_static("x-csrf-token"),
+            header::HeaderName::from_static("x-admission-token"),
         ])
         .allow_credentials(true);
 
@@ -0,0 +1,60 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+// Represents a row from the 'offer_access_codes' table, with when it was last redeemed.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct OfferAccessCode {
+    pub id: i32,
+    pub offer_id: i32,
+    pub batch_name: String,
+    pub code: String,
+    pub max_uses: i32,
+    pub use_count: i32,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+// A generated code matching what a user entered, and whether they may redeem it.
+#[derive(Debug, sqlx::FromRow)]
+pub struct AccessCodeMatch {
+    pub id: i32,
+    pub offer_id: i32,
+    pub has_uses_left: bool,
+    // The user redeemed this code before, which doesn't count as another use.
+    pub redeemed_by_user: bool,
+}
+
+// Payload for generating a batch of access codes for a presale offer.
+#[derive(Debug, Deserialize, Validate)]
+pub struct GenerateAccessCodesPayload {
+    #[validate(length(min = 1, max = 100, message = "Batch name must be between 1 and 100 characters."))]
+    pub batch_name: String,
+    #[validate(range(min = 1, max = 5000, message = "Between 1 and 5000 codes can be generated at once."))]
+    pub quantity: i32,
+    // How many users can redeem each code. Defaults to single-use codes.
+    #[validate(range(min = 1, max = 1000, message = "Max uses must be between 1 and 1000."))]
+    pub max_uses: Option<i32>,
+}
+
+// A batch of newly generated codes.
+#[derive(Debug, Serialize)]
+pub struct AccessCodeBatch {
+    pub offer_id: i32,
+    pub batch_name: String,
+    pub max_uses: i32,
+    pub codes: Vec<String>,
+}
+
+// Query parameters for listing an offer's access codes.
+#[derive(Debug, Deserialize)]
+pub struct AccessCodeListQuery {
+    pub batch_name: Option<String>,
+}
+
+// Payload for unlocking an event's presale offers with a code.
+#[derive(Debug, Deserialize, Validate)]
+pub struct UnlockOffersPayload {
+    #[validate(length(min = 1, max = 64, message = "Access code must be between 1 and 64 characters."))]
+    pub code: String,
+}
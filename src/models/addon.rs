@@ -0,0 +1,109 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+// Represents a row from the 'addon_items' table: something an event sells besides tickets.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AddonItem {
+    pub id: i32,
+    pub event_id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    // For donations, the least a buyer may give.
+    pub price: Decimal,
+    pub is_donation: bool,
+    // Issued as scannable vouchers, e.g. parking or a merch pickup.
+    pub is_redeemable: bool,
+    // None if the add-on can't sell out.
+    pub total_inventory: Option<i32>,
+    pub quantity_sold: i32,
+    pub created_at: DateTime<Utc>,
+    pub last_updated: DateTime<Utc>,
+}
+
+// Represents a row from the 'addon_variants' table, e.g. one size of a shirt.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AddonVariant {
+    pub id: i32,
+    pub addon_item_id: i32,
+    pub name: String,
+    pub total_inventory: i32,
+    pub quantity_sold: i32,
+}
+
+// An add-on in an event's catalog, with the variants it is sold in.
+#[derive(Debug, Serialize)]
+pub struct AddonItemInfo {
+    #[serde(flatten)]
+    pub item: AddonItem,
+    pub variants: Vec<AddonVariant>,
+}
+
+// Payload for adding an item to an event's add-on catalog.
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateAddonItemPayload {
+    #[validate(length(min = 3, max = 255, message = "Add-on name must be between 3 and 255 characters."))]
+    pub name: String,
+    pub description: Option<String>,
+    pub price: Option<Decimal>,
+    #[serde(default)]
+    pub is_donation: bool,
+    // Defaults to true, except for donations.
+    pub is_redeemable: Option<bool>,
+    // Leave out for an add-on that can't sell out. Ignored when there are variants.
+    #[validate(range(min = 0, message = "Inventory can't be negative."))]
+    pub total_inventory: Option<i32>,
+    #[serde(default)]
+    #[validate(length(max = 50, message = "An add-on can have at most 50 variants."))]
+    #[validate]
+    pub variants: Vec<AddonVariantPayload>,
+}
+
+// One variant of a new add-on.
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct AddonVariantPayload {
+    #[validate(length(min = 1, max = 100, message = "Variant name must be between 1 and 100 characters."))]
+    pub name: String,
+    #[validate(range(min = 0, message = "Inventory can't be negative."))]
+    pub total_inventory: i32,
+}
+
+// Represents a row from the 'order_addons' table, with what was bought.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct OrderAddon {
+    pub id: i32,
+    pub addon_item_id: i32,
+    pub addon_variant_id: Option<i32>,
+    pub name: String,
+    pub variant_name: Option<String>,
+    pub event_id: i32,
+    pub quantity: i32,
+    pub unit_price: Decimal,
+    // Set for add-ons that came in a bundle, at no price of their own.
+    pub order_bundle_id: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "voucher_status", rename_all = "snake_case")]
+pub enum VoucherStatus {
+    Valid,
+    Redeemed,
+    Voided,
+}
+
+// A user's voucher for an add-on, with what it is for.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct VoucherDetails {
+    pub voucher_id: Uuid,
+    pub order_id: Uuid,
+    pub qr_code_data: String,
+    pub status: VoucherStatus,
+    pub addon_name: String,
+    pub variant_name: Option<String>,
+    pub event_id: i32,
+    pub event_title: String,
+    pub event_start_time: DateTime<Utc>,
+    pub redeemed_at: Option<DateTime<Utc>>,
+}
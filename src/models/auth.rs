@@ -30,6 +30,15 @@ pub struct TokenClaims {
     pub role: String,   // The role, e.g., "user" or "admin"
     pub exp: usize,     // Expiration time
 }
+
+// The data encoded into a waiting room admission token.
+// It lets one user check out for one event until it expires.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdmissionClaims {
+    pub sub: String,    // The admitted user's ID
+    pub event_id: i32,  // The event they were let in to
+    pub exp: usize,     // When their admission ends
+}
 #[derive(Deserialize, Validate)]
 pub struct ForgotPasswordPayload {
     #[validate(custom(function = "validation::is_valid_email"))]
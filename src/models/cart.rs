@@ -0,0 +1,52 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+// Represents a row from the 'carts' table.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Cart {
+    pub id: i32,
+    pub user_id: i32,
+    // Everything in the cart is held until then.
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub last_updated: DateTime<Utc>,
+}
+
+// Represents a row from the 'cart_items' table.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CartItem {
+    pub id: i32,
+    pub cart_id: i32,
+    pub offer_id: i32,
+    pub event_id: i32,
+    pub ticket_tier_id: i32,
+    pub seat_id: Option<i32>,
+    pub quantity: i32,
+}
+
+// A cart item with what it is and what it sells at right now.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CartItemDetails {
+    pub id: i32,
+    pub offer_id: i32,
+    pub offer_name: String,
+    pub event_id: i32,
+    pub event_title: String,
+    pub ticket_tier_id: i32,
+    pub seat_id: Option<i32>,
+    pub quantity: i32,
+    pub unit_price: Decimal,
+}
+
+// A user's cart, with how long its holds last.
+#[derive(Debug, Serialize)]
+pub struct CartView {
+    pub id: i32,
+    pub expires_at: DateTime<Utc>,
+    // Counts down to `expires_at`, for the checkout timer.
+    pub seconds_remaining: i64,
+    pub items: Vec<CartItemDetails>,
+    // At current prices, before fees.
+    pub subtotal: Decimal,
+}
@@ -0,0 +1,89 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{Order, TicketStatus};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "dispute_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeStatus {
+    WarningNeedsResponse,
+    WarningUnderReview,
+    WarningClosed,
+    NeedsResponse,
+    UnderReview,
+    Won,
+    Lost,
+}
+
+// Lets a list of statuses be bound as a `dispute_status[]` parameter.
+impl sqlx::postgres::PgHasArrayType for DisputeStatus {
+    fn array_type_info() -> sqlx::postgres::PgTypeInfo {
+        sqlx::postgres::PgTypeInfo::with_name("_dispute_status")
+    }
+}
+
+impl From<stripe::DisputeStatus> for DisputeStatus {
+    fn from(status: stripe::DisputeStatus) -> Self {
+        match status {
+            stripe::DisputeStatus::WarningNeedsResponse => Self::WarningNeedsResponse,
+            stripe::DisputeStatus::WarningUnderReview => Self::WarningUnderReview,
+            stripe::DisputeStatus::WarningClosed => Self::WarningClosed,
+            stripe::DisputeStatus::NeedsResponse => Self::NeedsResponse,
+            stripe::DisputeStatus::UnderReview => Self::UnderReview,
+            stripe::DisputeStatus::Won => Self::Won,
+            stripe::DisputeStatus::Lost => Self::Lost,
+        }
+    }
+}
+
+// Represents a row from the 'disputes' table.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Dispute {
+    pub id: Uuid,
+    pub payment_id: Uuid,
+    pub order_id: Uuid,
+    // Shown to admins so they can respond to the dispute in the Stripe dashboard.
+    pub stripe_dispute_id: String,
+    pub status: DisputeStatus,
+    pub reason: String,
+    pub amount: Decimal,
+    pub currency: String,
+    pub amount_lost: Decimal,
+    pub evidence_due_by: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub closed_at: Option<DateTime<Utc>>,
+    pub last_updated: DateTime<Utc>,
+}
+
+// A ticket of a disputed order, with what we know about its use.
+// A check-in time is the strongest evidence that the customer received the service.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct DisputeTicketEvidence {
+    pub ticket_id: Uuid,
+    pub event_id: i32,
+    pub event_title: String,
+    pub event_start_time: DateTime<Utc>,
+    pub seat_id: Option<i32>,
+    pub price_paid: Decimal,
+    pub status: TicketStatus,
+    pub checked_in_at: Option<DateTime<Utc>>,
+}
+
+// An entry in the admin dispute queue: the dispute plus the evidence we hold for it.
+#[derive(Debug, Serialize)]
+pub struct DisputeCase {
+    pub dispute: Dispute,
+    pub order: Order,
+    pub buyer_email: String,
+    pub tickets: Vec<DisputeTicketEvidence>,
+}
+
+// Query parameters for the dispute queue.
+// Without a status filter, only disputes still awaiting a decision are returned.
+#[derive(Debug, Deserialize)]
+pub struct DisputeQueueQuery {
+    pub status: Option<DisputeStatus>,
+}
@@ -31,6 +31,8 @@ pub struct Event {
     pub end_time: Option<DateTime<Utc>>,
     pub price_min: Option<Decimal>,
     pub price_max: Option<Decimal>,
+    // Hours before the start when wheelchair and companion seats go on general sale.
+    pub accessible_release_hours: i32,
     pub created_at: DateTime<Utc>,
     pub last_updated: DateTime<Utc>,
 }
@@ -47,6 +49,8 @@ pub struct CreateEventPayload {
     pub segment_id: Option<i32>,
     pub genre_id: Option<i32>,
     pub sub_genre_id: Option<i32>,
+    #[validate(range(min = 0, max = 8760, message = "Accessible release hours must be between 0 and 8760."))]
+    pub accessible_release_hours: Option<i32>,
 }
 
 // Payload for updating an existing event (all fields are optional).
@@ -59,5 +63,7 @@ pub struct UpdateEventPayload {
     pub start_time: Option<DateTime<Utc>>,
     pub end_time: Option<DateTime<Utc>>,
     pub venue_id: Option<i32>,
+    #[validate(range(min = 0, max = 8760, message = "Accessible release hours must be between 0 and 8760."))]
+    pub accessible_release_hours: Option<i32>,
     // ... add any other fields you want to be updatable
 }
\ No newline at end of file
@@ -0,0 +1,95 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+use super::OrderItemPayload;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "group_booking_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum GroupBookingStatus {
+    Open,
+    Completed,
+    Closed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "group_share_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum GroupShareStatus {
+    Unpaid,
+    Pending,
+    Paid,
+    Released,
+}
+
+// Represents a row from the 'group_bookings' table.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct GroupBooking {
+    pub id: i32,
+    // The pending order holding the group's tickets.
+    pub order_id: Uuid,
+    pub organizer_id: i32,
+    pub event_id: i32,
+    // Shared with the members so they can pay.
+    pub invite_token: String,
+    pub payment_deadline: DateTime<Utc>,
+    pub status: GroupBookingStatus,
+    pub created_at: DateTime<Utc>,
+}
+
+// Represents a row from the 'group_booking_shares' table: one ticket of the group.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct GroupShare {
+    pub id: i32,
+    pub offer_id: i32,
+    pub ticket_tier_id: i32,
+    pub seat_id: Option<i32>,
+    // The ticket's price plus its service fee.
+    pub amount: Decimal,
+    pub status: GroupShareStatus,
+    // The member who paid for it, or claimed it last.
+    pub user_id: Option<i32>,
+    pub claim_expires_at: Option<DateTime<Utc>>,
+    pub paid_at: Option<DateTime<Utc>>,
+}
+
+// A group booking with its shares and how much of it is paid.
+#[derive(Debug, Serialize)]
+pub struct GroupBookingView {
+    #[serde(flatten)]
+    pub booking: GroupBooking,
+    pub shares: Vec<GroupShare>,
+    pub amount_paid: Decimal,
+    // What the shares not yet paid cost.
+    pub amount_outstanding: Decimal,
+}
+
+// Payload for reserving tickets for a group.
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateGroupBookingPayload {
+    #[validate(length(min = 1, max = 20, message = "A group booking can have at most 20 items."))]
+    #[validate]
+    pub items: Vec<OrderItemPayload>,
+
+    // How long the members have to pay. Defaults to 24 hours.
+    pub payment_deadline_hours: Option<i64>,
+}
+
+// Payload for a member to pay for some of a group's tickets.
+#[derive(Debug, Deserialize, Validate)]
+pub struct PayGroupSharesPayload {
+    #[validate(length(min = 1, max = 20, message = "Choose between 1 and 20 shares to pay for."))]
+    pub share_ids: Vec<i32>,
+}
+
+// The shares a member is paying for, and the client secret to pay with.
+#[derive(Debug, Serialize)]
+pub struct GroupPaymentResponse {
+    pub shares: Vec<GroupShare>,
+    pub amount: Decimal,
+    pub stripe_client_secret: String,
+}
@@ -0,0 +1,85 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+use super::{SeatSelector, Ticket};
+
+// Represents a row from the 'seat_holds' table.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SeatHold {
+    pub id: i32,
+    pub event_id: i32,
+    pub name: String,
+    pub release_at: Option<DateTime<Utc>>,
+    pub released_at: Option<DateTime<Utc>>,
+    pub created_by: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub last_updated: DateTime<Utc>,
+}
+
+// A hold with how many of its seats are still held and how many were comped.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SeatHoldSummary {
+    pub id: i32,
+    pub event_id: i32,
+    pub name: String,
+    pub release_at: Option<DateTime<Utc>>,
+    pub released_at: Option<DateTime<Utc>>,
+    pub held_seats: i64,
+    pub comped_seats: i64,
+}
+
+// Payload for putting seats of an event on a named hold.
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateSeatHoldPayload {
+    #[validate(length(min = 1, max = 100, message = "Hold name must be between 1 and 100 characters."))]
+    pub name: String,
+    pub selector: SeatSelector,
+    // Put the seats still held back on sale at this time.
+    pub release_at: Option<DateTime<Utc>>,
+}
+
+// Payload for renaming a hold or changing when it is released.
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateSeatHoldPayload {
+    #[validate(length(min = 1, max = 100, message = "Hold name must be between 1 and 100 characters."))]
+    pub name: Option<String>,
+    pub release_at: Option<DateTime<Utc>>,
+}
+
+// Payload for adding more seats to a hold.
+#[derive(Debug, Deserialize)]
+pub struct AddHoldSeatsPayload {
+    pub selector: SeatSelector,
+}
+
+// Payload for releasing held seats back to general sale.
+// Without `seat_ids`, every seat still held is released and the hold is closed.
+#[derive(Debug, Default, Deserialize)]
+pub struct ReleaseSeatHoldPayload {
+    pub seat_ids: Option<Vec<i32>>,
+}
+
+// Payload for issuing held seats to a user as complimentary tickets.
+#[derive(Debug, Deserialize, Validate)]
+pub struct IssueCompsPayload {
+    pub user_id: i32,
+    #[validate(length(min = 1, max = 50, message = "Between 1 and 50 seats can be comped at once."))]
+    pub seat_ids: Vec<i32>,
+}
+
+// The result of putting seats on a hold. Seats that were not available are skipped.
+#[derive(Debug, Serialize)]
+pub struct SeatHoldResult {
+    pub hold: SeatHold,
+    pub held_seat_ids: Vec<i32>,
+    pub skipped_seat_ids: Vec<i32>,
+}
+
+// The complimentary order created for comped seats, with its tickets.
+#[derive(Debug, Serialize)]
+pub struct CompIssueResult {
+    pub order_id: Uuid,
+    pub tickets: Vec<Ticket>,
+}
@@ -0,0 +1,146 @@
+use serde::Deserialize;
+
+use super::CreateSeatingChartPayload;
+
+// Compact section specs that are expanded into seats by the layout generator.
+// Coordinates are in the same units as `seats.pos_x`/`pos_y`. Angles are in degrees,
+// measured clockwise from the positive x axis, so 90° points straight down the map.
+
+// Payload for previewing a generated layout.
+#[derive(Debug, Deserialize)]
+pub struct GenerateLayoutPayload {
+    pub sections: Vec<SectionSpec>,
+}
+
+// Payload for creating a seating chart from generated sections.
+#[derive(Debug, Deserialize)]
+pub struct GenerateSeatingChartPayload {
+    #[serde(flatten)]
+    pub chart: CreateSeatingChartPayload,
+    pub sections: Vec<SectionSpec>,
+}
+
+// One section of the layout and how to generate its seats.
+#[derive(Debug, Deserialize)]
+pub struct SectionSpec {
+    pub name: String,
+    #[serde(flatten)]
+    pub shape: SectionShape,
+    #[serde(default = "default_seat_size")]
+    pub seat_width: f64,
+    #[serde(default = "default_seat_size")]
+    pub seat_height: f64,
+    #[serde(default)]
+    pub row_naming: RowNaming,
+    #[serde(default)]
+    pub seat_naming: SeatNaming,
+}
+
+// The geometry of a section. Rows are generated front (nearest the stage) to back.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "shape", rename_all = "snake_case")]
+pub enum SectionShape {
+    // Straight rows. `origin` is the top-left corner of the first seat of the first row.
+    // Aisles are left after the given (1-based) seat positions and rows.
+    Grid {
+        origin_x: f64,
+        origin_y: f64,
+        rows: u32,
+        seats_per_row: u32,
+        /// Center-to-center distance between seats in a row.
+        seat_spacing: f64,
+        /// Center-to-center distance between rows.
+        row_spacing: f64,
+        #[serde(default)]
+        aisles_after_seats: Vec<u32>,
+        #[serde(default)]
+        aisles_after_rows: Vec<u32>,
+        #[serde(default)]
+        aisle_width: f64,
+    },
+    // Concentric rows around `center` (e.g. the stage), spread evenly over the angle.
+    // Each row can hold more seats than the one in front of it.
+    Arc {
+        center_x: f64,
+        center_y: f64,
+        /// Radius of the first row.
+        radius: f64,
+        rows: u32,
+        row_spacing: f64,
+        start_angle: f64,
+        end_angle: f64,
+        /// Seats in the first row.
+        seats_per_row: u32,
+        #[serde(default)]
+        seats_added_per_row: u32,
+    },
+    // A wedge of a stadium bowl. Each row holds as many seats as fit at `seat_pitch`,
+    // and `aisles` radial gangways split the wedge into equal blocks.
+    StadiumSector {
+        center_x: f64,
+        center_y: f64,
+        inner_radius: f64,
+        rows: u32,
+        row_spacing: f64,
+        start_angle: f64,
+        end_angle: f64,
+        /// Center-to-center distance between seats along a row.
+        seat_pitch: f64,
+        #[serde(default)]
+        aisles: u32,
+        #[serde(default)]
+        aisle_width: f64,
+    },
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RowLabelStyle {
+    // A, B, ..., Z, AA, BB, ...
+    #[default]
+    Letters,
+    // 1, 2, 3, ...
+    Numbers,
+}
+
+// How rows are named, front to back.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct RowNaming {
+    pub style: RowLabelStyle,
+    /// The label of the first row. Defaults to "A" or "1".
+    pub start: Option<String>,
+    /// Labels never used, e.g. ["I", "O"] because they read like numbers.
+    pub skip: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SeatNumberingDirection {
+    // Lowest number at the left (grids) or at the start angle (arcs).
+    #[default]
+    LeftToRight,
+    RightToLeft,
+}
+
+// How seats are numbered within each row.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct SeatNaming {
+    pub start: u32,
+    /// 2 numbers seats with only odd or only even numbers, as on the sides of a theatre.
+    pub step: u32,
+    pub direction: SeatNumberingDirection,
+    /// Numbers never used, e.g. [13].
+    pub skip: Vec<u32>,
+}
+
+impl Default for SeatNaming {
+    fn default() -> Self {
+        Self { start: 1, step: 1, direction: SeatNumberingDirection::LeftToRight, skip: Vec::new() }
+    }
+}
+
+fn default_seat_size() -> f64 {
+    20.0
+}
@@ -0,0 +1,107 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "lottery_entry_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum LotteryEntryStatus {
+    Registered,
+    // The user may buy their allocation until `purchase_expires_at`.
+    Won,
+    // Not drawn; the user was put on the event's waitlist instead.
+    Lost,
+    Purchased,
+    Expired,
+}
+
+// Represents a row from the 'lotteries' table.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Lottery {
+    pub id: i32,
+    pub event_id: i32,
+    pub offer_id: i32,
+    pub registration_opens_at: DateTime<Utc>,
+    pub registration_closes_at: DateTime<Utc>,
+    pub max_per_user: i32,
+    pub purchase_window_hours: i32,
+    // Kept secret until the draw; `seed_hash` commits to it in the meantime.
+    #[serde(skip_serializing)]
+    pub seed: String,
+    pub seed_hash: String,
+    pub input_hash: Option<String>,
+    pub capacity: Option<i32>,
+    pub drawn_at: Option<DateTime<Utc>>,
+    pub created_by: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub last_updated: DateTime<Utc>,
+}
+
+// The public view of a lottery: the seed once drawn, and how much was asked for.
+#[derive(Debug, Serialize)]
+pub struct LotteryInfo {
+    #[serde(flatten)]
+    pub lottery: Lottery,
+    pub seed: Option<String>,
+    pub registrations: i64,
+    pub requested_tickets: i64,
+}
+
+// Represents a row from the 'lottery_entries' table.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct LotteryEntry {
+    pub id: i32,
+    pub lottery_id: i32,
+    pub user_id: i32,
+    pub quantity: i32,
+    pub status: LotteryEntryStatus,
+    pub draw_rank: Option<i32>,
+    pub allocated_quantity: i32,
+    pub purchase_expires_at: Option<DateTime<Utc>>,
+    pub order_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub last_updated: DateTime<Utc>,
+}
+
+// Payload for an organizer to sell a general admission offer by lottery.
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateLotteryPayload {
+    pub offer_id: i32,
+    pub registration_opens_at: DateTime<Utc>,
+    pub registration_closes_at: DateTime<Utc>,
+    #[validate(range(min = 1, max = 10, message = "The limit per user must be between 1 and 10."))]
+    pub max_per_user: i32,
+    // Defaults to 48 hours.
+    #[validate(range(min = 1, max = 720, message = "The purchase window must be between 1 and 720 hours."))]
+    pub purchase_window_hours: Option<i32>,
+}
+
+// Payload for a user to register for a lottery, or to change how many tickets they ask for.
+#[derive(Debug, Deserialize, Validate)]
+pub struct RegisterForLotteryPayload {
+    #[validate(range(min = 1, max = 10, message = "Quantity must be between 1 and 10."))]
+    pub quantity: i32,
+}
+
+// One registration as published after the draw: enough for anyone to recompute the input
+// hash and the ranking from the seed.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct LotteryDrawResult {
+    pub entry_id: i32,
+    pub quantity: i32,
+    pub draw_rank: Option<i32>,
+    pub allocated_quantity: i32,
+}
+
+// The published outcome of a lottery draw.
+#[derive(Debug, Serialize)]
+pub struct LotteryResults {
+    pub lottery_id: i32,
+    pub seed: String,
+    pub seed_hash: String,
+    pub input_hash: String,
+    pub drawn_at: DateTime<Utc>,
+    pub capacity: i32,
+    pub entries: Vec<LotteryDrawResult>,
+}
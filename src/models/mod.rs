@@ -12,16 +12,78 @@ pub mod seating;
 pub mod order;
 pub mod ticket;
 pub mod payment;
+pub mod settlement;
+pub mod dispute;
+pub mod reconciliation;
+pub mod realtime;
+pub mod layout_generator;
+pub mod seat_selection;
+pub mod hold;
+pub mod access_code;
+pub mod waiting_room;
+pub mod waitlist;
+pub mod lottery;
+pub mod addon;
+pub mod cart;
+pub mod store_credit;
+pub mod group_booking;
 
 // Re-export specific structs for convenience.
-pub use auth::{LoginPayload, LoginResponse, TokenClaims};
+pub use auth::{LoginPayload, LoginResponse, TokenClaims, AdmissionClaims};
 pub use user::{User, CreateUserPayload};
 pub use event::{Event, EventStatus, CreateEventPayload, UpdateEventPayload};
-pub use venue::{Venue, CreateVenuePayload};
+pub use venue::{Venue, CreateVenuePayload, VenueManager, AssignVenueManagerPayload};
 pub use attraction::{Attraction, AttractionType, AssignAttractionPayload};
 pub use category::{Segment, Genre, SubGenre, CreateCategoryPayload};
-pub use pricing::{TicketTier, Offer, OfferStatus, CreateTicketTierPayload, CreateOfferPayload};
-pub use seating::{SeatingChart, Section, Row, Seat, EventSeat, SeatStatus, SeatMapInfo};
-pub use order::{Order, OrderStatus, CreateOrderPayload};
+pub use pricing::{
+    TicketTier, Offer, OfferStatus, CreateTicketTierPayload, CreateOfferPayload, UpdateTicketTierPayload, UpdateOfferPayload,
+    PriceRuleKind, PriceAdjustmentType, OfferPriceRule, SetOfferPricingPayload, OfferPricing,
+    BundleComponent, SetBundleComponentsPayload,
+};
+pub use seating::{
+    SeatingChart, Section, Row, Seat, SeatStatus, SeatAttribute, SeatMapInfo, SeatStatusChange,
+    CreateSeatingChartPayload, UpdateSeatingChartPayload, SeatingLayout, ImportSeatingChartPayload,
+    SeatingChartLayout, SectionLayout, RowLayout, LayoutSection, LayoutRow, LayoutSeat,
+    SeatMapSeat, SeatMapVersion, SeatMapSvgQuery, RenderedSeatMap,
+    SeatSelector, UpdateEventSeatsPayload, UpdateEventSeatsResult, InitializeEventSeatingPayload, EventSeatingReport,
+};
+pub use hold::{
+    SeatHold, SeatHoldSummary, CreateSeatHoldPayload, UpdateSeatHoldPayload, AddHoldSeatsPayload,
+    ReleaseSeatHoldPayload, IssueCompsPayload, SeatHoldResult, CompIssueResult,
+};
+pub use access_code::{
+    OfferAccessCode, AccessCodeMatch, GenerateAccessCodesPayload, AccessCodeBatch, AccessCodeListQuery, UnlockOffersPayload,
+};
+pub use waiting_room::{
+    WaitingRoom, WaitingRoomInfo, WaitingRoomEntry, ConfigureWaitingRoomPayload, QueueState, QueueStatus, QueueProgress,
+};
+pub use waitlist::{
+    WaitlistStatus, WaitlistEntry, WaitlistEntryDetails, JoinWaitlistPayload, WaitlistClaim, ReservationOffer,
+};
+pub use lottery::{
+    Lottery, LotteryInfo, LotteryEntry, LotteryEntryStatus, CreateLotteryPayload, RegisterForLotteryPayload,
+    LotteryDrawResult, LotteryResults,
+};
+pub use addon::{AddonItem, AddonItemInfo, AddonVariant, CreateAddonItemPayload, OrderAddon, VoucherDetails};
+pub use order::{Order, OrderItem, OrderStatus, CreateOrderPayload, OrderItemPayload};
+pub use order::{OrderBundle, OrderReceipt};
+pub use cart::{Cart, CartItem, CartItemDetails, CartView};
+pub use store_credit::{
+    GiftCard, GiftCardStatus, PurchaseGiftCardPayload, RedeemGiftCardPayload, StoreCredit, StoreCreditEntry,
+    StoreCreditEntryType,
+};
+pub use group_booking::{
+    GroupBooking, GroupBookingStatus, GroupShare, GroupShareStatus, GroupBookingView, CreateGroupBookingPayload,
+    PayGroupSharesPayload, GroupPaymentResponse,
+};
 pub use ticket::{Ticket, TicketStatus, TicketDetails};
-pub use payment::{Payment, PaymentStatus};
\ No newline at end of file
+pub use payment::{Payment, PaymentStatus};
+pub use settlement::{SettlementEntryType, EventStatement, DailyStatement, OrganizerPayout, StatementRangeQuery, SettlementSyncReport};
+pub use dispute::{Dispute, DisputeStatus, DisputeCase, DisputeTicketEvidence, DisputeQueueQuery};
+pub use reconciliation::{ReconciliationRun, ReconciliationDiscrepancy, DiscrepancyKind, NewDiscrepancy};
+pub use realtime::{SeatMapMessage, EventUpdateNotification};
+pub use layout_generator::{
+    GenerateLayoutPayload, GenerateSeatingChartPayload, SectionSpec, SectionShape, RowNaming, RowLabelStyle,
+    SeatNaming, SeatNumberingDirection,
+};
+pub use seat_selection::{BestAvailablePayload, BestAvailableSeat, BestAvailableResult, RowSeat, SeatCandidate};
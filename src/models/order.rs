@@ -4,6 +4,8 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use validator::Validate;
 
+use super::OrderAddon;
+
 #[derive(Debug, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "order_status", rename_all = "snake_case")]
 pub enum OrderStatus {
@@ -23,6 +25,8 @@ pub struct Order {
     pub subtotal: Decimal,
     pub service_fee: Decimal,
     pub total_amount: Decimal,
+    // Paid with store credit; the rest of the total is charged to the card.
+    pub credit_applied: Decimal,
     pub created_at: DateTime<Utc>,
     pub last_updated: DateTime<Utc>,
     pub expires_at: DateTime<Utc>,
@@ -33,8 +37,18 @@ pub struct Order {
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct CreateOrderPayload {
     // A list of items the user wants to purchase.
-    #[validate(length(min = 1, message = "Order must contain at least one item."))]
+    #[validate(length(max = 50, message = "An order can contain at most 50 items."))]
     pub items: Vec<OrderItemPayload>,
+
+    // Add-ons bought with the tickets, or on their own.
+    #[serde(default)]
+    #[validate(length(max = 50, message = "An order can contain at most 50 add-ons."))]
+    #[validate]
+    pub addons: Vec<OrderAddonPayload>,
+
+    // Store credit to pay with, up to the order's total. The rest is charged to the card.
+    #[serde(default)]
+    pub apply_credit: Option<Decimal>,
 }
 
 
@@ -54,11 +68,61 @@ pub struct OrderItemPayload {
     pub quantity: i32,
 }
 
+// Represents one add-on in the checkout payload.
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct OrderAddonPayload {
+    pub addon_item_id: i32,
+
+    // Required for add-ons sold in variants, e.g. a shirt's size.
+    pub variant_id: Option<i32>,
+
+    #[validate(range(min = 1, max = 20, message = "Add-on quantity must be between 1 and 20."))]
+    pub quantity: i32,
+
+    // For donations, the amount given per unit. Not allowed for other add-ons.
+    pub amount: Option<Decimal>,
+}
+
 // A dedicated response struct for the order creation endpoint.
 #[derive(Debug, Serialize)]
 pub struct CreateOrderResponse {
     // We can use #[serde(flatten)] if we want to merge the fields,
     // but nesting is often clearer.
     pub order: Order,
-    pub stripe_client_secret: String,
-}
\ No newline at end of file
+    // None when store credit paid for the whole order, which is then already completed.
+    pub stripe_client_secret: Option<String>,
+}
+// Represents a row from the 'order_items' table.
+// Captures exactly what was sold, and at what price, when the order was placed.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct OrderItem {
+    pub id: i32,
+    pub order_id: Uuid,
+    pub offer_id: i32,
+    pub event_id: i32,
+    pub ticket_tier_id: i32,
+    pub seat_id: Option<i32>,
+    pub quantity: i32,
+    pub unit_price: Decimal,
+    pub unit_service_fee: Decimal,
+    // Set for the tickets of a bundle, whose package price is spread over them.
+    pub order_bundle_id: Option<i32>,
+}
+
+// Represents a row from the 'order_bundles' table: packages of a bundle offer in an order.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct OrderBundle {
+    pub id: i32,
+    pub offer_id: i32,
+    pub quantity: i32,
+    pub unit_price: Decimal,
+}
+
+// An order with everything that was bought in it, as a receipt.
+#[derive(Debug, Serialize)]
+pub struct OrderReceipt {
+    pub order: Order,
+    pub items: Vec<OrderItem>,
+    pub bundles: Vec<OrderBundle>,
+    pub addons: Vec<OrderAddon>,
+}
@@ -12,6 +12,17 @@ pub enum PaymentStatus {
     Refunded,
 }
 
+impl PaymentStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+            Self::Refunded => "refunded",
+        }
+    }
+}
+
 // Represents a row from the 'payments' table.
 #[derive(Debug, Serialize, sqlx::FromRow)]
 pub struct Payment {
@@ -22,6 +22,8 @@ pub struct TicketTier {
     pub name: String,
     pub description: Option<String>,
     pub total_inventory: i32,
+    // General admission tickets sold across all of the tier's offers.
+    pub quantity_sold: i32,
 }
 
 // Represents a row from the 'offers' table.
@@ -40,6 +42,14 @@ pub struct Offer {
     pub max_per_order: i32,
     #[serde(skip)] // Hide the access code from public view
     pub access_code: Option<String>,
+    // Sells wheelchair and companion seats before they are released to everyone.
+    pub is_accessible: bool,
+    // Hidden from the public; only users who unlocked it with a code can buy it.
+    pub is_presale: bool,
+    // Sells packages of the components in 'offer_bundle_components' rather than single tickets.
+    pub is_bundle: bool,
+    // What the offer sells at right now, after its pricing rules. `price` is the base price.
+    pub current_price: Decimal,
 }
 
 // Payload for creating a new ticket tier for an event.
@@ -52,6 +62,16 @@ pub struct CreateTicketTierPayload {
     pub total_inventory: i32,
 }
 
+// Payload for updating a ticket tier (all fields are optional).
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateTicketTierPayload {
+    #[validate(length(min = 3, message = "Tier name must be at least 3 characters."))]
+    pub name: Option<String>,
+    pub description: Option<String>,
+    #[validate(range(min = 1, message = "Inventory must be at least 1."))]
+    pub total_inventory: Option<i32>,
+}
+
 // Payload for creating a new offer for a ticket tier.
 #[derive(Debug, Deserialize, Serialize, Validate)]
 pub struct CreateOfferPayload {
@@ -68,4 +88,109 @@ pub struct CreateOfferPayload {
     pub sale_start_time: Option<DateTime<Utc>>,
     pub sale_end_time: Option<DateTime<Utc>>,
     pub access_code: Option<String>,
-}
\ No newline at end of file
+    #[serde(default)]
+    pub is_accessible: bool,
+}
+
+// Payload for updating an offer (all fields are optional).
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateOfferPayload {
+    #[validate(length(min = 3, message = "Offer name must be at least 3 characters."))]
+    pub name: Option<String>,
+    pub status: Option<OfferStatus>,
+    #[validate(custom(function = "validation::is_non_negative_decimal"))]
+    pub price: Option<Decimal>,
+    #[validate(range(min = 1, message = "Quantity must be at least 1."))]
+    pub quantity_for_sale: Option<i32>,
+    pub sale_start_time: Option<DateTime<Utc>>,
+    pub sale_end_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "price_rule_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum PriceRuleKind {
+    // Applies once `threshold` percent of the offer's tickets are sold.
+    SellThrough,
+    // Applies from `threshold` hours before the event starts.
+    TimeToEvent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "price_adjustment_type", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum PriceAdjustmentType {
+    Percent,
+    Amount,
+}
+
+// Represents a row from the 'offer_price_rules' table.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct OfferPriceRule {
+    pub id: i32,
+    pub offer_id: i32,
+    pub kind: PriceRuleKind,
+    pub threshold: i32,
+    pub adjustment_type: PriceAdjustmentType,
+    pub adjustment: Decimal,
+}
+
+// One pricing rule of an offer, e.g. +10% once 80% is sold, or -20% from 24 hours before the event.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PriceRulePayload {
+    pub kind: PriceRuleKind,
+    pub threshold: i32,
+    pub adjustment_type: PriceAdjustmentType,
+    // Negative for a discount.
+    pub adjustment: Decimal,
+}
+
+// Payload for replacing an offer's pricing rules and bounds.
+#[derive(Debug, Deserialize, Validate)]
+pub struct SetOfferPricingPayload {
+    #[validate(length(max = 50, message = "An offer can have at most 50 pricing rules."))]
+    pub rules: Vec<PriceRulePayload>,
+    #[validate(custom(function = "validation::is_non_negative_decimal"))]
+    pub price_floor: Option<Decimal>,
+    #[validate(custom(function = "validation::is_non_negative_decimal"))]
+    pub price_ceiling: Option<Decimal>,
+}
+
+// An offer's pricing: its base and current price, its bounds and its rules.
+#[derive(Debug, Serialize)]
+pub struct OfferPricing {
+    pub offer_id: i32,
+    pub base_price: Decimal,
+    pub current_price: Decimal,
+    pub price_floor: Option<Decimal>,
+    pub price_ceiling: Option<Decimal>,
+    pub rules: Vec<OfferPriceRule>,
+}
+
+// Represents a row from the 'offer_bundle_components' table: one part of a bundle package.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct BundleComponent {
+    pub id: i32,
+    pub offer_id: i32,
+    // Exactly one of `ticket_tier_id` and `addon_item_id` is set.
+    pub ticket_tier_id: Option<i32>,
+    pub addon_item_id: Option<i32>,
+    pub quantity: i32,
+}
+
+// One part of a bundle package: tickets of a general admission tier, or an add-on.
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct BundleComponentPayload {
+    pub ticket_tier_id: Option<i32>,
+    pub addon_item_id: Option<i32>,
+    #[validate(range(min = 1, max = 20, message = "A component's quantity must be between 1 and 20."))]
+    pub quantity: i32,
+}
+
+// Payload for turning an offer into a bundle, or changing what its packages contain.
+#[derive(Debug, Deserialize, Validate)]
+pub struct SetBundleComponentsPayload {
+    #[validate(length(min = 1, max = 20, message = "A bundle needs between 1 and 20 components."))]
+    #[validate]
+    pub components: Vec<BundleComponentPayload>,
+}
@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+use super::SeatStatusChange;
+
+// The number of tickets still available on a general admission offer.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct OfferInventoryChange {
+    pub offer_id: i32,
+    pub quantity_available: i32,
+}
+
+// The payload of a Postgres notification on the `event_updates` channel.
+// The query layer sends one whenever an event's seats or inventory change, and every
+// instance relays it to its own WebSocket clients.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EventUpdateNotification {
+    pub event_id: i32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub seats: Vec<SeatStatusChange>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub offers: Vec<OfferInventoryChange>,
+    // Set when the event's waiting room let more buyers in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub admitted_through: Option<i32>,
+}
+
+// A message sent over the seat map WebSocket.
+// `Resync` tells a client that may have missed updates to refetch the full seat map.
+// The queue WebSocket reads `QueueProgress` (and `Resync`) off the same channel.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SeatMapMessage {
+    SeatStatus {
+        event_id: i32,
+        changes: Vec<SeatStatusChange>,
+    },
+    OfferInventory {
+        event_id: i32,
+        offers: Vec<OfferInventoryChange>,
+    },
+    QueueProgress {
+        event_id: i32,
+        admitted_through: i32,
+    },
+    Resync {
+        event_id: i32,
+    },
+}
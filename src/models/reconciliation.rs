@@ -0,0 +1,71 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "reconciliation_run_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ReconciliationRunStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "reconciliation_discrepancy_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum DiscrepancyKind {
+    MissedSuccess,
+    AmountMismatch,
+    StatusMismatch,
+    UnknownPaymentIntent,
+    MissingPaymentIntent,
+}
+
+// Represents a row from the 'reconciliation_runs' table.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ReconciliationRun {
+    pub id: i32,
+    pub status: ReconciliationRunStatus,
+    pub window_start: DateTime<Utc>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub intents_checked: i32,
+    pub auto_resolved: i32,
+    pub discrepancies_found: i32,
+    pub error_message: Option<String>,
+}
+
+// Represents a row from the 'reconciliation_discrepancies' table.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ReconciliationDiscrepancy {
+    pub id: i32,
+    pub run_id: i32,
+    pub kind: DiscrepancyKind,
+    pub stripe_payment_intent_id: String,
+    pub payment_id: Option<Uuid>,
+    pub order_id: Option<Uuid>,
+    pub our_status: Option<String>,
+    pub provider_status: Option<String>,
+    pub our_amount: Option<Decimal>,
+    pub provider_amount: Option<Decimal>,
+    pub auto_resolved: bool,
+    pub details: String,
+    pub created_at: DateTime<Utc>,
+}
+
+// A discrepancy found during a run, before it is written to the report.
+#[derive(Debug)]
+pub struct NewDiscrepancy {
+    pub kind: DiscrepancyKind,
+    pub stripe_payment_intent_id: String,
+    pub payment_id: Option<Uuid>,
+    pub order_id: Option<Uuid>,
+    pub our_status: Option<String>,
+    pub provider_status: Option<String>,
+    pub our_amount: Option<Decimal>,
+    pub provider_amount: Option<Decimal>,
+    pub auto_resolved: bool,
+    pub details: String,
+}
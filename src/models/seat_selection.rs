@@ -0,0 +1,81 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use super::SeatStatus;
+
+// Payload for asking for the best available seats of an event.
+#[derive(Debug, Deserialize, Validate)]
+pub struct BestAvailablePayload {
+    #[validate(range(min = 1, max = 10, message = "Quantity must be between 1 and 10."))]
+    pub quantity: i32,
+    pub ticket_tier_id: Option<i32>,
+    // The most the buyer wants to pay per seat.
+    pub max_price: Option<Decimal>,
+    // Lock the seats for the buyer, so nobody else can take them before checkout.
+    #[serde(default)]
+    pub lock: bool,
+}
+
+// One seat of a best-available pick, with the offer to buy it through.
+#[derive(Debug, Serialize)]
+pub struct BestAvailableSeat {
+    pub seat_id: i32,
+    pub section_name: String,
+    pub row_name: String,
+    pub seat_number: String,
+    pub ticket_tier_id: i32,
+    pub offer_id: i32,
+    pub price: Decimal,
+}
+
+// The result of a best-available search.
+// `contiguous` is false when no block of adjacent seats was free and the
+// closest group of separate seats was picked instead.
+#[derive(Debug, Serialize)]
+pub struct BestAvailableResult {
+    pub seats: Vec<BestAvailableSeat>,
+    pub contiguous: bool,
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+// A seat considered by the best-available search: its place on the map and what it costs.
+// Seats without an on-sale public offer have no `offer_id`.
+#[derive(Debug, sqlx::FromRow)]
+pub struct SeatCandidate {
+    pub seat_id: i32,
+    pub row_id: i32,
+    pub section_name: String,
+    pub row_name: String,
+    pub seat_number: String,
+    pub pos_x: Decimal,
+    pub pos_y: Decimal,
+    pub width: Decimal,
+    pub height: Decimal,
+    pub status: SeatStatus,
+    pub ticket_tier_id: i32,
+    pub offer_id: Option<i32>,
+    pub price: Option<Decimal>,
+    // A wheelchair or companion seat not yet released to general sale.
+    pub is_protected: bool,
+    pub stage_x: Option<Decimal>,
+    pub stage_y: Option<Decimal>,
+}
+
+// A seat in one of the rows a selection touches, used to check the selection doesn't
+// strand single empty seats. `selected` marks the seats being taken.
+#[derive(Debug, sqlx::FromRow)]
+pub struct RowSeat {
+    pub event_id: i32,
+    pub seat_id: i32,
+    pub row_id: i32,
+    pub section_name: String,
+    pub row_name: String,
+    pub pos_x: Decimal,
+    pub pos_y: Decimal,
+    pub width: Decimal,
+    pub height: Decimal,
+    pub status: SeatStatus,
+    pub selected: bool,
+}
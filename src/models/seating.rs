@@ -1,15 +1,42 @@
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use validator::Validate;
 
-#[derive(Debug, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "seat_status", rename_all = "snake_case")]
 pub enum SeatStatus {
     Available,
     Locked,
     Sold,
     Unavailable,
+    // Kept off general sale on an organizer's hold.
+    Held,
+}
+
+// Our Rust enum mapping to the 'seat_attribute' PG ENUM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "seat_attribute", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SeatAttribute {
+    // A space for a wheelchair user, sold only through accessibility offers until release.
+    Wheelchair,
+    // A seat next to a wheelchair space, for the person accompanying its user.
+    Companion,
+    RestrictedView,
+    Aisle,
+}
+
+impl SeatAttribute {
+    // The value's name in the database enum.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SeatAttribute::Wheelchair => "wheelchair",
+            SeatAttribute::Companion => "companion",
+            SeatAttribute::RestrictedView => "restricted_view",
+            SeatAttribute::Aisle => "aisle",
+        }
+    }
 }
 
 // Represents a row from the 'seating_charts' table.
@@ -19,6 +46,8 @@ pub struct SeatingChart {
     pub venue_id: i32,
     pub name: String,
     pub background_image_url: Option<String>,
+    pub stage_x: Option<Decimal>,
+    pub stage_y: Option<Decimal>,
 }
 
 // Represents a row from the 'sections' table.
@@ -47,18 +76,9 @@ pub struct Seat {
     pub pos_y: Decimal,
     pub width: Decimal,
     pub height: Decimal,
-}
-
-// Represents the dynamic status of a seat for a specific event.
-#[derive(Debug, Serialize, sqlx::FromRow)]
-pub struct EventSeat {
-    pub event_id: i32,
-    pub seat_id: i32,
-    pub ticket_tier_id: i32,
-    pub status: SeatStatus,
-    #[serde(skip)]
-    pub lock_expires_at: Option<DateTime<Utc>>,
-    pub order_id: Option<Uuid>,
+    // Position along the row, counted from 1.
+    pub ordinal: i32,
+    pub attributes: Vec<SeatAttribute>,
 }
 
 // A combined structure for rendering the seat map on the frontend.
@@ -70,6 +90,7 @@ pub struct SeatMapInfo {
     pub seat_number: String,
     pub pos_x: Decimal,
     pub pos_y: Decimal,
+    pub attributes: Vec<SeatAttribute>,
     
     // Info from the EventSeat (dynamic status)
     pub status: SeatStatus,
@@ -78,4 +99,191 @@ pub struct SeatMapInfo {
     pub ticket_tier_id: i32,
     pub ticket_tier_name: String,
     pub price: Decimal,
-}
\ No newline at end of file
+}
+
+// Everything needed to draw one seat of an event's seat map.
+#[derive(Debug, sqlx::FromRow)]
+pub struct SeatMapSeat {
+    pub seat_id: i32,
+    pub section_name: String,
+    pub row_name: String,
+    pub seat_number: String,
+    pub pos_x: Decimal,
+    pub pos_y: Decimal,
+    pub width: Decimal,
+    pub height: Decimal,
+    pub status: SeatStatus,
+    pub ticket_tier_id: i32,
+    pub ticket_tier_name: String,
+}
+
+// Identifies the current state of an event's seat map, for cache validation.
+#[derive(Debug, sqlx::FromRow)]
+pub struct SeatMapVersion {
+    pub seat_count: i64,
+    pub last_changed: Option<DateTime<Utc>>,
+}
+
+// Query parameters for the rendered seat map.
+// `highlight` is a comma-separated list of seat IDs, e.g. the buyer's selection.
+#[derive(Debug, Deserialize)]
+pub struct SeatMapSvgQuery {
+    pub highlight: Option<String>,
+}
+
+// The outcome of a conditional request for the rendered seat map.
+#[derive(Debug)]
+pub enum RenderedSeatMap {
+    // The client's cached copy, identified by this ETag, is still current.
+    NotModified { etag: String },
+    Svg { etag: String, body: String },
+}
+
+// A change to one seat's status for an event, as pushed to live seat map clients.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SeatStatusChange {
+    #[serde(skip)]
+    pub event_id: i32,
+    pub seat_id: i32,
+    pub status: SeatStatus,
+}
+
+// --- Seating Chart Builder ---
+
+// Which seats of an event a bulk update applies to.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "by", rename_all = "snake_case")]
+pub enum SeatSelector {
+    // Every seat in a section.
+    Section { section: String },
+    // The rows of a section from `from_row` to `to_row` (inclusive), in layout order.
+    Rows { section: String, from_row: String, to_row: String },
+    // Explicit seats.
+    Seats { seat_ids: Vec<i32> },
+}
+
+// Payload for moving seats of an event to another tier, or taking them off sale.
+#[derive(Debug, Deserialize)]
+pub struct UpdateEventSeatsPayload {
+    pub selector: SeatSelector,
+    pub ticket_tier_id: Option<i32>,
+    // `Unavailable` kills seats (production holds, obstructed views), `Available` puts them back on sale.
+    pub status: Option<SeatStatus>,
+}
+
+// The result of a bulk event seat update.
+// Seats that are locked or sold are left as they are and listed as skipped.
+#[derive(Debug, Serialize)]
+pub struct UpdateEventSeatsResult {
+    pub updated: usize,
+    pub skipped_seat_ids: Vec<i32>,
+}
+
+// Payload for setting up an event's seats from a seating chart of its venue.
+// With `resync`, an event already set up gets the seats added to the chart since, and
+// loses those retired from it.
+#[derive(Debug, Deserialize)]
+pub struct InitializeEventSeatingPayload {
+    pub seating_chart_id: i32,
+    // The tier new seats are assigned to.
+    pub default_ticket_tier_id: i32,
+    #[serde(default)]
+    pub resync: bool,
+}
+
+// What setting up or re-syncing an event's seats changed.
+// Seats retired from the chart that are sold or in a checkout are kept and listed as kept.
+#[derive(Debug, Serialize)]
+pub struct EventSeatingReport {
+    pub seating_chart_id: i32,
+    pub seat_count: i64,
+    pub added_seat_ids: Vec<i32>,
+    pub retired_seat_ids: Vec<i32>,
+    pub kept_seat_ids: Vec<i32>,
+}
+
+// Payload for creating a seating chart.
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateSeatingChartPayload {
+    #[validate(length(min = 2, max = 255, message = "Chart name must be between 2 and 255 characters."))]
+    pub name: String,
+    #[validate(url(message = "Background image must be a valid URL."), length(max = 255))]
+    pub background_image_url: Option<String>,
+    // The stage focal point that seat quality is measured from.
+    pub stage_x: Option<Decimal>,
+    pub stage_y: Option<Decimal>,
+}
+
+// Payload for renaming a seating chart or changing its background.
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateSeatingChartPayload {
+    #[validate(length(min = 2, max = 255, message = "Chart name must be between 2 and 255 characters."))]
+    pub name: Option<String>,
+    #[validate(url(message = "Background image must be a valid URL."), length(max = 255))]
+    pub background_image_url: Option<String>,
+    // The stage focal point that seat quality is measured from.
+    pub stage_x: Option<Decimal>,
+    pub stage_y: Option<Decimal>,
+}
+
+// A whole seating layout: sections, their rows and the seats in each row.
+// Replaces the chart's existing layout when imported.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SeatingLayout {
+    pub sections: Vec<LayoutSection>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LayoutSection {
+    pub name: String,
+    pub rows: Vec<LayoutRow>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LayoutRow {
+    pub name: String,
+    pub seats: Vec<LayoutSeat>,
+}
+
+// A seat's rectangle on the map. Width and height default to the column defaults (20 x 20).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LayoutSeat {
+    pub seat_number: String,
+    pub pos_x: Decimal,
+    pub pos_y: Decimal,
+    pub width: Option<Decimal>,
+    pub height: Option<Decimal>,
+    #[serde(default)]
+    pub attributes: Vec<SeatAttribute>,
+}
+
+// Payload for creating a seating chart together with its layout in one request.
+#[derive(Debug, Deserialize)]
+pub struct ImportSeatingChartPayload {
+    #[serde(flatten)]
+    pub chart: CreateSeatingChartPayload,
+    #[serde(flatten)]
+    pub layout: SeatingLayout,
+}
+
+// A seating chart with its full layout, as returned to the chart builder.
+#[derive(Debug, Serialize)]
+pub struct SeatingChartLayout {
+    #[serde(flatten)]
+    pub chart: SeatingChart,
+    pub sections: Vec<SectionLayout>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SectionLayout {
+    pub id: i32,
+    pub name: String,
+    pub rows: Vec<RowLayout>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RowLayout {
+    pub id: i32,
+    pub name: String,
+    pub seats: Vec<Seat>,
+}
@@ -0,0 +1,72 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "settlement_entry_type", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SettlementEntryType {
+    Sale,
+    PlatformFee,
+    StripeFee,
+    Refund,
+    Dispute,
+}
+
+// Represents a row from the 'organizer_payouts' table.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct OrganizerPayout {
+    pub id: i32,
+    pub organizer_id: i32,
+    pub stripe_payout_id: String,
+    pub amount: Decimal,
+    pub currency: String,
+    pub status: String,
+    pub arrival_date: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub last_synced_at: DateTime<Utc>,
+}
+
+// The settlement totals for a single event. This is a DTO built from the ledger.
+// Fees, refunds and disputes are reported as positive amounts; `net_due` is what the
+// organizer is owed after all of them are deducted from `gross_sales`.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct EventStatement {
+    pub event_id: i32,
+    pub event_title: String,
+    pub gross_sales: Decimal,
+    pub refunds: Decimal,
+    pub platform_fees: Decimal,
+    pub stripe_fees: Decimal,
+    pub disputes: Decimal,
+    pub net_due: Decimal,
+}
+
+// The settlement totals for a single calendar day (UTC) across all of an organizer's events.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct DailyStatement {
+    pub date: NaiveDate,
+    pub gross_sales: Decimal,
+    pub refunds: Decimal,
+    pub platform_fees: Decimal,
+    pub stripe_fees: Decimal,
+    pub disputes: Decimal,
+    pub net_due: Decimal,
+    pub paid_out: Decimal,
+}
+
+// Query parameters for the daily statement endpoints.
+// Both bounds are inclusive; they default to the last 30 days.
+#[derive(Debug, Deserialize)]
+pub struct StatementRangeQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+// The result of reconciling the ledger against Stripe.
+#[derive(Debug, Default, Serialize)]
+pub struct SettlementSyncReport {
+    pub balance_transactions_checked: usize,
+    pub entries_recorded: u64,
+    pub payouts_synced: usize,
+}
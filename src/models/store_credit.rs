@@ -0,0 +1,82 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::utils::validation;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "gift_card_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum GiftCardStatus {
+    Pending,
+    Active,
+    Cancelled,
+}
+
+// Represents a row from the 'gift_cards' table.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct GiftCard {
+    pub id: i32,
+    pub code: String,
+    // The order the card was bought with.
+    pub order_id: Uuid,
+    pub purchaser_id: i32,
+    pub recipient_email: Option<String>,
+    pub message: Option<String>,
+    pub initial_amount: Decimal,
+    // What's left to redeem.
+    pub balance: Decimal,
+    pub status: GiftCardStatus,
+    pub created_at: DateTime<Utc>,
+}
+
+// Payload for buying a gift card.
+#[derive(Debug, Deserialize, Validate)]
+pub struct PurchaseGiftCardPayload {
+    pub amount: Decimal,
+
+    // Who the card is for, if it's a gift.
+    #[validate(custom(function = "validation::is_valid_email"))]
+    pub recipient_email: Option<String>,
+
+    #[validate(length(max = 500, message = "The message can be at most 500 characters."))]
+    pub message: Option<String>,
+}
+
+// Payload for redeeming a gift card onto the user's account.
+#[derive(Debug, Deserialize, Validate)]
+pub struct RedeemGiftCardPayload {
+    #[validate(length(min = 1, max = 32, message = "Enter the gift card's code."))]
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "store_credit_entry_type", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum StoreCreditEntryType {
+    GiftCard,
+    Refund,
+    OrderPayment,
+    OrderPaymentReversal,
+}
+
+// Represents a row from the 'store_credit_entries' table. Credit given is positive
+// and credit spent negative.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct StoreCreditEntry {
+    pub id: i32,
+    pub entry_type: StoreCreditEntryType,
+    pub amount: Decimal,
+    pub order_id: Option<Uuid>,
+    pub gift_card_id: Option<i32>,
+    pub created_at: DateTime<Utc>,
+}
+
+// A user's store credit: their balance and the ledger behind it, newest first.
+#[derive(Debug, Serialize)]
+pub struct StoreCredit {
+    pub balance: Decimal,
+    pub entries: Vec<StoreCreditEntry>,
+}
@@ -37,4 +37,17 @@ pub struct CreateVenuePayload {
     pub country: String,
     pub address_line_1: Option<String>,
     // ... add other optional fields as needed
-}
\ No newline at end of file
+}
+// Represents a row from the 'venue_managers' table.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct VenueManager {
+    pub venue_id: i32,
+    pub user_id: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+// Payload for an admin granting a user management of a venue's seating charts.
+#[derive(Debug, Deserialize)]
+pub struct AssignVenueManagerPayload {
+    pub user_id: i32,
+}
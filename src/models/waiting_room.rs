@@ -0,0 +1,76 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+// Represents a row from the 'waiting_rooms' table.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct WaitingRoom {
+    pub event_id: i32,
+    pub opens_at: DateTime<Utc>,
+    pub admit_per_minute: i32,
+    pub admission_minutes: i32,
+    pub opened_at: Option<DateTime<Utc>>,
+    pub admitted_through: i32,
+    pub created_at: DateTime<Utc>,
+    pub last_updated: DateTime<Utc>,
+}
+
+// The public view of a waiting room, with how many buyers are in line.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct WaitingRoomInfo {
+    pub event_id: i32,
+    pub opens_at: DateTime<Utc>,
+    pub opened_at: Option<DateTime<Utc>>,
+    pub admit_per_minute: i32,
+    pub admitted_through: i32,
+    pub queue_length: i64,
+}
+
+// A user's place in an event's queue, from the 'waiting_room_entries' table.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct WaitingRoomEntry {
+    pub user_id: i32,
+    pub position: Option<i32>,
+    pub admitted_at: Option<DateTime<Utc>>,
+}
+
+// Payload for putting a waiting room in front of an event's on-sale, or changing it.
+#[derive(Debug, Deserialize, Validate)]
+pub struct ConfigureWaitingRoomPayload {
+    // When the places in line are drawn and buyers start being let in.
+    pub opens_at: DateTime<Utc>,
+    #[validate(range(min = 1, max = 100000, message = "Admit per minute must be between 1 and 100000."))]
+    pub admit_per_minute: i32,
+    // How long an admitted buyer has to check out. Defaults to 15 minutes.
+    #[validate(range(min = 1, max = 120, message = "Admission minutes must be between 1 and 120."))]
+    pub admission_minutes: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueState {
+    Waiting,
+    Admitted,
+    // The buyer was let in but didn't check out in time; joining again goes to the back of the line.
+    Expired,
+}
+
+// A buyer's place in an event's queue. Sent by the queue endpoints and pushed over the queue WebSocket.
+// `position` is drawn when the room opens, and the admission token is only given out while admitted.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueStatus {
+    pub event_id: i32,
+    pub state: QueueState,
+    pub opens_at: DateTime<Utc>,
+    pub position: Option<i32>,
+    pub people_ahead: Option<i32>,
+    pub admission_token: Option<String>,
+    pub admission_expires_at: Option<DateTime<Utc>>,
+}
+
+// How far a waiting room has let buyers in, relayed to every instance when it moves.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct QueueProgress {
+    pub event_id: i32,
+    pub admitted_through: i32,
+}
@@ -0,0 +1,77 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "waitlist_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum WaitlistStatus {
+    Waiting,
+    // Tickets are reserved for the user until `offer_expires_at`.
+    Offered,
+    Purchased,
+    Expired,
+    Left,
+}
+
+// Represents a row from the 'waitlist_entries' table.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct WaitlistEntry {
+    pub id: i32,
+    pub event_id: i32,
+    pub ticket_tier_id: Option<i32>,
+    pub user_id: i32,
+    pub quantity: i32,
+    pub status: WaitlistStatus,
+    pub offer_id: Option<i32>,
+    // The secret of the purchase link; only ever shown to the user it was offered to.
+    #[serde(skip_serializing)]
+    pub claim_token: Option<String>,
+    pub offered_at: Option<DateTime<Utc>>,
+    pub offer_expires_at: Option<DateTime<Utc>>,
+    pub order_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub last_updated: DateTime<Utc>,
+}
+
+// A user's waitlist entry with how many are ahead of them and, while offered, their purchase link.
+#[derive(Debug, Serialize)]
+pub struct WaitlistEntryDetails {
+    #[serde(flatten)]
+    pub entry: WaitlistEntry,
+    pub people_ahead: Option<i64>,
+    pub purchase_url: Option<String>,
+}
+
+// Payload for joining the waitlist of a sold-out event, or of one of its tiers.
+#[derive(Debug, Deserialize, Validate)]
+pub struct JoinWaitlistPayload {
+    // Leave out to take tickets of any tier.
+    pub ticket_tier_id: Option<i32>,
+    #[validate(range(min = 1, max = 10, message = "Quantity must be between 1 and 10."))]
+    pub quantity: i32,
+}
+
+// The tickets reserved behind a purchase link.
+#[derive(Debug, Serialize)]
+pub struct WaitlistClaim {
+    pub entry_id: i32,
+    pub event_id: i32,
+    pub offer_id: i32,
+    pub price: Decimal,
+    pub quantity: i32,
+    // Empty for general admission.
+    pub seat_ids: Vec<i32>,
+    pub expires_at: DateTime<Utc>,
+}
+
+// An offer returned tickets could be reserved through, cheapest first.
+#[derive(Debug, sqlx::FromRow)]
+pub struct ReservationOffer {
+    pub offer_id: i32,
+    pub ticket_tier_id: i32,
+    // The tier is sold by seat rather than by quantity.
+    pub is_reserved: bool,
+}
@@ -0,0 +1,186 @@
+use crate::{
+    config::CONFIG,
+    db::{access_code_query, event_query, pricing_query},
+    errors::AppError,
+    models::{
+        AccessCodeBatch, AccessCodeListQuery, GenerateAccessCodesPayload, Offer, OfferAccessCode, UnlockOffersPayload,
+    },
+    utils::{random, validation},
+};
+use sqlx::PgPool;
+
+/// Length of a generated access code.
+const CODE_LENGTH: usize = 10;
+/// How many times to top up a batch whose codes collided with existing ones.
+const MAX_GENERATION_ROUNDS: usize = 5;
+
+/// Service for an organizer to generate a batch of access codes for an offer, e.g. one
+/// code per fan club member. The offer becomes a presale if it wasn't one already.
+pub async fn generate_codes(
+    pool: &PgPool,
+    offer_id: i32,
+    organizer_id: i32,
+    payload: &GenerateAccessCodesPayload,
+) -> Result<AccessCodeBatch, AppError> {
+    // 1. Validate the payload and check the user organizes the offer's event.
+    validation::validate_payload(payload)?;
+    authorize_offer_organizer(pool, offer_id, organizer_id).await?;
+    let quantity = payload.quantity as usize;
+    let max_uses = payload.max_uses.unwrap_or(1);
+
+    // 2. Insert random codes until the batch is complete, replacing any that already exist.
+    let mut tx = pool.begin().await?;
+    let mut codes = Vec::with_capacity(quantity);
+    for _ in 0..MAX_GENERATION_ROUNDS {
+        if codes.len() == quantity {
+            break;
+        }
+        let candidates: Vec<String> = (codes.len()..quantity)
+            .map(|_| random::generate_random_token(CODE_LENGTH).to_uppercase())
+            .collect();
+        codes.extend(
+            access_code_query::insert_codes(&mut tx, offer_id, &payload.batch_name, &candidates, max_uses, organizer_id)
+                .await?,
+        );
+    }
+    if codes.len() < quantity {
+        return Err(AppError::InternalServerError("Could not generate unique access codes.".to_string()));
+    }
+    access_code_query::mark_offer_presale(&mut tx, offer_id).await?;
+    tx.commit().await?;
+
+    tracing::info!("Generated {} access codes in batch '{}' for offer {}.", quantity, payload.batch_name, offer_id);
+    Ok(AccessCodeBatch { offer_id, batch_name: payload.batch_name.clone(), max_uses, codes })
+}
+
+/// Service to list an offer's generated codes with how often each was redeemed.
+pub async fn list_codes(
+    pool: &PgPool,
+    offer_id: i32,
+    organizer_id: i32,
+    query: &AccessCodeListQuery,
+) -> Result<Vec<OfferAccessCode>, AppError> {
+    authorize_offer_organizer(pool, offer_id, organizer_id).await?;
+    access_code_query::list_codes(pool, offer_id, query.batch_name.as_deref()).await
+}
+
+/// Service for a user to unlock an event's presale offers with a code: either an offer's
+/// shared access code or a generated one. The offers stay unlocked for the user for as
+/// long as a login session lasts, and checkout only sells presale offers that are.
+/// Each generated code counts one use per user who redeems it.
+pub async fn unlock_offers(
+    pool: &PgPool,
+    event_id: i32,
+    user_id: i32,
+    payload: &UnlockOffersPayload,
+) -> Result<Vec<Offer>, AppError> {
+    // 1. Validate the payload.
+    validation::validate_payload(payload)?;
+    let code = payload.code.trim();
+
+    // 2. Find what the code unlocks: offers sharing it, and the offer of a generated code.
+    let mut tx = pool.begin().await?;
+    let mut offer_ids = access_code_query::find_shared_code_offers(&mut tx, event_id, code).await?;
+    let generated = access_code_query::lock_generated_code(&mut tx, event_id, code, user_id).await?;
+    if let Some(generated) = &generated {
+        if !generated.redeemed_by_user {
+            if !generated.has_uses_left {
+                return Err(AppError::BadRequest("This access code has already been used.".to_string()));
+            }
+            access_code_query::count_code_use(&mut tx, generated.id).await?;
+        }
+        offer_ids.push(generated.offer_id);
+    }
+    if offer_ids.is_empty() {
+        return Err(AppError::BadRequest("This access code is not valid for this event.".to_string()));
+    }
+
+    // 3. Unlock the offers for the user.
+    let expires_at = chrono::Utc::now() + chrono::Duration::hours(CONFIG.jwt_expiration_hours);
+    access_code_query::unlock_offers(&mut tx, user_id, &offer_ids, generated.map(|g| g.id), expires_at).await?;
+    let offers = access_code_query::list_offers(&mut tx, &offer_ids).await?;
+    tx.commit().await?;
+
+    Ok(offers)
+}
+
+async fn authorize_offer_organizer(pool: &PgPool, offer_id: i32, organizer_id: i32) -> Result<(), AppError> {
+    let offer = pricing_query::get_offer(pool, offer_id).await?;
+    let tier = pricing_query::get_ticket_tier(pool, offer.ticket_tier_id).await?;
+    let event = event_query::get_by_id(pool, tier.event_id).await?;
+    if event.organizer_id != organizer_id {
+        return Err(AppError::Forbidden("You are not authorized to manage access codes for this offer.".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::payment_provider::FakePaymentProvider;
+    use crate::models::{CreateOrderPayload, OrderItemPayload};
+    use crate::service::order_service;
+    use crate::utils::test_fixtures;
+    use rust_decimal::Decimal;
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn presale_is_sold_only_to_users_who_unlocked_it(pool: PgPool) {
+        let provider = FakePaymentProvider::new();
+        let event_id = test_fixtures::create_event(&pool).await;
+        let organizer_id: i32 = sqlx::query_scalar("SELECT organizer_id FROM events WHERE id = $1")
+            .bind(event_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let tier_id = test_fixtures::create_tier(&pool, event_id, 10).await;
+        let fan_club = test_fixtures::create_offer(&pool, tier_id, Decimal::from(30), 10).await;
+        let shared = test_fixtures::create_offer(&pool, tier_id, Decimal::from(35), 10).await;
+        sqlx::query("UPDATE offers SET access_code = 'LOCALS', is_presale = TRUE WHERE id = $1")
+            .bind(shared)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let fan = test_fixtures::create_user(&pool, "fan").await;
+        let other_fan = test_fixtures::create_user(&pool, "other_fan").await;
+        let unlock = |code: &str| UnlockOffersPayload { code: code.to_string() };
+        let checkout = CreateOrderPayload {
+            items: vec![OrderItemPayload { offer_id: fan_club, seat_id: None, quantity: 1 }],
+            addons: Vec::new(),
+            apply_credit: None,
+        };
+
+        // 1. Generating codes turns the offer into a presale.
+        let payload = GenerateAccessCodesPayload { batch_name: "Fan club".to_string(), quantity: 2, max_uses: None };
+        let batch = generate_codes(&pool, fan_club, organizer_id, &payload).await.unwrap();
+        assert_eq!(batch.codes.len(), 2);
+        assert_ne!(batch.codes[0], batch.codes[1]);
+        assert!(matches!(
+            order_service::create_order(&pool, &provider, fan, &checkout, None).await,
+            Err(AppError::Forbidden(_))
+        ));
+
+        // 2. A single-use code unlocks the offer for the first user only, who may enter it again.
+        assert!(matches!(unlock_offers(&pool, event_id, fan, &unlock("NOPE")).await, Err(AppError::BadRequest(_))));
+        let offers = unlock_offers(&pool, event_id, fan, &unlock(&batch.codes[0])).await.unwrap();
+        assert_eq!(offers.iter().map(|o| o.id).collect::<Vec<_>>(), vec![fan_club]);
+        unlock_offers(&pool, event_id, fan, &unlock(&batch.codes[0])).await.unwrap();
+        assert!(matches!(
+            unlock_offers(&pool, event_id, other_fan, &unlock(&batch.codes[0])).await,
+            Err(AppError::BadRequest(_))
+        ));
+        order_service::create_order(&pool, &provider, fan, &checkout, None).await.unwrap();
+
+        // 3. A shared code can be used by anyone.
+        for user_id in [fan, other_fan] {
+            let offers = unlock_offers(&pool, event_id, user_id, &unlock(" LOCALS ")).await.unwrap();
+            assert_eq!(offers.iter().map(|o| o.id).collect::<Vec<_>>(), vec![shared]);
+        }
+
+        // 4. The organizer sees each code's uses.
+        let query = AccessCodeListQuery { batch_name: Some("Fan club".to_string()) };
+        let codes = list_codes(&pool, fan_club, organizer_id, &query).await.unwrap();
+        let uses: Vec<(&str, i32)> = codes.iter().map(|c| (c.code.as_str(), c.use_count)).collect();
+        assert!(uses.contains(&(batch.codes[0].as_str(), 1)) && uses.contains(&(batch.codes[1].as_str(), 0)));
+        assert!(matches!(list_codes(&pool, fan_club, fan, &query).await, Err(AppError::Forbidden(_))));
+    }
+}
@@ -0,0 +1,200 @@
+use crate::{
+    db::{addon_query, event_query},
+    errors::AppError,
+    models::{AddonItemInfo, CreateAddonItemPayload, VoucherDetails},
+    utils::validation,
+};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+
+/// Service for an organizer to add an item, e.g. parking, a shirt or a donation, to their
+/// event's add-on catalog. Add-ons are sold with tickets, on their own, or in bundles.
+pub async fn create_addon_item(
+    pool: &PgPool,
+    event_id: i32,
+    organizer_id: i32,
+    payload: &CreateAddonItemPayload,
+) -> Result<AddonItemInfo, AppError> {
+    // 1. Validate the payload and check the user organizes the event.
+    validation::validate_payload(payload)?;
+    let event = event_query::get_by_id(pool, event_id).await?;
+    if event.organizer_id != organizer_id {
+        return Err(AppError::Forbidden("You are not authorized to manage add-ons for this event.".to_string()));
+    }
+
+    // 2. Donations take the buyer's amount, with the price as the least they can give,
+    //    and are neither redeemed nor sold in variants.
+    let price = payload.price.unwrap_or(Decimal::ZERO);
+    if price < Decimal::ZERO || price.normalize().scale() > 2 {
+        return Err(AppError::BadRequest("The price must be a positive amount in cents.".to_string()));
+    }
+    let is_redeemable = payload.is_redeemable.unwrap_or(!payload.is_donation);
+    if payload.is_donation && (is_redeemable || !payload.variants.is_empty()) {
+        return Err(AppError::BadRequest("Donations can't be redeemable or have variants.".to_string()));
+    }
+
+    // 3. Add the item and its variants to the catalog.
+    let mut tx = pool.begin().await?;
+    let item = addon_query::create_addon_item(&mut tx, event_id, payload, price, is_redeemable).await?;
+    let variants = addon_query::list_variants(&mut *tx, &[item.id]).await?;
+    tx.commit().await?;
+
+    Ok(AddonItemInfo { item, variants })
+}
+
+/// Service to list an event's add-on catalog, with the variants of each add-on.
+pub async fn list_addon_items(pool: &PgPool, event_id: i32) -> Result<Vec<AddonItemInfo>, AppError> {
+    let items = addon_query::list_addon_items_for_event(pool, event_id).await?;
+    let ids: Vec<i32> = items.iter().map(|item| item.id).collect();
+    let mut catalog: Vec<AddonItemInfo> =
+        items.into_iter().map(|item| AddonItemInfo { item, variants: Vec::new() }).collect();
+    for variant in addon_query::list_variants(pool, &ids).await? {
+        if let Some(info) = catalog.iter_mut().find(|info| info.item.id == variant.addon_item_id) {
+            info.variants.push(variant);
+        }
+    }
+    Ok(catalog)
+}
+
+/// Service to list the add-on vouchers a user was issued.
+pub async fn list_my_vouchers(pool: &PgPool, user_id: i32) -> Result<Vec<VoucherDetails>, AppError> {
+    addon_query::list_vouchers_for_user(pool, user_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::payment_provider::{FakePaymentProvider, ProviderPaymentStatus};
+    use crate::models::{addon::AddonVariantPayload, order::OrderAddonPayload, CreateOrderPayload, OrderItemPayload};
+    use crate::service::{order_service, payment_service};
+    use crate::utils::test_fixtures;
+
+    async fn variant_sold(pool: &PgPool, variant_id: i32) -> i32 {
+        sqlx::query_scalar("SELECT quantity_sold FROM addon_variants WHERE id = $1")
+            .bind(variant_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn addons_are_priced_sold_by_variant_and_issued_as_vouchers(pool: PgPool) {
+        let provider = FakePaymentProvider::new();
+        let event_id = test_fixtures::create_event(&pool).await;
+        let organizer_id: i32 = sqlx::query_scalar("SELECT organizer_id FROM events WHERE id = $1")
+            .bind(event_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let tier_id = test_fixtures::create_tier(&pool, event_id, 10).await;
+        let offer_id = test_fixtures::create_offer(&pool, tier_id, Decimal::from(30), 10).await;
+        let addon = |name: &str, price, is_donation, variants: Vec<(&str, i32)>| CreateAddonItemPayload {
+            name: name.to_string(),
+            description: None,
+            price: Some(Decimal::from(price)),
+            is_donation,
+            is_redeemable: None,
+            total_inventory: None,
+            variants: variants
+                .into_iter()
+                .map(|(name, total_inventory)| AddonVariantPayload { name: name.to_string(), total_inventory })
+                .collect(),
+        };
+
+        // 1. The catalog: a shirt in two sizes, a donation and unlimited parking.
+        let shirt =
+            create_addon_item(&pool, event_id, organizer_id, &addon("Shirt", 25, false, vec![("S", 1), ("M", 5)]))
+                .await
+                .unwrap();
+        let (small, medium) = (shirt.variants[0].id, shirt.variants[1].id);
+        let donation = create_addon_item(&pool, event_id, organizer_id, &addon("Donation", 5, true, Vec::new()))
+            .await
+            .unwrap()
+            .item;
+        assert!(!donation.is_redeemable);
+        let parking = create_addon_item(&pool, event_id, organizer_id, &addon("Parking", 10, false, Vec::new()))
+            .await
+            .unwrap()
+            .item;
+        let redeemable_donation =
+            CreateAddonItemPayload { is_redeemable: Some(true), ..addon("Tip", 1, true, Vec::new()) };
+        assert!(matches!(
+            create_addon_item(&pool, event_id, organizer_id, &redeemable_donation).await,
+            Err(AppError::BadRequest(_))
+        ));
+
+        let user_id = test_fixtures::create_user(&pool, "buyer").await;
+        let checkout = |addons: Vec<(i32, Option<i32>, i32, Option<Decimal>)>| CreateOrderPayload {
+            items: vec![OrderItemPayload { offer_id, seat_id: None, quantity: 1 }],
+            addons: addons
+                .into_iter()
+                .map(|(addon_item_id, variant_id, quantity, amount)| OrderAddonPayload {
+                    addon_item_id,
+                    variant_id,
+                    quantity,
+                    amount,
+                })
+                .collect(),
+            apply_credit: None,
+        };
+
+        // 2. Shirts need a size of their own, donations an amount of at least their price in
+        //    cents, and fixed-price add-ons no amount.
+        for addons in [
+            vec![(shirt.item.id, None, 1, None)],
+            vec![(shirt.item.id, Some(parking.id), 1, None)],
+            vec![(donation.id, None, 1, Some(Decimal::from(4)))],
+            vec![(donation.id, None, 1, Some(Decimal::new(5555, 3)))],
+            vec![(parking.id, None, 1, Some(Decimal::from(10)))],
+        ] {
+            let result = order_service::create_order(&pool, &provider, user_id, &checkout(addons), None).await;
+            assert!(matches!(result, Err(AppError::BadRequest(_))));
+        }
+
+        // 3. A ticket with a small shirt, a donation and parking. The fee is for the ticket only.
+        let addons = vec![
+            (shirt.item.id, Some(small), 1, None),
+            (donation.id, None, 1, Some(Decimal::from(20))),
+            (parking.id, None, 2, None),
+        ];
+        let (order, _) = order_service::create_order(&pool, &provider, user_id, &checkout(addons), None).await.unwrap();
+        assert_eq!((order.subtotal, order.service_fee), (Decimal::from(95), Decimal::new(250, 2)));
+        let sold_out = checkout(vec![(shirt.item.id, Some(small), 1, None)]);
+        let result = order_service::create_order(&pool, &provider, user_id, &sold_out, None).await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+
+        // 4. Paying issues a voucher per redeemable add-on bought.
+        let payment_intent_id: String =
+            sqlx::query_scalar("SELECT stripe_payment_intent_id FROM payments WHERE order_id = $1")
+                .bind(order.id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        provider.set_status(&payment_intent_id, ProviderPaymentStatus::Succeeded);
+        payment_service::finalize_order_on_payment_success(&pool, &provider, &payment_intent_id).await.unwrap();
+        let mut vouchers: Vec<_> = list_my_vouchers(&pool, user_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|voucher| (voucher.addon_name, voucher.variant_name))
+            .collect();
+        vouchers.sort();
+        assert_eq!(vouchers, vec![
+            ("Parking".to_string(), None),
+            ("Parking".to_string(), None),
+            ("Shirt".to_string(), Some("S".to_string())),
+        ]);
+
+        // 5. An expired order gives its sizes back.
+        let medium_shirts = checkout(vec![(shirt.item.id, Some(medium), 2, None)]);
+        let (order, _) = order_service::create_order(&pool, &provider, user_id, &medium_shirts, None).await.unwrap();
+        assert_eq!(variant_sold(&pool, medium).await, 2);
+        sqlx::query("UPDATE orders SET expires_at = NOW() - INTERVAL '1 minute' WHERE id = $1")
+            .bind(order.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        order_service::expire_stale_orders(&pool, &provider).await.unwrap();
+        assert_eq!((variant_sold(&pool, small).await, variant_sold(&pool, medium).await), (1, 0));
+    }
+}
@@ -0,0 +1,271 @@
+use crate::{
+    db::{cart_query, order_query, waitlist_query},
+    errors::AppError,
+    models::{CartItem, CartView, CreateOrderPayload, OrderItemPayload},
+    service::{lottery_service, waiting_room_service, waitlist_service},
+    utils::validation,
+};
+use chrono::{Duration, Utc};
+use rust_decimal::Decimal;
+use sqlx::{PgPool, Postgres, Transaction};
+
+/// How long a cart holds its seats and tickets. The hold starts with the first item and
+/// covers everything added after it, so the whole cart expires at once.
+const CART_HOLD_MINUTES: i64 = 15;
+
+/// Service for a user to see their cart, with what's left of its hold and its subtotal.
+pub async fn get_cart(pool: &PgPool, user_id: i32) -> Result<CartView, AppError> {
+    let cart = cart_query::get_active_cart(pool, user_id).await?;
+    let items = cart_query::list_item_details(pool, cart.id).await?;
+    let subtotal = items
+        .iter()
+        .map(|item| item.unit_price * Decimal::from(item.quantity))
+        .sum();
+    let seconds_remaining = (cart.expires_at - Utc::now()).num_seconds().max(0);
+    Ok(CartView { id: cart.id, expires_at: cart.expires_at, seconds_remaining, items, subtotal })
+}
+
+/// Service to add a seat or general admission tickets to a user's cart, holding them
+/// until the cart expires. Items can come from any number of events; events behind a
+/// waiting room need the admission token as at checkout.
+pub async fn add_item(
+    pool: &PgPool,
+    user_id: i32,
+    payload: &OrderItemPayload,
+    admission_token: Option<&str>,
+) -> Result<CartView, AppError> {
+    // 1. Validate the item, with the same gates as a checkout.
+    validation::validate_payload(payload)?;
+    if payload.seat_id.is_some() && payload.quantity != 1 {
+        return Err(AppError::BadRequest("Reserved seat items must have a quantity of 1.".to_string()));
+    }
+    let order_payload = CreateOrderPayload {
+        items: vec![OrderItemPayload { offer_id: payload.offer_id, seat_id: payload.seat_id, quantity: payload.quantity }],
+        addons: Vec::new(),
+        apply_credit: None,
+    };
+    lottery_service::check_offers(pool, &order_payload).await?;
+    waiting_room_service::check_admission(pool, user_id, &order_payload, admission_token).await?;
+
+    // 2. Lock the user's cart, starting a new one if they have none or it ran out.
+    let mut tx = pool.begin().await?;
+    let cart = match cart_query::lock_cart(&mut tx, user_id).await? {
+        Some(cart) if cart.expires_at > Utc::now() => cart,
+        expired => {
+            if let Some(cart) = expired {
+                discard_carts(&mut tx, &[cart.id]).await?;
+            }
+            let expires_at = Utc::now() + Duration::minutes(CART_HOLD_MINUTES);
+            cart_query::create_cart(&mut tx, user_id, expires_at).await?
+        }
+    };
+
+    // 3. Hold the seat or the tickets for the cart.
+    let offer = order_query::get_checkout_offer(&mut tx, payload.offer_id, user_id).await?;
+    if offer.is_bundle {
+        return Err(AppError::BadRequest(format!(
+            "Offer {} sells packages, which are bought at checkout rather than held in a cart.",
+            payload.offer_id
+        )));
+    }
+    if let Some(seat_id) = payload.seat_id {
+        let locked =
+            cart_query::add_seat(&mut tx, &cart, payload.offer_id, offer.event_id, offer.ticket_tier_id, seat_id).await?;
+        if !locked {
+            return Err(
+                order_query::seat_lock_error(&mut tx, offer.event_id, seat_id, payload.offer_id, offer.ticket_tier_id)
+                    .await,
+            );
+        }
+        order_query::check_seat_access(&mut tx, offer.event_id, seat_id, payload.offer_id, offer.is_accessible).await?;
+    } else {
+        let reserved =
+            waitlist_query::reserve_general_admission(&mut tx, payload.offer_id, offer.ticket_tier_id, payload.quantity)
+                .await?;
+        if !reserved {
+            return Err(AppError::BadRequest("Not enough tickets available for this offer.".to_string()));
+        }
+        cart_query::add_general_admission(
+            &mut tx,
+            cart.id,
+            payload.offer_id,
+            offer.event_id,
+            offer.ticket_tier_id,
+            payload.quantity,
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+    get_cart(pool, user_id).await
+}
+
+/// Service to take an item out of a user's cart and put its seat or tickets back on sale.
+/// Removing the last item discards the cart.
+pub async fn remove_item(pool: &PgPool, user_id: i32, item_id: i32) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+    let cart = cart_query::lock_cart(&mut tx, user_id).await?.ok_or(AppError::Sqlx(sqlx::Error::RowNotFound))?;
+    let item = cart_query::delete_item(&mut tx, cart.id, item_id).await?;
+    release_items(&mut tx, &[cart.id], &[item]).await?;
+    if cart_query::count_items(&mut tx, cart.id).await? == 0 {
+        cart_query::delete_carts(&mut tx, &[cart.id]).await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Service for a user to empty their cart, putting everything in it back on sale.
+pub async fn clear_cart(pool: &PgPool, user_id: i32) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+    let cart = cart_query::lock_cart(&mut tx, user_id).await?.ok_or(AppError::Sqlx(sqlx::Error::RowNotFound))?;
+    discard_carts(&mut tx, &[cart.id]).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Turns a user's cart into the items of an order, for checkout to run in the same
+/// transaction. Its seats are handed over to the order, and its general admission tickets
+/// are put back for checkout to take again, so nothing is lost in between. The cart is
+/// gone once the checkout commits.
+pub async fn claim_cart(tx: &mut Transaction<'_, Postgres>, user_id: i32) -> Result<CreateOrderPayload, AppError> {
+    // 1. Lock the cart and check its hold is still on.
+    let cart = cart_query::lock_cart(tx, user_id).await?.ok_or(AppError::Sqlx(sqlx::Error::RowNotFound))?;
+    if cart.expires_at <= Utc::now() {
+        return Err(AppError::BadRequest("Your cart has expired. Add the items again to hold them.".to_string()));
+    }
+    let items = cart_query::list_items(tx, &[cart.id]).await?;
+    if items.is_empty() {
+        return Err(AppError::BadRequest("Your cart is empty.".to_string()));
+    }
+
+    // 2. Hand its inventory over to the checkout and build the order from it.
+    for item in items.iter().filter(|item| item.seat_id.is_none()) {
+        waitlist_query::release_general_admission(tx, item.offer_id, item.ticket_tier_id, item.quantity).await?;
+    }
+    cart_query::take_seats(tx, cart.id).await?;
+    cart_query::delete_carts(tx, &[cart.id]).await?;
+
+    let items = items
+        .into_iter()
+        .map(|item| OrderItemPayload { offer_id: item.offer_id, seat_id: item.seat_id, quantity: item.quantity })
+        .collect();
+    Ok(CreateOrderPayload { items, addons: Vec::new(), apply_credit: None })
+}
+
+/// Deletes carts and puts everything they held back on sale, offering it to waitlisted
+/// users first.
+async fn discard_carts(tx: &mut Transaction<'_, Postgres>, cart_ids: &[i32]) -> Result<(), AppError> {
+    let items = cart_query::list_items(tx, cart_ids).await?;
+    release_items(tx, cart_ids, &items).await?;
+    cart_query::delete_carts(tx, cart_ids).await
+}
+
+/// Puts the seats and tickets held for cart items back on sale, then offers them to
+/// waitlisted users before anyone else can take them.
+async fn release_items(tx: &mut Transaction<'_, Postgres>, cart_ids: &[i32], items: &[CartItem]) -> Result<(), AppError> {
+    for item in items {
+        match item.seat_id {
+            Some(seat_id) => {
+                cart_query::release_seats(tx, cart_ids, Some(seat_id)).await?;
+            }
+            None => {
+                waitlist_query::release_general_admission(tx, item.offer_id, item.ticket_tier_id, item.quantity)
+                    .await?;
+            }
+        }
+    }
+
+    let mut event_ids: Vec<i32> = items.iter().map(|item| item.event_id).collect();
+    event_ids.sort_unstable();
+    event_ids.dedup();
+    waitlist_service::offer_returned_inventory(tx, &event_ids).await?;
+    Ok(())
+}
+
+// --- Background Job Service ---
+
+/// Service function for a background worker to discard carts whose hold has run out.
+/// Returns how many carts were discarded.
+pub async fn expire_carts(pool: &PgPool) -> Result<usize, AppError> {
+    let mut tx = pool.begin().await?;
+    let cart_ids = cart_query::lock_expired_carts(&mut tx).await?;
+    if !cart_ids.is_empty() {
+        discard_carts(&mut tx, &cart_ids).await?;
+        tracing::info!("Discarded {} expired carts.", cart_ids.len());
+    }
+    tx.commit().await?;
+    Ok(cart_ids.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::payment_provider::FakePaymentProvider;
+    use crate::service::order_service;
+    use crate::utils::test_fixtures;
+
+    async fn quantity_sold(pool: &PgPool, offer_id: i32) -> i32 {
+        sqlx::query_scalar("SELECT quantity_sold FROM offers WHERE id = $1")
+            .bind(offer_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn seat_status(pool: &PgPool, seat_id: i32) -> String {
+        sqlx::query_scalar("SELECT status::text FROM event_seats WHERE seat_id = $1")
+            .bind(seat_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn cart_holds_items_of_several_events_until_checkout_or_expiry(pool: PgPool) {
+        let provider = FakePaymentProvider::new();
+        let festival = test_fixtures::create_event(&pool).await;
+        let festival_tier = test_fixtures::create_tier(&pool, festival, 3).await;
+        let day_pass = test_fixtures::create_offer(&pool, festival_tier, Decimal::from(30), 3).await;
+        let concert = test_fixtures::create_event(&pool).await;
+        let concert_tier = test_fixtures::create_tier(&pool, concert, 3).await;
+        let concert_seat = test_fixtures::create_offer(&pool, concert_tier, Decimal::from(50), 3).await;
+        let seat_ids = test_fixtures::create_seats(&pool, concert, concert_tier, 3).await;
+        let shopper = test_fixtures::create_user(&pool, "shopper").await;
+        let other = test_fixtures::create_user(&pool, "other").await;
+        let passes = |quantity| OrderItemPayload { offer_id: day_pass, seat_id: None, quantity };
+        let seat = |seat_id| OrderItemPayload { offer_id: concert_seat, seat_id: Some(seat_id), quantity: 1 };
+
+        // 1. The cart holds passes and a seat of two events, which nobody else can take.
+        add_item(&pool, shopper, &passes(2), None).await.unwrap();
+        let cart = add_item(&pool, shopper, &seat(seat_ids[0]), None).await.unwrap();
+        assert_eq!((cart.items.len(), cart.subtotal), (2, Decimal::from(110)));
+        assert!(cart.seconds_remaining > 0);
+        assert_eq!(quantity_sold(&pool, day_pass).await, 2);
+        for item in [passes(2), seat(seat_ids[0])] {
+            assert!(matches!(add_item(&pool, other, &item, None).await, Err(AppError::BadRequest(_))));
+        }
+
+        // 2. Removing an item puts it back on sale.
+        let pass_item_id = cart.items.iter().find(|item| item.seat_id.is_none()).unwrap().id;
+        remove_item(&pool, shopper, pass_item_id).await.unwrap();
+        assert_eq!(quantity_sold(&pool, day_pass).await, 0);
+        add_item(&pool, shopper, &passes(2), None).await.unwrap();
+
+        // 3. Checkout turns the cart into one order, without selling the holds twice.
+        let (order, _) = order_service::create_cart_order(&pool, &provider, shopper).await.unwrap();
+        assert_eq!((order.subtotal, order.service_fee), (Decimal::from(110), Decimal::new(750, 2)));
+        assert_eq!(quantity_sold(&pool, day_pass).await, 2);
+        assert_eq!(seat_status(&pool, seat_ids[0]).await, "locked");
+        assert!(get_cart(&pool, shopper).await.is_err());
+
+        // 4. A cart whose hold ran out can't be checked out, and the sweep releases it.
+        add_item(&pool, other, &passes(1), None).await.unwrap();
+        add_item(&pool, other, &seat(seat_ids[1]), None).await.unwrap();
+        sqlx::query("UPDATE carts SET expires_at = NOW() - INTERVAL '1 minute'").execute(&pool).await.unwrap();
+        let result = order_service::create_cart_order(&pool, &provider, other).await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+        assert_eq!(expire_carts(&pool).await.unwrap(), 1);
+        assert_eq!(quantity_sold(&pool, day_pass).await, 2);
+        assert_eq!(seat_status(&pool, seat_ids[1]).await, "available");
+    }
+}
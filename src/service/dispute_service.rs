@@ -0,0 +1,137 @@
+use crate::{
+    db::{dispute_query, group_booking_query, order_query, payment_query, seating_query, user_query},
+    errors::AppError,
+    models::{DisputeCase, DisputeStatus},
+    service::{settlement_service, waitlist_service},
+};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+
+/// The statuses shown in the admin queue when no filter is given.
+const OPEN_STATUSES: [DisputeStatus; 4] = [
+    DisputeStatus::WarningNeedsResponse,
+    DisputeStatus::NeedsResponse,
+    DisputeStatus::WarningUnderReview,
+    DisputeStatus::UnderReview,
+];
+
+/// Applies a `charge.dispute.*` webhook to our records.
+///
+/// - While a dispute is open, the tickets of the disputed order are voided so they can't be used.
+/// - When it is lost, the order and payment become 'refunded' and the amount lost is recorded.
+///   The order's tickets go back on sale. In a group booking, only the disputed member's
+///   tickets are voided and the payment refunded; the rest of the order stands.
+/// - When it is won (or an inquiry closes without a chargeback), the voided tickets are restored.
+///
+/// Every step is idempotent, because Stripe sends several events per dispute and may retry them.
+pub async fn handle_dispute_event(pool: &PgPool, dispute: &stripe::Dispute) -> Result<(), AppError> {
+    // 1. Find the payment the dispute was raised against.
+    let Some(payment_intent) = &dispute.payment_intent else {
+        tracing::warn!("Dispute {} has no PaymentIntent; ignoring it.", dispute.id);
+        return Ok(());
+    };
+    let payment_intent_id = payment_intent.id().to_string();
+
+    let mut tx = pool.begin().await?;
+    let payment = match payment_query::get_by_payment_intent_id(&mut tx, &payment_intent_id).await {
+        Ok(payment) => payment,
+        Err(AppError::Sqlx(sqlx::Error::RowNotFound)) => {
+            tracing::warn!(
+                "Dispute {} is for unknown PaymentIntent {}; ignoring it.",
+                dispute.id,
+                payment_intent_id
+            );
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+
+    // 2. Record the dispute, or refresh it if we've seen it before.
+    let status = DisputeStatus::from(dispute.status);
+    let record = dispute_query::upsert_dispute(
+        &mut tx,
+        payment.id,
+        payment.order_id,
+        dispute.id.as_str(),
+        status,
+        &dispute.reason,
+        settlement_service::cents_to_decimal(dispute.amount),
+        &dispute.currency.to_string(),
+        dispute.evidence_details.due_by.map(settlement_service::timestamp_to_datetime),
+    )
+    .await?;
+
+    // 3. Act on the dispute's status.
+    match status {
+        DisputeStatus::Lost => {
+            // What the dispute cost is the sum of its balance transactions (amount plus fee).
+            let net: i64 = dispute.balance_transactions.iter().map(|bt| bt.net).sum();
+            let amount_lost = if net < 0 {
+                settlement_service::cents_to_decimal(-net)
+            } else {
+                record.amount
+            };
+
+            dispute_query::void_tickets_for_dispute(&mut tx, record.id, record.order_id, payment.id).await?;
+            dispute_query::close_dispute(&mut tx, record.id, amount_lost).await?;
+            // A group booking's other members paid for their tickets, so its order stands.
+            let refunded = !group_booking_query::is_group_order(&mut tx, record.order_id).await?
+                && order_query::mark_order_refunded(&mut tx, record.order_id).await?;
+            payment_query::mark_payment_refunded(&mut tx, payment.id, record.amount).await?;
+            if refunded {
+                // The voided tickets' inventory goes back on sale, waitlisted users first.
+                order_query::release_general_admission_inventory(&mut tx, &[record.order_id]).await?;
+                seating_query::release_refunded_order_seats(&mut tx, record.order_id).await?;
+                let event_ids = order_query::list_event_ids_for_orders(&mut tx, &[record.order_id]).await?;
+                waitlist_service::offer_returned_inventory(&mut tx, &event_ids).await?;
+            }
+            tracing::warn!("Dispute {} lost; order {} refunded, {} lost.", dispute.id, record.order_id, amount_lost);
+        }
+        DisputeStatus::Won | DisputeStatus::WarningClosed => {
+            let restored = dispute_query::restore_tickets_for_dispute(&mut tx, record.id).await?;
+            dispute_query::close_dispute(&mut tx, record.id, Decimal::ZERO).await?;
+            tracing::info!("Dispute {} closed in our favour; {} tickets restored.", dispute.id, restored);
+        }
+        _ => {
+            let voided = dispute_query::void_tickets_for_dispute(&mut tx, record.id, record.order_id, payment.id).await?;
+            if voided > 0 {
+                tracing::warn!("Dispute {} opened; voided {} tickets of order {}.", dispute.id, voided, record.order_id);
+            }
+        }
+    }
+
+    tx.commit().await?;
+
+    // 4. Reflect the funds withdrawn or reinstated in the organizers' ledger.
+    settlement_service::record_dispute_balance_transactions(pool, &payment_intent_id, dispute).await?;
+
+    Ok(())
+}
+
+/// Builds the admin dispute queue: each dispute with its order, buyer and ticket evidence.
+/// Without a status filter, only disputes still awaiting a decision are listed.
+pub async fn list_dispute_queue(
+    pool: &PgPool,
+    status: Option<DisputeStatus>,
+) -> Result<Vec<DisputeCase>, AppError> {
+    let statuses = match status {
+        Some(status) => vec![status],
+        None => OPEN_STATUSES.to_vec(),
+    };
+
+    let disputes = dispute_query::list_disputes_by_status(pool, &statuses).await?;
+    let mut cases = Vec::with_capacity(disputes.len());
+    for dispute in disputes {
+        let order = order_query::get_by_id(pool, dispute.order_id).await?;
+        let buyer = user_query::get_by_id(pool, order.user_id).await?;
+        let tickets = dispute_query::list_ticket_evidence_for_order(pool, dispute.order_id).await?;
+        cases.push(DisputeCase {
+            dispute,
+            order,
+            buyer_email: buyer.email,
+            tickets,
+        });
+    }
+
+    Ok(cases)
+}
@@ -0,0 +1,465 @@
+use crate::{
+    clients::payment_provider::PaymentProvider,
+    db::{event_query, group_booking_query, order_query, payment_query, seating_query, settlement_query, waitlist_query},
+    errors::AppError,
+    models::{
+        CreateGroupBookingPayload, CreateOrderPayload, GroupBooking, GroupBookingStatus, GroupBookingView,
+        GroupPaymentResponse, GroupShare, GroupShareStatus, PayGroupSharesPayload, PaymentStatus, Ticket,
+    },
+    service::{lottery_service, seat_gap_service, waiting_room_service, waitlist_service},
+    utils::{random, validation},
+};
+use chrono::{Duration, Utc};
+use num_traits::ToPrimitive;
+use rust_decimal::Decimal;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::collections::HashMap;
+
+/// How many tickets a group booking can hold.
+const MIN_GROUP_TICKETS: i32 = 2;
+const MAX_GROUP_TICKETS: i32 = 20;
+/// How long the members have to pay when the organizer doesn't say, and the most they can get.
+const DEFAULT_DEADLINE_HOURS: i64 = 24;
+const MAX_DEADLINE_HOURS: i64 = 72;
+/// How long a member's claim on the shares they're paying for lasts.
+const CLAIM_MINUTES: i64 = 15;
+/// Length of the secret in an invite link.
+const INVITE_TOKEN_LENGTH: usize = 32;
+
+/// Service for an organizer to reserve tickets for their group. The tickets are held by a
+/// pending order until the payment deadline, with a share for every ticket that any member
+/// can pay for through the invite link.
+/// Seats and tickets are checked like any checkout: lottery offers and events behind a
+/// waiting room are enforced, and the seats mustn't strand single empty seats.
+pub async fn create_group_booking(
+    pool: &PgPool,
+    event_id: i32,
+    organizer_id: i32,
+    payload: CreateGroupBookingPayload,
+    admission_token: Option<&str>,
+) -> Result<GroupBookingView, AppError> {
+    // 1. Validate the payload, the group's size and the deadline.
+    validation::validate_payload(&payload)?;
+    let ticket_count: i32 = payload.items.iter().map(|item| item.quantity).sum();
+    if !(MIN_GROUP_TICKETS..=MAX_GROUP_TICKETS).contains(&ticket_count) {
+        return Err(AppError::BadRequest(format!(
+            "A group booking must hold between {} and {} tickets.",
+            MIN_GROUP_TICKETS, MAX_GROUP_TICKETS
+        )));
+    }
+    let deadline_hours = payload.payment_deadline_hours.unwrap_or(DEFAULT_DEADLINE_HOURS);
+    if !(1..=MAX_DEADLINE_HOURS).contains(&deadline_hours) {
+        return Err(AppError::BadRequest(format!(
+            "The payment deadline must be between 1 and {} hours away.",
+            MAX_DEADLINE_HOURS
+        )));
+    }
+    let payment_deadline = Utc::now() + Duration::hours(deadline_hours);
+    let event = event_query::get_by_id(pool, event_id).await?;
+    if payment_deadline >= event.start_time {
+        return Err(AppError::BadRequest("The payment deadline must be before the event starts.".to_string()));
+    }
+
+    let order_payload = CreateOrderPayload {
+        items: payload.items,
+        addons: vec![],
+        apply_credit: None,
+    };
+    lottery_service::check_offers(pool, &order_payload).await?;
+    waiting_room_service::check_admission(pool, organizer_id, &order_payload, admission_token).await?;
+
+    // 2. Hold the tickets with a pending order that lasts until the deadline, then create
+    //    the booking and its shares in the same transaction.
+    let mut tx = pool.begin().await?;
+    let order =
+        order_query::create_pending_order(&mut tx, organizer_id, &order_payload, deadline_hours * 60).await?;
+    let items = order_query::get_items_for_order(&mut *tx, order.id).await?;
+    if items.iter().any(|item| item.event_id != event_id) {
+        return Err(AppError::BadRequest("Every ticket of a group booking must be for this event.".to_string()));
+    }
+    if !order_query::get_bundles_for_order(&mut *tx, order.id).await?.is_empty() {
+        return Err(AppError::BadRequest("Bundles can't be booked for a group.".to_string()));
+    }
+    let row_seats = seating_query::list_row_seats_for_order(&mut *tx, order.id).await?;
+    seat_gap_service::check_selection(&row_seats)?;
+
+    let invite_token = random::generate_random_token(INVITE_TOKEN_LENGTH);
+    let booking = group_booking_query::create_booking(
+        &mut tx,
+        order.id,
+        organizer_id,
+        event_id,
+        &invite_token,
+        order.expires_at,
+    )
+    .await?;
+    let shares = group_booking_query::list_shares(&mut *tx, booking.id).await?;
+    tx.commit().await?;
+
+    Ok(build_view(booking, shares))
+}
+
+/// Service for anyone with the invite link to see a group booking and which of its shares
+/// are still to be paid.
+pub async fn get_group_booking(pool: &PgPool, invite_token: &str) -> Result<GroupBookingView, AppError> {
+    let booking = group_booking_query::get_by_token(pool, invite_token).await?;
+    let shares = group_booking_query::list_shares(pool, booking.id).await?;
+    Ok(build_view(booking, shares))
+}
+
+/// Service for an organizer to list the group bookings they made.
+pub async fn list_my_group_bookings(pool: &PgPool, organizer_id: i32) -> Result<Vec<GroupBookingView>, AppError> {
+    let bookings = group_booking_query::list_for_organizer(pool, organizer_id).await?;
+    let mut views = Vec::with_capacity(bookings.len());
+    for booking in bookings {
+        let shares = group_booking_query::list_shares(pool, booking.id).await?;
+        views.push(build_view(booking, shares));
+    }
+    Ok(views)
+}
+
+/// Service for a member to pay for some of a group's tickets.
+/// The shares are claimed for the member while their payment goes through; a claim that
+/// lapses unpaid frees the shares for the others. Each paid ticket is issued to whoever paid it.
+pub async fn pay_shares<P: PaymentProvider>(
+    pool: &PgPool,
+    payment_provider: &P,
+    invite_token: &str,
+    user_id: i32,
+    payload: &PayGroupSharesPayload,
+) -> Result<GroupPaymentResponse, AppError> {
+    // 1. Validate the payload and the booking, locked so payments for it go one at a time.
+    validation::validate_payload(payload)?;
+    let mut tx = pool.begin().await?;
+    let booking = group_booking_query::lock_by_token(&mut tx, invite_token).await?;
+    if booking.status != GroupBookingStatus::Open || booking.payment_deadline <= Utc::now() {
+        return Err(AppError::BadRequest("This group booking is no longer taking payments.".to_string()));
+    }
+
+    // 2. Claim the shares, until the deadline at the latest. All of them must still be unpaid.
+    let mut share_ids = payload.share_ids.clone();
+    share_ids.sort_unstable();
+    share_ids.dedup();
+    let claim_expires_at = (Utc::now() + Duration::minutes(CLAIM_MINUTES)).min(booking.payment_deadline);
+    let shares =
+        group_booking_query::claim_shares(&mut tx, booking.id, &share_ids, user_id, claim_expires_at).await?;
+    if shares.len() != share_ids.len() {
+        return Err(AppError::BadRequest(
+            "Some of these shares don't belong to this group booking or are already paid for.".to_string(),
+        ));
+    }
+
+    // 3. Create a Payment Intent for the shares. If Stripe fails, the claim is rolled back.
+    let amount: Decimal = shares.iter().map(|share| share.amount).sum();
+    let amount_in_cents = (amount * Decimal::from(100))
+        .to_i64()
+        .ok_or_else(|| AppError::InternalServerError("Failed to convert amount to cents".to_string()))?;
+    let payment_intent = match payment_provider.create_payment_intent(amount_in_cents, "usd").await {
+        Ok(pi) => pi,
+        Err(e) => {
+            tx.rollback().await?;
+            return Err(e);
+        }
+    };
+
+    // 4. Record the pending payment against the group's order and link the shares to it.
+    let payment = payment_query::create_pending_payment(&mut tx, booking.order_id, amount, &payment_intent.id).await?;
+    group_booking_query::set_payment(&mut tx, &share_ids, payment.id).await?;
+    tx.commit().await?;
+
+    Ok(GroupPaymentResponse {
+        shares,
+        amount,
+        stripe_client_secret: payment_intent.client_secret,
+    })
+}
+
+/// Finalizes a member's payment for their shares: issues them the tickets and marks the
+/// seats sold. Once every share is paid, the order is completed and the sale recorded.
+/// A payment that went through after its claim lapsed still pays for its shares if nobody
+/// else claimed them and the booking is open; otherwise it is refunded, never kept.
+/// MUST be run in the transaction that recorded the payment.
+pub async fn complete_share_payment<P: PaymentProvider>(
+    tx: &mut Transaction<'_, Postgres>,
+    payment_provider: &P,
+    booking: &GroupBooking,
+    stripe_payment_intent_id: &str,
+) -> Result<Vec<Ticket>, AppError> {
+    // 1. Nothing is left to do if Stripe retried the webhook.
+    let payment = payment_query::get_by_payment_intent_id(tx, stripe_payment_intent_id).await?;
+    if matches!(payment.status, PaymentStatus::Refunded) || group_booking_query::has_paid_shares(tx, payment.id).await? {
+        tracing::warn!("Payment {} was already finalized; skipping ticket issuance.", stripe_payment_intent_id);
+        return Ok(vec![]);
+    }
+
+    // 2. The payment must still cover exactly the shares it was made for. If any of them were
+    //    claimed by someone else or released in the meantime, give the member their money back.
+    //    Refunds are idempotent, so a webhook retried because this transaction didn't commit
+    //    doesn't refund the member twice.
+    let shares = group_booking_query::lock_shares_for_payment(tx, payment.id).await?;
+    let amount: Decimal = shares.iter().map(|share| share.amount).sum();
+    if shares.is_empty() || amount != payment.amount_charged || booking.status != GroupBookingStatus::Open {
+        payment_provider.refund_payment_intent(stripe_payment_intent_id).await?;
+        payment_query::mark_payment_refunded(tx, payment.id, payment.amount_charged).await?;
+        tracing::warn!(
+            "Payment {} of group booking {} came too late for its shares; refunded {}.",
+            stripe_payment_intent_id,
+            booking.id,
+            payment.amount_charged
+        );
+        return Ok(vec![]);
+    }
+    if matches!(payment.status, PaymentStatus::Failed) {
+        payment_query::mark_failed_payment_succeeded(tx, payment.id).await?;
+    }
+
+    // 3. Mark the shares paid, issue the tickets to the payer and mark their seats sold.
+    let share_ids: Vec<i32> = shares.iter().map(|share| share.id).collect();
+    let shares = group_booking_query::mark_paid(tx, &share_ids).await?;
+    let tickets = group_booking_query::issue_tickets(tx, booking.order_id, booking.event_id, &shares).await?;
+    let seat_ids: Vec<i32> = shares.iter().filter_map(|share| share.seat_id).collect();
+    group_booking_query::mark_seats_sold(tx, booking.order_id, &seat_ids).await?;
+
+    // 4. Complete the booking once the last share is paid.
+    if group_booking_query::count_outstanding(tx, booking.id).await? == 0 {
+        order_query::mark_order_completed(tx, booking.order_id).await?;
+        settlement_query::record_order_sale(tx, booking.order_id).await?;
+        group_booking_query::set_status(tx, booking.id, GroupBookingStatus::Completed).await?;
+    }
+
+    Ok(tickets)
+}
+
+/// Frees the shares whose claim lapsed unpaid, then closes the group bookings whose payment
+/// deadline has passed. Unpaid shares are released: their tickets go back on sale, waitlisted
+/// users first, and are taken off the order. The order is completed with what was paid, or
+/// cancelled if nothing was. The PaymentIntents of the claims given up on are cancelled, so
+/// members can't be charged for shares they no longer hold.
+/// Run periodically by the group booking job. Returns the number of bookings closed.
+pub async fn close_due_bookings<P: PaymentProvider>(pool: &PgPool, payment_provider: &P) -> Result<u64, AppError> {
+    // 1. Free the lapsed claims.
+    let mut tx = pool.begin().await?;
+    let mut abandoned_intents = group_booking_query::release_lapsed_claims(&mut tx).await?;
+    if !abandoned_intents.is_empty() {
+        tracing::info!("Freed the group booking shares of {} lapsed payments.", abandoned_intents.len());
+    }
+
+    // 2. Release the unpaid shares of every booking past its deadline.
+    let bookings = group_booking_query::lock_due_bookings(&mut tx).await?;
+    let mut event_ids = Vec::with_capacity(bookings.len());
+    for booking in &bookings {
+        abandoned_intents.extend(group_booking_query::fail_claimed_payments(&mut tx, booking.id).await?);
+        let released = group_booking_query::release_unpaid_shares(&mut tx, booking.id).await?;
+        let mut general_admission: HashMap<(i32, i32), i32> = HashMap::new();
+        for share in released.iter().filter(|share| share.seat_id.is_none()) {
+            *general_admission.entry((share.offer_id, share.ticket_tier_id)).or_default() += 1;
+        }
+        for ((offer_id, ticket_tier_id), quantity) in general_admission {
+            waitlist_query::release_general_admission(&mut tx, offer_id, ticket_tier_id, quantity).await?;
+        }
+        seating_query::release_order_seats(&mut tx, &[booking.order_id]).await?;
+
+        // 3. Complete the order with the paid shares, or cancel it if none were.
+        let paid = group_booking_query::list_shares(&mut *tx, booking.id)
+            .await?
+            .iter()
+            .any(|share| share.status == GroupShareStatus::Paid);
+        if paid {
+            group_booking_query::trim_order(&mut tx, booking.id, booking.order_id).await?;
+            order_query::mark_order_completed(&mut tx, booking.order_id).await?;
+            settlement_query::record_order_sale(&mut tx, booking.order_id).await?;
+            group_booking_query::set_status(&mut tx, booking.id, GroupBookingStatus::Closed).await?;
+        } else {
+            order_query::mark_order_cancelled(&mut tx, booking.order_id).await?;
+            group_booking_query::set_status(&mut tx, booking.id, GroupBookingStatus::Cancelled).await?;
+        }
+        tracing::info!(
+            "Group booking {} closed at its deadline; released {} unpaid tickets.",
+            booking.id,
+            released.len()
+        );
+        event_ids.push(booking.event_id);
+    }
+
+    // 4. Offer the returned inventory to waitlisted users before anyone else can take it.
+    event_ids.sort_unstable();
+    event_ids.dedup();
+    waitlist_service::offer_returned_inventory(&mut tx, &event_ids).await?;
+    tx.commit().await?;
+
+    // 5. Cancel the abandoned PaymentIntents. One that can't be cancelled any more is about to
+    //    succeed, and its webhook refunds it.
+    for payment_intent_id in &abandoned_intents {
+        if let Err(e) = payment_provider.cancel_payment_intent(payment_intent_id).await {
+            tracing::warn!("Failed to cancel abandoned PaymentIntent {}: {:?}", payment_intent_id, e);
+        }
+    }
+
+    Ok(bookings.len() as u64)
+}
+
+fn build_view(booking: GroupBooking, shares: Vec<GroupShare>) -> GroupBookingView {
+    let (amount_paid, amount_outstanding) =
+        shares
+            .iter()
+            .fold((Decimal::ZERO, Decimal::ZERO), |(paid, outstanding), share| match share.status {
+                GroupShareStatus::Paid => (paid + share.amount, outstanding),
+                GroupShareStatus::Released => (paid, outstanding),
+                _ => (paid, outstanding + share.amount),
+            });
+    GroupBookingView {
+        booking,
+        shares,
+        amount_paid,
+        amount_outstanding,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::payment_provider::{FakePaymentProvider, ProviderPaymentStatus};
+    use crate::models::{OrderItemPayload, OrderStatus};
+    use crate::service::payment_service;
+    use crate::utils::test_fixtures;
+
+    /// Books three general admission tickets for a group. Returns the booking and the offer.
+    async fn book_for_group(pool: &PgPool) -> (GroupBookingView, i32) {
+        let event_id = test_fixtures::create_event(pool).await;
+        let tier_id = test_fixtures::create_tier(pool, event_id, 100).await;
+        let offer_id = test_fixtures::create_offer(pool, tier_id, Decimal::new(5000, 2), 100).await;
+        let organizer_id = test_fixtures::create_user(pool, "host").await;
+
+        let payload = CreateGroupBookingPayload {
+            items: vec![OrderItemPayload { offer_id, seat_id: None, quantity: 3 }],
+            payment_deadline_hours: None,
+        };
+        let view = create_group_booking(pool, event_id, organizer_id, payload, None).await.unwrap();
+        (view, offer_id)
+    }
+
+    /// Starts a member's payment for one share. Returns the PaymentIntent's ID.
+    async fn pay_share(
+        pool: &PgPool,
+        provider: &FakePaymentProvider,
+        view: &GroupBookingView,
+        name: &str,
+        share: usize,
+    ) -> String {
+        let user_id = test_fixtures::create_user(pool, name).await;
+        let payload = PayGroupSharesPayload { share_ids: vec![view.shares[share].id] };
+        pay_shares(pool, provider, &view.booking.invite_token, user_id, &payload).await.unwrap();
+        sqlx::query_scalar(
+            "SELECT p.stripe_payment_intent_id FROM payments p
+             JOIN group_booking_shares s ON s.payment_id = p.id WHERE s.id = $1",
+        )
+        .bind(view.shares[share].id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn pass_deadline(pool: &PgPool, booking_id: i32) {
+        sqlx::query("UPDATE group_bookings SET payment_deadline = NOW() - INTERVAL '1 minute' WHERE id = $1")
+            .bind(booking_id)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    async fn quantity_sold(pool: &PgPool, offer_id: i32) -> i32 {
+        sqlx::query_scalar("SELECT quantity_sold FROM offers WHERE id = $1")
+            .bind(offer_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn payment_status(pool: &PgPool, payment_intent_id: &str) -> PaymentStatus {
+        let mut tx = pool.begin().await.unwrap();
+        payment_query::get_by_payment_intent_id(&mut tx, payment_intent_id).await.unwrap().status
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn deadline_completes_a_partly_paid_booking(pool: PgPool) {
+        let provider = FakePaymentProvider::new();
+        let (view, offer_id) = book_for_group(&pool).await;
+        let payment_intent_id = pay_share(&pool, &provider, &view, "member", 0).await;
+        provider.set_status(&payment_intent_id, ProviderPaymentStatus::Succeeded);
+
+        let tickets =
+            payment_service::finalize_order_on_payment_success(&pool, &provider, &payment_intent_id).await.unwrap();
+        assert_eq!(tickets.len(), 1);
+        let booking = get_group_booking(&pool, &view.booking.invite_token).await.unwrap();
+        assert_eq!(booking.booking.status, GroupBookingStatus::Open);
+        assert_eq!(booking.amount_paid, view.shares[0].amount);
+        assert_eq!(booking.amount_outstanding, view.shares[0].amount * Decimal::from(2));
+
+        pass_deadline(&pool, view.booking.id).await;
+        assert_eq!(close_due_bookings(&pool, &provider).await.unwrap(), 1);
+
+        let booking = get_group_booking(&pool, &view.booking.invite_token).await.unwrap();
+        assert_eq!(booking.booking.status, GroupBookingStatus::Closed);
+        let order = order_query::get_by_id(&pool, view.booking.order_id).await.unwrap();
+        assert!(matches!(order.status, OrderStatus::Completed));
+        assert_eq!(order.total_amount, view.shares[0].amount);
+        assert_eq!(quantity_sold(&pool, offer_id).await, 1);
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn deadline_cancels_an_unpaid_booking(pool: PgPool) {
+        let provider = FakePaymentProvider::new();
+        let (view, offer_id) = book_for_group(&pool).await;
+        let payment_intent_id = pay_share(&pool, &provider, &view, "member", 0).await;
+        assert_eq!(quantity_sold(&pool, offer_id).await, 3);
+
+        pass_deadline(&pool, view.booking.id).await;
+        assert_eq!(close_due_bookings(&pool, &provider).await.unwrap(), 1);
+
+        let booking = get_group_booking(&pool, &view.booking.invite_token).await.unwrap();
+        assert_eq!(booking.booking.status, GroupBookingStatus::Cancelled);
+        let order = order_query::get_by_id(&pool, view.booking.order_id).await.unwrap();
+        assert!(matches!(order.status, OrderStatus::Cancelled));
+        assert_eq!(quantity_sold(&pool, offer_id).await, 0);
+        assert_eq!(provider.status(&payment_intent_id), Some(ProviderPaymentStatus::Canceled));
+        assert!(matches!(payment_status(&pool, &payment_intent_id).await, PaymentStatus::Failed));
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn share_paid_after_the_deadline_is_refunded(pool: PgPool) {
+        let provider = FakePaymentProvider::new();
+        let (view, _) = book_for_group(&pool).await;
+        let paid_intent_id = pay_share(&pool, &provider, &view, "member", 0).await;
+        provider.set_status(&paid_intent_id, ProviderPaymentStatus::Succeeded);
+        payment_service::finalize_order_on_payment_success(&pool, &provider, &paid_intent_id).await.unwrap();
+
+        // The second member's payment is processing at the deadline, so it can't be cancelled.
+        let late_intent_id = pay_share(&pool, &provider, &view, "latecomer", 1).await;
+        provider.set_status(&late_intent_id, ProviderPaymentStatus::Processing);
+        pass_deadline(&pool, view.booking.id).await;
+        assert_eq!(close_due_bookings(&pool, &provider).await.unwrap(), 1);
+        provider.set_status(&late_intent_id, ProviderPaymentStatus::Succeeded);
+
+        // The first attempt refunds but doesn't commit; the retried webhook refunds again.
+        let mut tx = pool.begin().await.unwrap();
+        let booking = group_booking_query::lock_by_order(&mut tx, view.booking.order_id).await.unwrap().unwrap();
+        let tickets = complete_share_payment(&mut tx, &provider, &booking, &late_intent_id).await.unwrap();
+        assert!(tickets.is_empty());
+        tx.rollback().await.unwrap();
+        assert!(provider.is_refunded(&late_intent_id));
+
+        let tickets =
+            payment_service::finalize_order_on_payment_success(&pool, &provider, &late_intent_id).await.unwrap();
+        assert!(tickets.is_empty());
+        assert!(matches!(payment_status(&pool, &late_intent_id).await, PaymentStatus::Refunded));
+        let order = order_query::get_by_id(&pool, view.booking.order_id).await.unwrap();
+        assert!(matches!(order.status, OrderStatus::Completed));
+        assert_eq!(order.total_amount, view.shares[0].amount);
+
+        // Once recorded, a retried webhook changes nothing.
+        let tickets =
+            payment_service::finalize_order_on_payment_success(&pool, &provider, &late_intent_id).await.unwrap();
+        assert!(tickets.is_empty());
+        assert!(matches!(payment_status(&pool, &late_intent_id).await, PaymentStatus::Refunded));
+    }
+}
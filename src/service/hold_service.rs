@@ -0,0 +1,293 @@
+use crate::{
+    db::{event_query, hold_query, order_query, seating_query, ticket_query, user_query},
+    errors::AppError,
+    models::{
+        AddHoldSeatsPayload, CompIssueResult, CreateSeatHoldPayload, IssueCompsPayload, ReleaseSeatHoldPayload,
+        SeatHold, SeatHoldResult, SeatHoldSummary, SeatSelector, SeatStatus, UpdateSeatHoldPayload,
+    },
+    service::waitlist_service,
+    utils::validation,
+};
+use sqlx::{PgPool, Postgres, Transaction};
+
+/// Service for an organizer to put seats of their event on a named hold.
+/// Only available seats are held; the others are reported back as skipped.
+pub async fn create_hold(
+    pool: &PgPool,
+    event_id: i32,
+    organizer_id: i32,
+    payload: &CreateSeatHoldPayload,
+) -> Result<SeatHoldResult, AppError> {
+    // 1. Validate the payload and check the user organizes the event.
+    validation::validate_payload(payload)?;
+    authorize_organizer(pool, event_id, organizer_id).await?;
+
+    // 2. Create the hold and move the selected seats onto it, all or nothing.
+    let mut tx = pool.begin().await?;
+    let hold = hold_query::create_hold(&mut tx, event_id, &payload.name, payload.release_at, organizer_id).await?;
+    let result = hold_selected_seats(&mut tx, hold, &payload.selector).await?;
+    tx.commit().await?;
+
+    Ok(result)
+}
+
+/// Service to list the holds of an event with their seat counts.
+pub async fn list_holds(pool: &PgPool, event_id: i32, organizer_id: i32) -> Result<Vec<SeatHoldSummary>, AppError> {
+    authorize_organizer(pool, event_id, organizer_id).await?;
+    hold_query::list_holds_for_event(pool, event_id).await
+}
+
+/// Service to rename a hold or reschedule its release.
+pub async fn update_hold(
+    pool: &PgPool,
+    hold_id: i32,
+    organizer_id: i32,
+    payload: &UpdateSeatHoldPayload,
+) -> Result<SeatHold, AppError> {
+    validation::validate_payload(payload)?;
+    let hold = hold_query::get_hold(pool, hold_id).await?;
+    authorize_organizer(pool, hold.event_id, organizer_id).await?;
+    ensure_open(&hold)?;
+    hold_query::update_hold(pool, hold_id, payload).await
+}
+
+/// Service to add more seats to an open hold.
+pub async fn add_seats(
+    pool: &PgPool,
+    hold_id: i32,
+    organizer_id: i32,
+    payload: &AddHoldSeatsPayload,
+) -> Result<SeatHoldResult, AppError> {
+    let hold = hold_query::get_hold(pool, hold_id).await?;
+    authorize_organizer(pool, hold.event_id, organizer_id).await?;
+
+    let mut tx = pool.begin().await?;
+    let hold = hold_query::lock_hold(&mut tx, hold_id).await?;
+    ensure_open(&hold)?;
+    let result = hold_selected_seats(&mut tx, hold, &payload.selector).await?;
+    tx.commit().await?;
+
+    Ok(result)
+}
+
+/// Service to put held seats back on general sale. Without seat IDs, every seat still
+/// held is released and the hold is closed.
+pub async fn release_hold(
+    pool: &PgPool,
+    hold_id: i32,
+    organizer_id: i32,
+    payload: &ReleaseSeatHoldPayload,
+) -> Result<SeatHold, AppError> {
+    let hold = hold_query::get_hold(pool, hold_id).await?;
+    authorize_organizer(pool, hold.event_id, organizer_id).await?;
+    release(pool, hold_id, payload.seat_ids.as_deref()).await
+}
+
+/// Service to issue held seats to a user as complimentary tickets, without payment.
+/// The seats are sold to a zero-value order in the user's name.
+pub async fn issue_comps(
+    pool: &PgPool,
+    hold_id: i32,
+    organizer_id: i32,
+    payload: &IssueCompsPayload,
+) -> Result<CompIssueResult, AppError> {
+    // 1. Validate the payload, the organizer and the recipient.
+    validation::validate_payload(payload)?;
+    let hold = hold_query::get_hold(pool, hold_id).await?;
+    authorize_organizer(pool, hold.event_id, organizer_id).await?;
+    user_query::get_by_id(pool, payload.user_id).await?;
+
+    let mut seat_ids = payload.seat_ids.clone();
+    seat_ids.sort_unstable();
+    seat_ids.dedup();
+
+    // 2. In one transaction: create the order, sell it the seats and issue the tickets.
+    let mut tx = pool.begin().await?;
+    let hold = hold_query::lock_hold(&mut tx, hold_id).await?;
+    ensure_open(&hold)?;
+
+    let order_id = order_query::create_comp_order(&mut tx, payload.user_id).await?;
+    let seats = hold_query::sell_held_seats(&mut tx, hold_id, &seat_ids, order_id).await?;
+    if seats.len() != seat_ids.len() {
+        let missing: Vec<String> = seat_ids
+            .iter()
+            .filter(|id| !seats.iter().any(|(seat_id, _)| seat_id == *id))
+            .map(|id| id.to_string())
+            .collect();
+        return Err(AppError::BadRequest(format!("These seats are not on this hold: {}.", missing.join(", "))));
+    }
+    let tickets =
+        ticket_query::create_comp_tickets(&mut tx, order_id, payload.user_id, hold.event_id, hold_id, &seats).await?;
+    tx.commit().await?;
+
+    tracing::info!("Issued {} comp tickets from hold {} to user {}.", tickets.len(), hold_id, payload.user_id);
+    Ok(CompIssueResult { order_id, tickets })
+}
+
+// --- Background Job Service ---
+
+/// Service function for a background worker to release holds whose scheduled time has come.
+pub async fn release_due_holds(pool: &PgPool) -> Result<usize, AppError> {
+    let mut released = 0;
+    for hold_id in hold_query::list_due_holds(pool).await? {
+        // One hold failing (e.g. released by hand meanwhile) doesn't hold up the others.
+        match release(pool, hold_id, None).await {
+            Ok(_) => released += 1,
+            Err(e) => tracing::error!("Failed to release seat hold {}: {:?}", hold_id, e),
+        }
+    }
+    if released > 0 {
+        tracing::info!("Released {} scheduled seat holds.", released);
+    }
+    Ok(released)
+}
+
+// --- Helpers ---
+
+/// Releases the given seats of a hold, or all of them and closes it.
+async fn release(pool: &PgPool, hold_id: i32, seat_ids: Option<&[i32]>) -> Result<SeatHold, AppError> {
+    let mut tx = pool.begin().await?;
+    let hold = hold_query::lock_hold(&mut tx, hold_id).await?;
+    ensure_open(&hold)?;
+
+    let released = hold_query::release_hold_seats(&mut tx, hold_id, seat_ids).await?;
+    if seat_ids.is_none() {
+        hold_query::mark_hold_released(&mut tx, hold_id).await?;
+    }
+    // Seats of a sold-out event go to its waitlist first.
+    if !released.is_empty() {
+        waitlist_service::offer_returned_inventory(&mut tx, &[hold.event_id]).await?;
+    }
+    tx.commit().await?;
+
+    hold_query::get_hold(pool, hold_id).await
+}
+
+/// Moves the available seats a selector matches onto a hold.
+async fn hold_selected_seats(
+    tx: &mut Transaction<'_, Postgres>,
+    hold: SeatHold,
+    selector: &SeatSelector,
+) -> Result<SeatHoldResult, AppError> {
+    let selected = seating_query::list_selected_event_seats(tx, hold.event_id, selector).await?;
+    if selected.is_empty() {
+        return Err(AppError::BadRequest("No seats of this event match the selection.".to_string()));
+    }
+    let available: Vec<i32> = selected
+        .iter()
+        .filter(|(_, status)| *status == SeatStatus::Available)
+        .map(|&(seat_id, _)| seat_id)
+        .collect();
+
+    let held_seat_ids: Vec<i32> =
+        hold_query::hold_seats(tx, hold.event_id, hold.id, &available).await?.into_iter().map(|c| c.seat_id).collect();
+    let skipped_seat_ids =
+        selected.into_iter().map(|(seat_id, _)| seat_id).filter(|id| !held_seat_ids.contains(id)).collect();
+
+    Ok(SeatHoldResult { hold, held_seat_ids, skipped_seat_ids })
+}
+
+async fn authorize_organizer(pool: &PgPool, event_id: i32, organizer_id: i32) -> Result<(), AppError> {
+    let event = event_query::get_by_id(pool, event_id).await?;
+    if event.organizer_id != organizer_id {
+        return Err(AppError::Forbidden("You are not authorized to manage holds for this event.".to_string()));
+    }
+    Ok(())
+}
+
+fn ensure_open(hold: &SeatHold) -> Result<(), AppError> {
+    if hold.released_at.is_some() {
+        return Err(AppError::BadRequest("This hold has already been released.".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::payment_provider::FakePaymentProvider;
+    use crate::models::{CreateOrderPayload, OrderItemPayload, OrderStatus};
+    use crate::service::order_service;
+    use crate::utils::test_fixtures;
+    use chrono::{Duration, Utc};
+    use rust_decimal::Decimal;
+
+    async fn seat_statuses(pool: &PgPool, seat_ids: &[i32]) -> Vec<SeatStatus> {
+        sqlx::query_scalar("SELECT status FROM event_seats WHERE seat_id = ANY($1) ORDER BY seat_id")
+            .bind(seat_ids)
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn held_seats_are_comped_and_released_on_schedule(pool: PgPool) {
+        let event_id = test_fixtures::create_event(&pool).await;
+        let organizer_id: i32 = sqlx::query_scalar("SELECT organizer_id FROM events WHERE id = $1")
+            .bind(event_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let tier_id = test_fixtures::create_tier(&pool, event_id, 10).await;
+        let offer_id = test_fixtures::create_offer(&pool, tier_id, Decimal::from(40), 10).await;
+        let seat_ids = test_fixtures::create_seats(&pool, event_id, tier_id, 5).await;
+        sqlx::query("UPDATE event_seats SET status = 'sold' WHERE seat_id = $1")
+            .bind(seat_ids[4])
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // 1. Hold the section; the sold seat is skipped.
+        let payload = CreateSeatHoldPayload {
+            name: "Press".to_string(),
+            selector: SeatSelector::Section { section: "101".to_string() },
+            release_at: Some(Utc::now() + Duration::days(1)),
+        };
+        let result = create_hold(&pool, event_id, organizer_id, &payload).await.unwrap();
+        assert_eq!(result.held_seat_ids, seat_ids[..4]);
+        assert_eq!(result.skipped_seat_ids, vec![seat_ids[4]]);
+        let hold_id = result.hold.id;
+
+        // Held seats aren't on general sale.
+        let buyer_id = test_fixtures::create_user(&pool, "buyer").await;
+        let checkout = CreateOrderPayload {
+            items: vec![OrderItemPayload { offer_id, seat_id: Some(seat_ids[3]), quantity: 1 }],
+            addons: Vec::new(),
+            apply_credit: None,
+        };
+        let provider = FakePaymentProvider::new();
+        assert!(order_service::create_order(&pool, &provider, buyer_id, &checkout, None).await.is_err());
+
+        // 2. Comp two held seats. Seats that aren't on the hold can't be comped.
+        let guest_id = test_fixtures::create_user(&pool, "guest").await;
+        let comps = IssueCompsPayload { user_id: guest_id, seat_ids: vec![seat_ids[1], seat_ids[0]] };
+        let issued = issue_comps(&pool, hold_id, organizer_id, &comps).await.unwrap();
+        assert_eq!(issued.tickets.len(), 2);
+        let order = order_query::get_by_id(&pool, issued.order_id).await.unwrap();
+        assert!(matches!(order.status, OrderStatus::Completed));
+        assert_eq!(order.total_amount, Decimal::ZERO);
+        let stray = IssueCompsPayload { user_id: guest_id, seat_ids: vec![seat_ids[4]] };
+        assert!(matches!(issue_comps(&pool, hold_id, organizer_id, &stray).await, Err(AppError::BadRequest(_))));
+
+        // 3. Release one seat by hand, and the rest when the hold is due.
+        let one = ReleaseSeatHoldPayload { seat_ids: Some(vec![seat_ids[2]]) };
+        assert!(release_hold(&pool, hold_id, organizer_id, &one).await.unwrap().released_at.is_none());
+        assert_eq!(seat_statuses(&pool, &seat_ids[2..4]).await, vec![SeatStatus::Available, SeatStatus::Held]);
+
+        assert_eq!(release_due_holds(&pool).await.unwrap(), 0);
+        let due = UpdateSeatHoldPayload { name: None, release_at: Some(Utc::now() - Duration::minutes(1)) };
+        update_hold(&pool, hold_id, organizer_id, &due).await.unwrap();
+        assert_eq!(release_due_holds(&pool).await.unwrap(), 1);
+        assert_eq!(
+            seat_statuses(&pool, &seat_ids).await,
+            vec![SeatStatus::Sold, SeatStatus::Sold, SeatStatus::Available, SeatStatus::Available, SeatStatus::Sold]
+        );
+
+        let summary = list_holds(&pool, event_id, organizer_id).await.unwrap();
+        assert_eq!((summary[0].held_seats, summary[0].comped_seats), (0, 2));
+        assert!(summary[0].released_at.is_some());
+        let more = AddHoldSeatsPayload { selector: SeatSelector::Seats { seat_ids: vec![seat_ids[2]] } };
+        assert!(matches!(add_seats(&pool, hold_id, organizer_id, &more).await, Err(AppError::BadRequest(_))));
+        assert!(matches!(list_holds(&pool, event_id, guest_id).await, Err(AppError::Forbidden(_))));
+    }
+}
@@ -0,0 +1,466 @@
+use std::collections::HashSet;
+use std::f64::consts::PI;
+
+use crate::{
+    errors::AppError,
+    models::{
+        LayoutRow, LayoutSeat, LayoutSection, RowLabelStyle, RowNaming, SeatNaming, SeatNumberingDirection,
+        SeatingLayout, SectionShape, SectionSpec,
+    },
+};
+use rust_decimal::{prelude::FromPrimitive, Decimal};
+
+/// The most rows, or seats in a row, a single section spec may produce.
+const MAX_PER_DIMENSION: u32 = 1_000;
+/// The longest letter row label, so letters run from A to ZZZ.
+const MAX_ROW_LABEL_LETTERS: usize = 3;
+/// The highest first seat number, and the widest gap between seat numbers.
+const MAX_FIRST_SEAT_NUMBER: u32 = 100_000;
+const MAX_SEAT_NUMBER_STEP: u32 = 10;
+
+/// Expands section specs into a full layout with computed seat coordinates.
+/// Fails if a spec is malformed or the layout would hold more than `max_seats` seats.
+/// The result still has to go through the usual layout validation (overlaps, bounds).
+pub fn generate_layout(specs: &[SectionSpec], max_seats: usize) -> Result<SeatingLayout, AppError> {
+    let mut total_seats = 0;
+    let mut sections = Vec::with_capacity(specs.len());
+
+    for spec in specs {
+        // 1. Work out where every seat goes, row by row, ordered left to right.
+        check_seat_naming(spec)?;
+        let rows = match &spec.shape {
+            SectionShape::Grid { .. } => grid_rows(spec)?,
+            SectionShape::Arc { .. } => arc_rows(spec)?,
+            SectionShape::StadiumSector { .. } => stadium_rows(spec)?,
+        };
+
+        total_seats += rows.iter().map(Vec::len).sum::<usize>();
+        if total_seats > max_seats {
+            return Err(AppError::BadRequest(format!("A layout may have at most {} seats.", max_seats)));
+        }
+
+        // 2. Name the rows and number their seats.
+        let row_labels = row_labels(&spec.row_naming, rows.len())
+            .map_err(|e| AppError::BadRequest(format!("Section '{}': {}", spec.name, e)))?;
+        let rows = rows
+            .into_iter()
+            .zip(row_labels)
+            .map(|(centers, name)| {
+                let numbers = seat_numbers(&spec.seat_naming, centers.len());
+                let seats = centers
+                    .into_iter()
+                    .zip(numbers)
+                    .map(|((x, y), seat_number)| LayoutSeat {
+                        seat_number,
+                        pos_x: to_decimal(x - spec.seat_width / 2.0),
+                        pos_y: to_decimal(y - spec.seat_height / 2.0),
+                        width: Some(to_decimal(spec.seat_width)),
+                        height: Some(to_decimal(spec.seat_height)),
+                        attributes: Vec::new(),
+                    })
+                    .collect();
+                LayoutRow { name, seats }
+            })
+            .collect();
+
+        sections.push(LayoutSection { name: spec.name.clone(), rows });
+    }
+
+    Ok(SeatingLayout { sections })
+}
+
+// --- Shapes ---
+// Each returns the seat centers of every row, front row first, each row ordered
+// left to right (increasing x for grids, increasing angle for arcs).
+
+fn grid_rows(spec: &SectionSpec) -> Result<Vec<Vec<(f64, f64)>>, AppError> {
+    let SectionShape::Grid {
+        origin_x,
+        origin_y,
+        rows,
+        seats_per_row,
+        seat_spacing,
+        row_spacing,
+        aisles_after_seats,
+        aisles_after_rows,
+        aisle_width,
+    } = &spec.shape
+    else {
+        unreachable!()
+    };
+
+    check_seat_size(spec)?;
+    check_count(spec, "rows", *rows)?;
+    check_count(spec, "seats_per_row", *seats_per_row)?;
+    check_positive(spec, "seat_spacing", *seat_spacing)?;
+    check_positive(spec, "row_spacing", *row_spacing)?;
+    check_non_negative(spec, "aisle_width", *aisle_width)?;
+    check_finite(spec, &[*origin_x, *origin_y])?;
+
+    // The number of aisles before the seat (or row) at a 0-based index.
+    let aisles_before = |aisles: &[u32], index: u32| aisles.iter().filter(|&&after| after <= index).count() as f64;
+
+    Ok((0..*rows)
+        .map(|r| {
+            let y = origin_y + spec.seat_height / 2.0
+                + r as f64 * row_spacing
+                + aisles_before(aisles_after_rows, r) * aisle_width;
+            (0..*seats_per_row)
+                .map(|s| {
+                    let x = origin_x + spec.seat_width / 2.0
+                        + s as f64 * seat_spacing
+                        + aisles_before(aisles_after_seats, s) * aisle_width;
+                    (x, y)
+                })
+                .collect()
+        })
+        .collect())
+}
+
+fn arc_rows(spec: &SectionSpec) -> Result<Vec<Vec<(f64, f64)>>, AppError> {
+    let SectionShape::Arc {
+        center_x,
+        center_y,
+        radius,
+        rows,
+        row_spacing,
+        start_angle,
+        end_angle,
+        seats_per_row,
+        seats_added_per_row,
+    } = &spec.shape
+    else {
+        unreachable!()
+    };
+
+    check_seat_size(spec)?;
+    check_count(spec, "rows", *rows)?;
+    check_count(spec, "seats_per_row", *seats_per_row)?;
+    check_count(
+        spec,
+        "seats in the last row",
+        seats_per_row.saturating_add(seats_added_per_row.saturating_mul(rows - 1)),
+    )?;
+    check_positive(spec, "radius", *radius)?;
+    check_positive(spec, "row_spacing", *row_spacing)?;
+    check_finite(spec, &[*center_x, *center_y])?;
+    let (start, end) = check_angles(spec, *start_angle, *end_angle)?;
+
+    Ok((0..*rows)
+        .map(|r| {
+            let radius = radius + r as f64 * row_spacing;
+            let seats = seats_per_row + r * seats_added_per_row;
+            (0..seats)
+                .map(|s| {
+                    // A single seat sits in the middle of the arc.
+                    let angle = if seats == 1 {
+                        (start + end) / 2.0
+                    } else {
+                        start + (end - start) * s as f64 / (seats - 1) as f64
+                    };
+                    point_on_circle(*center_x, *center_y, radius, angle)
+                })
+                .collect()
+        })
+        .collect())
+}
+
+fn stadium_rows(spec: &SectionSpec) -> Result<Vec<Vec<(f64, f64)>>, AppError> {
+    let SectionShape::StadiumSector {
+        center_x,
+        center_y,
+        inner_radius,
+        rows,
+        row_spacing,
+        start_angle,
+        end_angle,
+        seat_pitch,
+        aisles,
+        aisle_width,
+    } = &spec.shape
+    else {
+        unreachable!()
+    };
+
+    check_seat_size(spec)?;
+    check_count(spec, "rows", *rows)?;
+    check_positive(spec, "inner_radius", *inner_radius)?;
+    check_positive(spec, "row_spacing", *row_spacing)?;
+    check_positive(spec, "seat_pitch", *seat_pitch)?;
+    check_non_negative(spec, "aisle_width", *aisle_width)?;
+    check_finite(spec, &[*center_x, *center_y])?;
+    if *aisles > MAX_PER_DIMENSION {
+        return Err(spec_error(spec, format!("aisles must be at most {}.", MAX_PER_DIMENSION)));
+    }
+    let (start, end) = check_angles(spec, *start_angle, *end_angle)?;
+    let blocks = (aisles + 1) as f64;
+
+    let mut result = Vec::with_capacity(*rows as usize);
+    for r in 0..*rows {
+        let radius = inner_radius + r as f64 * row_spacing;
+
+        // Aisles are a fixed width, so they take a smaller angle on the outer rows.
+        let aisle_angle = aisle_width / radius;
+        let block_angle = ((end - start) - *aisles as f64 * aisle_angle) / blocks;
+        if block_angle <= 0.0 {
+            return Err(spec_error(spec, "the aisles leave no room for seats.".to_string()));
+        }
+
+        // Fill each block with as many seats as fit, centered within the block.
+        let pitch_angle = seat_pitch / radius;
+        let per_block = (block_angle / pitch_angle).floor().min(MAX_PER_DIMENSION as f64 + 1.0) as u32;
+        check_count(spec, "seats per block", per_block)?;
+        let margin = (block_angle - (per_block - 1) as f64 * pitch_angle) / 2.0;
+
+        let mut row = Vec::new();
+        for b in 0..=*aisles {
+            let block_start = start + b as f64 * (block_angle + aisle_angle);
+            for s in 0..per_block {
+                let angle = block_start + margin + s as f64 * pitch_angle;
+                row.push(point_on_circle(*center_x, *center_y, radius, angle));
+            }
+        }
+        if row.len() > MAX_PER_DIMENSION as usize {
+            return Err(spec_error(spec, format!("a row may have at most {} seats.", MAX_PER_DIMENSION)));
+        }
+        result.push(row);
+    }
+
+    Ok(result)
+}
+
+// --- Naming ---
+
+/// Labels `count` rows, front to back.
+fn row_labels(naming: &RowNaming, count: usize) -> Result<Vec<String>, String> {
+    let skip: HashSet<&str> = naming.skip.iter().map(String::as_str).collect();
+    let label = |i: usize| match naming.style {
+        // A..Z, then AA..ZZ, then AAA..ZZZ and so on.
+        RowLabelStyle::Letters => ((b'A' + (i % 26) as u8) as char).to_string().repeat(i / 26 + 1),
+        RowLabelStyle::Numbers => (i + 1).to_string(),
+    };
+
+    let last = match naming.style {
+        RowLabelStyle::Letters => 26 * MAX_ROW_LABEL_LETTERS,
+        RowLabelStyle::Numbers => usize::MAX,
+    };
+
+    // Find where the sequence starts.
+    let first = match (&naming.start, naming.style) {
+        (None, _) => 0,
+        (Some(start), RowLabelStyle::Letters) => (0..last)
+            .find(|&i| label(i) == *start)
+            .ok_or_else(|| format!("'{}' is not a valid first row label.", start))?,
+        (Some(start), RowLabelStyle::Numbers) => match start.parse::<usize>() {
+            Ok(n) if (1..=1_000_000).contains(&n) => n - 1,
+            _ => return Err(format!("'{}' is not a valid first row number.", start)),
+        },
+    };
+
+    let labels: Vec<String> = (first..last)
+        .map(label)
+        .filter(|l| !skip.contains(l.as_str()))
+        .take(count)
+        .collect();
+    if labels.len() < count {
+        return Err(format!(
+            "letter row labels run out after {}; use numbers for more rows.",
+            "Z".repeat(MAX_ROW_LABEL_LETTERS)
+        ));
+    }
+    Ok(labels)
+}
+
+/// Numbers the `count` seats of a row, in left-to-right order.
+fn seat_numbers(naming: &SeatNaming, count: usize) -> Vec<String> {
+    let skip: HashSet<u32> = naming.skip.iter().copied().collect();
+    let step = naming.step as usize;
+    let mut numbers: Vec<String> = (naming.start..)
+        .step_by(step)
+        .filter(|n| !skip.contains(n))
+        .take(count)
+        .map(|n| n.to_string())
+        .collect();
+    if naming.direction == SeatNumberingDirection::RightToLeft {
+        numbers.reverse();
+    }
+    numbers
+}
+
+// --- Helpers ---
+
+fn point_on_circle(center_x: f64, center_y: f64, radius: f64, angle: f64) -> (f64, f64) {
+    (center_x + radius * angle.cos(), center_y + radius * angle.sin())
+}
+
+fn to_decimal(value: f64) -> Decimal {
+    Decimal::from_f64(value).unwrap_or_default().round_dp(2)
+}
+
+fn spec_error(spec: &SectionSpec, message: String) -> AppError {
+    AppError::BadRequest(format!("Section '{}': {}", spec.name, message))
+}
+
+fn check_seat_size(spec: &SectionSpec) -> Result<(), AppError> {
+    check_positive(spec, "seat_width", spec.seat_width)?;
+    check_positive(spec, "seat_height", spec.seat_height)
+}
+
+fn check_seat_naming(spec: &SectionSpec) -> Result<(), AppError> {
+    let naming = &spec.seat_naming;
+    if naming.start > MAX_FIRST_SEAT_NUMBER {
+        return Err(spec_error(spec, format!("the first seat number must be at most {}.", MAX_FIRST_SEAT_NUMBER)));
+    }
+    if naming.step == 0 || naming.step > MAX_SEAT_NUMBER_STEP {
+        return Err(spec_error(spec, format!("the seat number step must be between 1 and {}.", MAX_SEAT_NUMBER_STEP)));
+    }
+    Ok(())
+}
+
+fn check_count(spec: &SectionSpec, field: &str, value: u32) -> Result<(), AppError> {
+    if value == 0 || value > MAX_PER_DIMENSION {
+        return Err(spec_error(spec, format!("{} must be between 1 and {}.", field, MAX_PER_DIMENSION)));
+    }
+    Ok(())
+}
+
+fn check_positive(spec: &SectionSpec, field: &str, value: f64) -> Result<(), AppError> {
+    if !value.is_finite() || value <= 0.0 {
+        return Err(spec_error(spec, format!("{} must be greater than zero.", field)));
+    }
+    Ok(())
+}
+
+fn check_non_negative(spec: &SectionSpec, field: &str, value: f64) -> Result<(), AppError> {
+    if !value.is_finite() || value < 0.0 {
+        return Err(spec_error(spec, format!("{} must not be negative.", field)));
+    }
+    Ok(())
+}
+
+fn check_finite(spec: &SectionSpec, values: &[f64]) -> Result<(), AppError> {
+    if values.iter().any(|v| !v.is_finite()) {
+        return Err(spec_error(spec, "coordinates must be numbers.".to_string()));
+    }
+    Ok(())
+}
+
+/// Checks an angle range in degrees and returns it in radians.
+fn check_angles(spec: &SectionSpec, start: f64, end: f64) -> Result<(f64, f64), AppError> {
+    if !start.is_finite() || !end.is_finite() || end <= start || end - start > 360.0 {
+        return Err(spec_error(
+            spec,
+            "end_angle must be greater than start_angle, at most 360 degrees apart.".to_string(),
+        ));
+    }
+    Ok((start * PI / 180.0, end * PI / 180.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn spec(value: serde_json::Value) -> SectionSpec {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn row_names(layout: &SeatingLayout) -> Vec<&str> {
+        layout.sections[0].rows.iter().map(|row| row.name.as_str()).collect()
+    }
+
+    fn seat_numbers_of(row: &LayoutRow) -> Vec<&str> {
+        row.seats.iter().map(|seat| seat.seat_number.as_str()).collect()
+    }
+
+    #[test]
+    fn grid_leaves_room_for_aisles() {
+        let grid = spec(json!({
+            "name": "Floor", "shape": "grid", "origin_x": 100.0, "origin_y": 50.0,
+            "rows": 3, "seats_per_row": 4, "seat_spacing": 24.0, "row_spacing": 30.0,
+            "aisles_after_seats": [2], "aisles_after_rows": [1], "aisle_width": 40.0
+        }));
+        let layout = generate_layout(&[grid], 100).unwrap();
+
+        assert_eq!(row_names(&layout), vec!["A", "B", "C"]);
+        let xs: Vec<Decimal> = layout.sections[0].rows[0].seats.iter().map(|seat| seat.pos_x).collect();
+        assert_eq!(xs, [100, 124, 188, 212].map(Decimal::from));
+        let ys: Vec<Decimal> = layout.sections[0].rows.iter().map(|row| row.seats[0].pos_y).collect();
+        assert_eq!(ys, [50, 120, 150].map(Decimal::from));
+        assert_eq!(seat_numbers_of(&layout.sections[0].rows[0]), vec!["1", "2", "3", "4"]);
+    }
+
+    #[test]
+    fn arc_rows_grow_and_stay_on_their_circle() {
+        let arc = spec(json!({
+            "name": "Balcony", "shape": "arc", "center_x": 500.0, "center_y": 0.0, "radius": 200.0,
+            "rows": 3, "row_spacing": 30.0, "start_angle": 45.0, "end_angle": 135.0,
+            "seats_per_row": 1, "seats_added_per_row": 4
+        }));
+        let layout = generate_layout(&[arc], 100).unwrap();
+
+        let rows = &layout.sections[0].rows;
+        assert_eq!(rows.iter().map(|row| row.seats.len()).collect::<Vec<_>>(), vec![1, 5, 9]);
+        // A single seat sits in the middle of the arc, straight below the center.
+        assert_eq!((rows[0].seats[0].pos_x, rows[0].seats[0].pos_y), (Decimal::from(490), Decimal::from(190)));
+        for (r, row) in rows.iter().enumerate() {
+            for seat in &row.seats {
+                let dx: f64 = (seat.pos_x + Decimal::from(10)).try_into().unwrap();
+                let dy: f64 = (seat.pos_y + Decimal::from(10)).try_into().unwrap();
+                let distance = ((dx - 500.0).powi(2) + dy.powi(2)).sqrt();
+                assert!((distance - (200.0 + 30.0 * r as f64)).abs() < 0.05, "row {} at {}", r, distance);
+            }
+        }
+    }
+
+    #[test]
+    fn stadium_sector_splits_rows_into_blocks() {
+        let sector = |aisle_width: f64| {
+            spec(json!({
+                "name": "114", "shape": "stadium_sector", "center_x": 0.0, "center_y": 0.0,
+                "inner_radius": 300.0, "rows": 2, "row_spacing": 40.0, "start_angle": 0.0, "end_angle": 30.0,
+                "seat_pitch": 24.0, "aisles": 1, "aisle_width": aisle_width
+            }))
+        };
+        let layout = generate_layout(&[sector(30.0)], 1_000).unwrap();
+
+        // Outer rows are longer, so they hold more seats; each row has two equal blocks.
+        let rows = &layout.sections[0].rows;
+        assert!(rows[1].seats.len() > rows[0].seats.len());
+        assert!(rows.iter().all(|row| row.seats.len() % 2 == 0));
+
+        let error = generate_layout(&[sector(200.0)], 1_000).unwrap_err();
+        assert!(matches!(error, AppError::BadRequest(message) if message.contains("no room for seats")));
+    }
+
+    #[test]
+    fn rows_and_seats_are_named_as_configured() {
+        let skip = vec!["I".to_string()];
+        let naming = RowNaming { style: RowLabelStyle::Letters, start: Some("H".to_string()), skip };
+        assert_eq!(row_labels(&naming, 3).unwrap(), vec!["H", "J", "K"]);
+
+        let naming = RowNaming { style: RowLabelStyle::Letters, start: Some("Y".to_string()), skip: Vec::new() };
+        assert_eq!(row_labels(&naming, 4).unwrap(), vec!["Y", "Z", "AA", "BB"]);
+        assert!(row_labels(&naming, 26 * MAX_ROW_LABEL_LETTERS).is_err());
+
+        let naming = RowNaming { style: RowLabelStyle::Numbers, start: Some("10".to_string()), skip: Vec::new() };
+        assert_eq!(row_labels(&naming, 2).unwrap(), vec!["10", "11"]);
+
+        let naming = SeatNaming { start: 9, step: 2, direction: SeatNumberingDirection::RightToLeft, skip: vec![13] };
+        assert_eq!(seat_numbers(&naming, 4), vec!["17", "15", "11", "9"]);
+    }
+
+    #[test]
+    fn oversized_and_malformed_specs_are_rejected() {
+        let grid = |rows: u32, seat_spacing: f64| {
+            spec(json!({
+                "name": "Floor", "shape": "grid", "origin_x": 0.0, "origin_y": 0.0,
+                "rows": rows, "seats_per_row": 10, "seat_spacing": seat_spacing, "row_spacing": 30.0
+            }))
+        };
+        assert!(generate_layout(&[grid(10, 24.0)], 100).is_ok());
+        assert!(generate_layout(&[grid(10, 24.0), grid(1, 24.0)], 100).is_err());
+        assert!(generate_layout(&[grid(0, 24.0)], 100).is_err());
+        assert!(generate_layout(&[grid(1, -24.0)], 100).is_err());
+    }
+}
@@ -0,0 +1,415 @@
+use crate::{
+    db::{event_query, lottery_query, pricing_query, waitlist_query},
+    errors::AppError,
+    models::{
+        CreateLotteryPayload, CreateOrderPayload, Lottery, LotteryEntry, LotteryEntryStatus, LotteryInfo,
+        LotteryResults, OrderItemPayload, RegisterForLotteryPayload,
+    },
+    service::waitlist_service,
+    utils::{random, validation},
+};
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+
+/// Length of the secret seed a lottery is drawn with.
+const SEED_LENGTH: usize = 64;
+
+/// Service for an organizer to sell one of their event's general admission offers by lottery.
+/// The offer is hidden from the public sale, and the draw's seed is fixed now: only its hash
+/// is published until the draw, so the organizer can't pick a seed that suits them.
+pub async fn create_lottery(
+    pool: &PgPool,
+    event_id: i32,
+    organizer_id: i32,
+    payload: &CreateLotteryPayload,
+) -> Result<Lottery, AppError> {
+    // 1. Validate the payload and check the user organizes the event.
+    validation::validate_payload(payload)?;
+    authorize_organizer(pool, event_id, organizer_id).await?;
+    if payload.registration_closes_at <= payload.registration_opens_at {
+        return Err(AppError::BadRequest("Registration must close after it opens.".to_string()));
+    }
+
+    // 2. Only general admission offers of this event can be sold by lottery.
+    let mut tx = pool.begin().await?;
+    let offer = waitlist_query::get_reservation_offer(&mut tx, payload.offer_id).await?;
+    let tier = pricing_query::get_ticket_tier(&mut *tx, offer.ticket_tier_id).await?;
+    if tier.event_id != event_id {
+        return Err(AppError::BadRequest("This offer belongs to another event.".to_string()));
+    }
+    if offer.is_reserved {
+        return Err(AppError::BadRequest(
+            "Only general admission offers can be sold by lottery, not reserved seating.".to_string(),
+        ));
+    }
+    if pricing_query::get_offer(&mut *tx, payload.offer_id).await?.is_bundle {
+        return Err(AppError::BadRequest("Bundle offers can't be sold by lottery.".to_string()));
+    }
+
+    // 3. Commit to a secret seed and create the lottery.
+    let seed = random::generate_random_token(SEED_LENGTH);
+    let lottery = lottery_query::create_lottery(&mut tx, event_id, organizer_id, payload, &seed, &sha256_hex(&seed)).await?;
+    tx.commit().await?;
+
+    tracing::info!("Created lottery {} for offer {} of event {}.", lottery.id, lottery.offer_id, event_id);
+    Ok(lottery)
+}
+
+/// Service to list the lotteries of an event.
+pub async fn list_lotteries(pool: &PgPool, event_id: i32) -> Result<Vec<LotteryInfo>, AppError> {
+    let mut lotteries = Vec::new();
+    for lottery in lottery_query::list_lotteries_for_event(pool, event_id).await? {
+        lotteries.push(lottery_info(pool, lottery).await?);
+    }
+    Ok(lotteries)
+}
+
+/// Service to fetch the public view of a lottery.
+pub async fn get_lottery(pool: &PgPool, lottery_id: i32) -> Result<LotteryInfo, AppError> {
+    let lottery = lottery_query::get_lottery(pool, lottery_id).await?;
+    lottery_info(pool, lottery).await
+}
+
+/// Service for a user to register for a lottery, or to change how many tickets they ask for,
+/// while registration is open.
+pub async fn register(
+    pool: &PgPool,
+    lottery_id: i32,
+    user_id: i32,
+    payload: &RegisterForLotteryPayload,
+) -> Result<LotteryEntry, AppError> {
+    validation::validate_payload(payload)?;
+    let lottery = lottery_query::get_lottery(pool, lottery_id).await?;
+    ensure_registration_open(&lottery)?;
+    if payload.quantity > lottery.max_per_user {
+        return Err(AppError::BadRequest(format!(
+            "This lottery allows at most {} tickets per person.",
+            lottery.max_per_user
+        )));
+    }
+
+    lottery_query::upsert_entry(pool, lottery_id, user_id, payload.quantity).await
+}
+
+/// Service to fetch a user's entry in a lottery.
+pub async fn get_my_entry(pool: &PgPool, lottery_id: i32, user_id: i32) -> Result<LotteryEntry, AppError> {
+    lottery_query::get_entry(pool, lottery_id, user_id).await
+}
+
+/// Service for a user to withdraw from a lottery while registration is open.
+pub async fn withdraw(pool: &PgPool, lottery_id: i32, user_id: i32) -> Result<(), AppError> {
+    let lottery = lottery_query::get_lottery(pool, lottery_id).await?;
+    ensure_registration_open(&lottery)?;
+    if lottery_query::delete_entry(pool, lottery_id, user_id).await? == 0 {
+        return Err(AppError::Sqlx(sqlx::Error::RowNotFound));
+    }
+    Ok(())
+}
+
+/// Service for an organizer to run the draw once registration has closed.
+///
+/// The draw is deterministic, so anyone can check it from the published results:
+/// - the input hash is the SHA-256 of the registrations, one `entry_id:quantity` line each
+///   in order of entry ID;
+/// - each registration is ranked by the SHA-256 of `seed:entry_id`, lowest first;
+/// - in that order, a registration wins if all the tickets it asked for are still left.
+///
+/// Winners' tickets are reserved for their purchase window; losers join the event's waitlist.
+pub async fn draw(pool: &PgPool, lottery_id: i32, organizer_id: i32) -> Result<LotteryResults, AppError> {
+    // 1. Lock the lottery and check it is ready to be drawn.
+    let mut tx = pool.begin().await?;
+    let lottery = lottery_query::lock_lottery(&mut tx, lottery_id).await?;
+    authorize_organizer(pool, lottery.event_id, organizer_id).await?;
+    if lottery.drawn_at.is_some() {
+        return Err(AppError::BadRequest("This lottery has already been drawn.".to_string()));
+    }
+    if lottery.registration_closes_at > Utc::now() {
+        return Err(AppError::BadRequest("Registration for this lottery is still open.".to_string()));
+    }
+
+    // 2. Rank the registrations and hand out what the offer has left.
+    let entries = lottery_query::lock_registered_entries(&mut tx, lottery_id).await?;
+    let offer = waitlist_query::get_reservation_offer(&mut tx, lottery.offer_id).await?;
+    let capacity = lottery_query::lock_offer_capacity(&mut tx, offer.offer_id).await?;
+    let input_hash = input_hash(&entries);
+    let ranked = rank_entries(&lottery.seed, &entries);
+    let allocations = allocate(&ranked, capacity);
+
+    // 3. Reserve the winners' tickets and record the outcome.
+    let won: i32 = allocations.iter().sum();
+    if won > 0 && !waitlist_query::reserve_general_admission(&mut tx, offer.offer_id, offer.ticket_tier_id, won).await? {
+        return Err(AppError::InternalServerError("The lottery's tickets could not be reserved.".to_string()));
+    }
+    let entry_ids: Vec<i32> = ranked.iter().map(|entry| entry.id).collect();
+    let ranks: Vec<i32> = (1..=ranked.len() as i32).collect();
+    lottery_query::record_draw(&mut tx, &entry_ids, &ranks, &allocations, lottery.purchase_window_hours).await?;
+
+    // 4. Losers join the waitlist in draw order, for the tickets winners don't buy.
+    let (user_ids, quantities): (Vec<i32>, Vec<i32>) = ranked
+        .iter()
+        .zip(&allocations)
+        .filter(|(_, allocated)| **allocated == 0)
+        .map(|(entry, _)| (entry.user_id, entry.quantity))
+        .unzip();
+    if !user_ids.is_empty() {
+        waitlist_query::add_entries(&mut tx, lottery.event_id, Some(offer.ticket_tier_id), &user_ids, &quantities)
+            .await?;
+    }
+
+    // 5. Publish the input hash and reveal the seed.
+    let lottery = lottery_query::finish_draw(&mut tx, lottery_id, &input_hash, capacity).await?;
+    let results = lottery_results(&mut *tx, lottery).await?;
+    tx.commit().await?;
+
+    tracing::info!(
+        "Drew lottery {}: {} of {} registrations won {} of {} tickets.",
+        lottery_id,
+        ranked.len() - user_ids.len(),
+        ranked.len(),
+        won,
+        capacity
+    );
+    Ok(results)
+}
+
+/// Service to fetch the published outcome of a drawn lottery.
+pub async fn get_results(pool: &PgPool, lottery_id: i32) -> Result<LotteryResults, AppError> {
+    let lottery = lottery_query::get_lottery(pool, lottery_id).await?;
+    lottery_results(pool, lottery).await
+}
+
+/// Turns a winner's allocation into the items of an order, for checkout to run in the
+/// same transaction. The reserved tickets are put back for checkout to take again.
+pub async fn claim_allocation(
+    tx: &mut Transaction<'_, Postgres>,
+    lottery_id: i32,
+    user_id: i32,
+) -> Result<(LotteryEntry, CreateOrderPayload), AppError> {
+    // 1. Lock the entry and check its purchase window is open.
+    let entry = lottery_query::lock_entry(tx, lottery_id, user_id).await?;
+    match (entry.status, entry.purchase_expires_at) {
+        (LotteryEntryStatus::Won, Some(expires_at)) if expires_at > Utc::now() => {}
+        (LotteryEntryStatus::Won, _) | (LotteryEntryStatus::Expired, _) => {
+            return Err(AppError::BadRequest("Your purchase window for this lottery has closed.".to_string()));
+        }
+        (LotteryEntryStatus::Purchased, _) => {
+            return Err(AppError::BadRequest("You have already bought your lottery tickets.".to_string()));
+        }
+        _ => return Err(AppError::BadRequest("You didn't win tickets in this lottery.".to_string())),
+    }
+
+    // 2. Build the order from the allocation.
+    let lottery = lottery_query::get_lottery(&mut **tx, lottery_id).await?;
+    let offer = waitlist_query::get_reservation_offer(tx, lottery.offer_id).await?;
+    waitlist_query::release_general_admission(tx, offer.offer_id, offer.ticket_tier_id, entry.allocated_quantity)
+        .await?;
+    let items = vec![OrderItemPayload { offer_id: offer.offer_id, seat_id: None, quantity: entry.allocated_quantity }];
+
+    Ok((entry, CreateOrderPayload { items, addons: Vec::new(), apply_credit: None }))
+}
+
+/// Marks a winning entry as purchased by the order created from its allocation.
+pub async fn mark_purchased(
+    tx: &mut Transaction<'_, Postgres>,
+    entry: &LotteryEntry,
+    order_id: uuid::Uuid,
+) -> Result<(), AppError> {
+    lottery_query::close_entry(tx, entry.id, LotteryEntryStatus::Purchased, Some(order_id)).await
+}
+
+/// Rejects orders for offers sold by lottery: winners buy through their purchase window.
+pub async fn check_offers(pool: &PgPool, payload: &CreateOrderPayload) -> Result<(), AppError> {
+    let offer_ids: Vec<i32> = payload.items.iter().map(|item| item.offer_id).collect();
+    if let Some(offer_id) = lottery_query::list_lottery_offers(pool, &offer_ids).await?.first() {
+        return Err(AppError::BadRequest(format!(
+            "Offer {} is sold by lottery. Winners buy through their purchase window.",
+            offer_id
+        )));
+    }
+    Ok(())
+}
+
+// --- Background Job Service ---
+
+/// Service function for a background worker to close purchase windows that have passed.
+/// Their tickets are offered to the waitlist, where the lottery's losers are first in line.
+/// Returns how many windows were closed.
+pub async fn expire_purchase_windows(pool: &PgPool) -> Result<usize, AppError> {
+    let mut tx = pool.begin().await?;
+    let lapsed = lottery_query::lock_lapsed_entries(&mut tx).await?;
+    if lapsed.is_empty() {
+        tx.commit().await?;
+        return Ok(0);
+    }
+
+    let mut event_ids = Vec::new();
+    for entry in &lapsed {
+        let lottery = lottery_query::get_lottery(&mut *tx, entry.lottery_id).await?;
+        let offer = waitlist_query::get_reservation_offer(&mut tx, lottery.offer_id).await?;
+        waitlist_query::release_general_admission(&mut tx, offer.offer_id, offer.ticket_tier_id, entry.allocated_quantity)
+            .await?;
+        lottery_query::close_entry(&mut tx, entry.id, LotteryEntryStatus::Expired, None).await?;
+        if !event_ids.contains(&lottery.event_id) {
+            event_ids.push(lottery.event_id);
+        }
+    }
+    waitlist_service::offer_returned_inventory(&mut tx, &event_ids).await?;
+    tx.commit().await?;
+
+    tracing::info!("Closed {} lapsed lottery purchase windows.", lapsed.len());
+    Ok(lapsed.len())
+}
+
+// --- Helpers ---
+
+/// The SHA-256 of a lottery's registrations: one `entry_id:quantity` line each, by entry ID.
+fn input_hash(entries: &[LotteryEntry]) -> String {
+    let mut sorted: Vec<&LotteryEntry> = entries.iter().collect();
+    sorted.sort_by_key(|entry| entry.id);
+    let input: String = sorted.iter().map(|entry| format!("{}:{}\n", entry.id, entry.quantity)).collect();
+    sha256_hex(&input)
+}
+
+/// Orders registrations by the SHA-256 of `seed:entry_id`, lowest first.
+fn rank_entries<'a>(seed: &str, entries: &'a [LotteryEntry]) -> Vec<&'a LotteryEntry> {
+    let mut keyed: Vec<(String, &LotteryEntry)> = entries
+        .iter()
+        .map(|entry| (sha256_hex(&format!("{}:{}", seed, entry.id)), entry))
+        .collect();
+    keyed.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.id.cmp(&b.1.id)));
+    keyed.into_iter().map(|(_, entry)| entry).collect()
+}
+
+/// Hands out `capacity` tickets in draw order. A registration gets everything it asked
+/// for or nothing; those behind one that didn't fit may still win if they asked for fewer.
+fn allocate(ranked: &[&LotteryEntry], capacity: i32) -> Vec<i32> {
+    let mut left = capacity;
+    ranked
+        .iter()
+        .map(|entry| {
+            if entry.quantity <= left {
+                left -= entry.quantity;
+                entry.quantity
+            } else {
+                0
+            }
+        })
+        .collect()
+}
+
+fn sha256_hex(input: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(input.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+async fn lottery_info(pool: &PgPool, lottery: Lottery) -> Result<LotteryInfo, AppError> {
+    let (registrations, requested_tickets) = lottery_query::count_registrations(pool, lottery.id).await?;
+    let seed = lottery.drawn_at.map(|_| lottery.seed.clone());
+    Ok(LotteryInfo { lottery, seed, registrations, requested_tickets })
+}
+
+async fn lottery_results<'e, E>(executor: E, lottery: Lottery) -> Result<LotteryResults, AppError>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let (Some(drawn_at), Some(input_hash), Some(capacity)) = (lottery.drawn_at, lottery.input_hash, lottery.capacity)
+    else {
+        return Err(AppError::BadRequest("This lottery hasn't been drawn yet.".to_string()));
+    };
+    let entries = lottery_query::list_draw_results(executor, lottery.id).await?;
+    Ok(LotteryResults {
+        lottery_id: lottery.id,
+        seed: lottery.seed,
+        seed_hash: lottery.seed_hash,
+        input_hash,
+        drawn_at,
+        capacity,
+        entries,
+    })
+}
+
+fn ensure_registration_open(lottery: &Lottery) -> Result<(), AppError> {
+    let now = Utc::now();
+    if now < lottery.registration_opens_at {
+        return Err(AppError::BadRequest("Registration for this lottery hasn't opened yet.".to_string()));
+    }
+    if now >= lottery.registration_closes_at || lottery.drawn_at.is_some() {
+        return Err(AppError::BadRequest("Registration for this lottery has closed.".to_string()));
+    }
+    Ok(())
+}
+
+async fn authorize_organizer(pool: &PgPool, event_id: i32, organizer_id: i32) -> Result<(), AppError> {
+    let event = event_query::get_by_id(pool, event_id).await?;
+    if event.organizer_id != organizer_id {
+        return Err(AppError::Forbidden("You are not authorized to manage lotteries for this event.".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: &str = "lottery-seed";
+
+    /// Registrations as `(entry_id, quantity)`.
+    fn entries(registrations: &[(i32, i32)]) -> Vec<LotteryEntry> {
+        registrations
+            .iter()
+            .map(|&(id, quantity)| LotteryEntry {
+                id,
+                lottery_id: 1,
+                user_id: id,
+                quantity,
+                status: LotteryEntryStatus::Registered,
+                draw_rank: None,
+                allocated_quantity: 0,
+                purchase_expires_at: None,
+                order_id: None,
+                created_at: Utc::now(),
+                last_updated: Utc::now(),
+            })
+            .collect()
+    }
+
+    fn registrations() -> Vec<LotteryEntry> {
+        entries(&[(1, 2), (2, 4), (3, 1), (4, 3), (5, 2)])
+    }
+
+    #[test]
+    fn input_hash_is_pinned_and_ignores_order() {
+        let expected = "4627c809ee2cc49291ca089483c722f85677f9eea772d6161947268ab6377753";
+        let mut shuffled = registrations();
+        shuffled.reverse();
+
+        assert_eq!(input_hash(&registrations()), expected);
+        assert_eq!(input_hash(&shuffled), expected);
+        assert_ne!(input_hash(&entries(&[(1, 3), (2, 4), (3, 1), (4, 3), (5, 2)])), expected);
+    }
+
+    #[test]
+    fn ranking_follows_the_seeded_hashes() {
+        let entries = registrations();
+        let ranked: Vec<i32> = rank_entries(SEED, &entries).iter().map(|entry| entry.id).collect();
+        assert_eq!(ranked, vec![1, 5, 2, 4, 3]);
+
+        let mut shuffled = registrations();
+        shuffled.reverse();
+        let reranked: Vec<i32> = rank_entries(SEED, &shuffled).iter().map(|entry| entry.id).collect();
+        assert_eq!(reranked, ranked);
+    }
+
+    #[test]
+    fn allocation_skips_ahead_past_registrations_that_dont_fit() {
+        let entries = registrations();
+        let ranked = rank_entries(SEED, &entries);
+
+        // 1 and 5 take 4 of the 6 tickets, 2 and 4 ask for more than is left, 3 gets the last.
+        assert_eq!(allocate(&ranked, 6), vec![2, 2, 0, 0, 1]);
+        assert_eq!(allocate(&ranked, 12), vec![2, 2, 4, 3, 1]);
+        assert_eq!(allocate(&ranked, 0), vec![0, 0, 0, 0, 0]);
+    }
+}
@@ -15,4 +15,21 @@ pub mod pricing_service;
 pub mod seating_service;
 pub mod ticket_service;
 pub mod venue_service;
-pub mod organizer_service;
\ No newline at end of file
+pub mod organizer_service;
+pub mod settlement_service;
+pub mod dispute_service;
+pub mod reconciliation_service;
+pub mod realtime_service;
+pub mod layout_generator_service;
+pub mod seat_map_svg_service;
+pub mod seat_selection_service;
+pub mod seat_gap_service;
+pub mod hold_service;
+pub mod access_code_service;
+pub mod waiting_room_service;
+pub mod waitlist_service;
+pub mod lottery_service;
+pub mod addon_service;
+pub mod cart_service;
+pub mod store_credit_service;
+pub mod group_booking_service;
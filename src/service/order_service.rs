@@ -1,55 +1,57 @@
 use crate::{
-    db::{order_query, payment_query},
+    clients::payment_provider::PaymentProvider,
+    db::{addon_query, gift_card_query, group_booking_query, order_query, payment_query, seating_query, store_credit_query},
     errors::AppError,
-    models::{CreateOrderPayload, Order},
+    models::{CreateOrderPayload, Order, OrderReceipt, OrderStatus, PurchaseGiftCardPayload},
+    service::{
+        cart_service, lottery_service, payment_service, seat_gap_service, store_credit_service, waiting_room_service,
+        waitlist_service,
+    },
     utils::validation,
 };
-use sqlx::PgPool;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
   use num_traits::ToPrimitive;
 
-// We would have a stripe_client module to interact with the Stripe API
-// For now, we'll just define a placeholder struct.
-pub struct StripeClient;
-impl StripeClient {
-    // This function would call the Stripe API to create a PaymentIntent
-    pub async fn create_payment_intent(
-        &self,
-        amount: i64, // Stripe expects amount in cents
-        currency: &str,
-    ) -> Result<(String, String), AppError> {
-        // In a real app:
-        // let params = ...
-        // let pi = stripe::PaymentIntent::create(&self.client, params).await?;
-        // Ok((pi.id, pi.client_secret.unwrap_or_default()))
-
-        // Placeholder for compilation:
-        let placeholder_id = format!("pi_{}", uuid::Uuid::new_v4().simple());
-        let placeholder_secret = format!("pi_{}_secret_{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
-        Ok((placeholder_id, placeholder_secret))
-    }
-}
+/// How long a checkout holds its inventory before the order expires unpaid.
+const ORDER_EXPIRY_MINUTES: i64 = 15;
 
 /// The primary service function for starting a checkout process.
 /// It creates a pending order, locks inventory, and generates a payment intent.
+/// Part or all of the order can be paid with store credit, and only the rest is charged.
 /// This is a transactional operation.
-pub async fn create_order(
+pub async fn create_order<P: PaymentProvider>(
     pool: &PgPool,    
-    stripe_client: &StripeClient,
+    payment_provider: &P,
     user_id: i32,
     payload: &CreateOrderPayload,
-) -> Result<(Order, String), AppError> {
-    // 1. Validate the incoming payload.
+    admission_token: Option<&str>,
+) -> Result<(Order, Option<String>), AppError> {
+    // 1. Validate the incoming payload, and the admission token for events behind a waiting room.
+    //    Offers sold by lottery are bought through the winners' purchase windows instead.
     validation::validate_payload(payload)?;
+    if payload.items.is_empty() && payload.addons.is_empty() {
+        return Err(AppError::BadRequest("Order must contain at least one item.".to_string()));
+    }
+    lottery_service::check_offers(pool, payload).await?;
+    waiting_room_service::check_admission(pool, user_id, payload, admission_token).await?;
 
     // 2. Begin a database transaction.
     let mut tx = pool.begin().await?;
 
-    // 3. Define business rules
-    const ORDER_EXPIRY_MINUTES: i64 = 15;
-
-    // 4. Call the transactional query to create the pending order and lock inventory.
-    let order_result =
-        order_query::create_pending_order(&mut tx, user_id, payload, ORDER_EXPIRY_MINUTES).await;
+    // 3. Call the transactional query to create the pending order and lock inventory,
+    //    then make sure the order's seats don't strand single empty seats.
+    //    Store credit is spent in the same transaction, so it's only used if the checkout commits.
+    let order_result: Result<Order, AppError> = async {
+        let order = order_query::create_pending_order(&mut tx, user_id, payload, ORDER_EXPIRY_MINUTES).await?;
+        let row_seats = seating_query::list_row_seats_for_order(&mut *tx, order.id).await?;
+        seat_gap_service::check_selection(&row_seats)?;
+        match payload.apply_credit {
+            Some(requested) => store_credit_service::apply_to_order(&mut tx, &order, requested).await,
+            None => Ok(order),
+        }
+    }
+    .await;
 
     let order = match order_result {
         Ok(order) => order,
@@ -59,15 +61,134 @@ pub async fn create_order(
         }
     };
 
-    // 5. Create a Payment Intent with Stripe.
-    // This happens *after* the DB lock but *before* the commit. If Stripe fails, we can still rollback.
-    let amount_in_cents = (order.total_amount * rust_decimal::Decimal::from(100)).to_i64()
+    // 4. Create the payment and commit, then return the created order and the client secret
+    //    for the frontend to use, or the completed order if store credit paid for all of it.
+    start_payment(tx, payment_provider, order).await
+}
+
+/// Checks out the tickets reserved for a waitlisted user behind their purchase link.
+/// Works like `create_order`, except the inventory is already theirs: no admission token
+/// is needed, and the reserved seats are sold as they are, gaps or not.
+pub async fn create_waitlist_order<P: PaymentProvider>(
+    pool: &PgPool,
+    payment_provider: &P,
+    user_id: i32,
+    claim_token: &str,
+) -> Result<(Order, Option<String>), AppError> {
+    // 1. Turn the reservation into order items, in the same transaction as the checkout.
+    let mut tx = pool.begin().await?;
+    let (entry, payload) = waitlist_service::claim_reservation(&mut tx, user_id, claim_token).await?;
+
+    // 2. Create the pending order and lock the inventory for it.
+    let order = order_query::create_pending_order(&mut tx, user_id, &payload, ORDER_EXPIRY_MINUTES).await?;
+    waitlist_service::mark_purchased(&mut tx, &entry, order.id).await?;
+
+    // 3. Create the payment and commit.
+    start_payment(tx, payment_provider, order).await
+}
+
+/// Checks out the tickets a user won in a lottery, during their purchase window.
+/// Like a waitlist checkout, the tickets are already reserved, so no admission token is needed.
+pub async fn create_lottery_order<P: PaymentProvider>(
+    pool: &PgPool,
+    payment_provider: &P,
+    user_id: i32,
+    lottery_id: i32,
+) -> Result<(Order, Option<String>), AppError> {
+    // 1. Turn the allocation into order items, in the same transaction as the checkout.
+    let mut tx = pool.begin().await?;
+    let (entry, payload) = lottery_service::claim_allocation(&mut tx, lottery_id, user_id).await?;
+
+    // 2. Create the pending order and lock the inventory for it.
+    let order = order_query::create_pending_order(&mut tx, user_id, &payload, ORDER_EXPIRY_MINUTES).await?;
+    lottery_service::mark_purchased(&mut tx, &entry, order.id).await?;
+
+    // 3. Create the payment and commit.
+    start_payment(tx, payment_provider, order).await
+}
+
+/// Checks out everything in a user's cart as one order with a single payment.
+/// The cart's holds carry over to the order, so its seats and tickets are never back on sale
+/// in between. Admission was checked as the items were added.
+pub async fn create_cart_order<P: PaymentProvider>(
+    pool: &PgPool,
+    payment_provider: &P,
+    user_id: i32,
+) -> Result<(Order, Option<String>), AppError> {
+    // 1. Turn the cart into order items, in the same transaction as the checkout.
+    let mut tx = pool.begin().await?;
+    let payload = cart_service::claim_cart(&mut tx, user_id).await?;
+
+    // 2. Create the pending order and lock the inventory for it. Like any checkout,
+    //    the seats mustn't strand single empty seats.
+    let order = order_query::create_pending_order(&mut tx, user_id, &payload, ORDER_EXPIRY_MINUTES).await?;
+    let row_seats = seating_query::list_row_seats_for_order(&mut *tx, order.id).await?;
+    seat_gap_service::check_selection(&row_seats)?;
+
+    // 3. Create the payment and commit.
+    start_payment(tx, payment_provider, order).await
+}
+
+/// Buys a gift card. The card is created with a pending order and can be redeemed once the
+/// order is paid; if the order expires unpaid, the card is cancelled with it.
+/// Gift cards can't be paid for with store credit.
+pub async fn create_gift_card_order<P: PaymentProvider>(
+    pool: &PgPool,
+    payment_provider: &P,
+    user_id: i32,
+    payload: &PurchaseGiftCardPayload,
+) -> Result<(Order, Option<String>), AppError> {
+    // 1. Validate the purchase.
+    store_credit_service::check_purchase(payload)?;
+
+    // 2. Create the pending order and the card it buys.
+    let mut tx = pool.begin().await?;
+    let order = order_query::create_gift_card_order(&mut tx, user_id, payload.amount, ORDER_EXPIRY_MINUTES).await?;
+    store_credit_service::create_for_order(&mut tx, order.id, user_id, payload).await?;
+
+    // 3. Create the payment and commit.
+    start_payment(tx, payment_provider, order).await
+}
+
+/// Service for a user to see one of their orders with everything bought in it: tickets,
+/// bundles, and add-ons with the amounts donated.
+pub async fn get_order_receipt(pool: &PgPool, order_id: Uuid, user_id: i32) -> Result<OrderReceipt, AppError> {
+    let order = order_query::get_by_id(pool, order_id).await?;
+    if order.user_id != user_id {
+        return Err(AppError::Forbidden("You can only view your own orders.".to_string()));
+    }
+    let items = order_query::get_items_for_order(pool, order_id).await?;
+    let bundles = order_query::get_bundles_for_order(pool, order_id).await?;
+    let addons = addon_query::list_addons_for_order(pool, order_id).await?;
+    Ok(OrderReceipt { order, items, bundles, addons })
+}
+
+/// Creates the Payment Intent of a pending order, records it and commits the checkout.
+/// This happens *after* the DB lock but *before* the commit. If Stripe fails, we can still rollback,
+/// which also gives back any store credit spent on the order.
+/// Only what store credit didn't cover is charged. An order paid entirely with credit needs
+/// no payment and is completed right away, so there is no client secret to return.
+async fn start_payment<P: PaymentProvider>(
+    mut tx: Transaction<'_, Postgres>,
+    payment_provider: &P,
+    mut order: Order,
+) -> Result<(Order, Option<String>), AppError> {
+    let amount_due = order.total_amount - order.credit_applied;
+    if amount_due.is_zero() {
+        payment_service::complete_order(&mut tx, order.id, order.user_id).await?;
+        tx.commit().await?;
+        order.status = OrderStatus::Completed;
+        return Ok((order, None));
+    }
+
+    // 1. Create a Payment Intent with the payment provider.
+    let amount_in_cents = (amount_due * rust_decimal::Decimal::from(100)).to_i64()
         .ok_or_else(|| AppError::InternalServerError("Failed to convert amount to cents".to_string()))?;
-    let payment_intent_result = stripe_client
+    let payment_intent_result = payment_provider
         .create_payment_intent(amount_in_cents, "usd")
         .await;
 
-    let (pi_id, client_secret) = match payment_intent_result {
+    let payment_intent = match payment_intent_result {
         Ok(pi) => pi,
         Err(e) => {
             tx.rollback().await?;
@@ -75,18 +196,342 @@ pub async fn create_order(
         }
     };
 
-    // 6. Create the pending payment record in our DB, linking our order to the Stripe PI.
+    // 2. Create the pending payment record in our DB, linking our order to the Stripe PI.
     let payment_result =
-        payment_query::create_pending_payment(&mut tx, order.id, order.total_amount, &pi_id).await;
+        payment_query::create_pending_payment(&mut tx, order.id, amount_due, &payment_intent.id).await;
 
     if let Err(e) = payment_result {
         tx.rollback().await?;
         return Err(e);
     }
 
-    // 7. If everything has succeeded, commit the transaction.
+    // 3. If everything has succeeded, commit the transaction.
+    tx.commit().await?;
+    Ok((order, Some(payment_intent.client_secret)))
+}
+
+/// Cancels pending orders whose checkout window has passed and gives their inventory back:
+/// general admission tickets return to their offers and locked seats become available.
+/// Store credit spent on them is given back too, and their PaymentIntents are cancelled so
+/// the buyer can't be charged for an order that no longer holds anything.
+/// Run periodically by the expiry sweep job. Returns the number of orders cancelled.
+pub async fn expire_stale_orders<P: PaymentProvider>(pool: &PgPool, payment_provider: &P) -> Result<u64, AppError> {
+    // 1. Cancel the expired orders.
+    let mut tx = pool.begin().await?;
+    let order_ids = order_query::cancel_expired_orders(&mut tx).await?;
+    if order_ids.is_empty() {
+        tx.commit().await?;
+        return Ok(0);
+    }
+
+    // 2. Release their inventory, fail their payments and commit.
+    let (abandoned_intents, released_seats) = release_cancelled_orders(&mut tx, &order_ids).await?;
+    tx.commit().await?;
+    tracing::info!(
+        "Expired {} unpaid orders and released {} seats.",
+        order_ids.len(),
+        released_seats
+    );
+
+    // 3. Cancel the abandoned PaymentIntents. One that can't be cancelled any more is about to
+    //    succeed, and its webhook refunds it.
+    for payment_intent_id in &abandoned_intents {
+        if let Err(e) = payment_provider.cancel_payment_intent(payment_intent_id).await {
+            tracing::warn!("Failed to cancel abandoned PaymentIntent {}: {:?}", payment_intent_id, e);
+        }
+    }
+
+    Ok(order_ids.len() as u64)
+}
+
+/// Cancels a pending order whose payment can't go through any more, e.g. because its
+/// PaymentIntent was cancelled, and releases it the same way as an expired one.
+/// Group booking orders are left to the group booking job. Cancelling the PaymentIntent, if it
+/// isn't already, is up to the caller. Returns `false` if the order wasn't cancelled.
+pub async fn cancel_unpaid_order(pool: &PgPool, order_id: Uuid) -> Result<bool, AppError> {
+    let mut tx = pool.begin().await?;
+    if group_booking_query::is_group_order(&mut tx, order_id).await?
+        || !order_query::mark_order_cancelled(&mut tx, order_id).await?
+    {
+        tx.rollback().await?;
+        return Ok(false);
+    }
+
+    let (_, released_seats) = release_cancelled_orders(&mut tx, &[order_id]).await?;
     tx.commit().await?;
+    tracing::info!("Cancelled unpaid order {} and released {} seats.", order_id, released_seats);
+    Ok(true)
+}
+
+/// Gives back everything held by orders that were just cancelled: general admission tickets
+/// return to their offers and locked seats become available, the store credit spent on them
+/// is given back and the gift cards they bought are cancelled. Their pending payments are
+/// failed, and the returned inventory is offered to waitlisted users before anyone else can
+/// take it. Live seat maps are notified as part of the transaction.
+/// Returns the PaymentIntent IDs of the failed payments and the number of seats released.
+async fn release_cancelled_orders(
+    tx: &mut Transaction<'_, Postgres>,
+    order_ids: &[Uuid],
+) -> Result<(Vec<String>, usize), AppError> {
+    order_query::release_general_admission_inventory(tx, order_ids).await?;
+    let abandoned_intents = payment_query::mark_pending_payments_failed(tx, order_ids).await?;
+    store_credit_query::restore_for_orders(tx, order_ids).await?;
+    gift_card_query::cancel_for_orders(tx, order_ids).await?;
+    let released_seats = seating_query::release_order_seats(tx, order_ids).await?;
+
+    let event_ids = order_query::list_event_ids_for_orders(tx, order_ids).await?;
+    waitlist_service::offer_returned_inventory(tx, &event_ids).await?;
+    Ok((abandoned_intents, released_seats.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::payment_provider::{FakePaymentProvider, ProviderPaymentStatus};
+    use crate::models::{pricing::BundleComponentPayload, OrderItemPayload, PaymentStatus, SetBundleComponentsPayload};
+    use crate::service::pricing_service;
+    use crate::utils::test_fixtures;
+    use rust_decimal::Decimal;
+
+    /// Checks out two general admission tickets and lets the checkout window pass.
+    /// Returns the order, its PaymentIntent's ID and the offer.
+    async fn expired_checkout(pool: &PgPool, provider: &FakePaymentProvider) -> (Uuid, String, i32) {
+        let event_id = test_fixtures::create_event(pool).await;
+        let tier_id = test_fixtures::create_tier(pool, event_id, 100).await;
+        let offer_id = test_fixtures::create_offer(pool, tier_id, Decimal::new(5000, 2), 100).await;
+        let user_id = test_fixtures::create_user(pool, "buyer").await;
+
+        let payload = CreateOrderPayload {
+            items: vec![OrderItemPayload { offer_id, seat_id: None, quantity: 2 }],
+            addons: Vec::new(),
+            apply_credit: None,
+        };
+        let (order, _) = create_order(pool, provider, user_id, &payload, None).await.unwrap();
+        sqlx::query("UPDATE orders SET expires_at = NOW() - INTERVAL '1 minute' WHERE id = $1")
+            .bind(order.id)
+            .execute(pool)
+            .await
+            .unwrap();
+        let payment_intent_id: String =
+            sqlx::query_scalar("SELECT stripe_payment_intent_id FROM payments WHERE order_id = $1")
+                .bind(order.id)
+                .fetch_one(pool)
+                .await
+                .unwrap();
+        (order.id, payment_intent_id, offer_id)
+    }
+
+    async fn quantity_sold(pool: &PgPool, offer_id: i32) -> i32 {
+        sqlx::query_scalar("SELECT quantity_sold FROM offers WHERE id = $1")
+            .bind(offer_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn tier_sold(pool: &PgPool, tier_id: i32) -> i32 {
+        sqlx::query_scalar("SELECT quantity_sold FROM ticket_tiers WHERE id = $1")
+            .bind(tier_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn addon_sold(pool: &PgPool, addon_item_id: i32) -> i32 {
+        sqlx::query_scalar("SELECT quantity_sold FROM addon_items WHERE id = $1")
+            .bind(addon_item_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn payment_status(pool: &PgPool, payment_intent_id: &str) -> PaymentStatus {
+        let mut tx = pool.begin().await.unwrap();
+        payment_query::get_by_payment_intent_id(&mut tx, payment_intent_id).await.unwrap().status
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn expiry_cancels_the_payment_intent(pool: PgPool) {
+        let provider = FakePaymentProvider::new();
+        let (order_id, payment_intent_id, offer_id) = expired_checkout(&pool, &provider).await;
+        assert_eq!(quantity_sold(&pool, offer_id).await, 2);
 
-    // 8. Return the created order and the client secret for the frontend to use.
-    Ok((order, client_secret))
-}
\ No newline at end of file
+        assert_eq!(expire_stale_orders(&pool, &provider).await.unwrap(), 1);
+
+        let order = order_query::get_by_id(&pool, order_id).await.unwrap();
+        assert!(matches!(order.status, OrderStatus::Cancelled));
+        assert_eq!(provider.status(&payment_intent_id), Some(ProviderPaymentStatus::Canceled));
+        assert!(matches!(payment_status(&pool, &payment_intent_id).await, PaymentStatus::Failed));
+        assert_eq!(quantity_sold(&pool, offer_id).await, 0);
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn payment_after_expiry_is_refunded(pool: PgPool) {
+        let provider = FakePaymentProvider::new();
+        let (order_id, payment_intent_id, offer_id) = expired_checkout(&pool, &provider).await;
+
+        // The payment is already processing, so the sweep can't cancel it, and then succeeds.
+        provider.set_status(&payment_intent_id, ProviderPaymentStatus::Processing);
+        assert_eq!(expire_stale_orders(&pool, &provider).await.unwrap(), 1);
+        assert_eq!(provider.status(&payment_intent_id), Some(ProviderPaymentStatus::Processing));
+        provider.set_status(&payment_intent_id, ProviderPaymentStatus::Succeeded);
+
+        let tickets =
+            payment_service::finalize_order_on_payment_success(&pool, &provider, &payment_intent_id).await.unwrap();
+        assert!(tickets.is_empty());
+        assert!(provider.is_refunded(&payment_intent_id));
+        assert!(matches!(payment_status(&pool, &payment_intent_id).await, PaymentStatus::Refunded));
+        let order = order_query::get_by_id(&pool, order_id).await.unwrap();
+        assert!(matches!(order.status, OrderStatus::Cancelled));
+        assert_eq!(quantity_sold(&pool, offer_id).await, 0);
+
+        // A retried webhook changes nothing.
+        let tickets =
+            payment_service::finalize_order_on_payment_success(&pool, &provider, &payment_intent_id).await.unwrap();
+        assert!(tickets.is_empty());
+        assert!(matches!(payment_status(&pool, &payment_intent_id).await, PaymentStatus::Refunded));
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn wheelchair_seats_are_kept_for_accessibility_offers(pool: PgPool) {
+        let provider = FakePaymentProvider::new();
+        let event_id = test_fixtures::create_event(&pool).await;
+        let tier_id = test_fixtures::create_tier(&pool, event_id, 10).await;
+        let seat_ids = test_fixtures::create_seats(&pool, event_id, tier_id, 3).await;
+        let standard = test_fixtures::create_offer(&pool, tier_id, Decimal::new(5000, 2), 10).await;
+        let accessible = test_fixtures::create_offer(&pool, tier_id, Decimal::new(5000, 2), 10).await;
+        sqlx::query("UPDATE offers SET is_accessible = TRUE WHERE id = $1")
+            .bind(accessible)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE seats SET attributes = $2::seat_attribute[] WHERE id = $1")
+            .bind(seat_ids[0])
+            .bind(vec!["wheelchair"])
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE seats SET attributes = $2::seat_attribute[] WHERE id = $1")
+            .bind(seat_ids[1])
+            .bind(vec!["companion"])
+            .execute(&pool)
+            .await
+            .unwrap();
+        let user_id = test_fixtures::create_user(&pool, "buyer").await;
+        let seat_payload = |offer_id, seat_id| CreateOrderPayload {
+            items: vec![OrderItemPayload { offer_id, seat_id: Some(seat_id), quantity: 1 }],
+            addons: Vec::new(),
+            apply_credit: None,
+        };
+
+        // Before the release, a standard offer can't have the wheelchair seat,
+        // and the accessibility offer can't have an ordinary one.
+        let result = create_order(&pool, &provider, user_id, &seat_payload(standard, seat_ids[0]), None).await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+        let result = create_order(&pool, &provider, user_id, &seat_payload(accessible, seat_ids[2]), None).await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+        create_order(&pool, &provider, user_id, &seat_payload(accessible, seat_ids[0]), None).await.unwrap();
+
+        // Within the release window the companion seat is on general sale.
+        let result = create_order(&pool, &provider, user_id, &seat_payload(standard, seat_ids[1]), None).await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+        sqlx::query("UPDATE events SET start_time = NOW() + INTERVAL '1 hour' WHERE id = $1")
+            .bind(event_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        create_order(&pool, &provider, user_id, &seat_payload(standard, seat_ids[1]), None).await.unwrap();
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn checkout_takes_tickets_from_the_offer_and_its_tier(pool: PgPool) {
+        let provider = FakePaymentProvider::new();
+        let event_id = test_fixtures::create_event(&pool).await;
+        let floor = test_fixtures::create_tier(&pool, event_id, 10).await;
+        let early_bird = test_fixtures::create_offer(&pool, floor, Decimal::from(30), 8).await;
+        let regular = test_fixtures::create_offer(&pool, floor, Decimal::from(40), 8).await;
+        let balcony = test_fixtures::create_tier(&pool, event_id, 10).await;
+        let balcony_offer = test_fixtures::create_offer(&pool, balcony, Decimal::from(50), 10).await;
+        let seat_ids = test_fixtures::create_seats(&pool, event_id, balcony, 2).await;
+        let user_id = test_fixtures::create_user(&pool, "buyer").await;
+        let checkout = |offer_id, seat_id, quantity| CreateOrderPayload {
+            items: vec![OrderItemPayload { offer_id, seat_id, quantity }],
+            addons: Vec::new(),
+            apply_credit: None,
+        };
+
+        // 1. General admission is taken from the offer and the tier, and the overlapping
+        //    offers can't together sell more than the tier holds.
+        create_order(&pool, &provider, user_id, &checkout(early_bird, None, 6), None).await.unwrap();
+        assert_eq!((quantity_sold(&pool, early_bird).await, tier_sold(&pool, floor).await), (6, 6));
+        let result = create_order(&pool, &provider, user_id, &checkout(regular, None, 5), None).await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+        assert_eq!((quantity_sold(&pool, regular).await, tier_sold(&pool, floor).await), (0, 6));
+        create_order(&pool, &provider, user_id, &checkout(regular, None, 4), None).await.unwrap();
+        assert_eq!(tier_sold(&pool, floor).await, 10);
+
+        // 2. A seat is booked one at a time, through an offer of its own tier, and only once.
+        for payload in [checkout(balcony_offer, Some(seat_ids[0]), 2), checkout(regular, Some(seat_ids[0]), 1)] {
+            let result = create_order(&pool, &provider, user_id, &payload, None).await;
+            assert!(matches!(result, Err(AppError::BadRequest(_))));
+        }
+        let one_seat = checkout(balcony_offer, Some(seat_ids[0]), 1);
+        let (order, _) = create_order(&pool, &provider, user_id, &one_seat, None).await.unwrap();
+        assert_eq!(order.subtotal, Decimal::from(50));
+        let result = create_order(&pool, &provider, user_id, &one_seat, None).await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+        assert_eq!(tier_sold(&pool, balcony).await, 0);
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn bundle_packages_take_and_give_back_their_components(pool: PgPool) {
+        let provider = FakePaymentProvider::new();
+        let event_id = test_fixtures::create_event(&pool).await;
+        let organizer_id: i32 = sqlx::query_scalar("SELECT organizer_id FROM events WHERE id = $1")
+            .bind(event_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let tier_id = test_fixtures::create_tier(&pool, event_id, 10).await;
+        let bundle = test_fixtures::create_offer(&pool, tier_id, Decimal::from(100), 5).await;
+        let parking = test_fixtures::create_addon(&pool, event_id, Decimal::from(15), Some(1)).await;
+        let components = SetBundleComponentsPayload {
+            components: vec![
+                BundleComponentPayload { ticket_tier_id: Some(tier_id), addon_item_id: None, quantity: 3 },
+                BundleComponentPayload { ticket_tier_id: None, addon_item_id: Some(parking), quantity: 1 },
+            ],
+        };
+        pricing_service::set_bundle_components(&pool, bundle, organizer_id, &components).await.unwrap();
+        let user_id = test_fixtures::create_user(&pool, "buyer").await;
+        let checkout = |seat_id| CreateOrderPayload {
+            items: vec![OrderItemPayload { offer_id: bundle, seat_id, quantity: 1 }],
+            addons: Vec::new(),
+            apply_credit: None,
+        };
+
+        // 1. A package takes its tickets from the tier and its add-on from the catalog. Its
+        //    price is spread over the tickets to the cent, and the fee is charged per ticket.
+        let (order, _) = create_order(&pool, &provider, user_id, &checkout(None), None).await.unwrap();
+        assert_eq!((order.subtotal, order.service_fee), (Decimal::from(100), Decimal::new(750, 2)));
+        let items = order_query::get_items_for_order(&pool, order.id).await.unwrap();
+        let prices: Vec<_> = items.iter().map(|item| (item.quantity, item.unit_price)).collect();
+        assert_eq!(prices, vec![(1, Decimal::new(3334, 2)), (2, Decimal::new(3333, 2))]);
+        assert_eq!(quantity_sold(&pool, bundle).await, 1);
+        assert_eq!((tier_sold(&pool, tier_id).await, addon_sold(&pool, parking).await), (3, 1));
+
+        // 2. Without parking left, the next package can't be sold at all, nor sold for a seat.
+        for payload in [checkout(None), checkout(Some(1))] {
+            let result = create_order(&pool, &provider, user_id, &payload, None).await;
+            assert!(matches!(result, Err(AppError::BadRequest(_))));
+        }
+        assert_eq!(quantity_sold(&pool, bundle).await, 1);
+        assert_eq!((tier_sold(&pool, tier_id).await, addon_sold(&pool, parking).await), (3, 1));
+
+        // 3. When the order expires, everything in the package is given back.
+        sqlx::query("UPDATE orders SET expires_at = NOW() - INTERVAL '1 minute'").execute(&pool).await.unwrap();
+        assert_eq!(expire_stale_orders(&pool, &provider).await.unwrap(), 1);
+        assert_eq!(quantity_sold(&pool, bundle).await, 0);
+        assert_eq!((tier_sold(&pool, tier_id).await, addon_sold(&pool, parking).await), (0, 0));
+        create_order(&pool, &provider, user_id, &checkout(None), None).await.unwrap();
+    }
+}
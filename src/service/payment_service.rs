@@ -1,26 +1,31 @@
 use crate::{
-    db::{order_query, payment_query, ticket_query},
+    clients::payment_provider::PaymentProvider,
+    db::{addon_query, gift_card_query, group_booking_query, order_query, payment_query, seating_query, settlement_query, ticket_query},
     errors::AppError,
-    models::{order::OrderItemPayload, Ticket}, // Assuming a way to get order items
+    models::{PaymentStatus, Ticket},
+    service::group_booking_service,
 };
-use sqlx::PgPool;
+use sqlx::{PgPool, Postgres, Transaction};
 use uuid::Uuid;
 
 /// This is the most critical transaction in the application.
 /// It's triggered by a Stripe webhook when a payment succeeds.
-/// It finalizes the order and issues the tickets.
-pub async fn finalize_order_on_payment_success(
+/// It finalizes the order and issues the tickets, add-on vouchers and gift cards, or, for a
+/// group booking, the tickets of the shares the payment was for. A payment that went through
+/// after its order expired is refunded, never kept.
+pub async fn finalize_order_on_payment_success<P: PaymentProvider>(
     pool: &PgPool,
+    payment_provider: &P,
     stripe_payment_intent_id: &str,
 ) -> Result<Vec<Ticket>, AppError> {
     // 1. Begin a database transaction.
     let mut tx = pool.begin().await?;
 
     // 2. Mark the payment as succeeded in our DB.
+    let payment = payment_query::get_by_payment_intent_id(&mut tx, stripe_payment_intent_id).await?;
     payment_query::mark_payment_succeeded(&mut tx, stripe_payment_intent_id).await?;
 
     // 3. Fetch the order details needed for finalization.
-    // In a real app, you'd fetch the order and its items. Here we simplify.
     let order_info: (Uuid, i32) = sqlx::query_as(
         "SELECT o.id, o.user_id FROM orders o JOIN payments p ON o.id = p.order_id WHERE p.stripe_payment_intent_id = $1"
     )
@@ -30,20 +35,68 @@ pub async fn finalize_order_on_payment_success(
     
     let (order_id, user_id) = order_info;
 
-    // 4. Mark the order itself as 'completed'.
-    order_query::mark_order_completed(&mut tx, order_id).await?;
+    // 4. A group booking's order is paid share by share; the payment only pays for the
+    //    shares it was made for, and is refunded if it came too late for them.
+    if let Some(booking) = group_booking_query::lock_by_order(&mut tx, order_id).await? {
+        let tickets = group_booking_service::complete_share_payment(&mut tx, payment_provider, &booking, stripe_payment_intent_id).await?;
+        tx.commit().await?;
+        return Ok(tickets);
+    }
 
-    // 5. This is where you would fetch the original order items to create tickets.
-    // For this example, we'll assume a placeholder. In a real app, you'd query them.
-    let placeholder_items: Vec<OrderItemPayload> = vec![]; // This needs to be populated from the DB
+    // 5. We gave up on the payment when its order expired, and its inventory may be gone.
+    if matches!(payment.status, PaymentStatus::Failed) {
+        payment_provider.refund_payment_intent(stripe_payment_intent_id).await?;
+        payment_query::mark_payment_refunded(&mut tx, payment.id, payment.amount_charged).await?;
+        tx.commit().await?;
+        tracing::warn!(
+            "Payment {} came after order {} expired; refunded {}.",
+            stripe_payment_intent_id,
+            order_id,
+            payment.amount_charged
+        );
+        return Ok(vec![]);
+    }
 
-    // 6. Create the actual tickets.
-    let tickets = ticket_query::create_tickets_for_order(&mut tx, order_id, user_id, &placeholder_items).await?;
-
-    // 7. Commit the transaction. If any step failed, the rollback is handled by `?`.
+    // 6. Complete the order and commit. If any step failed, the rollback is handled by `?`.
+    let tickets = complete_order(&mut tx, order_id, user_id).await?;
     tx.commit().await?;
-    
+
     // Optional: Send a confirmation email to the user.
 
     Ok(tickets)
-}
\ No newline at end of file
+}
+
+/// Completes a paid order: issues its tickets, add-on vouchers and gift cards, records the
+/// sale and marks its seats as sold. Runs in the transaction that recorded the payment, or
+/// the checkout's own when store credit paid for the whole order.
+pub async fn complete_order(
+    tx: &mut Transaction<'_, Postgres>,
+    order_id: Uuid,
+    user_id: i32,
+) -> Result<Vec<Ticket>, AppError> {
+    // 1. Mark the order itself as 'completed'.
+    // If it was already completed (e.g. Stripe retried the webhook), there is nothing left to do.
+    if !order_query::mark_order_completed(tx, order_id).await? {
+        tracing::warn!("Order {} was already finalized; skipping ticket issuance.", order_id);
+        return Ok(vec![]);
+    }
+
+    // 2. Fetch the original order items to create tickets from.
+    let items = order_query::get_items_for_order(&mut **tx, order_id).await?;
+
+    // 3. Create the actual tickets.
+    let tickets = ticket_query::create_tickets_for_order(tx, order_id, user_id, &items).await?;
+
+    // 4. Issue a voucher for every redeemable add-on, bought on its own or in a bundle,
+    //    and activate the gift cards bought.
+    addon_query::create_vouchers_for_order(tx, order_id, user_id).await?;
+    gift_card_query::activate_for_order(tx, order_id).await?;
+
+    // 5. Record the sale in the organizers' settlement ledger.
+    settlement_query::record_order_sale(tx, order_id).await?;
+
+    // 6. Mark the order's reserved seats as sold.
+    seating_query::mark_order_seats_sold(tx, order_id).await?;
+
+    Ok(tickets)
+}
@@ -1,14 +1,20 @@
 use crate::{
-    db::{event_query, pricing_query},
+    db::{addon_query, event_query, pricing_query},
     errors::AppError,
-    models::{CreateOfferPayload, CreateTicketTierPayload, Offer, TicketTier},
+    models::{
+        BundleComponent, CreateOfferPayload, CreateTicketTierPayload, Offer, OfferPricing, PriceAdjustmentType,
+        PriceRuleKind, SetBundleComponentsPayload, SetOfferPricingPayload, TicketTier, UpdateOfferPayload,
+        UpdateTicketTierPayload,
+    },
     utils::validation,
 };
-use sqlx::PgPool;
+use rust_decimal::Decimal;
+use sqlx::{PgPool, Postgres, Transaction};
 
 // --- Ticket Tier Services ---
 
 /// Service to create a new ticket tier for an event.
+/// The event's tiers together may not hold more tickets than its venue's capacity.
 pub async fn create_ticket_tier(
     pool: &PgPool,
     event_id: i32,
@@ -26,8 +32,13 @@ pub async fn create_ticket_tier(
         ));
     }
 
-    // 3. Call the database query to create the tier.
-    pricing_query::create_ticket_tier(pool, event_id, payload).await
+    // 3. Check the venue has room for the tier and create it, holding the event meanwhile.
+    let mut tx = pool.begin().await?;
+    check_venue_capacity(&mut tx, event_id, None, payload.total_inventory).await?;
+    let tier = pricing_query::create_ticket_tier(&mut *tx, event_id, payload).await?;
+    tx.commit().await?;
+
+    Ok(tier)
 }
 
 /// Service to list all ticket tiers for a given event.
@@ -35,9 +46,48 @@ pub async fn list_tiers_for_event(pool: &PgPool, event_id: i32) -> Result<Vec<Ti
     pricing_query::list_tiers_for_event(pool, event_id).await
 }
 
+/// Service to update a ticket tier. Its inventory can't drop below what has been sold,
+/// what any one offer has for sale or what its public offers have for sale together,
+/// nor grow past the venue's capacity.
+pub async fn update_ticket_tier(
+    pool: &PgPool,
+    tier_id: i32,
+    organizer_id: i32,
+    payload: &UpdateTicketTierPayload,
+) -> Result<TicketTier, AppError> {
+    // 1. Validate the payload and check the user organizes the tier's event.
+    validation::validate_payload(payload)?;
+    let tier = pricing_query::get_ticket_tier(pool, tier_id).await?;
+    authorize_organizer(pool, tier.event_id, organizer_id).await?;
+
+    // 2. Check the new inventory against the venue and the tier's offers, holding both.
+    let mut tx = pool.begin().await?;
+    let tier = pricing_query::lock_ticket_tier(&mut tx, tier_id).await?;
+    if let Some(total_inventory) = payload.total_inventory {
+        check_venue_capacity(&mut tx, tier.event_id, Some(tier_id), total_inventory).await?;
+
+        let (public_allocated, largest_offer) = pricing_query::get_tier_allocation(&mut tx, tier_id, None).await?;
+        let required = i64::from(tier.quantity_sold).max(public_allocated).max(i64::from(largest_offer));
+        if i64::from(total_inventory) < required {
+            return Err(AppError::BadRequest(format!(
+                "This tier needs an inventory of at least {} for the tickets already sold and allocated to its offers.",
+                required
+            )));
+        }
+    }
+
+    // 3. Update the tier.
+    let tier = pricing_query::update_ticket_tier(&mut tx, tier_id, payload).await?;
+    tx.commit().await?;
+
+    Ok(tier)
+}
+
 // --- Offer Services ---
 
 /// Service to create a new sales offer. This is a transactional operation.
+/// No offer may sell more than its tier's inventory, and the tier's public offers together
+/// may not either. Presale offers (with an access code) can overlap the public ones.
 pub async fn create_offer(
     pool: &PgPool,
     ticket_tier_id: i32,
@@ -66,7 +116,9 @@ pub async fn create_offer(
     // 3. Begin a database transaction.
     let mut tx = pool.begin().await?;
 
-    // 4. Call the transactional database query.
+    // 4. Check the offer fits the tier, then call the transactional database query.
+    let tier = pricing_query::lock_ticket_tier(&mut tx, ticket_tier_id).await?;
+    check_offer_allocation(&mut tx, &tier, None, payload.quantity_for_sale, payload.access_code.is_none()).await?;
     let offer_result = pricing_query::create_offer(&mut tx, ticket_tier_id, payload).await;
 
     // 5. Commit or rollback the transaction based on the result.
@@ -82,7 +134,383 @@ pub async fn create_offer(
     }
 }
 
+/// Service to update an offer. A new quantity is held to the same rules as on creation
+/// and can't drop below what the offer has sold.
+pub async fn update_offer(
+    pool: &PgPool,
+    offer_id: i32,
+    organizer_id: i32,
+    payload: &UpdateOfferPayload,
+) -> Result<Offer, AppError> {
+    // 1. Validate the payload and check the user organizes the offer's event.
+    validation::validate_payload(payload)?;
+    let offer = pricing_query::get_offer(pool, offer_id).await?;
+    let tier = pricing_query::get_ticket_tier(pool, offer.ticket_tier_id).await?;
+    authorize_organizer(pool, tier.event_id, organizer_id).await?;
+
+    // 2. Check a new quantity against the tier, holding it meanwhile.
+    let mut tx = pool.begin().await?;
+    let tier = pricing_query::lock_ticket_tier(&mut tx, tier.id).await?;
+    if let Some(quantity) = payload.quantity_for_sale {
+        let offer = pricing_query::get_offer(&mut *tx, offer_id).await?;
+        if quantity < offer.quantity_sold {
+            return Err(AppError::BadRequest(format!(
+                "This offer has already sold {} tickets.",
+                offer.quantity_sold
+            )));
+        }
+        check_offer_allocation(&mut tx, &tier, Some(offer_id), quantity, !offer.is_presale).await?;
+    }
+
+    // 3. Update the offer and the event's price range.
+    let offer = pricing_query::update_offer(&mut tx, offer_id, payload).await?;
+    tx.commit().await?;
+
+    Ok(offer)
+}
+
 /// Service to list all publicly available offers for an event.
 pub async fn list_public_offers_for_event(pool: &PgPool, event_id: i32) -> Result<Vec<Offer>, AppError> {
     pricing_query::list_public_offers_for_event(pool, event_id).await
-}
\ No newline at end of file
+}
+
+// --- Pricing Rule Services ---
+
+/// Service for an organizer to see an offer's pricing rules and what it sells at right now.
+pub async fn get_offer_pricing(pool: &PgPool, offer_id: i32, organizer_id: i32) -> Result<OfferPricing, AppError> {
+    let offer = pricing_query::get_offer(pool, offer_id).await?;
+    let tier = pricing_query::get_ticket_tier(pool, offer.ticket_tier_id).await?;
+    authorize_organizer(pool, tier.event_id, organizer_id).await?;
+    offer_pricing(pool, offer_id).await
+}
+
+/// Service to replace an offer's pricing rules and its floor and ceiling.
+/// Prices are recomputed at checkout from these rules, and the event's price range with them.
+pub async fn set_offer_pricing(
+    pool: &PgPool,
+    offer_id: i32,
+    organizer_id: i32,
+    payload: &SetOfferPricingPayload,
+) -> Result<OfferPricing, AppError> {
+    // 1. Validate the payload and check the user organizes the offer's event.
+    validation::validate_payload(payload)?;
+    check_price_rules(payload)?;
+    let offer = pricing_query::get_offer(pool, offer_id).await?;
+    let tier = pricing_query::get_ticket_tier(pool, offer.ticket_tier_id).await?;
+    authorize_organizer(pool, tier.event_id, organizer_id).await?;
+
+    // 2. Replace the rules and refresh the event's price range.
+    let mut tx = pool.begin().await?;
+    pricing_query::replace_price_rules(&mut tx, offer_id, payload).await?;
+    tx.commit().await?;
+
+    offer_pricing(pool, offer_id).await
+}
+
+// --- Bundle Services ---
+
+/// Service to list what each package of a bundle offer contains.
+pub async fn list_bundle_components(pool: &PgPool, offer_id: i32) -> Result<Vec<BundleComponent>, AppError> {
+    pricing_query::get_offer(pool, offer_id).await?;
+    pricing_query::list_bundle_components(pool, offer_id).await
+}
+
+/// Service to turn an offer into a bundle, or change what its packages contain.
+/// Components are general admission tickets and add-ons of the offer's own event, and
+/// every package holds at least one ticket. The offer's quantity then counts packages.
+pub async fn set_bundle_components(
+    pool: &PgPool,
+    offer_id: i32,
+    organizer_id: i32,
+    payload: &SetBundleComponentsPayload,
+) -> Result<Vec<BundleComponent>, AppError> {
+    // 1. Validate the payload and check the user organizes the offer's event.
+    validation::validate_payload(payload)?;
+    let offer = pricing_query::get_offer(pool, offer_id).await?;
+    let tier = pricing_query::get_ticket_tier(pool, offer.ticket_tier_id).await?;
+    authorize_organizer(pool, tier.event_id, organizer_id).await?;
+
+    // 2. Check every component belongs to the event and can be sold without picking a seat.
+    for component in &payload.components {
+        match (component.ticket_tier_id, component.addon_item_id) {
+            (Some(tier_id), None) => {
+                let component_tier = pricing_query::get_ticket_tier(pool, tier_id)
+                    .await
+                    .map_err(|_| AppError::BadRequest(format!("Ticket tier with ID {} not found.", tier_id)))?;
+                if component_tier.event_id != tier.event_id {
+                    return Err(AppError::BadRequest(format!("Ticket tier {} belongs to another event.", tier_id)));
+                }
+                if pricing_query::is_reserved_tier(pool, tier_id).await? {
+                    return Err(AppError::BadRequest(format!(
+                        "Ticket tier {} is sold by seat and can't be part of a bundle.",
+                        tier_id
+                    )));
+                }
+            }
+            (None, Some(addon_item_id)) => {
+                let addon = addon_query::get_addon_item(pool, addon_item_id)
+                    .await
+                    .map_err(|_| AppError::BadRequest(format!("Add-on with ID {} not found.", addon_item_id)))?;
+                if addon.event_id != tier.event_id {
+                    return Err(AppError::BadRequest(format!("Add-on {} belongs to another event.", addon_item_id)));
+                }
+                if addon.is_donation || !addon_query::list_variants(pool, &[addon_item_id]).await?.is_empty() {
+                    return Err(AppError::BadRequest(format!(
+                        "Add-on {} is a donation or sold in variants and can't be part of a bundle.",
+                        addon_item_id
+                    )));
+                }
+            }
+            _ => {
+                return Err(AppError::BadRequest(
+                    "Each component is either a ticket tier or an add-on.".to_string(),
+                ));
+            }
+        }
+    }
+    if !payload.components.iter().any(|c| c.ticket_tier_id.is_some()) {
+        return Err(AppError::BadRequest("A bundle must include at least one ticket.".to_string()));
+    }
+
+    // 3. Replace the components.
+    let mut tx = pool.begin().await?;
+    pricing_query::replace_bundle_components(&mut tx, offer_id, payload).await?;
+    tx.commit().await?;
+
+    pricing_query::list_bundle_components(pool, offer_id).await
+}
+
+// --- Background Job Service ---
+
+/// Service function for a background worker to keep the price ranges of events with
+/// dynamically priced offers current, as time passes and inventory comes back.
+/// Returns how many events' ranges changed.
+pub async fn refresh_dynamic_price_ranges(pool: &PgPool) -> Result<u64, AppError> {
+    let event_ids = pricing_query::list_dynamically_priced_events(pool).await?;
+    if event_ids.is_empty() {
+        return Ok(0);
+    }
+    pricing_query::refresh_price_ranges(pool, &event_ids).await
+}
+
+// --- Helpers ---
+
+/// Checks that a tier of `total_inventory` tickets fits in the venue next to the event's
+/// other tiers. Events without a venue, or at a venue without a known capacity, aren't limited.
+/// Locks the event until the end of the transaction.
+async fn check_venue_capacity(
+    tx: &mut Transaction<'_, Postgres>,
+    event_id: i32,
+    tier_id: Option<i32>,
+    total_inventory: i32,
+) -> Result<(), AppError> {
+    let (capacity, allocated) = pricing_query::get_event_allocation(tx, event_id, tier_id).await?;
+    if let Some(capacity) = capacity
+        && allocated + i64::from(total_inventory) > i64::from(capacity)
+    {
+        return Err(AppError::BadRequest(format!(
+            "The venue holds {} people and this event's other tiers already take {}.",
+            capacity, allocated
+        )));
+    }
+    Ok(())
+}
+
+/// Checks that an offer of `quantity` tickets fits its tier. Public offers must also fit
+/// together with the tier's other public offers.
+async fn check_offer_allocation(
+    tx: &mut Transaction<'_, Postgres>,
+    tier: &TicketTier,
+    offer_id: Option<i32>,
+    quantity: i32,
+    is_public: bool,
+) -> Result<(), AppError> {
+    if quantity > tier.total_inventory {
+        return Err(AppError::BadRequest(format!(
+            "An offer can't sell more than the tier's inventory of {}.",
+            tier.total_inventory
+        )));
+    }
+    if is_public {
+        let (public_allocated, _) = pricing_query::get_tier_allocation(tx, tier.id, offer_id).await?;
+        if public_allocated + i64::from(quantity) > i64::from(tier.total_inventory) {
+            return Err(AppError::BadRequest(format!(
+                "The tier's public offers already have {} of its {} tickets for sale.",
+                public_allocated, tier.total_inventory
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Checks the thresholds and adjustments of pricing rules make sense for their kind, and
+/// that the floor isn't above the ceiling.
+fn check_price_rules(payload: &SetOfferPricingPayload) -> Result<(), AppError> {
+    if let (Some(floor), Some(ceiling)) = (payload.price_floor, payload.price_ceiling)
+        && floor > ceiling
+    {
+        return Err(AppError::BadRequest("The price floor can't be above the ceiling.".to_string()));
+    }
+    for rule in &payload.rules {
+        match rule.kind {
+            PriceRuleKind::SellThrough if !(1..=100).contains(&rule.threshold) => {
+                return Err(AppError::BadRequest(
+                    "Sell-through thresholds are a percentage sold, between 1 and 100.".to_string(),
+                ));
+            }
+            PriceRuleKind::TimeToEvent if rule.threshold < 1 => {
+                return Err(AppError::BadRequest(
+                    "Time-to-event thresholds are hours before the event, at least 1.".to_string(),
+                ));
+            }
+            _ => {}
+        }
+        if rule.adjustment_type == PriceAdjustmentType::Percent && rule.adjustment <= Decimal::from(-100) {
+            return Err(AppError::BadRequest("A percentage discount must be less than 100%.".to_string()));
+        }
+    }
+    Ok(())
+}
+
+async fn offer_pricing(pool: &PgPool, offer_id: i32) -> Result<OfferPricing, AppError> {
+    let (base_price, current_price, price_floor, price_ceiling) =
+        pricing_query::get_offer_price_bounds(pool, offer_id).await?;
+    let rules = pricing_query::list_price_rules(pool, offer_id).await?;
+    Ok(OfferPricing { offer_id, base_price, current_price, price_floor, price_ceiling, rules })
+}
+
+async fn authorize_organizer(pool: &PgPool, event_id: i32, organizer_id: i32) -> Result<(), AppError> {
+    let event = event_query::get_by_id(pool, event_id).await?;
+    if event.organizer_id != organizer_id {
+        return Err(AppError::Forbidden("You are not authorized to manage pricing for this event.".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::payment_provider::FakePaymentProvider;
+    use crate::models::{pricing::PriceRulePayload, CreateOrderPayload, OrderItemPayload};
+    use crate::service::order_service;
+    use crate::utils::test_fixtures;
+
+    /// Checks out seats through an offer for a new user. Returns the order's subtotal.
+    async fn buy_seats(
+        pool: &PgPool,
+        provider: &FakePaymentProvider,
+        user: &str,
+        offer_id: i32,
+        seat_ids: &[i32],
+    ) -> Decimal {
+        let user_id = test_fixtures::create_user(pool, user).await;
+        let payload = CreateOrderPayload {
+            items: seat_ids
+                .iter()
+                .map(|&seat_id| OrderItemPayload { offer_id, seat_id: Some(seat_id), quantity: 1 })
+                .collect(),
+            addons: Vec::new(),
+            apply_credit: None,
+        };
+        let (order, _) = order_service::create_order(pool, provider, user_id, &payload, None).await.unwrap();
+        order.subtotal
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn sell_through_counts_reserved_seats(pool: PgPool) {
+        let provider = FakePaymentProvider::new();
+        let event_id = test_fixtures::create_event(&pool).await;
+        let organizer_id: i32 = sqlx::query_scalar("SELECT organizer_id FROM events WHERE id = $1")
+            .bind(event_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let tier_id = test_fixtures::create_tier(&pool, event_id, 4).await;
+        let offer_id = test_fixtures::create_offer(&pool, tier_id, Decimal::new(100, 0), 4).await;
+        let seat_ids = test_fixtures::create_seats(&pool, event_id, tier_id, 4).await;
+
+        // 20% more once half of the seats are taken.
+        let payload = SetOfferPricingPayload {
+            rules: vec![PriceRulePayload {
+                kind: PriceRuleKind::SellThrough,
+                threshold: 50,
+                adjustment_type: PriceAdjustmentType::Percent,
+                adjustment: Decimal::new(20, 0),
+            }],
+            price_floor: None,
+            price_ceiling: None,
+        };
+        set_offer_pricing(&pool, offer_id, organizer_id, &payload).await.unwrap();
+
+        assert_eq!(buy_seats(&pool, &provider, "first", offer_id, &seat_ids[..1]).await, Decimal::new(100, 0));
+        assert_eq!(offer_pricing(&pool, offer_id).await.unwrap().current_price, Decimal::new(100, 0));
+
+        // The second seat reaches the threshold, so the third sells at the higher price.
+        assert_eq!(buy_seats(&pool, &provider, "second", offer_id, &seat_ids[1..2]).await, Decimal::new(100, 0));
+        assert_eq!(offer_pricing(&pool, offer_id).await.unwrap().current_price, Decimal::new(120, 0));
+        assert_eq!(buy_seats(&pool, &provider, "third", offer_id, &seat_ids[2..3]).await, Decimal::new(120, 0));
+
+        // Seats given back by expired orders no longer count.
+        sqlx::query("UPDATE orders SET expires_at = NOW() - INTERVAL '1 minute'").execute(&pool).await.unwrap();
+        order_service::expire_stale_orders(&pool, &provider).await.unwrap();
+        assert_eq!(offer_pricing(&pool, offer_id).await.unwrap().current_price, Decimal::new(100, 0));
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn tiers_fit_the_venue_and_offers_fit_their_tier(pool: PgPool) {
+        let event_id = test_fixtures::create_event(&pool).await;
+        let organizer_id: i32 = sqlx::query_scalar(
+            "WITH venue AS (
+                 INSERT INTO venues (name, city, postal_code, country, capacity)
+                 VALUES ('Club', 'City', '1000', 'NL', 100) RETURNING id
+             )
+             UPDATE events SET venue_id = venue.id FROM venue WHERE events.id = $1 RETURNING organizer_id",
+        )
+        .bind(event_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let tier =
+            |total_inventory| CreateTicketTierPayload { name: "Floor".to_string(), description: None, total_inventory };
+        let offer = |quantity_for_sale, access_code: Option<&str>| CreateOfferPayload {
+            name: "Standard".to_string(),
+            price: Decimal::from(30),
+            quantity_for_sale,
+            sale_start_time: None,
+            sale_end_time: None,
+            access_code: access_code.map(str::to_string),
+            is_accessible: false,
+        };
+
+        // 1. Tiers share the venue's capacity.
+        let tier_id = create_ticket_tier(&pool, event_id, organizer_id, &tier(60)).await.unwrap().id;
+        assert!(matches!(
+            create_ticket_tier(&pool, event_id, organizer_id, &tier(50)).await,
+            Err(AppError::BadRequest(_))
+        ));
+
+        // 2. Public offers share the tier's inventory; a presale may overlap them, but no
+        //    offer may sell more than the tier holds.
+        create_offer(&pool, tier_id, organizer_id, &offer(40, None)).await.unwrap();
+        assert!(matches!(
+            create_offer(&pool, tier_id, organizer_id, &offer(30, None)).await,
+            Err(AppError::BadRequest(_))
+        ));
+        create_offer(&pool, tier_id, organizer_id, &offer(50, Some("FANS"))).await.unwrap();
+        assert!(matches!(
+            create_offer(&pool, tier_id, organizer_id, &offer(70, Some("CREW"))).await,
+            Err(AppError::BadRequest(_))
+        ));
+
+        // 3. The tier can shrink to its largest offer and grow to the venue's capacity.
+        let resize = |total_inventory| UpdateTicketTierPayload { name: None, description: None, total_inventory };
+        for total_inventory in [49, 101] {
+            assert!(matches!(
+                update_ticket_tier(&pool, tier_id, organizer_id, &resize(Some(total_inventory))).await,
+                Err(AppError::BadRequest(_))
+            ));
+        }
+        let updated = update_ticket_tier(&pool, tier_id, organizer_id, &resize(Some(50))).await.unwrap();
+        assert_eq!(updated.total_inventory, 50);
+    }
+}
@@ -0,0 +1,186 @@
+use crate::models::{EventUpdateNotification, SeatMapMessage, SeatStatusChange};
+use dashmap::DashMap;
+use tokio::sync::broadcast;
+
+/// Per-event broadcast channels for live seat map clients, keyed by event id.
+/// Each message is a JSON-encoded `SeatMapMessage`.
+pub type EventChannels = DashMap<i32, broadcast::Sender<String>>;
+
+/// How many messages a slow client may fall behind before it is told to resync.
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Subscribes to the live updates of an event, creating its channel on first use.
+pub fn subscribe(channels: &EventChannels, event_id: i32) -> broadcast::Receiver<String> {
+    channels
+        .entry(event_id)
+        .or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0)
+        .subscribe()
+}
+
+/// Drops an event's channel once its last client has disconnected.
+pub fn release(channels: &EventChannels, event_id: i32) {
+    channels.remove_if(&event_id, |_, sender| sender.receiver_count() == 0);
+}
+
+/// Relays a notification from the `event_updates` channel to this instance's clients
+/// watching the event. Events nobody is watching here are skipped.
+pub fn relay_notification(channels: &EventChannels, payload: &str) {
+    let notification: EventUpdateNotification = match serde_json::from_str(payload) {
+        Ok(notification) => notification,
+        Err(e) => {
+            tracing::error!("Ignoring malformed event update notification: {}", e);
+            return;
+        }
+    };
+
+    let event_id = notification.event_id;
+    let Some(sender) = channels.get(&event_id) else {
+        return;
+    };
+
+    if !notification.seats.is_empty() {
+        // The event id is carried once per notification rather than per seat.
+        let changes = notification
+            .seats
+            .into_iter()
+            .map(|change| SeatStatusChange { event_id, ..change })
+            .collect();
+        send(&sender, &SeatMapMessage::SeatStatus { event_id, changes });
+    }
+    if !notification.offers.is_empty() {
+        send(&sender, &SeatMapMessage::OfferInventory { event_id, offers: notification.offers });
+    }
+    if let Some(admitted_through) = notification.admitted_through {
+        send(&sender, &SeatMapMessage::QueueProgress { event_id, admitted_through });
+    }
+}
+
+/// Tells every client on this instance to refetch its seat map.
+/// Used when notifications may have been missed, e.g. after the listener reconnects.
+pub fn resync_all(channels: &EventChannels) {
+    for entry in channels.iter() {
+        let event_id = *entry.key();
+        send(entry.value(), &SeatMapMessage::Resync { event_id });
+    }
+}
+
+fn send(sender: &broadcast::Sender<String>, message: &SeatMapMessage) {
+    match serde_json::to_string(message) {
+        // An error only means nobody is listening any more.
+        Ok(json) => {
+            let _ = sender.send(json);
+        }
+        Err(e) => tracing::error!("Failed to serialize seat map update: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::realtime_query::{self, EVENT_UPDATES_CHANNEL};
+    use crate::models::SeatStatus;
+    use crate::utils::test_fixtures;
+    use rust_decimal::Decimal;
+    use sqlx::postgres::PgListener;
+    use sqlx::PgPool;
+
+    fn next_message(receiver: &mut broadcast::Receiver<String>) -> SeatMapMessage {
+        serde_json::from_str(&receiver.try_recv().unwrap()).unwrap()
+    }
+
+    fn seat_change(event_id: i32, seat_id: i32) -> SeatStatusChange {
+        SeatStatusChange { event_id, seat_id, status: SeatStatus::Sold }
+    }
+
+    #[test]
+    fn notifications_reach_only_the_event_watched() {
+        let channels = EventChannels::new();
+        let mut watching = subscribe(&channels, 1);
+        let mut other = subscribe(&channels, 2);
+
+        relay_notification(
+            &channels,
+            r#"{"event_id":1,"seats":[{"seat_id":7,"status":"Sold"}],"admitted_through":40}"#,
+        );
+        relay_notification(&channels, r#"{"event_id":3,"offers":[{"offer_id":1,"quantity_available":0}]}"#);
+        relay_notification(&channels, "not json");
+
+        match next_message(&mut watching) {
+            SeatMapMessage::SeatStatus { event_id, changes } => {
+                assert_eq!(event_id, 1);
+                assert_eq!(changes[0].seat_id, 7);
+                assert_eq!(changes[0].status, SeatStatus::Sold);
+            }
+            message => panic!("unexpected message: {:?}", message),
+        }
+        assert!(matches!(
+            next_message(&mut watching),
+            SeatMapMessage::QueueProgress { event_id: 1, admitted_through: 40 }
+        ));
+        assert!(watching.try_recv().is_err());
+        assert!(other.try_recv().is_err());
+        assert!(!channels.contains_key(&3));
+
+        resync_all(&channels);
+        assert!(matches!(next_message(&mut watching), SeatMapMessage::Resync { event_id: 1 }));
+        assert!(matches!(next_message(&mut other), SeatMapMessage::Resync { event_id: 2 }));
+    }
+
+    #[test]
+    fn channels_are_dropped_with_their_last_client() {
+        let channels = EventChannels::new();
+        let first = subscribe(&channels, 1);
+        let second = subscribe(&channels, 1);
+
+        drop(first);
+        release(&channels, 1);
+        assert!(channels.contains_key(&1));
+        drop(second);
+        release(&channels, 1);
+        assert!(!channels.contains_key(&1));
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn committed_changes_are_relayed_across_instances(pool: PgPool) {
+        let event_id = test_fixtures::create_event(&pool).await;
+        let tier_id = test_fixtures::create_tier(&pool, event_id, 100).await;
+        let offer_id = test_fixtures::create_offer(&pool, tier_id, Decimal::new(5000, 2), 40).await;
+        let mut listener = PgListener::connect_with(&pool).await.unwrap();
+        listener.listen(EVENT_UPDATES_CHANNEL).await.unwrap();
+        let channels = EventChannels::new();
+        let mut receiver = subscribe(&channels, event_id);
+
+        // Nothing is announced for a transaction that rolls back.
+        let mut tx = pool.begin().await.unwrap();
+        realtime_query::notify_seat_changes(&mut *tx, &[seat_change(event_id, 1)]).await.unwrap();
+        tx.rollback().await.unwrap();
+
+        // A large change is split to fit Postgres' payload limit.
+        let changes: Vec<_> = (1..=150).map(|seat_id| seat_change(event_id, seat_id)).collect();
+        let mut tx = pool.begin().await.unwrap();
+        realtime_query::notify_seat_changes(&mut *tx, &changes).await.unwrap();
+        realtime_query::notify_offer_inventory(&mut *tx, &[offer_id]).await.unwrap();
+        tx.commit().await.unwrap();
+
+        for _ in 0..3 {
+            let notification = listener.recv().await.unwrap();
+            relay_notification(&channels, notification.payload());
+        }
+
+        let mut seats = 0;
+        for _ in 0..2 {
+            match next_message(&mut receiver) {
+                SeatMapMessage::SeatStatus { changes, .. } => seats += changes.len(),
+                message => panic!("unexpected message: {:?}", message),
+            }
+        }
+        assert_eq!(seats, 150);
+        match next_message(&mut receiver) {
+            SeatMapMessage::OfferInventory { offers, .. } => {
+                assert_eq!((offers[0].offer_id, offers[0].quantity_available), (offer_id, 40));
+            }
+            message => panic!("unexpected message: {:?}", message),
+        }
+        assert!(receiver.try_recv().is_err());
+    }
+}
@@ -0,0 +1,469 @@
+use std::collections::HashMap;
+
+use crate::{
+    clients::payment_provider::{PaymentProvider, ProviderPaymentIntent, ProviderPaymentStatus},
+    db::{order_query, payment_query, reconciliation_query},
+    errors::AppError,
+    models::{
+        DiscrepancyKind, NewDiscrepancy, OrderStatus, Payment, PaymentStatus, ReconciliationDiscrepancy,
+        ReconciliationRun,
+    },
+    service::{order_service, payment_service, settlement_service},
+};
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+
+/// How far back each run looks for PaymentIntents and payments.
+/// Longer than a day so a run that failed the night before is covered by the next one.
+pub const DEFAULT_WINDOW_HOURS: i64 = 48;
+
+/// How many runs the admin listing returns.
+const RUN_LIST_LIMIT: i64 = 50;
+
+/// Running totals for a reconciliation run.
+#[derive(Default)]
+struct RunTotals {
+    intents_checked: i32,
+    auto_resolved: i32,
+    discrepancies_found: i32,
+}
+
+/// Compares the provider's PaymentIntents from the last `window` with our `payments` and
+/// `orders`, fixes what is safe to fix and records everything else as a discrepancy.
+///
+/// The run itself is recorded, so a failure (e.g. the provider being unreachable)
+/// shows up in the admin report instead of only in the logs.
+pub async fn run_reconciliation<P: PaymentProvider>(
+    pool: &PgPool,
+    provider: &P,
+    window: Duration,
+) -> Result<ReconciliationRun, AppError> {
+    let window_start = Utc::now() - window;
+    let run = reconciliation_query::create_run(pool, window_start).await?;
+
+    match reconcile(pool, provider, run.id, window_start).await {
+        Ok(totals) => {
+            tracing::info!(
+                "Reconciliation run {}: {} intents checked, {} auto-resolved, {} discrepancies.",
+                run.id,
+                totals.intents_checked,
+                totals.auto_resolved,
+                totals.discrepancies_found
+            );
+            reconciliation_query::complete_run(
+                pool,
+                run.id,
+                totals.intents_checked,
+                totals.auto_resolved,
+                totals.discrepancies_found,
+            )
+            .await
+        }
+        Err(e) => {
+            tracing::error!("Reconciliation run {} failed: {:?}", run.id, e);
+            reconciliation_query::fail_run(pool, run.id, &e.to_string()).await?;
+            Err(e)
+        }
+    }
+}
+
+/// Service to list recent reconciliation runs.
+pub async fn list_runs(pool: &PgPool) -> Result<Vec<ReconciliationRun>, AppError> {
+    reconciliation_query::list_runs(pool, RUN_LIST_LIMIT).await
+}
+
+/// Service to fetch the discrepancy report of a run.
+pub async fn list_discrepancies(
+    pool: &PgPool,
+    run_id: i32,
+) -> Result<Vec<ReconciliationDiscrepancy>, AppError> {
+    reconciliation_query::list_discrepancies_for_run(pool, run_id).await
+}
+
+async fn reconcile<P: PaymentProvider>(
+    pool: &PgPool,
+    provider: &P,
+    run_id: i32,
+    window_start: chrono::DateTime<Utc>,
+) -> Result<RunTotals, AppError> {
+    let mut totals = RunTotals::default();
+
+    // 1. Load our side first, so any payment created while we talk to the provider
+    //    can't be mistaken for one the provider doesn't know about.
+    let mut payments: HashMap<String, Payment> = payment_query::list_created_since(pool, window_start)
+        .await?
+        .into_iter()
+        .map(|p| (p.stripe_payment_intent_id.clone(), p))
+        .collect();
+
+    // 2. Compare every PaymentIntent the provider has with our payment for it.
+    let intents = provider.list_payment_intents_since(window_start).await?;
+    for intent in &intents {
+        totals.intents_checked += 1;
+        let payment = payments.remove(&intent.id);
+        if let Some(discrepancy) = check_intent(pool, provider, intent, payment.as_ref()).await? {
+            if discrepancy.auto_resolved {
+                totals.auto_resolved += 1;
+            } else {
+                totals.discrepancies_found += 1;
+            }
+            reconciliation_query::insert_discrepancy(pool, run_id, &discrepancy).await?;
+        }
+    }
+
+    // 3. Pending payments the provider didn't return can never complete.
+    for payment in payments.values().filter(|p| matches!(p.status, PaymentStatus::Pending)) {
+        totals.discrepancies_found += 1;
+        let discrepancy = new_discrepancy(
+            DiscrepancyKind::MissingPaymentIntent,
+            &payment.stripe_payment_intent_id,
+            None,
+            Some(payment),
+            false,
+            "The provider has no PaymentIntent for this pending payment.".to_string(),
+        );
+        reconciliation_query::insert_discrepancy(pool, run_id, &discrepancy).await?;
+    }
+
+    Ok(totals)
+}
+
+/// Compares one PaymentIntent with our payment for it.
+/// Runs the finalize path for missed successes; returns `None` when both sides agree.
+async fn check_intent<P: PaymentProvider>(
+    pool: &PgPool,
+    provider: &P,
+    intent: &ProviderPaymentIntent,
+    payment: Option<&Payment>,
+) -> Result<Option<NewDiscrepancy>, AppError> {
+    // A PaymentIntent we have no record of only matters if money was taken.
+    let Some(payment) = payment else {
+        if intent.status == ProviderPaymentStatus::Succeeded {
+            return Ok(Some(new_discrepancy(
+                DiscrepancyKind::UnknownPaymentIntent,
+                &intent.id,
+                Some(intent),
+                None,
+                false,
+                "The customer was charged but we have no payment for this PaymentIntent.".to_string(),
+            )));
+        }
+        return Ok(None);
+    };
+
+    // Never auto-fix a payment whose amount we don't agree on.
+    let provider_amount = settlement_service::cents_to_decimal(intent.amount);
+    if provider_amount != payment.amount_charged || !intent.currency.eq_ignore_ascii_case(&payment.currency) {
+        return Ok(Some(new_discrepancy(
+            DiscrepancyKind::AmountMismatch,
+            &intent.id,
+            Some(intent),
+            Some(payment),
+            false,
+            format!(
+                "We recorded {} {} but the PaymentIntent is for {} {}.",
+                payment.amount_charged, payment.currency, provider_amount, intent.currency
+            ),
+        )));
+    }
+
+    let discrepancy = match (&payment.status, intent.status) {
+        // The webhook was missed. Finalizing is safe as long as the order is still waiting for it.
+        (PaymentStatus::Pending, ProviderPaymentStatus::Succeeded) => {
+            let order = order_query::get_by_id(pool, payment.order_id).await?;
+            if matches!(order.status, OrderStatus::Pending) {
+                let tickets = payment_service::finalize_order_on_payment_success(pool, provider, &intent.id).await?;
+                new_discrepancy(
+                    DiscrepancyKind::MissedSuccess,
+                    &intent.id,
+                    Some(intent),
+                    Some(payment),
+                    true,
+                    format!("Finalized the order and issued {} tickets.", tickets.len()),
+                )
+            } else {
+                new_discrepancy(
+                    DiscrepancyKind::StatusMismatch,
+                    &intent.id,
+                    Some(intent),
+                    Some(payment),
+                    false,
+                    format!(
+                        "The customer was charged but the order is {:?}; refund or restore it manually.",
+                        order.status
+                    ),
+                )
+            }
+        }
+        // We gave up on the payment, but it went through and its webhook was missed. The
+        // finalize path refunds it, or pays for a group booking's shares if they're still held.
+        (PaymentStatus::Failed, ProviderPaymentStatus::Succeeded) => {
+            let tickets = payment_service::finalize_order_on_payment_success(pool, provider, &intent.id).await?;
+            let outcome = if tickets.is_empty() {
+                "refunded the customer".to_string()
+            } else {
+                format!("issued {} tickets", tickets.len())
+            };
+            new_discrepancy(
+                DiscrepancyKind::StatusMismatch,
+                &intent.id,
+                Some(intent),
+                Some(payment),
+                true,
+                format!("The customer was charged after we gave up on the payment; {}.", outcome),
+            )
+        }
+        // The payment can't go through any more: its PaymentIntent was cancelled, or the customer
+        // never finished paying before the checkout window passed. The order is cancelled like an
+        // expired one, which fails the payment and releases the tickets.
+        (PaymentStatus::Pending, ProviderPaymentStatus::Canceled | ProviderPaymentStatus::RequiresAction) => {
+            let order = order_query::get_by_id(pool, payment.order_id).await?;
+            if intent.status == ProviderPaymentStatus::RequiresAction {
+                if order.expires_at > Utc::now() {
+                    return Ok(None);
+                }
+                if let Err(e) = provider.cancel_payment_intent(&intent.id).await {
+                    tracing::warn!("Failed to cancel expired PaymentIntent {}: {:?}", intent.id, e);
+                    return Ok(None);
+                }
+            }
+
+            if order_service::cancel_unpaid_order(pool, order.id).await? {
+                new_discrepancy(
+                    DiscrepancyKind::StatusMismatch,
+                    &intent.id,
+                    Some(intent),
+                    Some(payment),
+                    true,
+                    "The payment can't go through any more; cancelled the order and released its tickets."
+                        .to_string(),
+                )
+            } else {
+                new_discrepancy(
+                    DiscrepancyKind::StatusMismatch,
+                    &intent.id,
+                    Some(intent),
+                    Some(payment),
+                    false,
+                    format!(
+                        "The payment can't go through any more but the order is {:?}; resolve it manually.",
+                        order.status
+                    ),
+                )
+            }
+        }
+        // In-flight or settled payments where both sides agree.
+        (PaymentStatus::Pending, ProviderPaymentStatus::Processing)
+        | (PaymentStatus::Succeeded | PaymentStatus::Refunded, ProviderPaymentStatus::Succeeded)
+        | (PaymentStatus::Failed, ProviderPaymentStatus::Canceled | ProviderPaymentStatus::RequiresAction) => {
+            return Ok(None);
+        }
+        _ => new_discrepancy(
+            DiscrepancyKind::StatusMismatch,
+            &intent.id,
+            Some(intent),
+            Some(payment),
+            false,
+            format!(
+                "Our payment is {} but the PaymentIntent is {}.",
+                payment.status.as_str(),
+                intent.status.as_str()
+            ),
+        ),
+    };
+
+    Ok(Some(discrepancy))
+}
+
+fn new_discrepancy(
+    kind: DiscrepancyKind,
+    stripe_payment_intent_id: &str,
+    intent: Option<&ProviderPaymentIntent>,
+    payment: Option<&Payment>,
+    auto_resolved: bool,
+    details: String,
+) -> NewDiscrepancy {
+    NewDiscrepancy {
+        kind,
+        stripe_payment_intent_id: stripe_payment_intent_id.to_string(),
+        payment_id: payment.map(|p| p.id),
+        order_id: payment.map(|p| p.order_id),
+        our_status: payment.map(|p| p.status.as_str().to_string()),
+        provider_status: intent.map(|i| i.status.as_str().to_string()),
+        our_amount: payment.map(|p| p.amount_charged),
+        provider_amount: intent.map(|i| settlement_service::cents_to_decimal(i.amount)),
+        auto_resolved,
+        details,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::payment_provider::FakePaymentProvider;
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
+    /// Creates a pending order for the buyer with a pending payment of `recorded` for a fake
+    /// PaymentIntent of `charged_cents`. Returns the order and the PaymentIntent's ID.
+    async fn seed_pending_payment(
+        pool: &PgPool,
+        provider: &FakePaymentProvider,
+        recorded: Decimal,
+        charged_cents: i64,
+    ) -> (Uuid, String) {
+        let user_id: i32 = sqlx::query_scalar(
+            "INSERT INTO users (email, username, password_hash) VALUES ('buyer@example.com', 'buyer', 'x')
+             ON CONFLICT (email) DO UPDATE SET username = EXCLUDED.username RETURNING id",
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        let order_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO orders (user_id, subtotal, service_fee, total_amount, expires_at, status)
+             VALUES ($1, $2, 0, $2, NOW() + INTERVAL '15 minutes', 'pending') RETURNING id",
+        )
+        .bind(user_id)
+        .bind(recorded)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+
+        let intent = provider.create_payment_intent(charged_cents, "usd").await.unwrap();
+        let mut tx = pool.begin().await.unwrap();
+        payment_query::create_pending_payment(&mut tx, order_id, recorded, &intent.id).await.unwrap();
+        tx.commit().await.unwrap();
+        (order_id, intent.id)
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn missed_success_is_finalized(pool: PgPool) {
+        let provider = FakePaymentProvider::new();
+        let (order_id, intent_id) = seed_pending_payment(&pool, &provider, Decimal::new(5000, 2), 5000).await;
+        assert!(provider.set_status(&intent_id, ProviderPaymentStatus::Succeeded));
+
+        let run = run_reconciliation(&pool, &provider, Duration::hours(DEFAULT_WINDOW_HOURS)).await.unwrap();
+        assert_eq!((run.intents_checked, run.auto_resolved, run.discrepancies_found), (1, 1, 0));
+
+        let discrepancies = list_discrepancies(&pool, run.id).await.unwrap();
+        assert_eq!(discrepancies.len(), 1);
+        assert_eq!(discrepancies[0].kind, DiscrepancyKind::MissedSuccess);
+        assert!(discrepancies[0].auto_resolved);
+
+        let order = order_query::get_by_id(&pool, order_id).await.unwrap();
+        assert!(matches!(order.status, OrderStatus::Completed));
+        let mut tx = pool.begin().await.unwrap();
+        let payment = payment_query::get_by_payment_intent_id(&mut tx, &intent_id).await.unwrap();
+        assert!(matches!(payment.status, PaymentStatus::Succeeded));
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn amount_mismatch_is_reported_not_fixed(pool: PgPool) {
+        let provider = FakePaymentProvider::new();
+        let (order_id, intent_id) = seed_pending_payment(&pool, &provider, Decimal::new(5000, 2), 6000).await;
+        assert!(provider.set_status(&intent_id, ProviderPaymentStatus::Succeeded));
+
+        let run = run_reconciliation(&pool, &provider, Duration::hours(DEFAULT_WINDOW_HOURS)).await.unwrap();
+        assert_eq!((run.intents_checked, run.auto_resolved, run.discrepancies_found), (1, 0, 1));
+
+        let discrepancies = list_discrepancies(&pool, run.id).await.unwrap();
+        assert_eq!(discrepancies.len(), 1);
+        assert_eq!(discrepancies[0].kind, DiscrepancyKind::AmountMismatch);
+        assert!(!discrepancies[0].auto_resolved);
+        assert_eq!(discrepancies[0].provider_amount, Some(Decimal::new(6000, 2)));
+
+        let order = order_query::get_by_id(&pool, order_id).await.unwrap();
+        assert!(matches!(order.status, OrderStatus::Pending));
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn status_mismatch_is_reported_not_fixed(pool: PgPool) {
+        let provider = FakePaymentProvider::new();
+        let (order_id, intent_id) = seed_pending_payment(&pool, &provider, Decimal::new(5000, 2), 5000).await;
+        sqlx::query("UPDATE orders SET status = 'completed' WHERE id = $1").bind(order_id).execute(&pool).await.unwrap();
+        sqlx::query("UPDATE payments SET status = 'succeeded' WHERE order_id = $1")
+            .bind(order_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(provider.set_status(&intent_id, ProviderPaymentStatus::Canceled));
+
+        let run = run_reconciliation(&pool, &provider, Duration::hours(DEFAULT_WINDOW_HOURS)).await.unwrap();
+        assert_eq!((run.intents_checked, run.auto_resolved, run.discrepancies_found), (1, 0, 1));
+
+        let discrepancies = list_discrepancies(&pool, run.id).await.unwrap();
+        assert_eq!(discrepancies.len(), 1);
+        assert_eq!(discrepancies[0].kind, DiscrepancyKind::StatusMismatch);
+        assert!(!discrepancies[0].auto_resolved);
+
+        let order = order_query::get_by_id(&pool, order_id).await.unwrap();
+        assert!(matches!(order.status, OrderStatus::Completed));
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn cancelled_intent_cancels_the_order(pool: PgPool) {
+        let provider = FakePaymentProvider::new();
+        let (order_id, intent_id) = seed_pending_payment(&pool, &provider, Decimal::new(5000, 2), 5000).await;
+        assert!(provider.set_status(&intent_id, ProviderPaymentStatus::Canceled));
+
+        let run = run_reconciliation(&pool, &provider, Duration::hours(DEFAULT_WINDOW_HOURS)).await.unwrap();
+        assert_eq!((run.intents_checked, run.auto_resolved, run.discrepancies_found), (1, 1, 0));
+
+        let discrepancies = list_discrepancies(&pool, run.id).await.unwrap();
+        assert_eq!(discrepancies.len(), 1);
+        assert_eq!(discrepancies[0].kind, DiscrepancyKind::StatusMismatch);
+        assert!(discrepancies[0].auto_resolved);
+
+        let order = order_query::get_by_id(&pool, order_id).await.unwrap();
+        assert!(matches!(order.status, OrderStatus::Cancelled));
+        let mut tx = pool.begin().await.unwrap();
+        let payment = payment_query::get_by_payment_intent_id(&mut tx, &intent_id).await.unwrap();
+        assert!(matches!(payment.status, PaymentStatus::Failed));
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn unfinished_payment_is_cancelled_once_the_order_expires(pool: PgPool) {
+        let provider = FakePaymentProvider::new();
+        let (open_order_id, open_intent_id) =
+            seed_pending_payment(&pool, &provider, Decimal::new(5000, 2), 5000).await;
+        let (order_id, intent_id) = seed_pending_payment(&pool, &provider, Decimal::new(2500, 2), 2500).await;
+        sqlx::query("UPDATE orders SET expires_at = NOW() - INTERVAL '1 minute' WHERE id = $1")
+            .bind(order_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let run = run_reconciliation(&pool, &provider, Duration::hours(DEFAULT_WINDOW_HOURS)).await.unwrap();
+        assert_eq!((run.intents_checked, run.auto_resolved, run.discrepancies_found), (2, 1, 0));
+
+        assert_eq!(provider.status(&intent_id), Some(ProviderPaymentStatus::Canceled));
+        let order = order_query::get_by_id(&pool, order_id).await.unwrap();
+        assert!(matches!(order.status, OrderStatus::Cancelled));
+
+        // The customer is still paying for the other order.
+        assert_eq!(provider.status(&open_intent_id), Some(ProviderPaymentStatus::RequiresAction));
+        let order = order_query::get_by_id(&pool, open_order_id).await.unwrap();
+        assert!(matches!(order.status, OrderStatus::Pending));
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn late_success_after_expiry_is_refunded(pool: PgPool) {
+        let provider = FakePaymentProvider::new();
+        let (order_id, intent_id) = seed_pending_payment(&pool, &provider, Decimal::new(5000, 2), 5000).await;
+        sqlx::query("UPDATE orders SET status = 'cancelled' WHERE id = $1").bind(order_id).execute(&pool).await.unwrap();
+        sqlx::query("UPDATE payments SET status = 'failed' WHERE order_id = $1")
+            .bind(order_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(provider.set_status(&intent_id, ProviderPaymentStatus::Succeeded));
+
+        let run = run_reconciliation(&pool, &provider, Duration::hours(DEFAULT_WINDOW_HOURS)).await.unwrap();
+        assert_eq!((run.intents_checked, run.auto_resolved, run.discrepancies_found), (1, 1, 0));
+
+        assert!(provider.is_refunded(&intent_id));
+        let mut tx = pool.begin().await.unwrap();
+        let payment = payment_query::get_by_payment_intent_id(&mut tx, &intent_id).await.unwrap();
+        assert!(matches!(payment.status, PaymentStatus::Refunded));
+    }
+}
@@ -0,0 +1,224 @@
+use std::ops::Range;
+
+use crate::{
+    errors::AppError,
+    models::{RowSeat, SeatStatus},
+};
+use rust_decimal::prelude::ToPrimitive;
+
+/// Two seats in a row are side by side if their centers are at most this many seat widths
+/// apart. Anything wider is an aisle or a gap in the layout.
+const ADJACENT_SEAT_WIDTHS: f64 = 1.5;
+
+/// A seat's rectangle on the map: x, y, width and height.
+pub type SeatRect = (f64, f64, f64, f64);
+
+/// Checks that taking the selected seats doesn't leave a single empty seat between two
+/// taken seats that wasn't there before. `row_seats` must be in row order.
+///
+/// A row is only refused if the same number of seats can be taken from it without leaving
+/// a gap; otherwise the gap can't be helped. The error suggests those seats instead,
+/// together with the selected seats of every other row.
+pub fn check_selection(row_seats: &[RowSeat]) -> Result<(), AppError> {
+    let mut problem_rows = Vec::new();
+    let mut suggested_seat_ids = Vec::new();
+
+    for row in row_seats.chunk_by(|a, b| a.event_id == b.event_id && a.row_id == b.row_id) {
+        let selected: Vec<usize> = (0..row.len()).filter(|&i| row[i].selected).collect();
+        if selected.is_empty() {
+            continue;
+        }
+
+        // 1. Free seats before the selection (the selected seats still count as free)
+        //    and after it.
+        let free_before: Vec<bool> = row.iter().map(|s| s.status == SeatStatus::Available || s.selected).collect();
+        let free_after: Vec<bool> = row.iter().map(|s| s.status == SeatStatus::Available && !s.selected).collect();
+        let blocks = side_by_side_blocks(row, |_, _| true, rect);
+        let leaves_gap = blocks.iter().any(|block| {
+            block.clone().any(|i| {
+                is_stranded(&free_after[block.clone()], i - block.start)
+                    && !is_stranded(&free_before[block.clone()], i - block.start)
+            })
+        });
+
+        // 2. Look for as many seats in the row that leave no gap, as close as possible
+        //    to the selection.
+        let alternative = if leaves_gap { suggest_for_row(&free_before, &blocks, &selected) } else { None };
+        match alternative {
+            Some(seats) => {
+                problem_rows.push(format!("row {} of section {}", row[0].row_name, row[0].section_name));
+                suggested_seat_ids.extend(seats.map(|i| row[i].seat_id));
+            }
+            None => suggested_seat_ids.extend(selected.iter().map(|&i| row[i].seat_id)),
+        }
+    }
+
+    if problem_rows.is_empty() {
+        return Ok(());
+    }
+    Err(AppError::SeatGap {
+        message: format!(
+            "Your selection would leave a single empty seat in {}. Please choose the suggested seats instead.",
+            problem_rows.join(", ")
+        ),
+        suggested_seat_ids,
+    })
+}
+
+/// Whether taking the free seats `start..end` of a block of side-by-side seats would
+/// leave a single empty seat next to them.
+pub fn window_leaves_gap(free: &[bool], start: usize, end: usize) -> bool {
+    let left = start >= 2 && free[start - 1] && !free[start - 2];
+    let right = end + 1 < free.len() && free[end] && !free[end + 1];
+    left || right
+}
+
+/// Splits seats in row order into blocks of side-by-side seats, breaking at the end of
+/// every row and at aisles.
+pub fn side_by_side_blocks<T>(
+    seats: &[T],
+    same_row: impl Fn(&T, &T) -> bool,
+    rect: impl Fn(&T) -> SeatRect,
+) -> Vec<Range<usize>> {
+    let mut blocks = Vec::new();
+    let mut start = 0;
+    for i in 1..=seats.len() {
+        if i < seats.len() && same_row(&seats[i - 1], &seats[i]) && are_side_by_side(rect(&seats[i - 1]), rect(&seats[i])) {
+            continue;
+        }
+        if start < i {
+            blocks.push(start..i);
+        }
+        start = i;
+    }
+    blocks
+}
+
+/// Whether two seats next to each other in a row are close enough to sit side by side.
+pub fn are_side_by_side(a: SeatRect, b: SeatRect) -> bool {
+    let center = |(x, y, w, h): SeatRect| (x + w / 2.0, y + h / 2.0);
+    let (a_center, b_center) = (center(a), center(b));
+    (a_center.0 - b_center.0).hypot(a_center.1 - b_center.1) <= a.2.max(b.2) * ADJACENT_SEAT_WIDTHS
+}
+
+/// The seats of a row that leave no gap, as many as are selected and closest to them,
+/// or `None` if there are no such seats.
+fn suggest_for_row(free: &[bool], blocks: &[Range<usize>], selected: &[usize]) -> Option<Range<usize>> {
+    let quantity = selected.len();
+    let middle = selected.iter().sum::<usize>() as f64 / quantity as f64;
+
+    let mut best: Option<(f64, usize)> = None;
+    for block in blocks {
+        let block_free = &free[block.clone()];
+        for start in block.start..block.end.saturating_sub(quantity - 1) {
+            let end = start + quantity;
+            if !free[start..end].iter().all(|&f| f)
+                || window_leaves_gap(block_free, start - block.start, end - block.start)
+            {
+                continue;
+            }
+            let cost: f64 = (start..end).map(|i| (i as f64 - middle).abs()).sum();
+            if best.is_none_or(|(best_cost, _)| cost < best_cost) {
+                best = Some((cost, start));
+            }
+        }
+    }
+    best.map(|(_, start)| start..start + quantity)
+}
+
+/// Whether the seat at `index` is a single free seat with a taken seat on both sides.
+fn is_stranded(free: &[bool], index: usize) -> bool {
+    index > 0 && index + 1 < free.len() && free[index] && !free[index - 1] && !free[index + 1]
+}
+
+fn rect(seat: &RowSeat) -> SeatRect {
+    (
+        seat.pos_x.to_f64().unwrap_or_default(),
+        seat.pos_y.to_f64().unwrap_or_default(),
+        seat.width.to_f64().unwrap_or_default(),
+        seat.height.to_f64().unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    /// A row drawn as text: `.` is a free seat, `x` a taken one, `S` a selected one and `|`
+    /// an aisle. Seat IDs are `row_id * 100` plus the seat's position in the row.
+    fn row(row_id: i32, pattern: &str) -> Vec<RowSeat> {
+        let mut x = 0;
+        let mut seats = Vec::new();
+        for c in pattern.chars() {
+            if c == '|' {
+                x += 40;
+                continue;
+            }
+            seats.push(RowSeat {
+                event_id: 1,
+                seat_id: row_id * 100 + seats.len() as i32,
+                row_id,
+                section_name: "101".to_string(),
+                row_name: ((b'A' + row_id as u8 - 1) as char).to_string(),
+                pos_x: Decimal::from(x),
+                pos_y: Decimal::from(row_id * 30),
+                width: Decimal::from(20),
+                height: Decimal::from(20),
+                status: if c == 'x' { SeatStatus::Sold } else { SeatStatus::Available },
+                selected: c == 'S',
+            });
+            x += 24;
+        }
+        seats
+    }
+
+    fn suggestion(result: Result<(), AppError>) -> (String, Vec<i32>) {
+        match result {
+            Err(AppError::SeatGap { message, suggested_seat_ids }) => (message, suggested_seat_ids),
+            other => panic!("expected a seat gap error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn selections_that_leave_no_new_gap_are_accepted() {
+        assert!(check_selection(&row(1, "..SS..")).is_ok());
+        // A seat at the end of a row, or next to an aisle, is never stranded.
+        assert!(check_selection(&row(1, ".S....")).is_ok());
+        assert!(check_selection(&row(1, "x.|S...")).is_ok());
+        // The gap was there before the selection.
+        assert!(check_selection(&row(1, "x.xS..")).is_ok());
+        // Any two of these seats strand the third, so the gap can't be helped.
+        assert!(check_selection(&row(1, "xSS.xx")).is_ok());
+    }
+
+    #[test]
+    fn gaps_are_refused_with_a_suggestion() {
+        let (message, suggested) = suggestion(check_selection(&row(1, "x.S...")));
+        assert!(message.contains("row A of section 101"), "{}", message);
+        assert_eq!(suggested, vec![101]);
+
+        let (_, suggested) = suggestion(check_selection(&row(1, "x.SS...x")));
+        assert_eq!(suggested, vec![101, 102]);
+    }
+
+    #[test]
+    fn other_rows_keep_their_selection_in_the_suggestion() {
+        let mut seats = row(1, "..SS..");
+        seats.extend(row(2, "...S.x"));
+
+        let (message, suggested) = suggestion(check_selection(&seats));
+        assert!(message.contains("row B") && !message.contains("row A"), "{}", message);
+        // Seats 202 and 204 are as close to the selection; the first one wins.
+        assert_eq!(suggested, vec![102, 103, 202]);
+    }
+
+    #[test]
+    fn windows_that_strand_a_seat_are_detected() {
+        let free = [false, true, true, true, true, false];
+        assert!(window_leaves_gap(&free, 2, 4));
+        assert!(window_leaves_gap(&free, 1, 4));
+        assert!(!window_leaves_gap(&free, 1, 3));
+        assert!(!window_leaves_gap(&free, 1, 5));
+    }
+}
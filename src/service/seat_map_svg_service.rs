@@ -0,0 +1,264 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+
+use crate::models::{SeatMapSeat, SeatStatus};
+use rust_decimal::prelude::ToPrimitive;
+
+/// Space around the seats, in map units.
+const MARGIN: f64 = 24.0;
+/// Height of one legend entry.
+const LEGEND_LINE_HEIGHT: f64 = 22.0;
+const LABEL_FONT_SIZE: f64 = 14.0;
+
+/// Fill colors for available seats, one per ticket tier in tier order.
+/// Repeats for events with more tiers than colors.
+const TIER_COLORS: [&str; 8] =
+    ["#1e88e5", "#43a047", "#8e24aa", "#f4511e", "#00897b", "#c0ca33", "#6d4c41", "#d81b60"];
+const LOCKED_COLOR: &str = "#ffcc80";
+const SOLD_COLOR: &str = "#bdbdbd";
+const UNAVAILABLE_COLOR: &str = "#eeeeee";
+const HIGHLIGHT_COLOR: &str = "#ffd600";
+const HIGHLIGHT_STROKE: &str = "#212121";
+
+/// Draws an event's seat map as a standalone SVG document.
+///
+/// Available seats are filled with their tier's color, other seats by status, and the
+/// seats in `highlight` are drawn as selected. Each section is labelled above its seats
+/// and a legend of tiers and statuses is drawn below the map. Every seat carries its
+/// ID in `data-seat-id` and a tooltip, so clients can make the map interactive.
+pub fn render(seats: &[SeatMapSeat], highlight: &HashSet<i32>) -> String {
+    // 1. Work out the bounds of the map and the extent of each section.
+    let mut bounds = Bounds::default();
+    let mut sections: BTreeMap<&str, Bounds> = BTreeMap::new();
+    for seat in seats {
+        let (x, y, w, h) = rect(seat);
+        bounds.include(x, y, w, h);
+        sections.entry(seat.section_name.as_str()).or_default().include(x, y, w, h);
+    }
+    if seats.is_empty() {
+        bounds.include(0.0, 0.0, 0.0, 0.0);
+    }
+
+    // 2. Give each tier a color, in tier order.
+    let mut tiers: BTreeMap<i32, &str> = BTreeMap::new();
+    for seat in seats {
+        tiers.insert(seat.ticket_tier_id, &seat.ticket_tier_name);
+    }
+    let tier_color = |tier_id: i32| {
+        let index = tiers.keys().position(|&id| id == tier_id).unwrap_or(0);
+        TIER_COLORS[index % TIER_COLORS.len()]
+    };
+
+    // 3. Size the canvas: room above for section labels, below for the legend.
+    let legend_entries = tiers.len() + 3 + usize::from(!highlight.is_empty());
+    let min_x = bounds.min_x - MARGIN;
+    let min_y = bounds.min_y - MARGIN - LABEL_FONT_SIZE;
+    let width = (bounds.max_x - bounds.min_x) + 2.0 * MARGIN;
+    let legend_top = bounds.max_y + MARGIN;
+    let height = legend_top + legend_entries as f64 * LEGEND_LINE_HEIGHT + MARGIN - min_y;
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{:.2} {:.2} {:.2} {:.2}" width="{:.0}" height="{:.0}" font-family="sans-serif">"#,
+        min_x, min_y, width, height, width, height
+    );
+    let _ = write!(
+        svg,
+        r##"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="#ffffff"/>"##,
+        min_x, min_y, width, height
+    );
+
+    // 4. Seats.
+    svg.push_str(r#"<g class="seats">"#);
+    for seat in seats {
+        let (x, y, w, h) = rect(seat);
+        let selected = highlight.contains(&seat.seat_id);
+        let fill = if selected {
+            HIGHLIGHT_COLOR
+        } else {
+            match seat.status {
+                SeatStatus::Available => tier_color(seat.ticket_tier_id),
+                SeatStatus::Locked => LOCKED_COLOR,
+                SeatStatus::Sold => SOLD_COLOR,
+                // Buyers don't need to know why a seat is off sale.
+                SeatStatus::Unavailable | SeatStatus::Held => UNAVAILABLE_COLOR,
+            }
+        };
+        let stroke = if selected { format!(r#" stroke="{}" stroke-width="2""#, HIGHLIGHT_STROKE) } else { String::new() };
+        let _ = write!(
+            svg,
+            r#"<rect class="seat seat-{}" data-seat-id="{}" x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" rx="3" fill="{}"{}><title>{}</title></rect>"#,
+            status_name(seat.status),
+            seat.seat_id,
+            x,
+            y,
+            w,
+            h,
+            fill,
+            stroke,
+            escape(&format!(
+                "Section {}, Row {}, Seat {} - {} ({})",
+                seat.section_name,
+                seat.row_name,
+                seat.seat_number,
+                seat.ticket_tier_name,
+                status_name(seat.status)
+            ))
+        );
+    }
+    svg.push_str("</g>");
+
+    // 5. Section labels, centered above each section.
+    svg.push_str(r##"<g class="sections" text-anchor="middle" font-weight="bold" fill="#424242">"##);
+    for (name, section) in &sections {
+        let _ = write!(
+            svg,
+            r#"<text x="{:.2}" y="{:.2}" font-size="{}">{}</text>"#,
+            (section.min_x + section.max_x) / 2.0,
+            section.min_y - 6.0,
+            LABEL_FONT_SIZE,
+            escape(name)
+        );
+    }
+    svg.push_str("</g>");
+
+    // 6. Legend: one swatch per tier, then the statuses.
+    let mut entries: Vec<(String, &str)> =
+        tiers.iter().map(|(&id, name)| (format!("{} (available)", name), tier_color(id))).collect();
+    entries.push(("Reserved".to_string(), LOCKED_COLOR));
+    entries.push(("Sold".to_string(), SOLD_COLOR));
+    entries.push(("Unavailable".to_string(), UNAVAILABLE_COLOR));
+    if !highlight.is_empty() {
+        entries.push(("Your selection".to_string(), HIGHLIGHT_COLOR));
+    }
+    svg.push_str(r##"<g class="legend" font-size="12" fill="#212121">"##);
+    for (i, (label, color)) in entries.iter().enumerate() {
+        let y = legend_top + i as f64 * LEGEND_LINE_HEIGHT;
+        let _ = write!(
+            svg,
+            r##"<rect x="{:.2}" y="{:.2}" width="14" height="14" rx="3" fill="{}" stroke="#9e9e9e"/><text x="{:.2}" y="{:.2}">{}</text>"##,
+            bounds.min_x,
+            y,
+            color,
+            bounds.min_x + 20.0,
+            y + 11.0,
+            escape(label)
+        );
+    }
+    svg.push_str("</g></svg>");
+
+    svg
+}
+
+/// The bounding box of a set of rectangles.
+struct Bounds {
+    min_x: f64,
+    min_y: f64,
+    max_x: f64,
+    max_y: f64,
+}
+
+impl Default for Bounds {
+    fn default() -> Self {
+        Self { min_x: f64::MAX, min_y: f64::MAX, max_x: f64::MIN, max_y: f64::MIN }
+    }
+}
+
+impl Bounds {
+    fn include(&mut self, x: f64, y: f64, w: f64, h: f64) {
+        self.min_x = self.min_x.min(x);
+        self.min_y = self.min_y.min(y);
+        self.max_x = self.max_x.max(x + w);
+        self.max_y = self.max_y.max(y + h);
+    }
+}
+
+fn rect(seat: &SeatMapSeat) -> (f64, f64, f64, f64) {
+    (
+        seat.pos_x.to_f64().unwrap_or_default(),
+        seat.pos_y.to_f64().unwrap_or_default(),
+        seat.width.to_f64().unwrap_or_default(),
+        seat.height.to_f64().unwrap_or_default(),
+    )
+}
+
+fn status_name(status: SeatStatus) -> &'static str {
+    match status {
+        SeatStatus::Available => "available",
+        SeatStatus::Locked => "locked",
+        SeatStatus::Sold => "sold",
+        SeatStatus::Unavailable | SeatStatus::Held => "unavailable",
+    }
+}
+
+/// Escapes text for use in SVG content and attribute values.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    fn seat(seat_id: i32, section_name: &str, x: i64, status: SeatStatus, ticket_tier_id: i32) -> SeatMapSeat {
+        SeatMapSeat {
+            seat_id,
+            section_name: section_name.to_string(),
+            row_name: "A".to_string(),
+            seat_number: seat_id.to_string(),
+            pos_x: Decimal::from(x),
+            pos_y: Decimal::from(100),
+            width: Decimal::from(20),
+            height: Decimal::from(20),
+            status,
+            ticket_tier_id,
+            ticket_tier_name: format!("Tier {}", ticket_tier_id),
+        }
+    }
+
+    /// The `<rect>` drawn for a seat.
+    fn seat_rect(svg: &str, seat_id: i32) -> &str {
+        let id = svg.find(&format!(r#"data-seat-id="{}""#, seat_id)).unwrap();
+        let start = svg[..id].rfind("<rect").unwrap();
+        &svg[start..id + svg[id..].find("</rect>").unwrap()]
+    }
+
+    #[test]
+    fn seats_are_colored_by_tier_and_status() {
+        let seats = vec![
+            seat(1, "101", 0, SeatStatus::Available, 7),
+            seat(2, "101", 24, SeatStatus::Available, 9),
+            seat(3, "101", 48, SeatStatus::Sold, 7),
+            seat(4, "101", 72, SeatStatus::Held, 7),
+            seat(5, "101", 96, SeatStatus::Locked, 9),
+        ];
+        let svg = render(&seats, &HashSet::from([5]));
+
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>"));
+        assert!(seat_rect(&svg, 1).contains(TIER_COLORS[0]));
+        assert!(seat_rect(&svg, 2).contains(TIER_COLORS[1]));
+        assert!(seat_rect(&svg, 3).contains(SOLD_COLOR));
+        // Holds look like any other seat that isn't for sale.
+        assert!(seat_rect(&svg, 4).contains(UNAVAILABLE_COLOR));
+        assert!(seat_rect(&svg, 4).contains("seat-unavailable"));
+        assert!(seat_rect(&svg, 5).contains(HIGHLIGHT_COLOR));
+        assert!(seat_rect(&svg, 5).contains(HIGHLIGHT_STROKE));
+        assert!(svg.contains("Tier 7 (available)") && svg.contains("Your selection"));
+        assert!(svg.contains(r#"viewBox="-24.00 62.00 164.00 "#), "{}", &svg[..120]);
+    }
+
+    #[test]
+    fn names_are_escaped() {
+        let seats = vec![seat(1, r#"<Box & "Suite">"#, 0, SeatStatus::Available, 1)];
+        let svg = render(&seats, &HashSet::new());
+
+        assert!(svg.contains("&lt;Box &amp; &quot;Suite&quot;&gt;"));
+        assert!(!svg.contains("<Box"));
+        assert!(!svg.contains("Your selection"));
+    }
+}
@@ -0,0 +1,310 @@
+use crate::{
+    db::seating_query,
+    errors::AppError,
+    models::{BestAvailablePayload, BestAvailableResult, BestAvailableSeat, SeatCandidate, SeatStatus},
+    service::{
+        seat_gap_service::{self, SeatRect},
+        seating_service::SEAT_LOCK_MINUTES,
+    },
+    utils::validation,
+};
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use sqlx::PgPool;
+
+/// How often a pick is retried when other buyers take its seats before they can be locked.
+const MAX_LOCK_ATTEMPTS: usize = 3;
+
+/// How many of the best seats are tried as the center of a group when no
+/// contiguous block is free.
+const FALLBACK_ANCHORS: usize = 50;
+
+/// Service to find the best available seats of an event for a buyer.
+///
+/// Seats are ranked by their distance from the chart's stage focal point. The best block
+/// of `quantity` side-by-side seats in one row wins; if there is none, the closest group
+/// of separate seats is returned instead. With `lock`, the seats are held for the buyer
+/// (replacing any seats they held for this event before) until checkout.
+pub async fn find_best_available(
+    pool: &PgPool,
+    event_id: i32,
+    user_id: i32,
+    payload: &BestAvailablePayload,
+) -> Result<BestAvailableResult, AppError> {
+    // 1. Validate the request.
+    validation::validate_payload(payload)?;
+    let quantity = payload.quantity as usize;
+
+    for _ in 0..MAX_LOCK_ATTEMPTS {
+        // 2. Load the event's seats and pick the best group.
+        let candidates = seating_query::list_seat_candidates(pool, event_id, user_id).await?;
+        if candidates.is_empty() {
+            // The event doesn't exist or has no reserved seating.
+            return Err(AppError::Sqlx(sqlx::Error::RowNotFound));
+        }
+        let Some((picked, contiguous)) = pick_seats(&candidates, quantity, payload) else {
+            return Err(AppError::BadRequest(
+                "Not enough seats are available that match your request.".to_string(),
+            ));
+        };
+        let seats: Vec<BestAvailableSeat> = picked.into_iter().map(|i| to_result_seat(&candidates[i])).collect();
+
+        if !payload.lock {
+            return Ok(BestAvailableResult { seats, contiguous, locked_until: None });
+        }
+
+        // 3. Lock the pick. If another buyer took one of the seats since we looked,
+        //    nothing is kept and the search starts over.
+        let locked_until = chrono::Utc::now() + chrono::Duration::minutes(SEAT_LOCK_MINUTES);
+        let seat_ids: Vec<i32> = seats.iter().map(|s| s.seat_id).collect();
+        let mut tx = pool.begin().await?;
+        seating_query::release_user_seat_locks(&mut tx, event_id, user_id).await?;
+        let locked = seating_query::lock_seats_for_user(&mut tx, event_id, user_id, &seat_ids, locked_until).await?;
+        if locked.len() == seat_ids.len() {
+            tx.commit().await?;
+            return Ok(BestAvailableResult { seats, contiguous, locked_until: Some(locked_until) });
+        }
+        tx.rollback().await?;
+    }
+
+    Err(AppError::BadRequest(
+        "Seats are selling fast and we couldn't hold a group for you. Please try again.".to_string(),
+    ))
+}
+
+/// Picks `quantity` seats, returning their indexes into `candidates` and whether
+/// they are side by side in one row. `candidates` must be in row order.
+fn pick_seats(
+    candidates: &[SeatCandidate],
+    quantity: usize,
+    payload: &BestAvailablePayload,
+) -> Option<(Vec<usize>, bool)> {
+    let (stage_x, stage_y) = focal_point(candidates);
+    let centers: Vec<(f64, f64)> = candidates.iter().map(center).collect();
+    let quality: Vec<f64> = centers.iter().map(|&c| distance(c, (stage_x, stage_y))).collect();
+    let eligible: Vec<bool> = candidates.iter().map(|c| is_eligible(c, payload)).collect();
+
+    // 1. Split the rows into blocks of side-by-side seats and slide a window of `quantity`
+    //    seats over each block. The lowest total distance wins among windows of eligible
+    //    seats, preferring those that don't strand a single empty seat next to them.
+    let free: Vec<bool> = candidates.iter().map(|c| c.status == SeatStatus::Available).collect();
+    let blocks = seat_gap_service::side_by_side_blocks(candidates, |a, b| a.row_id == b.row_id, rect);
+    let mut best_block: Option<((bool, f64), usize)> = None;
+    for block in blocks {
+        for start in block.start..block.end.saturating_sub(quantity - 1) {
+            let end = start + quantity;
+            if !eligible[start..end].iter().all(|&e| e) {
+                continue;
+            }
+            let leaves_gap =
+                seat_gap_service::window_leaves_gap(&free[block.clone()], start - block.start, end - block.start);
+            let rank = (leaves_gap, quality[start..end].iter().sum::<f64>());
+            if best_block.is_none_or(|(best, _)| rank < best) {
+                best_block = Some((rank, start));
+            }
+        }
+    }
+    if let Some((_, start)) = best_block {
+        return Some(((start..start + quantity).collect(), true));
+    }
+
+    // 2. No block is free: try the best seats as the center of a group, each with its
+    //    nearest eligible seats, and keep the group that is tightest and best placed.
+    let mut pool: Vec<usize> = (0..candidates.len()).filter(|&i| eligible[i]).collect();
+    if pool.len() < quantity {
+        return None;
+    }
+    pool.sort_by(|&a, &b| quality[a].total_cmp(&quality[b]));
+
+    let mut best_group: Option<(f64, Vec<usize>)> = None;
+    for &anchor in pool.iter().take(FALLBACK_ANCHORS) {
+        let mut nearest = pool.clone();
+        nearest.sort_by(|&a, &b| {
+            distance(centers[a], centers[anchor]).total_cmp(&distance(centers[b], centers[anchor]))
+        });
+        nearest.truncate(quantity);
+        let cost: f64 = nearest.iter().map(|&i| quality[i] + distance(centers[i], centers[anchor])).sum();
+        if best_group.as_ref().is_none_or(|(best, _)| cost < *best) {
+            best_group = Some((cost, nearest));
+        }
+    }
+    best_group.map(|(_, mut group)| {
+        group.sort_unstable();
+        (group, false)
+    })
+}
+
+fn is_eligible(seat: &SeatCandidate, payload: &BestAvailablePayload) -> bool {
+    let Some(price) = seat.price else {
+        // Nothing is on sale for this seat's tier.
+        return false;
+    };
+    seat.status == SeatStatus::Available
+        && !seat.is_protected
+        && payload.ticket_tier_id.is_none_or(|tier| tier == seat.ticket_tier_id)
+        && payload.max_price.is_none_or(|max| price <= max)
+}
+
+fn rect(seat: &SeatCandidate) -> SeatRect {
+    (to_f64(seat.pos_x), to_f64(seat.pos_y), to_f64(seat.width), to_f64(seat.height))
+}
+
+/// The chart's stage focal point, or the top center of the layout if it has none.
+fn focal_point(candidates: &[SeatCandidate]) -> (f64, f64) {
+    if let Some(SeatCandidate { stage_x: Some(x), stage_y: Some(y), .. }) = candidates.first() {
+        return (to_f64(*x), to_f64(*y));
+    }
+    let min_x = candidates.iter().map(|c| to_f64(c.pos_x)).fold(f64::MAX, f64::min);
+    let max_x = candidates.iter().map(|c| to_f64(c.pos_x + c.width)).fold(f64::MIN, f64::max);
+    let min_y = candidates.iter().map(|c| to_f64(c.pos_y)).fold(f64::MAX, f64::min);
+    ((min_x + max_x) / 2.0, min_y)
+}
+
+fn center(seat: &SeatCandidate) -> (f64, f64) {
+    (
+        to_f64(seat.pos_x) + to_f64(seat.width) / 2.0,
+        to_f64(seat.pos_y) + to_f64(seat.height) / 2.0,
+    )
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+fn to_f64(value: Decimal) -> f64 {
+    value.to_f64().unwrap_or_default()
+}
+
+fn to_result_seat(seat: &SeatCandidate) -> BestAvailableSeat {
+    BestAvailableSeat {
+        seat_id: seat.seat_id,
+        section_name: seat.section_name.clone(),
+        row_name: seat.row_name.clone(),
+        seat_number: seat.seat_number.clone(),
+        ticket_tier_id: seat.ticket_tier_id,
+        // Only eligible seats are picked, and those always have an offer.
+        offer_id: seat.offer_id.unwrap_or_default(),
+        price: seat.price.unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_fixtures;
+
+    /// Rows of 10 seats, 24 apart, with the rows 30 apart and the stage above the first.
+    fn candidates(rows: i32) -> Vec<SeatCandidate> {
+        (0..rows)
+            .flat_map(|row| {
+                (0..10).map(move |seat| SeatCandidate {
+                    seat_id: row * 10 + seat + 1,
+                    row_id: row + 1,
+                    section_name: "101".to_string(),
+                    row_name: ((b'A' + row as u8) as char).to_string(),
+                    seat_number: (seat + 1).to_string(),
+                    pos_x: Decimal::from(seat * 24),
+                    pos_y: Decimal::from(row * 30),
+                    width: Decimal::from(20),
+                    height: Decimal::from(20),
+                    status: SeatStatus::Available,
+                    ticket_tier_id: if row == 0 { 1 } else { 2 },
+                    offer_id: Some(1),
+                    price: Some(Decimal::from(if row == 0 { 100 } else { 50 })),
+                    is_protected: false,
+                    stage_x: None,
+                    stage_y: None,
+                })
+            })
+            .collect()
+    }
+
+    fn payload(quantity: i32) -> BestAvailablePayload {
+        BestAvailablePayload { quantity, ticket_tier_id: None, max_price: None, lock: false }
+    }
+
+    fn seat_ids(candidates: &[SeatCandidate], picked: &[usize]) -> Vec<i32> {
+        picked.iter().map(|&i| candidates[i].seat_id).collect()
+    }
+
+    #[test]
+    fn best_block_is_central_and_nearest_the_stage() {
+        let seats = candidates(3);
+        let (picked, contiguous) = pick_seats(&seats, 2, &payload(2)).unwrap();
+        assert!(contiguous);
+        assert_eq!(seat_ids(&seats, &picked), vec![5, 6]);
+
+        // The buyer's tier and budget rule out the front row.
+        let cheaper = BestAvailablePayload { max_price: Some(Decimal::from(60)), ..payload(2) };
+        let (picked, _) = pick_seats(&seats, 2, &cheaper).unwrap();
+        assert_eq!(seat_ids(&seats, &picked), vec![15, 16]);
+        let tier = BestAvailablePayload { ticket_tier_id: Some(2), ..payload(2) };
+        assert_eq!(pick_seats(&seats, 2, &tier).unwrap().0, picked);
+    }
+
+    #[test]
+    fn blocks_that_strand_a_single_seat_are_avoided() {
+        // Only four seats are left in the front row, so any three of them strand the fourth.
+        let mut seats = candidates(2);
+        for seat in seats.iter_mut().filter(|seat| seat.row_id == 1 && seat.seat_id > 4) {
+            seat.status = SeatStatus::Sold;
+        }
+        let (picked, contiguous) = pick_seats(&seats, 3, &payload(3)).unwrap();
+        assert!(contiguous);
+        assert!(picked.iter().all(|&i| seats[i].row_id == 2));
+
+        // With no other choice, the stranding block is still offered.
+        seats.retain(|seat| seat.row_id == 1);
+        assert!(pick_seats(&seats, 3, &payload(3)).is_some());
+    }
+
+    #[test]
+    fn scattered_seats_are_grouped_when_no_block_is_free() {
+        let mut seats = candidates(2);
+        for seat in seats.iter_mut().filter(|seat| seat.seat_id % 2 == 0) {
+            seat.status = SeatStatus::Sold;
+        }
+        seats[0].is_protected = true;
+        seats[2].price = None;
+
+        let (picked, contiguous) = pick_seats(&seats, 3, &payload(3)).unwrap();
+        assert!(!contiguous);
+        assert_eq!(picked.len(), 3);
+        assert!(picked.iter().all(|&i| is_eligible(&seats[i], &payload(3))));
+        assert!(pick_seats(&seats, 9, &payload(9)).is_none());
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn locked_picks_are_not_offered_to_others(pool: PgPool) {
+        let event_id = test_fixtures::create_event(&pool).await;
+        let tier_id = test_fixtures::create_tier(&pool, event_id, 10).await;
+        test_fixtures::create_offer(&pool, tier_id, Decimal::from(40), 10).await;
+        test_fixtures::create_seats(&pool, event_id, tier_id, 6).await;
+        let first = test_fixtures::create_user(&pool, "first").await;
+        let second = test_fixtures::create_user(&pool, "second").await;
+
+        let request = BestAvailablePayload { lock: true, ..payload(2) };
+        let held = find_best_available(&pool, event_id, first, &request).await.unwrap();
+        assert!(held.contiguous && held.locked_until.is_some());
+        assert_eq!(held.seats.iter().map(|seat| seat.seat_number.as_str()).collect::<Vec<_>>(), vec!["3", "4"]);
+        assert_eq!(held.seats[0].price, Decimal::from(40));
+
+        // Another buyer only gets what's left; the first buyer's pick is replaced, not added to.
+        let other = find_best_available(&pool, event_id, second, &request).await.unwrap();
+        assert!(other.seats.iter().all(|seat| !held.seats.iter().any(|h| h.seat_id == seat.seat_id)));
+        find_best_available(&pool, event_id, first, &BestAvailablePayload { lock: true, ..payload(1) }).await.unwrap();
+        let locked: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM event_seats WHERE event_id = $1 AND status = 'locked'")
+                .bind(event_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(locked, 3);
+
+        // The second buyer's own seats count as free to them, the first buyer's don't.
+        assert_eq!(find_best_available(&pool, event_id, second, &payload(5)).await.unwrap().seats.len(), 5);
+        assert!(matches!(
+            find_best_available(&pool, event_id, second, &payload(6)).await,
+            Err(AppError::BadRequest(_))
+        ));
+    }
+}
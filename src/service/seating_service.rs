@@ -1,38 +1,335 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BinaryHeap, HashMap, HashSet},
+};
+
 use crate::{
-    db::{event_query, seating_query},
+    db::{event_query, pricing_query, realtime_query, seating_query, venue_query},
     errors::AppError,
-    models::{SeatMapInfo, Seat}, // We don't need to expose all sub-models here
+    models::{
+        CreateSeatingChartPayload, EventSeatingReport, GenerateLayoutPayload, InitializeEventSeatingPayload, SeatStatusChange, GenerateSeatingChartPayload, ImportSeatingChartPayload,
+        RenderedSeatMap, RowLayout, SeatStatus, UpdateEventSeatsPayload, UpdateEventSeatsResult, Seat, SeatMapInfo, SeatMapVersion, SeatingChart, SeatingChartLayout, SeatingLayout, SectionLayout, UpdateSeatingChartPayload,
+    },
+    service::{layout_generator_service, seat_map_svg_service},
+    utils::validation,
 };
 use rust_decimal::Decimal;
+use sha2::{Digest, Sha256};
 use sqlx::PgPool;
 
-// --- Layout Management Services (Admin/Organizer Setup) ---
+// --- Layout Management Services (Admin/Venue Manager Setup) ---
+
+/// The most seats a single layout may hold; larger than any real venue.
+const MAX_SEATS_PER_CHART: usize = 100_000;
+
+/// Seat coordinates and sizes are stored as DECIMAL(8, 2).
+const MAX_COORDINATE: i64 = 1_000_000;
+
+/// How many layout problems are reported back at once.
+const MAX_REPORTED_PROBLEMS: usize = 20;
+
+/// How long a seat stays locked for a buyer before it goes back on sale.
+/// The business rule for cart expiration.
+pub const SEAT_LOCK_MINUTES: i64 = 15;
+
+/// Service to list the seating charts of a venue.
+pub async fn list_charts_for_venue(pool: &PgPool, venue_id: i32) -> Result<Vec<SeatingChart>, AppError> {
+    seating_query::list_charts_for_venue(pool, venue_id).await
+}
+
+/// Service to fetch a seating chart with its full layout.
+pub async fn get_chart_layout(pool: &PgPool, chart_id: i32) -> Result<SeatingChartLayout, AppError> {
+    // 1. Fetch the chart and every level of its layout.
+    let chart = seating_query::get_chart(pool, chart_id).await?;
+    let sections = seating_query::list_sections_for_chart(pool, chart_id).await?;
+    let rows = seating_query::list_rows_for_chart(pool, chart_id).await?;
+    let seats = seating_query::list_seats_for_chart(pool, chart_id).await?;
+
+    // 2. Nest the seats into their rows, and the rows into their sections.
+    let mut seats_by_row: HashMap<i32, Vec<Seat>> = HashMap::new();
+    for seat in seats {
+        seats_by_row.entry(seat.row_id).or_default().push(seat);
+    }
+    let mut rows_by_section: HashMap<i32, Vec<RowLayout>> = HashMap::new();
+    for row in rows {
+        rows_by_section.entry(row.section_id).or_default().push(RowLayout {
+            id: row.id,
+            seats: seats_by_row.remove(&row.id).unwrap_or_default(),
+            name: row.name,
+        });
+    }
+    let sections = sections
+        .into_iter()
+        .map(|section| SectionLayout {
+            id: section.id,
+            rows: rows_by_section.remove(&section.id).unwrap_or_default(),
+            name: section.name,
+        })
+        .collect();
+
+    Ok(SeatingChartLayout { chart, sections })
+}
+
+/// Service to create an empty seating chart for a venue.
+pub async fn create_chart(
+    pool: &PgPool,
+    venue_id: i32,
+    user_id: i32,
+    role: &str,
+    payload: &CreateSeatingChartPayload,
+) -> Result<SeatingChart, AppError> {
+    validation::validate_payload(payload)?;
+    authorize_venue_editor(pool, venue_id, user_id, role).await?;
+    seating_query::create_chart(pool, venue_id, payload).await
+}
+
+/// Service to create a seating chart together with its whole layout.
+/// Either the chart and every seat are created, or nothing is.
+pub async fn import_chart(
+    pool: &PgPool,
+    venue_id: i32,
+    user_id: i32,
+    role: &str,
+    payload: &ImportSeatingChartPayload,
+) -> Result<SeatingChartLayout, AppError> {
+    create_chart_with_layout(pool, venue_id, user_id, role, &payload.chart, &payload.layout).await
+}
+
+/// Service to expand section specs into a layout without saving anything,
+/// so the chart builder can show the result before it is committed.
+pub fn preview_generated_layout(payload: &GenerateLayoutPayload) -> Result<SeatingLayout, AppError> {
+    let layout = layout_generator_service::generate_layout(&payload.sections, MAX_SEATS_PER_CHART)?;
+    validate_layout(&layout)?;
+    Ok(layout)
+}
+
+/// Service to create a seating chart from section specs.
+pub async fn generate_chart(
+    pool: &PgPool,
+    venue_id: i32,
+    user_id: i32,
+    role: &str,
+    payload: &GenerateSeatingChartPayload,
+) -> Result<SeatingChartLayout, AppError> {
+    let layout = layout_generator_service::generate_layout(&payload.sections, MAX_SEATS_PER_CHART)?;
+    create_chart_with_layout(pool, venue_id, user_id, role, &payload.chart, &layout).await
+}
+
+async fn create_chart_with_layout(
+    pool: &PgPool,
+    venue_id: i32,
+    user_id: i32,
+    role: &str,
+    chart: &CreateSeatingChartPayload,
+    layout: &SeatingLayout,
+) -> Result<SeatingChartLayout, AppError> {
+    // 1. Validate the chart details and the layout.
+    validation::validate_payload(chart)?;
+    validate_layout(layout)?;
+
+    // 2. Authorization.
+    authorize_venue_editor(pool, venue_id, user_id, role).await?;
+
+    // 3. Create the chart and its layout in one transaction.
+    let mut tx = pool.begin().await?;
+    let chart = seating_query::create_chart(&mut *tx, venue_id, chart).await?;
+    let seat_count = seating_query::write_layout(&mut tx, chart.id, layout).await?;
+    tx.commit().await?;
+
+    tracing::info!("Created seating chart {} with {} seats.", chart.id, seat_count);
+    get_chart_layout(pool, chart.id).await
+}
+
+/// Service to replace the layout of an existing chart.
+/// Once an event uses the chart, its seats are kept: seats matching the new layout by
+/// section, row and number are updated in place and the others are retired. Events pick
+/// up the changes when their seating is re-synced.
+pub async fn replace_layout(
+    pool: &PgPool,
+    chart_id: i32,
+    user_id: i32,
+    role: &str,
+    layout: &SeatingLayout,
+) -> Result<SeatingChartLayout, AppError> {
+    // 1. Validate the layout.
+    validate_layout(layout)?;
+
+    // 2. Authorization.
+    let chart = seating_query::get_chart(pool, chart_id).await?;
+    authorize_venue_editor(pool, chart.venue_id, user_id, role).await?;
+
+    // 3. Swap the layout while holding the chart, so no event can start using it meanwhile.
+    let mut tx = pool.begin().await?;
+    seating_query::lock_chart(&mut tx, chart_id).await?;
+    if !seating_query::is_chart_in_use(&mut tx, chart_id).await? {
+        seating_query::clear_layout(&mut tx, chart_id).await?;
+    }
+    let seat_count = seating_query::write_layout(&mut tx, chart_id, layout).await?;
+    tx.commit().await?;
+
+    tracing::info!("Replaced the layout of seating chart {} with {} seats.", chart_id, seat_count);
+    get_chart_layout(pool, chart_id).await
+}
 
-/// Service to create a new physical seat.
-/// In a real application, this would likely be part of a larger, transactional
-/// "bulk create" service for setting up an entire venue layout at once.
-/// For simplicity, here's the single-seat version.
-pub async fn create_seat(
+/// Service to rename a seating chart or change its background.
+pub async fn update_chart(
     pool: &PgPool,
-    row_id: i32,
-    seat_number: &str,
-    pos_x: Decimal,
-    pos_y: Decimal,
-) -> Result<Seat, AppError> {
-    // Authorization logic would go here (e.g., check if user is venue admin)
-    seating_query::create_seat(pool, row_id, seat_number, pos_x, pos_y).await
+    chart_id: i32,
+    user_id: i32,
+    role: &str,
+    payload: &UpdateSeatingChartPayload,
+) -> Result<SeatingChart, AppError> {
+    validation::validate_payload(payload)?;
+    let chart = seating_query::get_chart(pool, chart_id).await?;
+    authorize_venue_editor(pool, chart.venue_id, user_id, role).await?;
+    seating_query::update_chart(pool, chart_id, payload).await
+}
+
+/// Service to delete a seating chart. Refused once an event uses the chart.
+pub async fn delete_chart(pool: &PgPool, chart_id: i32, user_id: i32, role: &str) -> Result<(), AppError> {
+    // 1. Authorization.
+    let chart = seating_query::get_chart(pool, chart_id).await?;
+    authorize_venue_editor(pool, chart.venue_id, user_id, role).await?;
+
+    // 2. Delete it unless an event depends on its seats.
+    let mut tx = pool.begin().await?;
+    seating_query::lock_chart(&mut tx, chart_id).await?;
+    if seating_query::is_chart_in_use(&mut tx, chart_id).await? {
+        return Err(AppError::BadRequest(
+            "This seating chart is used by an event and can't be deleted.".to_string(),
+        ));
+    }
+    seating_query::delete_chart(&mut tx, chart_id).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Checks that the user may edit the seating charts of a venue: admins may edit any venue,
+/// other users only the venues they manage.
+async fn authorize_venue_editor(pool: &PgPool, venue_id: i32, user_id: i32, role: &str) -> Result<(), AppError> {
+    // Make sure the venue exists, so a missing venue is a 404 rather than a 403.
+    venue_query::get_by_id(pool, venue_id).await?;
+    if role == "admin" || venue_query::is_venue_manager(pool, venue_id, user_id).await? {
+        Ok(())
+    } else {
+        Err(AppError::Forbidden(
+            "You are not authorized to manage the seating charts of this venue.".to_string(),
+        ))
+    }
+}
+
+/// Checks a layout before anything is written: names must be present and unique at each
+/// level, seat rectangles must be within bounds and no two seats may overlap.
+/// All problems found (up to a limit) are reported together.
+fn validate_layout(layout: &SeatingLayout) -> Result<(), AppError> {
+    let mut problems = Vec::new();
+    let max_coordinate = Decimal::from(MAX_COORDINATE);
+    let default_size = Decimal::from(20);
+
+    if layout.sections.is_empty() {
+        problems.push("The layout must have at least one section.".to_string());
+    }
+
+    // 1. Names, and every seat's rectangle.
+    let mut rects = Vec::new();
+    let mut section_names = HashSet::new();
+    for section in &layout.sections {
+        if let Err(e) = validation::is_valid_text("Section name", &section.name, 1, 255) {
+            problems.push(e);
+        }
+        if !section_names.insert(section.name.as_str()) {
+            problems.push(format!("Section '{}' appears more than once.", section.name));
+        }
+
+        let mut row_names = HashSet::new();
+        for row in &section.rows {
+            if let Err(e) = validation::is_valid_text("Row name", &row.name, 1, 255) {
+                problems.push(format!("{} (section '{}')", e, section.name));
+            }
+            if !row_names.insert(row.name.as_str()) {
+                problems.push(format!("Row '{}' appears more than once in section '{}'.", row.name, section.name));
+            }
+
+            let mut seat_numbers = HashSet::new();
+            for seat in &row.seats {
+                let label = format!("seat '{}' in row '{}' of section '{}'", seat.seat_number, row.name, section.name);
+                if let Err(e) = validation::is_valid_text("Seat number", &seat.seat_number, 1, 10) {
+                    problems.push(format!("{} ({})", e, label));
+                }
+                if !seat_numbers.insert(seat.seat_number.as_str()) {
+                    problems.push(format!("Duplicate {}.", label));
+                }
+
+                let width = seat.width.unwrap_or(default_size);
+                let height = seat.height.unwrap_or(default_size);
+                if width <= Decimal::ZERO || height <= Decimal::ZERO {
+                    problems.push(format!("The {} must have a positive width and height.", label));
+                    continue;
+                }
+                if seat.pos_x < Decimal::ZERO
+                    || seat.pos_y < Decimal::ZERO
+                    || seat.pos_x + width >= max_coordinate
+                    || seat.pos_y + height >= max_coordinate
+                {
+                    problems.push(format!("The {} is outside the map.", label));
+                    continue;
+                }
+                rects.push((seat.pos_x, seat.pos_y, width, height, label));
+            }
+        }
+    }
+
+    if rects.len() > MAX_SEATS_PER_CHART {
+        problems.push(format!("A layout may have at most {} seats.", MAX_SEATS_PER_CHART));
+    } else {
+        // 2. Overlaps. Sweeping left to right, the seats still under the sweep line are kept
+        //    ordered by their top edge, so a seat is only compared with the seat just above it
+        //    and the seats starting within its height. Rectangles that merely touch don't overlap.
+        rects.sort_by_key(|r| r.0);
+        let mut active: BTreeMap<(Decimal, usize), Decimal> = BTreeMap::new();
+        let mut right_edges: BinaryHeap<Reverse<(Decimal, usize)>> = BinaryHeap::new();
+        'sweep: for (i, (x, y, w, h, label)) in rects.iter().enumerate() {
+            while let Some(&Reverse((right, j))) = right_edges.peek() {
+                if right > *x {
+                    break;
+                }
+                right_edges.pop();
+                active.remove(&(rects[j].1, j));
+            }
+
+            let bottom = *y + *h;
+            let above = active.range(..(*y, 0)).next_back().filter(|(_, other_bottom)| **other_bottom > *y);
+            let below = active.range((*y, 0)..).take_while(|((other_y, _), _)| *other_y < bottom);
+            for ((_, j), _) in above.into_iter().chain(below) {
+                problems.push(format!("The {} overlaps the {}.", rects[*j].4, label));
+                if problems.len() >= MAX_REPORTED_PROBLEMS {
+                    break 'sweep;
+                }
+            }
+
+            active.insert((*y, i), bottom);
+            right_edges.push(Reverse((*x + *w, i)));
+        }
+    }
+
+    if problems.is_empty() {
+        return Ok(());
+    }
+    problems.truncate(MAX_REPORTED_PROBLEMS);
+    Err(AppError::BadRequest(format!("Invalid seating layout: {}", problems.join(" "))))
 }
 
-/// The critical service function to "instantiate" a seating chart for a new event.
+/// The critical service function to "instantiate" a seating chart for an event.
 /// This populates the `event_seats` table with a record for every seat in the chart.
-/// This is a transactional operation.
+/// Setting up an event again with the same chart changes nothing; with `resync`, the
+/// seats added to the chart since are added and those retired from it are removed,
+/// except sold seats and seats in a checkout. This is a transactional operation.
 pub async fn initialize_event_seating(
     pool: &PgPool,
     event_id: i32,
     organizer_id: i32, // User performing the action
-    seating_chart_id: i32,
-    default_ticket_tier_id: i32, // All seats will be assigned this tier initially
-) -> Result<u64, AppError> {
+    payload: &InitializeEventSeatingPayload,
+) -> Result<EventSeatingReport, AppError> {
     // 1. Authorization: Check if the user is the organizer of the event.
     let event = event_query::get_by_id(pool, event_id).await?;
     if event.organizer_id != organizer_id {
@@ -40,33 +337,125 @@ pub async fn initialize_event_seating(
             "You are not authorized to configure seating for this event.".to_string(),
         ));
     }
-    
-    // In a real app, you'd also check that the seating_chart_id is valid for the event's venue
-    // and that the ticket_tier_id belongs to this event.
 
-    // 2. Begin a transaction.
+    // 2. The chart must be one of the event's venue, and the tier one of the event's.
+    let chart = seating_query::get_chart(pool, payload.seating_chart_id).await?;
+    if event.venue_id != Some(chart.venue_id) {
+        return Err(AppError::BadRequest(format!(
+            "Seating chart {} does not belong to this event's venue.",
+            chart.id
+        )));
+    }
+    ensure_tier_belongs_to_event(pool, event_id, payload.default_ticket_tier_id).await?;
+
+    // 3. Hold the chart, so it can't be rebuilt meanwhile, and see what the event already uses.
     let mut tx = pool.begin().await?;
+    seating_query::lock_chart(&mut tx, chart.id).await?;
+    let current_chart_id = seating_query::get_event_chart_id(&mut tx, event_id).await?;
+    if current_chart_id.is_some_and(|id| id != chart.id) {
+        return Err(AppError::BadRequest(format!(
+            "This event's seating is already set up from seating chart {}.",
+            current_chart_id.unwrap_or_default()
+        )));
+    }
 
-    // 3. Call the transactional database query.
-    let result = seating_query::create_event_seats_for_chart(
-        &mut tx,
-        event_id,
-        seating_chart_id,
-        default_ticket_tier_id,
-    )
-    .await;
-
-    // 4. Commit or rollback.
-    match result {
-        Ok(rows_affected) => {
-            tx.commit().await?;
-            Ok(rows_affected)
-        }
-        Err(e) => {
-            tx.rollback().await?;
-            Err(e)
-        }
+    // 4. Add the chart's seats the event doesn't have and, on a re-sync, remove the retired ones.
+    let mut added_seat_ids = Vec::new();
+    let mut retired_seat_ids = Vec::new();
+    if current_chart_id.is_none() || payload.resync {
+        added_seat_ids =
+            seating_query::add_event_seats_for_chart(&mut tx, event_id, chart.id, payload.default_ticket_tier_id)
+                .await?;
+        retired_seat_ids = seating_query::retire_event_seats(&mut tx, event_id).await?;
+    }
+    let kept_seat_ids = seating_query::list_kept_retired_event_seats(&mut tx, event_id).await?;
+
+    // 5. Let live seat maps know, then commit.
+    let changes: Vec<SeatStatusChange> = added_seat_ids
+        .iter()
+        .map(|&seat_id| SeatStatusChange { event_id, seat_id, status: SeatStatus::Available })
+        .chain(retired_seat_ids.iter().map(|&seat_id| SeatStatusChange {
+            event_id,
+            seat_id,
+            status: SeatStatus::Unavailable,
+        }))
+        .collect();
+    realtime_query::notify_seat_changes(&mut *tx, &changes).await?;
+    tx.commit().await?;
+
+    if !changes.is_empty() {
+        tracing::info!(
+            "Synced the seating of event {}: {} seats added, {} retired.",
+            event_id,
+            added_seat_ids.len(),
+            retired_seat_ids.len()
+        );
     }
+    let seat_count = seating_query::get_seat_map_version(pool, event_id).await?.seat_count;
+    Ok(EventSeatingReport { seating_chart_id: chart.id, seat_count, added_seat_ids, retired_seat_ids, kept_seat_ids })
+}
+
+/// Service for an organizer to move a set of an event's seats to another tier (price
+/// zoning) and/or take them off sale. Seats are picked by section, row range or ID.
+/// Locked and sold seats are never changed; they are reported back as skipped.
+pub async fn update_event_seats(
+    pool: &PgPool,
+    event_id: i32,
+    organizer_id: i32,
+    payload: &UpdateEventSeatsPayload,
+) -> Result<UpdateEventSeatsResult, AppError> {
+    // 1. Validate the change.
+    if payload.ticket_tier_id.is_none() && payload.status.is_none() {
+        return Err(AppError::BadRequest("Provide a ticket_tier_id, a status or both.".to_string()));
+    }
+    // Holds are placed and released through the hold endpoints, which track who holds what.
+    if !matches!(payload.status, None | Some(SeatStatus::Available | SeatStatus::Unavailable)) {
+        return Err(AppError::BadRequest("Seats can only be made Available or Unavailable.".to_string()));
+    }
+
+    // 2. Authorization: Check if the user is the organizer of the event, and that the
+    //    tier is one of its own.
+    let event = event_query::get_by_id(pool, event_id).await?;
+    if event.organizer_id != organizer_id {
+        return Err(AppError::Forbidden(
+            "You are not authorized to configure seating for this event.".to_string(),
+        ));
+    }
+    if let Some(tier_id) = payload.ticket_tier_id {
+        ensure_tier_belongs_to_event(pool, event_id, tier_id).await?;
+    }
+
+    // 3. Find the seats and update those that are not taken, in one transaction.
+    let mut tx = pool.begin().await?;
+    let selected = seating_query::list_selected_event_seats(&mut tx, event_id, &payload.selector).await?;
+    if selected.is_empty() {
+        return Err(AppError::BadRequest("No seats of this event match the selection.".to_string()));
+    }
+    let (open, taken): (Vec<_>, Vec<_>) = selected
+        .into_iter()
+        .partition(|(_, status)| matches!(status, SeatStatus::Available | SeatStatus::Unavailable));
+    let seat_ids: Vec<i32> = open.into_iter().map(|(seat_id, _)| seat_id).collect();
+    let changes =
+        seating_query::update_event_seats(&mut tx, event_id, &seat_ids, payload.ticket_tier_id, payload.status)
+            .await?;
+    tx.commit().await?;
+
+    Ok(UpdateEventSeatsResult {
+        updated: changes.len(),
+        skipped_seat_ids: taken.into_iter().map(|(seat_id, _)| seat_id).collect(),
+    })
+}
+
+/// Fails unless the ticket tier exists and belongs to the event.
+async fn ensure_tier_belongs_to_event(pool: &PgPool, event_id: i32, tier_id: i32) -> Result<(), AppError> {
+    let tier = match pricing_query::get_ticket_tier(pool, tier_id).await {
+        Err(AppError::Sqlx(sqlx::Error::RowNotFound)) => None,
+        result => Some(result?),
+    };
+    if tier.is_none_or(|tier| tier.event_id != event_id) {
+        return Err(AppError::BadRequest(format!("Ticket tier {} does not belong to this event.", tier_id)));
+    }
+    Ok(())
 }
 
 // --- Public / Checkout Services ---
@@ -80,33 +469,354 @@ pub async fn get_seat_map_for_event(
     seating_query::get_seat_map_for_event(pool, event_id).await
 }
 
-/// Service to lock a seat during the checkout process.
-/// This is an internal function, likely called by the `order_service`.
-pub async fn lock_seat(
+/// Service to render an event's seat map as SVG, honouring `If-None-Match`.
+///
+/// The ETag is derived from the time of the event's last seat change, its number of
+/// seats and the highlighted seats, so the (comparatively expensive) rendering is
+/// skipped whenever the client's copy is still current.
+pub async fn get_seat_map_svg(
     pool: &PgPool,
     event_id: i32,
-    seat_id: i32,
-) -> Result<(), AppError> {
-    const LOCK_DURATION_MINUTES: i64 = 15; // The business rule for cart expiration
-    seating_query::lock_seat(pool, event_id, seat_id, LOCK_DURATION_MINUTES).await
+    highlight: Option<&str>,
+    if_none_match: Option<&str>,
+) -> Result<RenderedSeatMap, AppError> {
+    // 1. Parse the highlighted seats.
+    let highlight = parse_seat_ids(highlight)?;
+
+    // 2. Compute the ETag from the seat map's version alone.
+    let version = seating_query::get_seat_map_version(pool, event_id).await?;
+    if version.seat_count == 0 {
+        // The event doesn't exist or has no reserved seating.
+        return Err(AppError::Sqlx(sqlx::Error::RowNotFound));
+    }
+    let etag = seat_map_etag(event_id, &version, &highlight);
+    if if_none_match.is_some_and(|header| etag_matches(header, &etag)) {
+        return Ok(RenderedSeatMap::NotModified { etag });
+    }
+
+    // 3. Render the current map.
+    let seats = seating_query::get_seat_map_seats(pool, event_id).await?;
+    let body = seat_map_svg_service::render(&seats, &highlight.into_iter().collect());
+    Ok(RenderedSeatMap::Svg { etag, body })
 }
 
-/// Service to unlock a seat if a user abandons their cart.
-pub async fn unlock_seat(
-    pool: &PgPool,
-    event_id: i32,
-    seat_id: i32,
-) -> Result<(), AppError> {
-    seating_query::unlock_seat(pool, event_id, seat_id).await
+/// The most seats that can be highlighted at once; more than any order may hold.
+const MAX_HIGHLIGHTED_SEATS: usize = 100;
+
+/// Parses a comma-separated list of seat IDs, sorted and without duplicates.
+fn parse_seat_ids(list: Option<&str>) -> Result<Vec<i32>, AppError> {
+    let mut ids = list
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+            id.parse::<i32>()
+                .map_err(|_| AppError::BadRequest(format!("'{}' is not a valid seat ID.", id)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    ids.sort_unstable();
+    ids.dedup();
+    if ids.len() > MAX_HIGHLIGHTED_SEATS {
+        return Err(AppError::BadRequest(format!(
+            "At most {} seats can be highlighted.",
+            MAX_HIGHLIGHTED_SEATS
+        )));
+    }
+    Ok(ids)
+}
+
+fn seat_map_etag(event_id: i32, version: &SeatMapVersion, highlight: &[i32]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(event_id.to_be_bytes());
+    hasher.update(version.seat_count.to_be_bytes());
+    hasher.update(version.last_changed.map(|t| t.timestamp_micros()).unwrap_or_default().to_be_bytes());
+    for id in highlight {
+        hasher.update(id.to_be_bytes());
+    }
+    format!("\"{}\"", &hex::encode(hasher.finalize())[..32])
+}
+
+/// Checks an `If-None-Match` header (a list of ETags, or `*`) against the current ETag.
+fn etag_matches(header: &str, etag: &str) -> bool {
+    header
+        .split(',')
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
 }
 
 // --- Background Job Service ---
 
 /// Service function for a background worker to periodically clean up expired locks.
 pub async fn release_expired_locks(pool: &PgPool) -> Result<u64, AppError> {
-    let rows_affected = seating_query::release_expired_locks(pool).await?;
+    let rows_affected = seating_query::release_expired_locks(pool).await?.len() as u64;
     if rows_affected > 0 {
         tracing::info!("Released {} expired seat locks.", rows_affected);
     }
     Ok(rows_affected)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::payment_provider::FakePaymentProvider;
+    use crate::models::{CreateOrderPayload, LayoutRow, LayoutSeat, LayoutSection, OrderItemPayload, SeatSelector};
+    use crate::service::order_service;
+    use crate::utils::test_fixtures;
+
+    /// A single-row layout from seat rectangles given as `(x, y, width, height)`.
+    fn layout(seats: &[(i64, i64, i64, i64)]) -> SeatingLayout {
+        let seats = seats
+            .iter()
+            .enumerate()
+            .map(|(n, &(x, y, width, height))| LayoutSeat {
+                seat_number: (n + 1).to_string(),
+                pos_x: Decimal::from(x),
+                pos_y: Decimal::from(y),
+                width: Some(Decimal::from(width)),
+                height: Some(Decimal::from(height)),
+                attributes: Vec::new(),
+            })
+            .collect();
+        SeatingLayout {
+            sections: vec![LayoutSection { name: "101".to_string(), rows: vec![LayoutRow { name: "A".to_string(), seats }] }],
+        }
+    }
+
+    fn problems(layout: &SeatingLayout) -> String {
+        match validate_layout(layout) {
+            Err(AppError::BadRequest(message)) => message,
+            other => panic!("expected the layout to be rejected, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn large_layouts_of_touching_seats_are_valid() {
+        // Every seat shares the same x range, the worst case for a sweep on x alone.
+        let seats: Vec<_> = (0..MAX_SEATS_PER_CHART as i64).map(|n| (0, n * 5, 20, 5)).collect();
+        assert!(validate_layout(&layout(&seats)).is_ok());
+
+        let grid: Vec<_> = (0..MAX_SEATS_PER_CHART as i64).map(|n| ((n % 400) * 20, (n / 400) * 20, 20, 20)).collect();
+        assert!(validate_layout(&layout(&grid)).is_ok());
+    }
+
+    #[test]
+    fn overlaps_are_found_above_and_below() {
+        // Seat 2 starts inside seat 1.
+        let message = problems(&layout(&[(0, 0, 20, 20), (10, 10, 20, 20)]));
+        assert!(message.contains("The seat '1' in row 'A' of section '101' overlaps the seat '2'"), "{}", message);
+
+        // A tall seat overlaps a seat further down the column, not just its neighbour.
+        let message = problems(&layout(&[(0, 0, 20, 20), (0, 20, 20, 20), (0, 40, 20, 20), (5, 0, 10, 30)]));
+        assert!(message.contains("seat '1' in row 'A' of section '101' overlaps the seat '4'"), "{}", message);
+        assert!(message.contains("seat '2' in row 'A' of section '101' overlaps the seat '4'"), "{}", message);
+        assert!(!message.contains("seat '3'"), "{}", message);
+
+        // Seat 2 starts below the top of seat 1 but inside it.
+        let message = problems(&layout(&[(0, 0, 20, 40), (5, 30, 20, 20)]));
+        assert!(message.contains("The seat '1' in row 'A' of section '101' overlaps the seat '2'"), "{}", message);
+
+        let touching = layout(&[(0, 0, 20, 20), (20, 0, 20, 20), (0, 20, 20, 20)]);
+        assert!(validate_layout(&touching).is_ok());
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn seat_map_svg_is_revalidated_by_etag(pool: PgPool) {
+        let event_id = test_fixtures::create_event(&pool).await;
+        let tier_id = test_fixtures::create_tier(&pool, event_id, 10).await;
+        let seat_ids = test_fixtures::create_seats(&pool, event_id, tier_id, 3).await;
+
+        let RenderedSeatMap::Svg { etag, body } = get_seat_map_svg(&pool, event_id, None, None).await.unwrap() else {
+            panic!("expected the seat map to be rendered");
+        };
+        assert!(body.contains(&format!(r#"data-seat-id="{}""#, seat_ids[2])));
+        assert!(matches!(
+            get_seat_map_svg(&pool, event_id, None, Some(&format!("W/{}, \"other\"", etag))).await.unwrap(),
+            RenderedSeatMap::NotModified { .. }
+        ));
+
+        // Highlighting seats or changing one gives the map a new ETag.
+        let highlight = format!("{}, {}", seat_ids[1], seat_ids[0]);
+        let RenderedSeatMap::Svg { etag: highlighted, .. } =
+            get_seat_map_svg(&pool, event_id, Some(&highlight), Some(&etag)).await.unwrap()
+        else {
+            panic!("expected the highlighted seat map to be rendered");
+        };
+        assert_ne!(highlighted, etag);
+        sqlx::query("UPDATE event_seats SET status = 'sold' WHERE seat_id = $1")
+            .bind(seat_ids[0])
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(matches!(
+            get_seat_map_svg(&pool, event_id, None, Some(&etag)).await.unwrap(),
+            RenderedSeatMap::Svg { .. }
+        ));
+
+        assert!(matches!(get_seat_map_svg(&pool, event_id, Some("1,x"), None).await, Err(AppError::BadRequest(_))));
+        let no_seats = test_fixtures::create_event(&pool).await;
+        assert!(matches!(
+            get_seat_map_svg(&pool, no_seats, None, None).await,
+            Err(AppError::Sqlx(sqlx::Error::RowNotFound))
+        ));
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn seats_are_rezoned_and_killed_unless_taken(pool: PgPool) {
+        let provider = FakePaymentProvider::new();
+        let event_id = test_fixtures::create_event(&pool).await;
+        let organizer_id: i32 = sqlx::query_scalar("SELECT organizer_id FROM events WHERE id = $1")
+            .bind(event_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let stalls = test_fixtures::create_tier(&pool, event_id, 10).await;
+        let premium = test_fixtures::create_tier(&pool, event_id, 10).await;
+        let stalls_offer = test_fixtures::create_offer(&pool, stalls, Decimal::from(40), 10).await;
+        let premium_offer = test_fixtures::create_offer(&pool, premium, Decimal::from(90), 10).await;
+        let seat_ids = test_fixtures::create_seats(&pool, event_id, stalls, 4).await;
+        sqlx::query("UPDATE event_seats SET status = 'sold' WHERE seat_id = $1")
+            .bind(seat_ids[0])
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // 1. Move the section to the premium tier; the sold seat stays where it was.
+        let rezone = UpdateEventSeatsPayload {
+            selector: SeatSelector::Section { section: "101".to_string() },
+            ticket_tier_id: Some(premium),
+            status: None,
+        };
+        let result = update_event_seats(&pool, event_id, organizer_id, &rezone).await.unwrap();
+        assert_eq!((result.updated, result.skipped_seat_ids), (3, vec![seat_ids[0]]));
+
+        // 2. Kill a seat.
+        let kill = UpdateEventSeatsPayload {
+            selector: SeatSelector::Seats { seat_ids: vec![seat_ids[3]] },
+            ticket_tier_id: None,
+            status: Some(SeatStatus::Unavailable),
+        };
+        assert_eq!(update_event_seats(&pool, event_id, organizer_id, &kill).await.unwrap().updated, 1);
+
+        // 3. Rezoned seats are sold through their new tier's offer only, and killed ones not at all.
+        let buyer_id = test_fixtures::create_user(&pool, "buyer").await;
+        let checkout = |offer_id: i32, seat_id: i32| CreateOrderPayload {
+            items: vec![OrderItemPayload { offer_id, seat_id: Some(seat_id), quantity: 1 }],
+            addons: Vec::new(),
+            apply_credit: None,
+        };
+        for (offer_id, seat_id) in [(stalls_offer, seat_ids[1]), (premium_offer, seat_ids[3])] {
+            let payload = checkout(offer_id, seat_id);
+            let result = order_service::create_order(&pool, &provider, buyer_id, &payload, None).await;
+            assert!(result.is_err(), "seat {} was sold through offer {}", seat_id, offer_id);
+        }
+        let payload = checkout(premium_offer, seat_ids[1]);
+        let (order, _) = order_service::create_order(&pool, &provider, buyer_id, &payload, None).await.unwrap();
+        assert_eq!(order.subtotal, Decimal::from(90));
+
+        // 4. Only the organizer can change seats, to one of the event's tiers, and holds go
+        //    through the hold endpoints.
+        let other_event = test_fixtures::create_event(&pool).await;
+        let other_tier = test_fixtures::create_tier(&pool, other_event, 10).await;
+        let stranger = test_fixtures::create_user(&pool, "stranger").await;
+        assert!(matches!(
+            update_event_seats(&pool, event_id, stranger, &rezone).await,
+            Err(AppError::Forbidden(_))
+        ));
+        let foreign_tier = UpdateEventSeatsPayload { ticket_tier_id: Some(other_tier), ..rezone };
+        assert!(matches!(
+            update_event_seats(&pool, event_id, organizer_id, &foreign_tier).await,
+            Err(AppError::BadRequest(_))
+        ));
+        let hold = UpdateEventSeatsPayload { status: Some(SeatStatus::Held), ..kill };
+        assert!(matches!(update_event_seats(&pool, event_id, organizer_id, &hold).await, Err(AppError::BadRequest(_))));
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn resync_adds_new_seats_and_retires_unsold_ones(pool: PgPool) {
+        let event_id = test_fixtures::create_event(&pool).await;
+        let organizer_id: i32 = sqlx::query_scalar("SELECT organizer_id FROM events WHERE id = $1")
+            .bind(event_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let tier_id = test_fixtures::create_tier(&pool, event_id, 10).await;
+        let seat_ids = test_fixtures::create_seats(&pool, event_id, tier_id, 3).await;
+        let (chart_id, row_id): (i32, i32) = sqlx::query_as(
+            "SELECT sec.seating_chart_id, s.row_id
+             FROM seats s JOIN rows r ON s.row_id = r.id JOIN sections sec ON r.section_id = sec.id
+             WHERE s.id = $1",
+        )
+        .bind(seat_ids[0])
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        sqlx::query("UPDATE events SET venue_id = (SELECT venue_id FROM seating_charts WHERE id = $2) WHERE id = $1")
+            .bind(event_id)
+            .bind(chart_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let mut payload = InitializeEventSeatingPayload {
+            seating_chart_id: chart_id,
+            default_ticket_tier_id: tier_id,
+            resync: false,
+        };
+
+        // 1. The chart gains a seat and retires an unsold and a sold one.
+        let new_seat_id: i32 = sqlx::query_scalar(
+            "INSERT INTO seats (row_id, seat_number, pos_x, pos_y, ordinal) VALUES ($1, '4', 72, 0, 4) RETURNING id",
+        )
+        .bind(row_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        sqlx::query("UPDATE seats SET retired_at = NOW() WHERE id = ANY($1)")
+            .bind(&seat_ids[..2])
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE event_seats SET status = 'sold' WHERE seat_id = $1")
+            .bind(seat_ids[1])
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // 2. Setting up the event again without a re-sync changes nothing.
+        let report = initialize_event_seating(&pool, event_id, organizer_id, &payload).await.unwrap();
+        assert!(report.added_seat_ids.is_empty() && report.retired_seat_ids.is_empty());
+        assert_eq!(report.seat_count, 3);
+
+        // 3. A re-sync adds the new seat and removes the unsold retired one.
+        payload.resync = true;
+        let report = initialize_event_seating(&pool, event_id, organizer_id, &payload).await.unwrap();
+        assert_eq!(report.added_seat_ids, vec![new_seat_id]);
+        assert_eq!(report.retired_seat_ids, vec![seat_ids[0]]);
+        assert_eq!(report.kept_seat_ids, vec![seat_ids[1]]);
+        assert_eq!(report.seat_count, 3);
+        let report = initialize_event_seating(&pool, event_id, organizer_id, &payload).await.unwrap();
+        assert!(report.added_seat_ids.is_empty() && report.retired_seat_ids.is_empty());
+
+        // 4. Only the organizer can do it, and only with a chart of the event's venue.
+        let stranger = test_fixtures::create_user(&pool, "stranger").await;
+        assert!(matches!(
+            initialize_event_seating(&pool, event_id, stranger, &payload).await,
+            Err(AppError::Forbidden(_))
+        ));
+        let other_chart_id: i32 = sqlx::query_scalar(
+            "WITH venue AS (
+                 INSERT INTO venues (name, city, postal_code, country)
+                 VALUES ('Arena', 'City', '1000', 'NL') RETURNING id
+             )
+             INSERT INTO seating_charts (venue_id, name) SELECT id, 'Main' FROM venue RETURNING id",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let foreign_chart = InitializeEventSeatingPayload { seating_chart_id: other_chart_id, ..payload };
+        assert!(matches!(
+            initialize_event_seating(&pool, event_id, organizer_id, &foreign_chart).await,
+            Err(AppError::BadRequest(_))
+        ));
+    }
+}
@@ -0,0 +1,318 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use crate::{
+    db::{event_query, organizer_query, settlement_query},
+    errors::AppError,
+    models::{DailyStatement, EventStatement, OrganizerPayout, SettlementEntryType, SettlementSyncReport},
+};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use stripe::{
+    AccountId, BalanceTransaction, BalanceTransactionSourceUnion, BalanceTransactionType, Client, Dispute,
+    ListBalanceTransactions, ListPayouts, Payout, RangeBounds, RangeQuery,
+};
+
+/// How many objects to request per page from the Stripe list endpoints.
+const STRIPE_PAGE_SIZE: u64 = 100;
+
+/// The default window for daily statements when the caller gives no range.
+const DEFAULT_STATEMENT_DAYS: i64 = 30;
+
+/// How far before its cursor a sync starts reading again. Charges of payments confirmed
+/// late (e.g. by the nightly reconciliation) are only recognised once the payment succeeded.
+const SYNC_OVERLAP_HOURS: i64 = 48;
+
+// --- Statements ---
+
+/// Service to fetch the settlement statement of every event owned by the organizer.
+pub async fn list_event_statements(
+    pool: &PgPool,
+    organizer_id: i32,
+) -> Result<Vec<EventStatement>, AppError> {
+    settlement_query::list_event_statements(pool, organizer_id).await
+}
+
+/// Service to fetch the settlement statement of a single event.
+/// Only the event's organizer may see it.
+pub async fn get_event_statement(
+    pool: &PgPool,
+    event_id: i32,
+    organizer_id: i32,
+) -> Result<EventStatement, AppError> {
+    let event = event_query::get_by_id(pool, event_id).await?;
+    if event.organizer_id != organizer_id {
+        return Err(AppError::Forbidden(
+            "You are not authorized to view the statement for this event.".to_string(),
+        ));
+    }
+
+    settlement_query::get_event_statement(pool, event_id).await
+}
+
+/// Service to fetch the organizer's daily statements for an inclusive date range.
+/// Defaults to the last 30 days when no bounds are given.
+pub async fn list_daily_statements(
+    pool: &PgPool,
+    organizer_id: i32,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> Result<Vec<DailyStatement>, AppError> {
+    let to = to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = from.unwrap_or(to - Duration::days(DEFAULT_STATEMENT_DAYS));
+    if from > to {
+        return Err(AppError::BadRequest("`from` must not be after `to`.".to_string()));
+    }
+
+    settlement_query::list_daily_statements(pool, organizer_id, from, to).await
+}
+
+/// Service to list the payouts synced from the organizer's connected account.
+pub async fn list_payouts(pool: &PgPool, organizer_id: i32) -> Result<Vec<OrganizerPayout>, AppError> {
+    settlement_query::list_payouts(pool, organizer_id).await
+}
+
+// --- Reconciliation with Stripe ---
+
+/// Reconciles the organizer's ledger against Stripe.
+///
+/// 1. Pages through the platform's balance transactions created since the last sync (or
+///    the organizer's first payment) and records the Stripe fee of every charge, and every
+///    refund and dispute, that belongs to one of their orders.
+/// 2. Fetches the payouts of the organizer's connected account.
+///
+/// Safe to run repeatedly: entries are keyed by balance transaction and payouts by ID, so
+/// re-reading the overlap before the cursor records nothing twice.
+pub async fn sync_with_stripe(
+    pool: &PgPool,
+    stripe_client: &Client,
+    organizer_id: i32,
+) -> Result<SettlementSyncReport, AppError> {
+    let mut report = SettlementSyncReport::default();
+
+    // 1. Collect the organizer's payments so we only look at relevant balance transactions.
+    let shares = settlement_query::list_payment_shares_for_organizer(pool, organizer_id).await?;
+    let payment_intent_ids: HashSet<&str> =
+        shares.iter().map(|s| s.stripe_payment_intent_id.as_str()).collect();
+
+    if let Some(first_payment) = shares.iter().map(|s| s.payment_created_at).min() {
+        let since = match settlement_query::get_sync_cursor(pool, organizer_id).await? {
+            Some(cursor) => first_payment.max(cursor - Duration::hours(SYNC_OVERLAP_HOURS)),
+            None => first_payment,
+        };
+        let mut synced_until = None;
+        let mut starting_after = None;
+        loop {
+            let mut params = ListBalanceTransactions::new();
+            params.created = Some(RangeQuery::Bounds(RangeBounds {
+                gte: Some(since.timestamp()),
+                ..Default::default()
+            }));
+            params.expand = &["data.source"];
+            params.limit = Some(STRIPE_PAGE_SIZE);
+            params.starting_after = starting_after.take();
+
+            let page = BalanceTransaction::list(stripe_client, &params).await?;
+            for balance_transaction in &page.data {
+                report.balance_transactions_checked += 1;
+                synced_until = synced_until.max(Some(balance_transaction.created));
+                report.entries_recorded +=
+                    record_balance_transaction(pool, balance_transaction, &payment_intent_ids).await?;
+            }
+
+            match (page.has_more, page.data.last()) {
+                (true, Some(last)) => starting_after = Some(last.id.clone()),
+                _ => break,
+            }
+        }
+
+        // Only move the cursor once every page was recorded.
+        if let Some(synced_until) = synced_until {
+            settlement_query::advance_sync_cursor(pool, organizer_id, timestamp_to_datetime(synced_until)).await?;
+        }
+    }
+
+    // 2. Fetch payouts from the connected account, if the organizer finished onboarding.
+    let profile = organizer_query::get_profile_by_user_id(pool, organizer_id).await?;
+    if let Some(account_id) = profile.stripe_account_id {
+        let account_id = AccountId::from_str(&account_id).map_err(|e| {
+            tracing::error!("Failed to parse Stripe Account ID from database: {}. ID was: {}", e, account_id);
+            AppError::InternalServerError("Invalid Stripe Account ID format encountered.".to_string())
+        })?;
+        let connected_client = stripe_client.clone().with_stripe_account(account_id);
+
+        let mut starting_after = None;
+        loop {
+            let mut params = ListPayouts::new();
+            params.limit = Some(STRIPE_PAGE_SIZE);
+            params.starting_after = starting_after.take();
+
+            let page = Payout::list(&connected_client, &params).await?;
+            for payout in &page.data {
+                settlement_query::upsert_payout(
+                    pool,
+                    organizer_id,
+                    payout.id.as_str(),
+                    cents_to_decimal(payout.amount),
+                    &payout.currency.to_string(),
+                    &payout.status,
+                    timestamp_to_datetime(payout.arrival_date),
+                    timestamp_to_datetime(payout.created),
+                )
+                .await?;
+                report.payouts_synced += 1;
+            }
+
+            match (page.has_more, page.data.last()) {
+                (true, Some(last)) => starting_after = Some(last.id.clone()),
+                _ => break,
+            }
+        }
+    }
+
+    tracing::info!(
+        "Settlement sync for organizer {}: {} balance transactions checked, {} entries recorded, {} payouts synced.",
+        organizer_id,
+        report.balance_transactions_checked,
+        report.entries_recorded,
+        report.payouts_synced
+    );
+    Ok(report)
+}
+
+/// Records the ledger entry for a single balance transaction, if it is a charge, a refund
+/// or a dispute of one of the given payment intents. Returns the number of entries inserted.
+async fn record_balance_transaction(
+    pool: &PgPool,
+    balance_transaction: &BalanceTransaction,
+    payment_intent_ids: &HashSet<&str>,
+) -> Result<u64, AppError> {
+    let source = balance_transaction.source.as_ref().and_then(|s| s.as_object());
+
+    // Work out which payment this is for, and what it means for the organizer.
+    let (payment_intent_id, entry_type, amount) = match (&balance_transaction.type_, source) {
+        (
+            BalanceTransactionType::Charge | BalanceTransactionType::Payment,
+            Some(BalanceTransactionSourceUnion::Charge(charge)),
+        ) => match &charge.payment_intent {
+            Some(pi) => (pi.id().to_string(), SettlementEntryType::StripeFee, -balance_transaction.fee),
+            None => return Ok(0),
+        },
+        (
+            BalanceTransactionType::Refund | BalanceTransactionType::PaymentRefund,
+            Some(BalanceTransactionSourceUnion::Refund(refund)),
+        ) => match &refund.payment_intent {
+            // A refund's amount is already negative.
+            Some(pi) => (pi.id().to_string(), SettlementEntryType::Refund, balance_transaction.amount),
+            None => return Ok(0),
+        },
+        (BalanceTransactionType::Adjustment, Some(BalanceTransactionSourceUnion::Dispute(dispute))) => {
+            match &dispute.payment_intent {
+                // Withdrawals (and the dispute fee) are negative, reinstatements positive.
+                Some(pi) => (pi.id().to_string(), SettlementEntryType::Dispute, balance_transaction.net),
+                None => return Ok(0),
+            }
+        }
+        _ => return Ok(0),
+    };
+
+    if !payment_intent_ids.contains(payment_intent_id.as_str()) {
+        return Ok(0);
+    }
+
+    record_split_entry(pool, &payment_intent_id, balance_transaction, entry_type, amount).await
+}
+
+/// Records the funds withdrawn and reinstated by a dispute in the ledger.
+/// Called from the dispute webhooks, so organizers see a dispute before the next sync.
+/// Returns the number of entries inserted.
+pub async fn record_dispute_balance_transactions(
+    pool: &PgPool,
+    stripe_payment_intent_id: &str,
+    dispute: &Dispute,
+) -> Result<u64, AppError> {
+    let mut inserted = 0;
+    for balance_transaction in &dispute.balance_transactions {
+        inserted += record_split_entry(
+            pool,
+            stripe_payment_intent_id,
+            balance_transaction,
+            SettlementEntryType::Dispute,
+            balance_transaction.net,
+        )
+        .await?;
+    }
+    Ok(inserted)
+}
+
+/// Splits `amount` over the events of a payment's order, in proportion to what each
+/// was charged, and records one ledger entry per event.
+async fn record_split_entry(
+    pool: &PgPool,
+    stripe_payment_intent_id: &str,
+    balance_transaction: &BalanceTransaction,
+    entry_type: SettlementEntryType,
+    amount: i64,
+) -> Result<u64, AppError> {
+    if amount == 0 {
+        return Ok(0);
+    }
+
+    let shares = settlement_query::list_shares_for_payment_intent(pool, stripe_payment_intent_id).await?;
+    let weights: Vec<Decimal> = shares.iter().map(|s| s.gross_amount).collect();
+    let allocations = allocate_proportionally(cents_to_decimal(amount), &weights);
+
+    let currency = balance_transaction.currency.to_string();
+    let occurred_at = timestamp_to_datetime(balance_transaction.created);
+    let mut inserted = 0;
+    for (share, allocated) in shares.iter().zip(allocations) {
+        inserted += settlement_query::insert_stripe_entry(
+            pool,
+            share.event_id,
+            share.order_id,
+            entry_type,
+            allocated,
+            &currency,
+            balance_transaction.id.as_str(),
+            occurred_at,
+        )
+        .await?;
+    }
+
+    Ok(inserted)
+}
+
+// --- Helpers ---
+
+/// Splits `total` across `weights` proportionally, rounded to cents.
+/// The last share absorbs the rounding difference so the parts always add up to `total`.
+fn allocate_proportionally(total: Decimal, weights: &[Decimal]) -> Vec<Decimal> {
+    let weight_sum: Decimal = weights.iter().sum();
+    if weights.is_empty() || weight_sum.is_zero() {
+        return weights.iter().map(|_| Decimal::ZERO).collect();
+    }
+
+    let mut allocated = Decimal::ZERO;
+    let mut parts = Vec::with_capacity(weights.len());
+    for (i, weight) in weights.iter().enumerate() {
+        let part = if i + 1 == weights.len() {
+            total - allocated
+        } else {
+            (total * weight / weight_sum).round_dp(2)
+        };
+        allocated += part;
+        parts.push(part);
+    }
+    parts
+}
+
+/// Converts an amount in the smallest currency unit (as Stripe reports it) to a Decimal.
+pub fn cents_to_decimal(amount: i64) -> Decimal {
+    Decimal::new(amount, 2)
+}
+
+/// Converts a Stripe UNIX timestamp to a `DateTime<Utc>`.
+pub fn timestamp_to_datetime(timestamp: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(timestamp, 0).unwrap_or_else(Utc::now)
+}
@@ -0,0 +1,276 @@
+use crate::{
+    db::{gift_card_query, group_booking_query, order_query, seating_query, settlement_query, store_credit_query, ticket_query},
+    errors::AppError,
+    models::{
+        GiftCard, GiftCardStatus, Order, OrderStatus, PurchaseGiftCardPayload, RedeemGiftCardPayload, StoreCredit,
+        StoreCreditEntryType,
+    },
+    service::waitlist_service,
+    utils::{random, validation},
+};
+use rust_decimal::Decimal;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// The length of a gift card's code.
+const CODE_LENGTH: usize = 16;
+/// The smallest and largest gift cards sold.
+const MIN_GIFT_CARD_AMOUNT: i64 = 5;
+const MAX_GIFT_CARD_AMOUNT: i64 = 1_000;
+
+// --- Gift Cards ---
+
+/// Checks a gift card purchase before its order is created.
+pub fn check_purchase(payload: &PurchaseGiftCardPayload) -> Result<(), AppError> {
+    validation::validate_payload(payload)?;
+    if payload.amount < Decimal::from(MIN_GIFT_CARD_AMOUNT)
+        || payload.amount > Decimal::from(MAX_GIFT_CARD_AMOUNT)
+        || payload.amount.normalize().scale() > 2
+    {
+        return Err(AppError::BadRequest(format!(
+            "Gift cards are sold for {} to {}, in whole cents.",
+            MIN_GIFT_CARD_AMOUNT, MAX_GIFT_CARD_AMOUNT
+        )));
+    }
+    Ok(())
+}
+
+/// Creates the gift card bought with a pending order, under a fresh code.
+pub async fn create_for_order(
+    tx: &mut Transaction<'_, Postgres>,
+    order_id: Uuid,
+    purchaser_id: i32,
+    payload: &PurchaseGiftCardPayload,
+) -> Result<GiftCard, AppError> {
+    let code = random::generate_random_token(CODE_LENGTH).to_uppercase();
+    gift_card_query::create_gift_card(tx, &code, order_id, purchaser_id, payload).await
+}
+
+/// Service to list the gift cards a user bought, with their codes to pass on.
+pub async fn list_my_gift_cards(pool: &PgPool, user_id: i32) -> Result<Vec<GiftCard>, AppError> {
+    gift_card_query::list_for_purchaser(pool, user_id).await
+}
+
+/// Service for a user to redeem a gift card. Its whole remaining balance becomes store
+/// credit on their account, recorded in both the card's and the account's ledgers.
+pub async fn redeem(pool: &PgPool, user_id: i32, payload: &RedeemGiftCardPayload) -> Result<StoreCredit, AppError> {
+    // 1. Validate the payload and lock the card. Codes are issued in upper case, but may be
+    //    typed in any case.
+    validation::validate_payload(payload)?;
+    let code = payload.code.trim().to_uppercase();
+    let mut tx = pool.begin().await?;
+    let card = gift_card_query::lock_by_code(&mut tx, &code)
+        .await
+        .map_err(|_| AppError::BadRequest("This gift card code isn't valid.".to_string()))?;
+
+    // 2. Only paid-for cards with something left on them can be redeemed.
+    if card.status != GiftCardStatus::Active {
+        return Err(AppError::BadRequest("This gift card hasn't been paid for.".to_string()));
+    }
+    if card.balance <= Decimal::ZERO {
+        return Err(AppError::BadRequest("This gift card has already been redeemed.".to_string()));
+    }
+
+    // 3. Move the balance onto the user's account.
+    gift_card_query::redeem(&mut tx, card.id, user_id, card.balance).await?;
+    store_credit_query::add_credit(&mut tx, user_id, StoreCreditEntryType::GiftCard, card.balance, None, Some(card.id))
+        .await?;
+    tx.commit().await?;
+
+    get_store_credit(pool, user_id).await
+}
+
+// --- Store Credit ---
+
+/// Service for a user to see their store credit balance and its ledger.
+pub async fn get_store_credit(pool: &PgPool, user_id: i32) -> Result<StoreCredit, AppError> {
+    let balance = store_credit_query::get_balance(pool, user_id).await?;
+    let entries = store_credit_query::list_entries(pool, user_id).await?;
+    Ok(StoreCredit { balance, entries })
+}
+
+/// Pays for up to `requested` of a pending order with the user's store credit, in the
+/// checkout's transaction. Only the rest of the total is charged to the card.
+pub async fn apply_to_order(
+    tx: &mut Transaction<'_, Postgres>,
+    order: &Order,
+    requested: Decimal,
+) -> Result<Order, AppError> {
+    if requested <= Decimal::ZERO || requested.normalize().scale() > 2 {
+        return Err(AppError::BadRequest("The store credit to apply must be a positive amount in cents.".to_string()));
+    }
+    let amount = requested.min(order.total_amount);
+    if amount.is_zero() {
+        return order_query::set_credit_applied(tx, order.id, amount).await;
+    }
+    if !store_credit_query::spend_credit(tx, order.user_id, amount, order.id).await? {
+        return Err(AppError::BadRequest(format!("Your store credit doesn't cover {}.", amount)));
+    }
+    order_query::set_credit_applied(tx, order.id, amount).await
+}
+
+/// Service for an admin to refund a completed order as store credit instead of to the card.
+/// The buyer gets everything they paid back as credit, the order's tickets and vouchers are
+/// voided, and its inventory goes back on sale, waitlisted users first.
+/// Group booking orders, paid by several members, are refunded to the card instead.
+pub async fn refund_order_as_credit(pool: &PgPool, order_id: Uuid) -> Result<StoreCredit, AppError> {
+    // 1. Refund the order. Only ticket orders whose tickets and vouchers are all unused qualify.
+    let mut tx = pool.begin().await?;
+    let order = order_query::get_by_id(pool, order_id).await?;
+    if !matches!(order.status, OrderStatus::Completed) {
+        return Err(AppError::BadRequest("Only completed orders can be refunded.".to_string()));
+    }
+    if !ticket_query::all_valid_for_order(&mut tx, order_id).await? {
+        return Err(AppError::BadRequest(
+            "This order has tickets or vouchers that were used, transferred or disputed.".to_string(),
+        ));
+    }
+    if gift_card_query::exists_for_order(&mut tx, order_id).await? {
+        return Err(AppError::BadRequest("Gift card orders can't be refunded as store credit.".to_string()));
+    }
+    if group_booking_query::is_group_order(&mut tx, order_id).await? {
+        return Err(AppError::BadRequest(
+            "Group booking orders were paid by several members and can't be refunded as store credit.".to_string(),
+        ));
+    }
+    if !order_query::mark_order_refunded(&mut tx, order_id).await? {
+        return Err(AppError::BadRequest("Only completed orders can be refunded.".to_string()));
+    }
+
+    // 2. Void what was issued and put the inventory back on sale.
+    ticket_query::void_tickets_for_order(&mut tx, order_id).await?;
+    order_query::release_general_admission_inventory(&mut tx, &[order_id]).await?;
+    seating_query::release_refunded_order_seats(&mut tx, order_id).await?;
+    let event_ids = order_query::list_event_ids_for_orders(&mut tx, &[order_id]).await?;
+    waitlist_service::offer_returned_inventory(&mut tx, &event_ids).await?;
+
+    // 3. Reverse the sale for the organizers and credit the buyer.
+    settlement_query::record_order_refund(&mut tx, order_id).await?;
+    if order.total_amount > Decimal::ZERO {
+        store_credit_query::add_credit(
+            &mut tx,
+            order.user_id,
+            StoreCreditEntryType::Refund,
+            order.total_amount,
+            Some(order_id),
+            None,
+        )
+        .await?;
+    }
+    tx.commit().await?;
+    tracing::info!("Refunded order {} as {} of store credit.", order_id, order.total_amount);
+
+    get_store_credit(pool, order.user_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::payment_provider::{FakePaymentProvider, ProviderPaymentStatus};
+    use crate::models::{CreateOrderPayload, OrderItemPayload};
+    use crate::service::{order_service, payment_service};
+    use crate::utils::test_fixtures;
+
+    /// Buys a gift card of `amount` for `user_id` and pays for it. Returns its code.
+    async fn paid_gift_card(pool: &PgPool, provider: &FakePaymentProvider, user_id: i32, amount: Decimal) -> String {
+        let payload = PurchaseGiftCardPayload { amount, recipient_email: None, message: None };
+        order_service::create_gift_card_order(pool, provider, user_id, &payload).await.unwrap();
+        let payment_intent_id: String = sqlx::query_scalar(
+            "SELECT p.stripe_payment_intent_id FROM payments p JOIN gift_cards g ON g.order_id = p.order_id
+             WHERE g.purchaser_id = $1 ORDER BY p.created_at DESC LIMIT 1",
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        provider.set_status(&payment_intent_id, ProviderPaymentStatus::Succeeded);
+        payment_service::finalize_order_on_payment_success(pool, provider, &payment_intent_id).await.unwrap();
+
+        let cards = list_my_gift_cards(pool, user_id).await.unwrap();
+        cards.into_iter().find(|card| card.status == GiftCardStatus::Active && card.balance == amount).unwrap().code
+    }
+
+    /// A checkout of two 50.00 tickets, paid with up to `credit` of store credit.
+    async fn checkout_with_credit(
+        pool: &PgPool,
+        provider: &FakePaymentProvider,
+        user_id: i32,
+        credit: Decimal,
+    ) -> (Order, Option<String>) {
+        let event_id = test_fixtures::create_event(pool).await;
+        let tier_id = test_fixtures::create_tier(pool, event_id, 100).await;
+        let offer_id = test_fixtures::create_offer(pool, tier_id, Decimal::new(5000, 2), 100).await;
+        let payload = CreateOrderPayload {
+            items: vec![OrderItemPayload { offer_id, seat_id: None, quantity: 2 }],
+            addons: Vec::new(),
+            apply_credit: Some(credit),
+        };
+        order_service::create_order(pool, provider, user_id, &payload, None).await.unwrap()
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn gift_card_is_redeemed_once(pool: PgPool) {
+        let provider = FakePaymentProvider::new();
+        let buyer_id = test_fixtures::create_user(&pool, "buyer").await;
+        let friend_id = test_fixtures::create_user(&pool, "friend").await;
+        let code = paid_gift_card(&pool, &provider, buyer_id, Decimal::from(25)).await;
+
+        let payload = RedeemGiftCardPayload { code: code.to_lowercase() };
+        let credit = redeem(&pool, friend_id, &payload).await.unwrap();
+        assert_eq!(credit.balance, Decimal::from(25));
+        assert_eq!(credit.entries.len(), 1);
+
+        for user_id in [friend_id, buyer_id] {
+            assert!(matches!(redeem(&pool, user_id, &payload).await, Err(AppError::BadRequest(_))));
+        }
+        assert_eq!(get_store_credit(&pool, friend_id).await.unwrap().balance, Decimal::from(25));
+        assert_eq!(get_store_credit(&pool, buyer_id).await.unwrap().balance, Decimal::ZERO);
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn credit_beyond_the_total_pays_for_the_whole_order(pool: PgPool) {
+        let provider = FakePaymentProvider::new();
+        let user_id = test_fixtures::create_user(&pool, "buyer").await;
+        let code = paid_gift_card(&pool, &provider, user_id, Decimal::from(500)).await;
+        redeem(&pool, user_id, &RedeemGiftCardPayload { code }).await.unwrap();
+
+        let (order, client_secret) = checkout_with_credit(&pool, &provider, user_id, Decimal::from(500)).await;
+        assert!(client_secret.is_none());
+        assert!(matches!(order.status, OrderStatus::Completed));
+        assert_eq!(order.credit_applied, order.total_amount);
+
+        let credit = get_store_credit(&pool, user_id).await.unwrap();
+        assert_eq!(credit.balance, Decimal::from(500) - order.total_amount);
+        let tickets: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tickets WHERE order_id = $1")
+            .bind(order.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(tickets, 2);
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn credit_is_given_back_when_the_order_expires(pool: PgPool) {
+        let provider = FakePaymentProvider::new();
+        let user_id = test_fixtures::create_user(&pool, "buyer").await;
+        let code = paid_gift_card(&pool, &provider, user_id, Decimal::from(30)).await;
+        redeem(&pool, user_id, &RedeemGiftCardPayload { code }).await.unwrap();
+
+        let (order, client_secret) = checkout_with_credit(&pool, &provider, user_id, Decimal::from(30)).await;
+        assert!(client_secret.is_some());
+        assert_eq!(order.credit_applied, Decimal::from(30));
+        assert_eq!(get_store_credit(&pool, user_id).await.unwrap().balance, Decimal::ZERO);
+
+        sqlx::query("UPDATE orders SET expires_at = NOW() - INTERVAL '1 minute' WHERE id = $1")
+            .bind(order.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(order_service::expire_stale_orders(&pool, &provider).await.unwrap(), 1);
+
+        let credit = get_store_credit(&pool, user_id).await.unwrap();
+        assert_eq!(credit.balance, Decimal::from(30));
+        assert_eq!(credit.entries[0].entry_type, StoreCreditEntryType::OrderPaymentReversal);
+        assert_eq!(credit.entries[0].amount, Decimal::from(30));
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
-    db::venue_query,
+    db::{user_query, venue_query},
     errors::AppError,
-    models::{CreateVenuePayload, Venue},
+    models::{CreateVenuePayload, Venue, VenueManager},
     utils::validation,
 };
 use sqlx::PgPool;
@@ -20,4 +20,21 @@ pub async fn get_by_id(pool: &PgPool, id: i32) -> Result<Venue, AppError> {
 /// Service to list all active venues.
 pub async fn list_all(pool: &PgPool) -> Result<Vec<Venue>, AppError> {
     venue_query::list_all(pool).await
-}
\ No newline at end of file
+}
+/// Service for an admin to let a user manage the seating charts of a venue.
+pub async fn add_manager(pool: &PgPool, venue_id: i32, user_id: i32) -> Result<VenueManager, AppError> {
+    // Make sure both exist, so a typo is a 404 rather than a database error.
+    venue_query::get_by_id(pool, venue_id).await?;
+    user_query::get_by_id(pool, user_id).await?;
+    venue_query::add_manager(pool, venue_id, user_id).await
+}
+
+/// Service for an admin to revoke a user's management of a venue.
+pub async fn remove_manager(pool: &PgPool, venue_id: i32, user_id: i32) -> Result<(), AppError> {
+    venue_query::remove_manager(pool, venue_id, user_id).await
+}
+
+/// Service to list the managers of a venue.
+pub async fn list_managers(pool: &PgPool, venue_id: i32) -> Result<Vec<VenueManager>, AppError> {
+    venue_query::list_managers(pool, venue_id).await
+}
@@ -0,0 +1,267 @@
+use std::time::Duration;
+
+use crate::{
+    db::{event_query, realtime_query, waiting_room_query},
+    errors::AppError,
+    models::{
+        ConfigureWaitingRoomPayload, CreateOrderPayload, QueueProgress, QueueState, QueueStatus, WaitingRoom,
+        WaitingRoomEntry, WaitingRoomInfo,
+    },
+    utils::{self, validation},
+};
+use chrono::Utc;
+use sqlx::PgPool;
+
+/// Service for an organizer to put a waiting room in front of their event's on-sale,
+/// or to change its rate. The opening time can't move once the line has been drawn.
+pub async fn configure_room(
+    pool: &PgPool,
+    event_id: i32,
+    organizer_id: i32,
+    payload: &ConfigureWaitingRoomPayload,
+) -> Result<WaitingRoom, AppError> {
+    // 1. Validate the payload and check the user organizes the event.
+    validation::validate_payload(payload)?;
+    authorize_organizer(pool, event_id, organizer_id).await?;
+
+    // 2. An open room keeps its opening time.
+    if let Some(room) = waiting_room_query::find_room(pool, event_id).await?
+        && room.opened_at.is_some()
+        && room.opens_at != payload.opens_at
+    {
+        return Err(AppError::BadRequest(
+            "This waiting room has already opened, so its opening time can't be changed.".to_string(),
+        ));
+    }
+
+    waiting_room_query::upsert_room(pool, event_id, payload).await
+}
+
+/// Service to take the waiting room away from an event, e.g. once demand has died down.
+/// Checkout no longer needs an admission token afterwards.
+pub async fn remove_room(pool: &PgPool, event_id: i32, organizer_id: i32) -> Result<(), AppError> {
+    authorize_organizer(pool, event_id, organizer_id).await?;
+    if waiting_room_query::delete_room(pool, event_id).await? == 0 {
+        return Err(AppError::Sqlx(sqlx::Error::RowNotFound));
+    }
+    Ok(())
+}
+
+/// Service to fetch the public view of an event's waiting room.
+pub async fn get_room_info(pool: &PgPool, event_id: i32) -> Result<WaitingRoomInfo, AppError> {
+    waiting_room_query::get_room_info(pool, event_id).await
+}
+
+/// Service for a user to join an event's queue. Joining again is harmless, except after
+/// an admission expired unused: then the user goes to the back of the line.
+pub async fn join_queue(pool: &PgPool, event_id: i32, user_id: i32) -> Result<QueueStatus, AppError> {
+    // 1. Lock the room so places at the back of the line are handed out one at a time.
+    let mut tx = pool.begin().await?;
+    let room = waiting_room_query::lock_room(&mut tx, event_id).await?;
+
+    // 2. Keep an existing place in line, or take a new one.
+    let entry = match waiting_room_query::find_entry(&mut *tx, event_id, user_id).await? {
+        Some(entry) if queue_state(&room, &entry) != QueueState::Expired => entry,
+        _ => waiting_room_query::join_queue(&mut tx, event_id, user_id, room.opened_at.is_some()).await?,
+    };
+    tx.commit().await?;
+
+    queue_status(&room, &entry)
+}
+
+/// Service to fetch a user's place in an event's queue, with their admission token once let in.
+pub async fn get_queue_status(pool: &PgPool, event_id: i32, user_id: i32) -> Result<QueueStatus, AppError> {
+    let room = waiting_room_query::find_room(pool, event_id)
+        .await?
+        .ok_or(AppError::Sqlx(sqlx::Error::RowNotFound))?;
+    let entry = waiting_room_query::find_entry(pool, event_id, user_id)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("You haven't joined the queue for this event.".to_string()))?;
+    queue_status(&room, &entry)
+}
+
+/// Checks that a user checking out for an event behind a waiting room has been let in.
+/// The admission token must be for this user and the order's event.
+pub async fn check_admission(
+    pool: &PgPool,
+    user_id: i32,
+    payload: &CreateOrderPayload,
+    admission_token: Option<&str>,
+) -> Result<(), AppError> {
+    let offer_ids: Vec<i32> = payload.items.iter().map(|item| item.offer_id).collect();
+    let queued_events = waiting_room_query::list_queued_events_for_offers(pool, &offer_ids).await?;
+    if queued_events.is_empty() {
+        return Ok(());
+    }
+
+    let token = admission_token.ok_or_else(|| {
+        AppError::Forbidden(
+            "This event has a waiting room. Join its queue and check out with the admission token you get when you're let in."
+                .to_string(),
+        )
+    })?;
+    let claims = utils::decode_admission_token(token)?;
+    if claims.sub != user_id.to_string() || queued_events.iter().any(|&event_id| event_id != claims.event_id) {
+        return Err(AppError::Forbidden("This admission token was issued to another user or for another event.".to_string()));
+    }
+    Ok(())
+}
+
+// --- Background Job Service ---
+
+/// Service function for a background worker to move the waiting rooms along: rooms whose
+/// opening time has come draw their line, then every open room lets in as many buyers as
+/// its rate allows over `interval`. Returns how many rooms moved.
+pub async fn advance_waiting_rooms(pool: &PgPool, interval: Duration) -> Result<usize, AppError> {
+    // 1. Open the rooms that are due. One failing doesn't hold up the others.
+    for event_id in waiting_room_query::list_due_rooms(pool).await? {
+        match open_room(pool, event_id).await {
+            Ok(in_line) => tracing::info!("Opened the waiting room of event {} with {} in line.", event_id, in_line),
+            Err(e) => tracing::error!("Failed to open the waiting room of event {}: {:?}", event_id, e),
+        }
+    }
+
+    // 2. Let the next buyers in and tell the queue sockets.
+    let mut tx = pool.begin().await?;
+    let progress = waiting_room_query::admit_next(&mut tx, interval.as_secs_f64()).await?;
+    realtime_query::notify_queue_progress(&mut *tx, &progress).await?;
+    tx.commit().await?;
+
+    Ok(progress.len())
+}
+
+// --- Helpers ---
+
+/// Draws the line of a due waiting room and announces it, so queued users fetch their place.
+async fn open_room(pool: &PgPool, event_id: i32) -> Result<u64, AppError> {
+    let mut tx = pool.begin().await?;
+    let room = waiting_room_query::lock_room(&mut tx, event_id).await?;
+    if room.opened_at.is_some() {
+        return Ok(0);
+    }
+    let in_line = waiting_room_query::open_room(&mut tx, event_id).await?;
+    realtime_query::notify_queue_progress(&mut *tx, &[QueueProgress { event_id, admitted_through: 0 }]).await?;
+    tx.commit().await?;
+    Ok(in_line)
+}
+
+fn queue_state(room: &WaitingRoom, entry: &WaitingRoomEntry) -> QueueState {
+    match entry.admitted_at {
+        Some(admitted_at) if admitted_at + chrono::Duration::minutes(room.admission_minutes as i64) <= Utc::now() => {
+            QueueState::Expired
+        }
+        Some(_) => QueueState::Admitted,
+        None => QueueState::Waiting,
+    }
+}
+
+fn queue_status(room: &WaitingRoom, entry: &WaitingRoomEntry) -> Result<QueueStatus, AppError> {
+    let state = queue_state(room, entry);
+    let admission_expires_at =
+        entry.admitted_at.map(|admitted_at| admitted_at + chrono::Duration::minutes(room.admission_minutes as i64));
+
+    let admission_token = match (state, admission_expires_at) {
+        (QueueState::Admitted, Some(expires_at)) => {
+            Some(utils::create_admission_token(entry.user_id, room.event_id, expires_at)?)
+        }
+        _ => None,
+    };
+    let people_ahead = match (state, entry.position) {
+        (QueueState::Waiting, Some(position)) => Some((position - room.admitted_through - 1).max(0)),
+        _ => None,
+    };
+
+    Ok(QueueStatus {
+        event_id: room.event_id,
+        state,
+        opens_at: room.opens_at,
+        position: entry.position,
+        people_ahead,
+        admission_token,
+        admission_expires_at,
+    })
+}
+
+async fn authorize_organizer(pool: &PgPool, event_id: i32, organizer_id: i32) -> Result<(), AppError> {
+    let event = event_query::get_by_id(pool, event_id).await?;
+    if event.organizer_id != organizer_id {
+        return Err(AppError::Forbidden("You are not authorized to manage the waiting room for this event.".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::OrderItemPayload;
+    use crate::utils::test_fixtures;
+    use rust_decimal::Decimal;
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn queue_lets_buyers_in_at_the_room_rate(pool: PgPool) {
+        let event_id = test_fixtures::create_event(&pool).await;
+        let organizer_id: i32 = sqlx::query_scalar("SELECT organizer_id FROM events WHERE id = $1")
+            .bind(event_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let tier_id = test_fixtures::create_tier(&pool, event_id, 10).await;
+        let offer_id = test_fixtures::create_offer(&pool, tier_id, Decimal::from(30), 10).await;
+        let room = |opens_at| ConfigureWaitingRoomPayload { opens_at, admit_per_minute: 60, admission_minutes: None };
+        configure_room(&pool, event_id, organizer_id, &room(Utc::now() + chrono::Duration::hours(1))).await.unwrap();
+
+        // 1. Buyers who join before the opening wait without a place in line.
+        let mut user_ids = Vec::new();
+        for name in ["first", "second", "third"] {
+            let user_id = test_fixtures::create_user(&pool, name).await;
+            let status = join_queue(&pool, event_id, user_id).await.unwrap();
+            assert_eq!((status.state, status.position), (QueueState::Waiting, None));
+            user_ids.push(user_id);
+        }
+
+        // 2. At the opening the line is drawn, and a second's worth of buyers is let in.
+        let opens_at = Utc::now() - chrono::Duration::seconds(1);
+        configure_room(&pool, event_id, organizer_id, &room(opens_at)).await.unwrap();
+        assert_eq!(advance_waiting_rooms(&pool, Duration::from_secs(1)).await.unwrap(), 1);
+        let mut statuses = Vec::new();
+        for &user_id in &user_ids {
+            statuses.push((user_id, get_queue_status(&pool, event_id, user_id).await.unwrap()));
+        }
+        statuses.sort_by_key(|(_, status)| status.position);
+        let summary: Vec<_> = statuses.iter().map(|(_, s)| (s.position, s.state, s.people_ahead)).collect();
+        assert_eq!(summary, vec![
+            (Some(1), QueueState::Admitted, None),
+            (Some(2), QueueState::Waiting, Some(0)),
+            (Some(3), QueueState::Waiting, Some(1)),
+        ]);
+        assert!(matches!(
+            configure_room(&pool, event_id, organizer_id, &room(Utc::now())).await,
+            Err(AppError::BadRequest(_))
+        ));
+
+        // 3. Checkout needs the admitted buyer's own token.
+        let (admitted_id, other_id) = (statuses[0].0, statuses[1].0);
+        let token = statuses[0].1.admission_token.as_deref();
+        let checkout = CreateOrderPayload {
+            items: vec![OrderItemPayload { offer_id, seat_id: None, quantity: 1 }],
+            addons: Vec::new(),
+            apply_credit: None,
+        };
+        check_admission(&pool, admitted_id, &checkout, token).await.unwrap();
+        for (user_id, token) in [(admitted_id, None), (other_id, token)] {
+            assert!(matches!(check_admission(&pool, user_id, &checkout, token).await, Err(AppError::Forbidden(_))));
+        }
+
+        // 4. Late joiners go to the back, as does a buyer whose admission ran out.
+        let late_id = test_fixtures::create_user(&pool, "late").await;
+        assert_eq!(join_queue(&pool, event_id, late_id).await.unwrap().position, Some(4));
+        sqlx::query("UPDATE waiting_room_entries SET admitted_at = NOW() - INTERVAL '1 hour' WHERE user_id = $1")
+            .bind(admitted_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(get_queue_status(&pool, event_id, admitted_id).await.unwrap().state, QueueState::Expired);
+        let status = join_queue(&pool, event_id, admitted_id).await.unwrap();
+        assert_eq!((status.state, status.position), (QueueState::Waiting, Some(5)));
+    }
+}
@@ -0,0 +1,322 @@
+use crate::{
+    config::CONFIG,
+    db::{event_query, pricing_query, waitlist_query},
+    errors::AppError,
+    models::{
+        CreateOrderPayload, JoinWaitlistPayload, OrderItemPayload, WaitlistClaim, WaitlistEntry, WaitlistEntryDetails,
+        WaitlistStatus,
+    },
+    utils::{random, validation},
+};
+use chrono::{Duration, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+
+/// How long a waitlisted user has to buy the tickets reserved for them.
+const OFFER_MINUTES: i64 = 30;
+/// Length of the secret in a purchase link.
+const CLAIM_TOKEN_LENGTH: usize = 32;
+
+/// Service for a user to join the waitlist of a sold-out event, or of one of its tiers.
+/// Only allowed while fewer tickets than they want are left.
+pub async fn join_waitlist(
+    pool: &PgPool,
+    event_id: i32,
+    user_id: i32,
+    payload: &JoinWaitlistPayload,
+) -> Result<WaitlistEntryDetails, AppError> {
+    // 1. Validate the payload, the event and the tier.
+    validation::validate_payload(payload)?;
+    event_query::get_by_id(pool, event_id).await?;
+    if let Some(tier_id) = payload.ticket_tier_id {
+        let tier = pricing_query::get_ticket_tier(pool, tier_id).await?;
+        if tier.event_id != event_id {
+            return Err(AppError::BadRequest("This ticket tier belongs to another event.".to_string()));
+        }
+    }
+
+    // 2. Nobody waits for tickets that are on sale.
+    let available = waitlist_query::count_available(pool, event_id, payload.ticket_tier_id).await?;
+    if available >= payload.quantity as i64 {
+        return Err(AppError::BadRequest(format!(
+            "There are still {} tickets available; buy them instead of joining the waitlist.",
+            available
+        )));
+    }
+
+    // 3. Take a place at the back of the line.
+    let entry =
+        waitlist_query::create_entry(pool, event_id, payload.ticket_tier_id, user_id, payload.quantity).await?;
+    tracing::info!("User {} joined the waitlist of event {} for {} tickets.", user_id, event_id, entry.quantity);
+
+    let mut entries = list_my_entries(pool, user_id).await?;
+    let index = entries
+        .iter()
+        .position(|details| details.entry.id == entry.id)
+        .ok_or_else(|| AppError::InternalServerError("The new waitlist entry went missing.".to_string()))?;
+    Ok(entries.swap_remove(index))
+}
+
+/// Service to list a user's waitlist entries, with the purchase link of any offered to them.
+pub async fn list_my_entries(pool: &PgPool, user_id: i32) -> Result<Vec<WaitlistEntryDetails>, AppError> {
+    let entries = waitlist_query::list_entries_for_user(pool, user_id).await?;
+    Ok(entries
+        .into_iter()
+        .map(|(entry, people_ahead)| {
+            let purchase_url = match (&entry.status, &entry.claim_token) {
+                (WaitlistStatus::Offered, Some(token)) => Some(purchase_url(token)),
+                _ => None,
+            };
+            WaitlistEntryDetails { entry, people_ahead, purchase_url }
+        })
+        .collect())
+}
+
+/// Service for a user to leave a waitlist. Tickets reserved for them go to the next in line.
+pub async fn leave_waitlist(pool: &PgPool, entry_id: i32, user_id: i32) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+    let entry = waitlist_query::lock_entry(&mut tx, entry_id).await?;
+    if entry.user_id != user_id {
+        return Err(AppError::Forbidden("This waitlist entry belongs to another user.".to_string()));
+    }
+    if !matches!(entry.status, WaitlistStatus::Waiting | WaitlistStatus::Offered) {
+        return Err(AppError::BadRequest("You are no longer on this waitlist.".to_string()));
+    }
+
+    release_reservation(&mut tx, &entry).await?;
+    waitlist_query::close_entry(&mut tx, entry.id, WaitlistStatus::Left, None).await?;
+    offer_returned_inventory(&mut tx, &[entry.event_id]).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Service to show the tickets behind a purchase link to the user it was sent to.
+pub async fn get_claim(pool: &PgPool, user_id: i32, claim_token: &str) -> Result<WaitlistClaim, AppError> {
+    let entry = waitlist_query::get_entry_by_token(pool, claim_token).await?;
+    let offer_id = ensure_claimable(&entry, user_id)?;
+    let offer = pricing_query::get_offer(pool, offer_id).await?;
+    let seat_ids = waitlist_query::list_entry_seats(pool, entry.id).await?;
+
+    Ok(WaitlistClaim {
+        entry_id: entry.id,
+        event_id: entry.event_id,
+        offer_id,
+        price: offer.price,
+        quantity: entry.quantity,
+        seat_ids,
+        expires_at: entry.offer_expires_at.unwrap_or_else(Utc::now),
+    })
+}
+
+/// Turns the reservation behind a purchase link into the items of an order, for checkout
+/// to run in the same transaction. Reserved seats are handed over to the order, and reserved
+/// general admission tickets are put back for checkout to take again.
+pub async fn claim_reservation(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: i32,
+    claim_token: &str,
+) -> Result<(WaitlistEntry, CreateOrderPayload), AppError> {
+    // 1. Lock the entry and check the link is this user's and still valid.
+    let entry = waitlist_query::lock_entry_by_token(tx, claim_token).await?;
+    let offer_id = ensure_claimable(&entry, user_id)?;
+
+    // 2. Build the order from what was reserved.
+    let seat_ids = waitlist_query::take_entry_seats(tx, entry.id).await?;
+    let items = if seat_ids.is_empty() {
+        let offer = waitlist_query::get_reservation_offer(tx, offer_id).await?;
+        waitlist_query::release_general_admission(tx, offer_id, offer.ticket_tier_id, entry.quantity).await?;
+        vec![OrderItemPayload { offer_id, seat_id: None, quantity: entry.quantity }]
+    } else {
+        seat_ids
+            .into_iter()
+            .map(|seat_id| OrderItemPayload { offer_id, seat_id: Some(seat_id), quantity: 1 })
+            .collect()
+    };
+
+    Ok((entry, CreateOrderPayload { items, addons: Vec::new(), apply_credit: None }))
+}
+
+/// Reserves tickets that came back on sale for the users waiting for them, first come first.
+/// Each gets a purchase link valid for `OFFER_MINUTES`. Someone who wants more tickets than
+/// came back keeps their place, while those behind them who want fewer may be served.
+/// Runs inside the transaction that returned the inventory, so nobody else can take it first.
+/// Returns how many users were offered tickets.
+pub async fn offer_returned_inventory(tx: &mut Transaction<'_, Postgres>, event_ids: &[i32]) -> Result<usize, AppError> {
+    let mut offered = 0;
+    for &event_id in event_ids {
+        let mut available = waitlist_query::count_available(&mut **tx, event_id, None).await?;
+        if available == 0 {
+            continue;
+        }
+
+        for entry in waitlist_query::lock_waiting_entries(tx, event_id).await? {
+            if available == 0 {
+                break;
+            }
+            if let Some(offer_id) = reserve_for_entry(tx, &entry).await? {
+                let token = random::generate_random_token(CLAIM_TOKEN_LENGTH);
+                let expires_at = Utc::now() + Duration::minutes(OFFER_MINUTES);
+                waitlist_query::mark_offered(tx, entry.id, offer_id, &token, expires_at).await?;
+                available -= entry.quantity as i64;
+                offered += 1;
+                tracing::info!("Offered {} returned tickets of event {} to user {}.", entry.quantity, event_id, entry.user_id);
+            }
+        }
+    }
+    Ok(offered)
+}
+
+/// Marks a waitlist entry as purchased by the order created from its reservation.
+pub async fn mark_purchased(
+    tx: &mut Transaction<'_, Postgres>,
+    entry: &WaitlistEntry,
+    order_id: uuid::Uuid,
+) -> Result<(), AppError> {
+    waitlist_query::close_entry(tx, entry.id, WaitlistStatus::Purchased, Some(order_id)).await
+}
+
+// --- Background Job Service ---
+
+/// Service function for a background worker to move the waitlists along: expired purchase
+/// links give their tickets to the next in line, and tickets that came back any other way
+/// (e.g. lapsed seat locks) are offered too. Returns how many users were offered tickets.
+pub async fn process_waitlists(pool: &PgPool) -> Result<usize, AppError> {
+    let mut tx = pool.begin().await?;
+
+    // 1. Take back the tickets of expired purchase links.
+    let lapsed = waitlist_query::lock_lapsed_entries(&mut tx).await?;
+    for entry in &lapsed {
+        release_reservation(&mut tx, entry).await?;
+        waitlist_query::close_entry(&mut tx, entry.id, WaitlistStatus::Expired, None).await?;
+    }
+
+    // 2. Offer whatever is on sale to the events' waitlists.
+    let event_ids = waitlist_query::list_waitlisted_events(&mut tx).await?;
+    let offered = offer_returned_inventory(&mut tx, &event_ids).await?;
+    tx.commit().await?;
+
+    if !lapsed.is_empty() || offered > 0 {
+        tracing::info!("Waitlists: {} purchase links expired, {} users offered tickets.", lapsed.len(), offered);
+    }
+    Ok(offered)
+}
+
+// --- Helpers ---
+
+/// Reserves tickets for one waitlist entry through the cheapest offer that has enough left.
+/// Returns that offer, or `None` if none has.
+async fn reserve_for_entry(tx: &mut Transaction<'_, Postgres>, entry: &WaitlistEntry) -> Result<Option<i32>, AppError> {
+    let expires_at = Utc::now() + Duration::minutes(OFFER_MINUTES);
+    for offer in waitlist_query::list_reservation_offers(tx, entry.event_id, entry.ticket_tier_id).await? {
+        if offer.is_reserved {
+            let seat_ids =
+                waitlist_query::find_available_seats(tx, entry.event_id, offer.ticket_tier_id, entry.quantity).await?;
+            if seat_ids.len() == entry.quantity as usize {
+                waitlist_query::reserve_seats(tx, entry, &seat_ids, expires_at).await?;
+                return Ok(Some(offer.offer_id));
+            }
+        } else if waitlist_query::reserve_general_admission(tx, offer.offer_id, offer.ticket_tier_id, entry.quantity)
+            .await?
+        {
+            return Ok(Some(offer.offer_id));
+        }
+    }
+    Ok(None)
+}
+
+/// Puts the tickets reserved for an offered entry back on sale.
+async fn release_reservation(tx: &mut Transaction<'_, Postgres>, entry: &WaitlistEntry) -> Result<(), AppError> {
+    let (WaitlistStatus::Offered, Some(offer_id)) = (entry.status, entry.offer_id) else {
+        return Ok(());
+    };
+    let offer = waitlist_query::get_reservation_offer(tx, offer_id).await?;
+    if offer.is_reserved {
+        waitlist_query::release_entry_seats(tx, entry.id).await?;
+    } else {
+        waitlist_query::release_general_admission(tx, offer_id, offer.ticket_tier_id, entry.quantity).await?;
+    }
+    Ok(())
+}
+
+/// Checks a purchase link belongs to the user and can still be used. Returns its offer.
+fn ensure_claimable(entry: &WaitlistEntry, user_id: i32) -> Result<i32, AppError> {
+    if entry.user_id != user_id {
+        return Err(AppError::Forbidden("This purchase link was sent to another user.".to_string()));
+    }
+    match (entry.status, entry.offer_id, entry.offer_expires_at) {
+        (WaitlistStatus::Offered, Some(offer_id), Some(expires_at)) if expires_at > Utc::now() => Ok(offer_id),
+        (WaitlistStatus::Offered, _, _) | (WaitlistStatus::Expired, _, _) => {
+            Err(AppError::BadRequest("This purchase link has expired.".to_string()))
+        }
+        _ => Err(AppError::BadRequest("This purchase link has already been used.".to_string())),
+    }
+}
+
+fn purchase_url(claim_token: &str) -> String {
+    format!("{}/waitlist/{}", CONFIG.frontend_origin, claim_token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::payment_provider::FakePaymentProvider;
+    use crate::service::order_service;
+    use crate::utils::test_fixtures;
+    use rust_decimal::Decimal;
+
+    async fn entry(pool: &PgPool, user_id: i32) -> WaitlistEntryDetails {
+        list_my_entries(pool, user_id).await.unwrap().pop().unwrap()
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn returned_tickets_go_to_the_waitlist_in_order(pool: PgPool) {
+        let provider = FakePaymentProvider::new();
+        let event_id = test_fixtures::create_event(&pool).await;
+        let tier_id = test_fixtures::create_tier(&pool, event_id, 2).await;
+        let offer_id = test_fixtures::create_offer(&pool, tier_id, Decimal::from(30), 2).await;
+        let buyer = test_fixtures::create_user(&pool, "buyer").await;
+        let pair = test_fixtures::create_user(&pool, "pair").await;
+        let single = test_fixtures::create_user(&pool, "single").await;
+        let wants = |quantity| JoinWaitlistPayload { ticket_tier_id: Some(tier_id), quantity };
+
+        // 1. Nobody waits while there are tickets; once they're gone the line forms.
+        assert!(matches!(join_waitlist(&pool, event_id, pair, &wants(2)).await, Err(AppError::BadRequest(_))));
+        let checkout = CreateOrderPayload {
+            items: vec![OrderItemPayload { offer_id, seat_id: None, quantity: 2 }],
+            addons: Vec::new(),
+            apply_credit: None,
+        };
+        order_service::create_order(&pool, &provider, buyer, &checkout, None).await.unwrap();
+        join_waitlist(&pool, event_id, pair, &wants(2)).await.unwrap();
+        assert_eq!(join_waitlist(&pool, event_id, single, &wants(1)).await.unwrap().people_ahead, Some(1));
+
+        // 2. The expired order's tickets are reserved for the first in line.
+        sqlx::query("UPDATE orders SET expires_at = NOW() - INTERVAL '1 minute'").execute(&pool).await.unwrap();
+        order_service::expire_stale_orders(&pool, &provider).await.unwrap();
+        let offered = entry(&pool, pair).await;
+        assert_eq!(offered.entry.status, WaitlistStatus::Offered);
+        assert!(offered.purchase_url.is_some());
+        assert_eq!(entry(&pool, single).await.entry.status, WaitlistStatus::Waiting);
+
+        // 3. An unused purchase link lapses, and the tickets move down the line.
+        let expired_token = offered.entry.claim_token.unwrap();
+        sqlx::query("UPDATE waitlist_entries SET offer_expires_at = NOW() - INTERVAL '1 minute' WHERE user_id = $1")
+            .bind(pair)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(process_waitlists(&pool).await.unwrap(), 1);
+        assert_eq!(entry(&pool, pair).await.entry.status, WaitlistStatus::Expired);
+        let result = order_service::create_waitlist_order(&pool, &provider, pair, &expired_token).await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+
+        // 4. The link is only for the user it was sent to, and works once.
+        let token = entry(&pool, single).await.entry.claim_token.unwrap();
+        let result = order_service::create_waitlist_order(&pool, &provider, pair, &token).await;
+        assert!(matches!(result, Err(AppError::Forbidden(_))));
+        let (order, _) = order_service::create_waitlist_order(&pool, &provider, single, &token).await.unwrap();
+        assert_eq!(order.subtotal, Decimal::from(30));
+        assert_eq!(entry(&pool, single).await.entry.status, WaitlistStatus::Purchased);
+        let result = order_service::create_waitlist_order(&pool, &provider, single, &token).await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+}
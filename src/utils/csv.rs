@@ -0,0 +1,55 @@
+// File: src/utils/csv.rs
+
+use rust_decimal::Decimal;
+
+/// Builds a CSV document (RFC 4180) from a header row and data rows.
+///
+/// Fields containing a comma, a double quote or a line break are quoted,
+/// and embedded double quotes are doubled. Fields a spreadsheet would run as a formula
+/// (starting with `=`, `+`, `-`, `@`, a tab or a carriage return) are prefixed with `'`;
+/// plain numbers such as `-12.50` are left as they are.
+///
+/// # Example
+///
+/// ```
+/// let csv = to_csv(&["name", "amount"], vec![vec!["Early Bird".to_string(), "75.50".to_string()]]);
+/// assert_eq!(csv, "name,amount\r\nEarly Bird,75.50\r\n");
+/// ```
+pub fn to_csv<I>(headers: &[&str], rows: I) -> String
+where
+    I: IntoIterator<Item = Vec<String>>,
+{
+    let mut out = String::new();
+    push_row(&mut out, headers.iter().copied());
+    for row in rows {
+        push_row(&mut out, row.iter().map(String::as_str));
+    }
+    out
+}
+
+fn push_row<'a>(out: &mut String, fields: impl Iterator<Item = &'a str>) {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let escaped;
+        let field = if is_formula(field) {
+            escaped = format!("'{}", field);
+            escaped.as_str()
+        } else {
+            field
+        };
+        if field.contains([',', '"', '\n', '\r']) {
+            out.push('"');
+            out.push_str(&field.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(field);
+        }
+    }
+    out.push_str("\r\n");
+}
+
+fn is_formula(field: &str) -> bool {
+    field.starts_with(['=', '+', '-', '@', '\t', '\r']) && field.parse::<Decimal>().is_err()
+}
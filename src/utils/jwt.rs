@@ -2,8 +2,8 @@
 
 use crate::config::CONFIG;
 use crate::errors::AppError;
-use crate::models::TokenClaims;
-use jsonwebtoken::{encode, EncodingKey, Header};
+use crate::models::{AdmissionClaims, TokenClaims};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 
 /// Creates a JSON Web Token (JWT) for a given principal ID and role.
 // We've added the `role` parameter here.
@@ -26,4 +26,31 @@ pub fn create_jwt(principal_id: i32, role: &str) -> Result<String, AppError> {
         &EncodingKey::from_secret(CONFIG.jwt_secret.as_ref()),
     )
     .map_err(|_| AppError::JwtCreationError)
-}
\ No newline at end of file
+}
+/// Creates a waiting room admission token letting a user check out for an event until `expires_at`.
+pub fn create_admission_token(
+    user_id: i32,
+    event_id: i32,
+    expires_at: chrono::DateTime<chrono::Utc>,
+) -> Result<String, AppError> {
+    let claims = AdmissionClaims {
+        sub: user_id.to_string(),
+        event_id,
+        exp: expires_at.timestamp() as usize,
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(CONFIG.jwt_secret.as_ref()),
+    )
+    .map_err(|_| AppError::JwtCreationError)
+}
+
+/// Decodes a waiting room admission token, checking its signature and expiry.
+/// Login tokens lack the event claim, so they are refused here, and vice versa.
+pub fn decode_admission_token(token: &str) -> Result<AdmissionClaims, AppError> {
+    decode::<AdmissionClaims>(token, &DecodingKey::from_secret(CONFIG.jwt_secret.as_ref()), &Validation::default())
+        .map(|data| data.claims)
+        .map_err(|_| AppError::Forbidden("Your admission token is invalid or has expired.".to_string()))
+}
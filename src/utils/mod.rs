@@ -4,10 +4,14 @@ pub mod jwt;
 pub mod validation;
 pub mod random;
 pub mod csrf;
+pub mod csv;
+#[cfg(test)]
+pub mod test_fixtures;
 
 // For convenience, we can re-export the functions.
 // This allows other modules to use `crate::utils::create_jwt`
 // instead of the longer `crate::utils::jwt::create_jwt`.
 pub use jwt::create_jwt;
+pub use jwt::{create_admission_token, decode_admission_token};
 pub use validation::is_valid_email;
 pub use random::generate_random_token;
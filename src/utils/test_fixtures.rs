@@ -0,0 +1,114 @@
+// File: src/utils/test_fixtures.rs
+
+//! Rows the service tests build their scenarios on. Each helper inserts the minimum a
+//! checkout needs and returns the new row's ID.
+
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+
+/// Creates an attendee named `name`.
+pub async fn create_user(pool: &PgPool, name: &str) -> i32 {
+    sqlx::query_scalar(
+        "INSERT INTO users (email, username, password_hash) VALUES ($1 || '@example.com', $1, 'x') RETURNING id",
+    )
+    .bind(name)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+/// Creates a published event a month away. Every event has the same organizer.
+pub async fn create_event(pool: &PgPool) -> i32 {
+    let organizer_id: i32 = sqlx::query_scalar(
+        "INSERT INTO users (email, username, password_hash, role)
+         VALUES ('organizer@example.com', 'organizer', 'x', 'organizer')
+         ON CONFLICT (email) DO UPDATE SET role = EXCLUDED.role RETURNING id",
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    sqlx::query_scalar(
+        "INSERT INTO events (organizer_id, title, status, start_time)
+         VALUES ($1, 'Concert', 'published', NOW() + INTERVAL '30 days') RETURNING id",
+    )
+    .bind(organizer_id)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+/// Creates a ticket tier of `inventory` tickets, named after how many the event has.
+pub async fn create_tier(pool: &PgPool, event_id: i32, inventory: i32) -> i32 {
+    sqlx::query_scalar(
+        "INSERT INTO ticket_tiers (event_id, name, total_inventory)
+         SELECT $1, 'Tier ' || (COUNT(*) + 1), $2 FROM ticket_tiers WHERE event_id = $1 RETURNING id",
+    )
+    .bind(event_id)
+    .bind(inventory)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+/// Creates an offer on sale for `quantity` tickets of a tier at `price`.
+pub async fn create_offer(pool: &PgPool, ticket_tier_id: i32, price: Decimal, quantity: i32) -> i32 {
+    sqlx::query_scalar(
+        "INSERT INTO offers (ticket_tier_id, name, status, price, quantity_for_sale)
+         VALUES ($1, 'Standard', 'on_sale', $2, $3) RETURNING id",
+    )
+    .bind(ticket_tier_id)
+    .bind(price)
+    .bind(quantity)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+/// Creates an add-on sold at `price` for an event, of which `inventory` are for sale, if limited.
+pub async fn create_addon(pool: &PgPool, event_id: i32, price: Decimal, inventory: Option<i32>) -> i32 {
+    sqlx::query_scalar(
+        "INSERT INTO addon_items (event_id, name, price, total_inventory) VALUES ($1, 'Parking', $2, $3) RETURNING id",
+    )
+    .bind(event_id)
+    .bind(price)
+    .bind(inventory)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+/// Creates a row of `count` side-by-side seats, numbered from 1, and sets them up for an
+/// event in a tier. Returns the seat IDs from left to right.
+pub async fn create_seats(pool: &PgPool, event_id: i32, ticket_tier_id: i32, count: i32) -> Vec<i32> {
+    let row_id: i32 = sqlx::query_scalar(
+        "WITH venue AS (
+             INSERT INTO venues (name, city, postal_code, country) VALUES ('Hall', 'City', '1000', 'NL') RETURNING id
+         ), chart AS (
+             INSERT INTO seating_charts (venue_id, name) SELECT id, 'Main' FROM venue RETURNING id
+         ), section AS (
+             INSERT INTO sections (seating_chart_id, name) SELECT id, '101' FROM chart RETURNING id
+         )
+         INSERT INTO rows (section_id, name, ordinal) SELECT id, 'A', 1 FROM section RETURNING id",
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    let seat_ids: Vec<i32> = sqlx::query_scalar(
+        "INSERT INTO seats (row_id, seat_number, pos_x, pos_y, ordinal)
+         SELECT $1, n::text, (n - 1) * 24, 0, n FROM generate_series(1, $2) AS n
+         RETURNING id",
+    )
+    .bind(row_id)
+    .bind(count)
+    .fetch_all(pool)
+    .await
+    .unwrap();
+    sqlx::query("INSERT INTO event_seats (event_id, seat_id, ticket_tier_id) SELECT $1, UNNEST($2::int[]), $3")
+        .bind(event_id)
+        .bind(&seat_ids)
+        .bind(ticket_tier_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    seat_ids
+}